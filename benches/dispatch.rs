@@ -0,0 +1,37 @@
+//Nanoseconds per tick of a hot loop of ALU, skip and I instructions, with and without the decode cache
+//cargo bench --bench dispatch. Without a harness, so it runs on stable with no extra dependencies
+
+use chip8::Emulator;
+
+use std::hint::black_box;
+use std::time::Instant;
+
+const TICKS: u32 = 5_000_000;
+const ROUNDS: usize = 5;
+
+//V0 := 1, then a loop: V1 += 1, V0 += V1, V2 |= V0, V3 := V2, V3 >>= 1, skip if V0 = 5, I := 0x300,
+//I += V0, skip V4 := 0 if V1 != V2, jump back
+const ROM: [u8; 24] = [
+    0x60, 0x01, 0x71, 0x01, 0x80, 0x14, 0x82, 0x01, 0x83, 0x20, 0x83, 0x36, 0x30, 0x05, 0xA3, 0x00, 0xF0, 0x1E, 0x91, 0x20, 0x64, 0x00, 0x12, 0x02,
+];
+
+//The fastest of ROUNDS runs
+fn ns_per_tick(decode_cache: bool) -> f64 {
+    (0..ROUNDS)
+        .map(|_| {
+            let mut emulator = Emulator::new();
+            emulator.set_decode_cache(decode_cache);
+            emulator.load_rom(&ROM).unwrap();
+            let start = Instant::now();
+            for _ in 0..TICKS {
+                black_box(&mut emulator).tick().unwrap();
+            }
+            start.elapsed().as_nanos() as f64 / TICKS as f64
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+fn main() {
+    println!("dispatch, decode cache off: {:.1} ns/tick", ns_per_tick(false));
+    println!("dispatch, decode cache on:  {:.1} ns/tick", ns_per_tick(true));
+}
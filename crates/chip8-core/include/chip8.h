@@ -0,0 +1,61 @@
+/* C API of chip8-core, built with: cargo build -p chip8-core --release --features ffi
+ * Link against libchip8_core (.so, .dylib or .dll). Mirrors src/ffi.rs, keep the two in sync.
+ *
+ * Ownership: chip8_new hands out a handle that the host owns and must give back with chip8_free.
+ * Pointers returned by the library belong to the handle. ROM data passed in stays the host's.
+ * A handle is not thread safe, use each from one thread at a time. */
+#ifndef CHIP8_H
+#define CHIP8_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Status codes, everything but CHIP8_OK is negative */
+#define CHIP8_OK 0
+#define CHIP8_NULL_POINTER (-1)
+#define CHIP8_ROM_EMPTY (-2)
+#define CHIP8_ROM_TOO_LARGE (-3)
+/* The program faulted, the emulator stays halted until chip8_reset */
+#define CHIP8_FAULT (-4)
+#define CHIP8_INVALID_KEY (-5)
+#define CHIP8_PANIC (-6)
+/* chip8_load_rom_at was given an address below the fonts or past the end of memory */
+#define CHIP8_INVALID_ADDRESS (-7)
+
+typedef struct Chip8 Chip8;
+
+Chip8 *chip8_new(void);
+/* Null is ignored */
+void chip8_free(Chip8 *chip8);
+
+int32_t chip8_load_rom(Chip8 *chip8, const uint8_t *data, size_t len);
+/* Load and start at address instead of 0x200, e.g. 0x600 for ETI-660 programs */
+int32_t chip8_load_rom_at(Chip8 *chip8, uint16_t address, const uint8_t *data, size_t len);
+int32_t chip8_reset(Chip8 *chip8);
+/* chip8_reset and chip8_load_rom in one, the emulator is left alone if the ROM is refused */
+int32_t chip8_reload_rom(Chip8 *chip8, const uint8_t *data, size_t len);
+/* One instruction */
+int32_t chip8_tick(Chip8 *chip8);
+/* One 60 Hz frame */
+int32_t chip8_run_frame(Chip8 *chip8);
+/* key is 0-15 */
+int32_t chip8_key(Chip8 *chip8, uint8_t key, bool pressed);
+
+/* width * height bytes, row major, each a bitmask of the planes the pixel is lit in (0 is off).
+ * width and height may be null. Valid until the next chip8_framebuffer or chip8_free */
+const uint8_t *chip8_framebuffer(Chip8 *chip8, size_t *width, size_t *height);
+/* Whether the sound timer is running */
+bool chip8_sound(const Chip8 *chip8);
+/* Message for the last failing call, null if none since chip8_reset. Valid until the next call */
+const char *chip8_last_error(const Chip8 *chip8);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
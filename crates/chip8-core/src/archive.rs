@@ -0,0 +1,113 @@
+//The CHIP-8 archive's programs.json (github.com/JohnEarnest/chip8Archive): title, authors, release date
+//and the Octo settings each program was made for, keyed by the ROM's file name without .ch8
+//Fields this crate has no use for (colours, touch modes, ...) are ignored
+
+use crate::chip8::RAM_SIZE;
+use crate::quirks::Quirks;
+use crate::romdb::RomSettings;
+
+use serde::Deserialize;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Archive {
+    //By id, ascending
+    programs: Vec<Program>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct Program {
+    //The key in programs.json, the ROM is <id>.ch8
+    #[serde(skip)]
+    pub id: String,
+    pub title: String,
+    pub authors: Vec<String>,
+    //"YYYY-MM-DD"
+    pub release: Option<String>,
+    //The game jam it was made for, e.g. "OctoJam 2"
+    pub event: Option<String>,
+    pub desc: Option<String>,
+    //"chip8", "schip" or "xochip"
+    pub platform: Option<String>,
+    pub options: ProgramOptions,
+}
+
+//Octo's options, a quirk left out keeps the platform's setting
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ProgramOptions {
+    //Instructions a frame
+    pub tickrate: Option<usize>,
+    //8XY6/8XYE shift Vx in place
+    pub shift_quirks: Option<bool>,
+    //FX55/FX65 leave I alone
+    pub load_store_quirks: Option<bool>,
+    pub clip_quirks: Option<bool>,
+    pub jump_quirks: Option<bool>,
+    pub logic_quirks: Option<bool>,
+    pub v_blank_quirks: Option<bool>,
+}
+
+impl Archive {
+    pub fn parse(json: &str) -> Result<Self, serde_json::Error> {
+        let programs: BTreeMap<String, Program> = serde_json::from_str(json)?;
+        let programs = programs.into_iter().map(|(id, program)| Program { id, ..program }).collect();
+        Ok(Self { programs })
+    }
+
+    pub fn programs(&self) -> &[Program] {
+        &self.programs
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Program> {
+        self.programs.iter().find(|program| program.id == id)
+    }
+
+    //The program a ROM file belongs to, going by its name as the archive lays its ROMs out
+    pub fn for_path(&self, rom: &Path) -> Option<&Program> {
+        self.get(rom.file_stem()?.to_str()?)
+    }
+}
+
+impl Program {
+    pub fn release_year(&self) -> Option<u16> {
+        self.release.as_deref()?.get(..4)?.parse().ok()
+    }
+
+    //The settings the program was made for, for RomSettings::apply
+    //Starts from the platform's preset (XO-CHIP, Octo's own, when unknown), then the options on top
+    //XO-CHIP programs get its 64 KB
+    pub fn settings(&self) -> RomSettings {
+        let (mut quirks, memory_size) = match self.platform.as_deref() {
+            Some("chip8") => (Quirks::cosmac_vip(), None),
+            Some("schip") => (Quirks::schip(), None),
+            _ => (Quirks::xochip(), Some(RAM_SIZE)),
+        };
+        let options = self.options;
+        //Octo's quirks turn the VIP behaviour off, several of ours turn it on
+        if let Some(quirk) = options.shift_quirks {
+            quirks.shift_uses_vy = !quirk;
+        }
+        if let Some(quirk) = options.load_store_quirks {
+            quirks.load_store_increments_i = !quirk;
+        }
+        if let Some(quirk) = options.clip_quirks {
+            quirks.clip_sprites = quirk;
+        }
+        if let Some(quirk) = options.jump_quirks {
+            quirks.jump_uses_vx = quirk;
+        }
+        if let Some(quirk) = options.logic_quirks {
+            quirks.vf_reset = quirk;
+        }
+        if let Some(quirk) = options.v_blank_quirks {
+            quirks.display_wait = quirk;
+        }
+        RomSettings { quirks: Some(quirks), ticks_per_frame: options.tickrate.filter(|&ticks| ticks > 0), memory_size }
+    }
+}
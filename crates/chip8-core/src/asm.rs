@@ -0,0 +1,688 @@
+use crate::instruction::Instruction;
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+
+//Programs are assembled to run from the standard load address
+pub const ORIGIN: u16 = 0x200;
+//Highest address a jump, call or i := reaches, i := long reaches all of memory
+const MAX_TARGET: u16 = 0xFFF;
+//One past the last byte of a 16 bit address space
+const MEMORY_END: usize = 0x10000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for AsmError {}
+
+//Assembled binary (loaded at origin, ORIGIN for assemble) plus the address of every label
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Assembly {
+    pub origin: u16,
+    pub bytes: Vec<u8>,
+    pub labels: BTreeMap<String, u16>,
+    //What each source line assembled to, in source order (lines that emit nothing are left out)
+    pub spans: Vec<Span>,
+}
+
+//Bytes emitted by one statement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub line: usize,
+    pub address: u16,
+    pub len: u16,
+}
+
+//Assemble Octo style source into a CHIP-8 binary
+//Supported: labels (: name), :const, :alias, :org, :byte, raw numbers as data bytes,
+//the standard statements (v0 := 5, i := label, sprite v0 v1 5, ...), if/then, if/begin/else/end,
+//loop/while/again and calling a subroutine by writing its name. # starts a comment
+//SCHIP: hires, lores, scroll-down n, scroll-right, scroll-left, exit, i := bighex vx
+//XO-CHIP: plane n, i := long target, save vx - vy, load vx - vy, audio, pitch := vx
+pub fn assemble(source: &str) -> Result<Assembly, AsmError> {
+    assemble_at(source, ORIGIN)
+}
+
+//Assemble code that will run from origin instead of the load address, e.g. a snippet patched into RAM
+pub fn assemble_at(source: &str, origin: u16) -> Result<Assembly, AsmError> {
+    let mut assembler = Assembler::new(source, origin);
+    assembler.run()?;
+    assembler.finish()
+}
+
+struct Token<'a> {
+    text: &'a str,
+    line: usize,
+}
+
+//A 12 bit operand that may refer to a label defined later in the source
+enum Target {
+    Address(u16),
+    Label(String),
+}
+
+//Condition of an if/while, compiled into one of the skip instructions
+enum Condition {
+    EqImm(u8, u8),
+    NeImm(u8, u8),
+    EqReg(u8, u8),
+    NeReg(u8, u8),
+    Key(u8),
+    NotKey(u8),
+}
+
+impl Condition {
+    //Instruction that skips the next one when the condition evaluates to `when`
+    fn skip_if(&self, when: bool) -> Instruction {
+        match (self, when) {
+            (Condition::EqImm(x, nn), true) | (Condition::NeImm(x, nn), false) => Instruction::SkipEqImm { x: *x, nn: *nn },
+            (Condition::EqImm(x, nn), false) | (Condition::NeImm(x, nn), true) => Instruction::SkipNeImm { x: *x, nn: *nn },
+            (Condition::EqReg(x, y), true) | (Condition::NeReg(x, y), false) => Instruction::SkipEqReg { x: *x, y: *y },
+            (Condition::EqReg(x, y), false) | (Condition::NeReg(x, y), true) => Instruction::SkipNeReg { x: *x, y: *y },
+            (Condition::Key(x), true) | (Condition::NotKey(x), false) => Instruction::SkipKeyPressed { x: *x },
+            (Condition::Key(x), false) | (Condition::NotKey(x), true) => Instruction::SkipKeyNotPressed { x: *x },
+        }
+    }
+}
+
+enum Block {
+    //Offset of the jump taken when the if condition is false
+    If(usize),
+    //Offset of the jump over the else branch
+    Else(usize),
+    //Loop start address and the offsets of the jumps emitted by while
+    Loop(u16, Vec<usize>),
+}
+
+struct Fixup {
+    offset: usize,
+    label: String,
+    line: usize,
+    //16 bit operand word of i := long, instead of the low 12 bits of an instruction
+    long: bool,
+}
+
+struct Assembler<'a> {
+    tokens: Vec<Token<'a>>,
+    origin: u16,
+    position: usize,
+    bytes: Vec<u8>,
+    labels: BTreeMap<String, u16>,
+    constants: BTreeMap<String, u16>,
+    aliases: BTreeMap<String, u8>,
+    fixups: Vec<Fixup>,
+    blocks: Vec<(Block, usize)>,
+    spans: Vec<Span>,
+}
+
+impl<'a> Assembler<'a> {
+    fn new(source: &'a str, origin: u16) -> Self {
+        let tokens = source
+            .lines()
+            .enumerate()
+            .flat_map(|(i, line)| {
+                let code = line.split('#').next().unwrap_or("");
+                code.split_whitespace().map(move |text| Token { text, line: i + 1 })
+            })
+            .collect();
+        Self {
+            tokens,
+            origin,
+            position: 0,
+            bytes: Vec::new(),
+            labels: BTreeMap::new(),
+            constants: BTreeMap::new(),
+            aliases: BTreeMap::new(),
+            fixups: Vec::new(),
+            blocks: Vec::new(),
+            spans: Vec::new(),
+        }
+    }
+
+    fn run(&mut self) -> Result<(), AsmError> {
+        while self.position < self.tokens.len() {
+            let (line, address) = (self.line(), self.here()?);
+            //:org padding is not code of the line
+            let org = self.peek() == Some(":org");
+            self.statement()?;
+            if self.end() > MEMORY_END {
+                return Err(AsmError { line, message: "program runs past the end of memory (0xFFFF)".to_string() });
+            }
+            if !org && self.end() > address as usize {
+                self.spans.push(Span { line, address, len: (self.end() - address as usize) as u16 });
+            }
+        }
+        if let Some((_, line)) = self.blocks.last() {
+            return Err(AsmError { line: *line, message: "unclosed block".to_string() });
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Assembly, AsmError> {
+        for fixup in core::mem::take(&mut self.fixups) {
+            let address = match self.labels.get(&fixup.label) {
+                Some(address) => *address,
+                None => return Err(AsmError { line: fixup.line, message: format!("undefined label '{}'", fixup.label) }),
+            };
+            if fixup.long {
+                self.bytes[fixup.offset] = (address >> 8) as u8;
+            } else if address > MAX_TARGET {
+                return Err(AsmError { line: fixup.line, message: out_of_range(&fixup.label, address) });
+            } else {
+                self.bytes[fixup.offset] = (self.bytes[fixup.offset] & 0xF0) | ((address >> 8) & 0xF) as u8;
+            }
+            self.bytes[fixup.offset + 1] = address as u8;
+        }
+        Ok(Assembly { origin: self.origin, bytes: self.bytes, labels: self.labels, spans: self.spans })
+    }
+
+    //Line of the token about to be read (or the last one at the end of input)
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .or_else(|| self.tokens.last())
+            .map_or(0, |token| token.line)
+    }
+
+    fn error<T>(&self, message: String) -> Result<T, AsmError> {
+        Err(AsmError { line: self.line(), message })
+    }
+
+    fn next(&mut self) -> Result<&'a str, AsmError> {
+        match self.tokens.get(self.position) {
+            Some(token) => {
+                self.position += 1;
+                Ok(token.text)
+            },
+            None => self.error("unexpected end of input".to_string()),
+        }
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.position).map(|token| token.text)
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), AsmError> {
+        let token = self.next()?;
+        if token != expected {
+            self.position -= 1;
+            return self.error(format!("expected '{}', found '{}'", expected, token));
+        }
+        Ok(())
+    }
+
+    //Address of the next byte emitted, an error once the program has filled memory
+    fn here(&self) -> Result<u16, AsmError> {
+        match u16::try_from(self.end()) {
+            Ok(address) => Ok(address),
+            Err(_) => self.error("program runs past the end of memory (0xFFFF)".to_string()),
+        }
+    }
+
+    //here() that can be one past 0xFFFF
+    fn end(&self) -> usize {
+        self.origin as usize + self.bytes.len()
+    }
+
+    //here() for a jump or call to it
+    fn here_target(&self) -> Result<u16, AsmError> {
+        let address = self.here()?;
+        if address > MAX_TARGET {
+            return self.error(format!("target out of range: the block ends at {:#X}, past {:#X}", address, MAX_TARGET));
+        }
+        Ok(address)
+    }
+
+    fn emit(&mut self, instruction: Instruction) {
+        self.bytes.extend_from_slice(&instruction.to_bytes());
+    }
+
+    //Emit an instruction with a 12 bit operand, patched in finish() if the label is not known yet
+    fn emit_target(&mut self, make: fn(u16) -> Instruction, target: Target) {
+        match target {
+            Target::Address(address) => self.emit(make(address)),
+            Target::Label(label) => {
+                let line = self.tokens[self.position - 1].line;
+                self.fixups.push(Fixup { offset: self.bytes.len(), label, line, long: false });
+                self.emit(make(0));
+            },
+        }
+    }
+
+    //Emit a jump to be pointed somewhere later with patch_jump
+    fn emit_placeholder_jump(&mut self) -> usize {
+        let offset = self.bytes.len();
+        self.emit(Instruction::Jump(0));
+        offset
+    }
+
+    fn patch_jump(&mut self, offset: usize) -> Result<(), AsmError> {
+        let address = self.here_target()?;
+        self.bytes[offset] = 0x10 | (address >> 8) as u8;
+        self.bytes[offset + 1] = address as u8;
+        Ok(())
+    }
+
+    fn register(&mut self) -> Result<u8, AsmError> {
+        let token = self.next()?;
+        match parse_register(token).or_else(|| self.aliases.get(token).copied()) {
+            Some(register) => Ok(register),
+            None => {
+                self.position -= 1;
+                self.error(format!("expected a register, found '{}'", token))
+            },
+        }
+    }
+
+    fn is_register(&self, token: &str) -> bool {
+        parse_register(token).is_some() || self.aliases.contains_key(token)
+    }
+
+    //Number or previously defined constant
+    fn value(&mut self) -> Result<u16, AsmError> {
+        let token = self.next()?;
+        if let Some(value) = parse_number(token).or_else(|| self.constants.get(token).copied()) {
+            return Ok(value);
+        }
+        self.position -= 1;
+        self.error(format!("expected a number or constant, found '{}'", token))
+    }
+
+    fn byte(&mut self) -> Result<u8, AsmError> {
+        let value = self.value()?;
+        if value > 0xFF {
+            self.position -= 1;
+            return self.error(format!("value {:#X} does not fit in a byte", value));
+        }
+        Ok(value as u8)
+    }
+
+    fn nibble(&mut self) -> Result<u8, AsmError> {
+        let value = self.value()?;
+        if value > 0xF {
+            self.position -= 1;
+            return self.error(format!("value {:#X} does not fit in a nibble", value));
+        }
+        Ok(value as u8)
+    }
+
+    //A 12 bit address, as jump, :call and i := take
+    fn target(&mut self) -> Result<Target, AsmError> {
+        match self.long_target()? {
+            Target::Address(address) if address > MAX_TARGET => {
+                self.position -= 1;
+                let token = self.tokens[self.position].text;
+                self.error(out_of_range(token, address))
+            },
+            target => Ok(target),
+        }
+    }
+
+    //Like target, but keeps all 16 bits of the address (i := long)
+    fn long_target(&mut self) -> Result<Target, AsmError> {
+        let token = self.next()?;
+        if let Some(value) = parse_number(token).or_else(|| self.constants.get(token).copied()) {
+            return Ok(Target::Address(value));
+        }
+        if let Some(address) = self.labels.get(token) {
+            return Ok(Target::Address(*address));
+        }
+        if !is_identifier(token) {
+            self.position -= 1;
+            return self.error(format!("expected an address or label, found '{}'", token));
+        }
+        Ok(Target::Label(token.to_string()))
+    }
+
+    fn define_label(&mut self, name: &str) -> Result<(), AsmError> {
+        if !is_identifier(name) {
+            return self.error(format!("invalid label name '{}'", name));
+        }
+        let address = self.here()?;
+        if self.labels.insert(name.to_string(), address).is_some() {
+            return self.error(format!("label '{}' defined twice", name));
+        }
+        Ok(())
+    }
+
+    fn statement(&mut self) -> Result<(), AsmError> {
+        let line = self.line();
+        let token = self.next()?;
+        match token {
+            ":" => {
+                let name = self.next()?;
+                self.define_label(name)?;
+            },
+            ":const" => {
+                let name = self.next()?;
+                let value = self.value()?;
+                self.constants.insert(name.to_string(), value);
+            },
+            ":alias" => {
+                let name = self.next()?;
+                let register = self.register()?;
+                self.aliases.insert(name.to_string(), register);
+            },
+            ":org" => {
+                let address = self.value()?;
+                let here = self.here()?;
+                if address < here {
+                    return self.error(format!(":org {:#X} is behind the current address {:#X}", address, here));
+                }
+                self.bytes.resize((address - self.origin) as usize, 0);
+            },
+            ":byte" => {
+                let byte = self.byte()?;
+                self.bytes.push(byte);
+            },
+            ":call" => {
+                let target = self.target()?;
+                self.emit_target(Instruction::Call, target);
+            },
+            "clear" => self.emit(Instruction::ClearScreen),
+            "hires" => self.emit(Instruction::HighRes),
+            "lores" => self.emit(Instruction::LowRes),
+            "scroll-down" => {
+                let n = self.nibble()?;
+                self.emit(Instruction::ScrollDown(n));
+            },
+            "scroll-right" => self.emit(Instruction::ScrollRight),
+            "scroll-left" => self.emit(Instruction::ScrollLeft),
+            "exit" => self.emit(Instruction::Exit),
+            "plane" => {
+                let n = self.value()?;
+                if n > 3 {
+                    self.position -= 1;
+                    return self.error(format!("plane {} is not 0-3", n));
+                }
+                self.emit(Instruction::Plane(n as u8));
+            },
+            "audio" => self.emit(Instruction::LoadAudio),
+            "return" | ";" => self.emit(Instruction::Return),
+            "jump" => {
+                let target = self.target()?;
+                self.emit_target(Instruction::Jump, target);
+            },
+            "jump0" => {
+                let target = self.target()?;
+                self.emit_target(Instruction::JumpV0, target);
+            },
+            "sprite" => {
+                let x = self.register()?;
+                let y = self.register()?;
+                let n = self.nibble()?;
+                self.emit(Instruction::Draw { x, y, n });
+            },
+            "bcd" => {
+                let x = self.register()?;
+                self.emit(Instruction::StoreBcd { x });
+            },
+            "save" => {
+                let x = self.register()?;
+                match self.register_range_end()? {
+                    Some(y) => self.emit(Instruction::SaveRange { x, y }),
+                    None => self.emit(Instruction::StoreRegs { x }),
+                }
+            },
+            "load" => {
+                let x = self.register()?;
+                match self.register_range_end()? {
+                    Some(y) => self.emit(Instruction::LoadRange { x, y }),
+                    None => self.emit(Instruction::LoadRegs { x }),
+                }
+            },
+            "saveflags" => {
+                let x = self.register()?;
+                self.emit(Instruction::SaveFlags { x });
+            },
+            "loadflags" => {
+                let x = self.register()?;
+                self.emit(Instruction::LoadFlags { x });
+            },
+            "delay" => {
+                self.expect(":=")?;
+                let x = self.register()?;
+                self.emit(Instruction::SetDelay { x });
+            },
+            "buzzer" => {
+                self.expect(":=")?;
+                let x = self.register()?;
+                self.emit(Instruction::SetSound { x });
+            },
+            "pitch" => {
+                self.expect(":=")?;
+                let x = self.register()?;
+                self.emit(Instruction::SetPitch { x });
+            },
+            "i" => self.i_statement()?,
+            "if" => {
+                let condition = self.condition()?;
+                match self.next()? {
+                    "then" => {
+                        self.emit(condition.skip_if(false));
+                        self.statement()?;
+                    },
+                    "begin" => {
+                        self.emit(condition.skip_if(true));
+                        let jump = self.emit_placeholder_jump();
+                        self.blocks.push((Block::If(jump), line));
+                    },
+                    other => return self.error(format!("expected 'then' or 'begin', found '{}'", other)),
+                }
+            },
+            "else" => {
+                match self.blocks.pop() {
+                    Some((Block::If(jump), line)) => {
+                        let skip_else = self.emit_placeholder_jump();
+                        self.patch_jump(jump)?;
+                        self.blocks.push((Block::Else(skip_else), line));
+                    },
+                    _ => return Err(AsmError { line, message: "'else' without 'if ... begin'".to_string() }),
+                }
+            },
+            "end" => {
+                match self.blocks.pop() {
+                    Some((Block::If(jump), _)) | Some((Block::Else(jump), _)) => self.patch_jump(jump)?,
+                    _ => return Err(AsmError { line, message: "'end' without 'if ... begin'".to_string() }),
+                }
+            },
+            "loop" => {
+                let start = self.here_target()?;
+                self.blocks.push((Block::Loop(start, Vec::new()), line));
+            },
+            "while" => {
+                let condition = self.condition()?;
+                self.emit(condition.skip_if(true));
+                let jump = self.emit_placeholder_jump();
+                match self.blocks.iter_mut().rev().find_map(|(block, _)| match block {
+                    Block::Loop(_, breaks) => Some(breaks),
+                    _ => None,
+                }) {
+                    Some(breaks) => breaks.push(jump),
+                    None => return Err(AsmError { line, message: "'while' outside of a loop".to_string() }),
+                }
+            },
+            "again" => {
+                match self.blocks.pop() {
+                    Some((Block::Loop(start, breaks), _)) => {
+                        self.emit(Instruction::Jump(start));
+                        for jump in breaks {
+                            self.patch_jump(jump)?;
+                        }
+                    },
+                    _ => return Err(AsmError { line, message: "'again' without 'loop'".to_string() }),
+                }
+            },
+            _ if self.is_register(token) => {
+                self.position -= 1;
+                self.register_statement()?;
+            },
+            _ if parse_number(token).is_some() || self.constants.contains_key(token) => {
+                self.position -= 1;
+                let byte = self.byte()?;
+                self.bytes.push(byte);
+            },
+            _ if is_identifier(token) => {
+                self.position -= 1;
+                let target = self.target()?;
+                self.emit_target(Instruction::Call, target);
+            },
+            _ => return Err(AsmError { line, message: format!("unexpected '{}'", token) }),
+        }
+        Ok(())
+    }
+
+    fn i_statement(&mut self) -> Result<(), AsmError> {
+        match self.next()? {
+            ":=" => {
+                if self.peek() == Some("hex") {
+                    self.position += 1;
+                    let x = self.register()?;
+                    self.emit(Instruction::LoadFont { x });
+                } else if self.peek() == Some("bighex") {
+                    self.position += 1;
+                    let x = self.register()?;
+                    self.emit(Instruction::LoadBigFont { x });
+                } else if self.peek() == Some("long") {
+                    self.position += 1;
+                    match self.long_target()? {
+                        Target::Address(address) => self.emit(Instruction::LoadILong(address)),
+                        Target::Label(label) => {
+                            let line = self.tokens[self.position - 1].line;
+                            self.fixups.push(Fixup { offset: self.bytes.len() + 2, label, line, long: true });
+                            self.emit(Instruction::LoadILong(0));
+                        },
+                    }
+                } else {
+                    let target = self.target()?;
+                    self.emit_target(Instruction::LoadI, target);
+                }
+            },
+            "+=" => {
+                let x = self.register()?;
+                self.emit(Instruction::AddI { x });
+            },
+            other => return self.error(format!("unknown operator 'i {}'", other)),
+        }
+        Ok(())
+    }
+
+    //The "- vy" of an XO-CHIP save/load range, if there is one
+    fn register_range_end(&mut self) -> Result<Option<u8>, AsmError> {
+        if self.peek() != Some("-") {
+            return Ok(None);
+        }
+        self.position += 1;
+        Ok(Some(self.register()?))
+    }
+
+    fn register_statement(&mut self) -> Result<(), AsmError> {
+        let x = self.register()?;
+        let operator = self.next()?;
+        let rhs_is_register = self.peek().is_some_and(|token| self.is_register(token));
+        let instruction = match operator {
+            ":=" => match self.peek() {
+                _ if rhs_is_register => Instruction::LoadReg { x, y: self.register()? },
+                Some("random") => {
+                    self.position += 1;
+                    Instruction::Random { x, nn: self.byte()? }
+                },
+                Some("key") => {
+                    self.position += 1;
+                    Instruction::WaitKey { x }
+                },
+                Some("delay") => {
+                    self.position += 1;
+                    Instruction::LoadDelay { x }
+                },
+                _ => Instruction::LoadImm { x, nn: self.byte()? },
+            },
+            "+=" if rhs_is_register => Instruction::AddReg { x, y: self.register()? },
+            "+=" => Instruction::AddImm { x, nn: self.byte()? },
+            "-=" if rhs_is_register => Instruction::SubReg { x, y: self.register()? },
+            "-=" => Instruction::AddImm { x, nn: self.byte()?.wrapping_neg() },
+            "=-" => Instruction::SubN { x, y: self.register()? },
+            "|=" => Instruction::Or { x, y: self.register()? },
+            "&=" => Instruction::And { x, y: self.register()? },
+            "^=" => Instruction::Xor { x, y: self.register()? },
+            ">>=" => Instruction::ShiftRight { x, y: self.register()? },
+            "<<=" => Instruction::ShiftLeft { x, y: self.register()? },
+            other => return self.error(format!("unknown operator '{}'", other)),
+        };
+        self.emit(instruction);
+        Ok(())
+    }
+
+    fn condition(&mut self) -> Result<Condition, AsmError> {
+        let x = self.register()?;
+        match self.next()? {
+            "key" => Ok(Condition::Key(x)),
+            "-key" => Ok(Condition::NotKey(x)),
+            operator @ ("==" | "!=") => {
+                let equal = operator == "==";
+                if self.peek().is_some_and(|token| self.is_register(token)) {
+                    let y = self.register()?;
+                    Ok(if equal { Condition::EqReg(x, y) } else { Condition::NeReg(x, y) })
+                } else {
+                    let nn = self.byte()?;
+                    Ok(if equal { Condition::EqImm(x, nn) } else { Condition::NeImm(x, nn) })
+                }
+            },
+            other => self.error(format!("unsupported condition operator '{}'", other)),
+        }
+    }
+}
+
+fn parse_register(token: &str) -> Option<u8> {
+    let digit = token.strip_prefix('v').or_else(|| token.strip_prefix('V'))?;
+    if digit.len() != 1 {
+        return None;
+    }
+    u8::from_str_radix(digit, 16).ok()
+}
+
+//Decimal, 0x hex or 0b binary. Negative numbers are stored as two's complement bytes
+fn parse_number(token: &str) -> Option<u16> {
+    let (negative, digits) = match token.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, token),
+    };
+    let value = if let Some(hex) = digits.strip_prefix("0x") {
+        u16::from_str_radix(hex, 16).ok()?
+    } else if let Some(binary) = digits.strip_prefix("0b") {
+        u16::from_str_radix(binary, 2).ok()?
+    } else {
+        digits.parse::<u16>().ok()?
+    };
+    if negative {
+        if value > 0x80 {
+            return None;
+        }
+        return Some((value as u8).wrapping_neg() as u16);
+    }
+    Some(value)
+}
+
+fn is_identifier(token: &str) -> bool {
+    let mut chars = token.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+//Octo's "cannot exceed 12 bits", for a jump, call or i := to what (as written) at address
+fn out_of_range(what: &str, address: u16) -> String {
+    format!("target out of range: '{}' is {:#X}, past {:#X} (i := long reaches it)", what, address, MAX_TARGET)
+}
@@ -0,0 +1,167 @@
+//Sound for frontends: the emulator only counts the sound timer down, these turn it into samples
+//Output is mono, ready to hand to an SDL or cpal callback
+
+use crate::chip8::AUDIO_PATTERN_SIZE;
+
+pub const DEFAULT_FREQUENCY: f32 = 440.0;
+pub const DEFAULT_VOLUME: f32 = 0.25;
+//Pitch register value until FX3A sets one, the pattern plays at 4000 bits a second
+pub const DEFAULT_PITCH: u8 = 64;
+
+//Bits a second the XO-CHIP audio pattern plays at for a pitch register value
+//Float functions come from libm, core has none of them without std
+pub fn playback_rate(pitch: u8) -> f32 {
+    4000.0 * libm::powf(2.0, (pitch as f32 - 64.0) / 48.0)
+}
+
+//What the audio thread needs from the emulator for one frame, from Emulator::audio_state
+//Copy it across once a frame instead of sharing the emulator with the audio callback
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioState {
+    //The sound timer is running
+    pub sound: bool,
+    pub pattern: [u8; AUDIO_PATTERN_SIZE],
+    pub pitch: u8,
+}
+
+//Silent, what a fresh emulator plays
+impl Default for AudioState {
+    fn default() -> Self {
+        Self { sound: false, pattern: [0; AUDIO_PATTERN_SIZE], pitch: DEFAULT_PITCH }
+    }
+}
+
+impl AudioState {
+    //Whether the program loaded a pattern with F002. Classic programs never do, play Beeper for them
+    pub fn has_pattern(&self) -> bool {
+        self.pattern.iter().any(|&byte| byte != 0)
+    }
+}
+
+//A square wave while the sound timer runs, silence otherwise
+//The phase carries over between calls so buffers join without clicks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Beeper {
+    sample_rate: u32,
+    frequency: f32,
+    //Amplitude of the wave, 0.0 to 1.0
+    volume: f32,
+    //Position in the current period, 0.0 to 1.0
+    phase: f32,
+}
+
+impl Beeper {
+    pub fn new(sample_rate: u32) -> Self {
+        Self { sample_rate: sample_rate.max(1), frequency: DEFAULT_FREQUENCY, volume: DEFAULT_VOLUME, phase: 0.0 }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate.max(1);
+    }
+
+    pub fn frequency(&self) -> f32 {
+        self.frequency
+    }
+
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.frequency = frequency.max(0.0);
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume.clamp(0.0, 1.0);
+    }
+
+    //Fill out with the next samples. sound is whether the sound timer is running:
+    //emulator.sound_timer() > 0, or FrameOutput::sound when the callback runs on another thread
+    pub fn fill(&mut self, sound: bool, out: &mut [f32]) {
+        for sample in out {
+            *sample = self.next_sample(sound);
+        }
+    }
+
+    //Same as fill for devices that take signed 16 bit samples
+    pub fn fill_i16(&mut self, sound: bool, out: &mut [i16]) {
+        for sample in out {
+            *sample = (self.next_sample(sound) * i16::MAX as f32) as i16;
+        }
+    }
+
+    fn next_sample(&mut self, sound: bool) -> f32 {
+        if !sound {
+            //The next beep starts at the beginning of a period
+            self.phase = 0.0;
+            return 0.0;
+        }
+        let sample = if self.phase < 0.5 { self.volume } else { -self.volume };
+        let phase = self.phase + self.frequency / self.sample_rate as f32;
+        self.phase = phase - libm::truncf(phase);
+        sample
+    }
+}
+
+//Told when the sound timer starts and stops running, so a frontend can start and stop its audio
+//device instead of checking sound_timer every frame. Set with Emulator::set_audio_sink
+pub trait AudioSink: Send {
+    //The sound timer went from zero to non zero (FX18, or loading a state)
+    fn sound_start(&mut self);
+    //The sound timer reached zero, was set to zero, or the emulator was reset
+    fn sound_stop(&mut self);
+}
+
+//Plays the XO-CHIP audio pattern: 128 one bit samples, most significant bit first, looped at the
+//pitch's playback rate and resampled to the output rate. The position carries over between calls
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PatternPlayer {
+    sample_rate: u32,
+    volume: f32,
+    //Bit of the pattern being played, with the fraction of it already played
+    position: f32,
+}
+
+impl PatternPlayer {
+    pub fn new(sample_rate: u32) -> Self {
+        Self { sample_rate: sample_rate.max(1), volume: DEFAULT_VOLUME, position: 0.0 }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate.max(1);
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume.clamp(0.0, 1.0);
+    }
+
+    //Fill out with the next samples, silence while the sound timer is stopped
+    pub fn fill(&mut self, audio: &AudioState, out: &mut [f32]) {
+        if !audio.sound {
+            out.fill(0.0);
+            //The pattern starts over the next time the sound timer is set
+            self.position = 0.0;
+            return;
+        }
+        let bits = (AUDIO_PATTERN_SIZE * 8) as f32;
+        let step = playback_rate(audio.pitch) / self.sample_rate as f32;
+        for sample in out {
+            let bit = self.position as usize;
+            let on = audio.pattern[bit / 8] & (0x80 >> (bit % 8)) != 0;
+            *sample = if on { self.volume } else { -self.volume };
+            self.position = (self.position + step) % bits;
+        }
+    }
+}
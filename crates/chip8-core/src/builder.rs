@@ -0,0 +1,160 @@
+use crate::chip8::{Emulator, MemoryProtection, TICKS_PER_FRAME};
+use crate::error::BuildError;
+use crate::memory::MemoryMap;
+use crate::quirks::Quirks;
+use crate::timing::Timing;
+
+use rand::RngCore;
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+//Everything Emulator::new plus setters would configure, checked together in build()
+//e.g. Emulator::builder().quirks(Quirks::schip()).seed(1).rom(&rom).build()?
+pub struct EmulatorBuilder {
+    quirks: Quirks,
+    seed: Option<u64>,
+    rng: Option<Box<dyn RngCore + Send>>,
+    ticks_per_frame: usize,
+    timing: Timing,
+    //None for MemoryMap::default, or MemoryMap::xochip with Quirks::xochip
+    memory_map: Option<MemoryMap>,
+    //Overrides the memory map's
+    start_address: Option<u16>,
+    font: Option<Vec<u8>>,
+    big_font: Option<Vec<u8>>,
+    protection: MemoryProtection,
+    end_detection: bool,
+    rom: Option<Vec<u8>>,
+}
+
+impl EmulatorBuilder {
+    //The settings of Emulator::new
+    pub fn new() -> Self {
+        Self {
+            quirks: Quirks::default(),
+            seed: None,
+            rng: None,
+            ticks_per_frame: TICKS_PER_FRAME,
+            timing: Timing::default(),
+            memory_map: None,
+            start_address: None,
+            font: None,
+            big_font: None,
+            protection: MemoryProtection::default(),
+            end_detection: false,
+            rom: None,
+        }
+    }
+
+    pub fn quirks(mut self, quirks: Quirks) -> Self {
+        self.quirks = quirks;
+        self
+    }
+
+    //See Emulator::set_seed, overrides the quirks' random model
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    //See Emulator::set_rng, cannot be combined with a seed
+    pub fn rng(mut self, rng: impl RngCore + Send + 'static) -> Self {
+        self.rng = Some(Box::new(rng));
+        self
+    }
+
+    //Instructions a frame, the emulator's speed
+    pub fn ticks_per_frame(mut self, ticks: usize) -> Self {
+        self.ticks_per_frame = ticks;
+        self
+    }
+
+    pub fn timing(mut self, timing: Timing) -> Self {
+        self.timing = timing;
+        self
+    }
+
+    pub fn start_address(mut self, address: u16) -> Self {
+        self.start_address = Some(address);
+        self
+    }
+
+    //See Emulator::set_memory_map, e.g. MemoryMap::xochip() for 64 KB. Without one RAM is 4 KB, or 64 KB
+    //with the XO-CHIP quirks
+    pub fn memory_map(mut self, map: MemoryMap) -> Self {
+        self.memory_map = Some(map);
+        self
+    }
+
+    pub fn fontset(mut self, font: &[u8]) -> Self {
+        self.font = Some(font.to_vec());
+        self
+    }
+
+    pub fn big_fontset(mut self, font: &[u8]) -> Self {
+        self.big_font = Some(font.to_vec());
+        self
+    }
+
+    pub fn memory_protection(mut self, protection: MemoryProtection) -> Self {
+        self.protection = protection;
+        self
+    }
+
+    pub fn end_detection(mut self, enabled: bool) -> Self {
+        self.end_detection = enabled;
+        self
+    }
+
+    //Loaded at the start address, without one the emulator is built empty
+    pub fn rom(mut self, rom: &[u8]) -> Self {
+        self.rom = Some(rom.to_vec());
+        self
+    }
+
+    //The first setting that does not hold is the error
+    pub fn build(self) -> Result<Emulator, BuildError> {
+        if self.ticks_per_frame == 0 {
+            return Err(BuildError::NoTicks);
+        }
+        if self.seed.is_some() && self.rng.is_some() {
+            return Err(BuildError::SeedAndRng);
+        }
+        //Whatever the random model
+        let xochip = Quirks { random: self.quirks.random, ..Quirks::xochip() } == self.quirks;
+        let memory_map = self.memory_map.unwrap_or(if xochip { MemoryMap::xochip() } else { MemoryMap::default() });
+        let mut emulator = Emulator::new();
+        emulator.set_memory_map(memory_map).map_err(BuildError::MemoryMap)?;
+        emulator.set_quirks(self.quirks);
+        if let Some(seed) = self.seed {
+            emulator.set_seed(seed);
+        }
+        if let Some(rng) = self.rng {
+            emulator.set_rng(rng);
+        }
+        emulator.set_ticks_per_frame(self.ticks_per_frame);
+        emulator.set_timing(self.timing);
+        if let Some(address) = self.start_address {
+            emulator.set_start_address(address).map_err(BuildError::Rom)?;
+        }
+        if let Some(font) = &self.font {
+            emulator.set_fontset(font).map_err(BuildError::Font)?;
+        }
+        if let Some(font) = &self.big_font {
+            emulator.set_big_fontset(font).map_err(BuildError::Font)?;
+        }
+        emulator.set_memory_protection(self.protection);
+        emulator.set_end_detection(self.end_detection);
+        if let Some(rom) = &self.rom {
+            emulator.load_rom(rom).map_err(BuildError::Rom)?;
+        }
+        Ok(emulator)
+    }
+}
+
+impl Default for EmulatorBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,194 @@
+//Cheats: memory patches the emulator applies itself (see Emulator::set_cheats), each one on or off
+//Cheat files have one cheat a line, the kind, the RAM address, the bytes in hex and a name, and a leading
+//'-' for one that is off. Blank lines and lines starting with # are skipped:
+//
+//  # A game that keeps the lives left at 0x2F5
+//  freeze 0x2F5 03 Infinite lives
+//  -poke 0x2F5 09 Nine lives now
+//  rom 0x23A 1240 Skip the title screen
+
+use crate::chip8::Emulator;
+use crate::error::CheatError;
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheatKind {
+    //Written once, at the end of the first frame after it is turned on
+    Poke,
+    //Written again at the end of every frame, so whatever the program does the bytes stay
+    Freeze,
+    //Written over the program as load_rom loads it, e.g. to change an instruction. One turned on later
+    //waits for the next load (reload_rom)
+    Rom,
+}
+
+impl CheatKind {
+    fn name(self) -> &'static str {
+        match self {
+            CheatKind::Poke => "poke",
+            CheatKind::Freeze => "freeze",
+            CheatKind::Rom => "rom",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cheat {
+    pub name: String,
+    pub kind: CheatKind,
+    pub address: u16,
+    //Written from address up
+    pub bytes: Vec<u8>,
+    pub enabled: bool,
+    //A poke still to write
+    pending: bool,
+}
+
+impl Cheat {
+    pub fn new(name: impl Into<String>, kind: CheatKind, address: u16, bytes: Vec<u8>) -> Self {
+        Self { name: name.into(), kind, address, bytes, enabled: true, pending: true }
+    }
+
+    fn write(&self, emulator: &mut Emulator) {
+        for (offset, &byte) in self.bytes.iter().enumerate() {
+            emulator.poke(self.address.wrapping_add(offset as u16), byte);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cheats {
+    cheats: Vec<Cheat>,
+}
+
+impl Cheats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //The ROM's path with the extension swapped for .cht, like FlagFile::for_rom
+    #[cfg(feature = "std")]
+    pub fn path_for_rom(rom: &Path) -> PathBuf {
+        rom.with_extension("cht")
+    }
+
+    pub fn parse(text: &str) -> Result<Self, CheatError> {
+        let mut cheats = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let malformed = |message: &str| CheatError { line: index + 1, message: message.to_string() };
+            let (enabled, line) = match line.strip_prefix('-') {
+                Some(rest) => (false, rest),
+                None => (true, line),
+            };
+            let (kind, rest) = split_field(line);
+            let (address, rest) = split_field(rest);
+            let (bytes, name) = split_field(rest);
+            if bytes.is_empty() {
+                return Err(malformed("expected a kind, an address and bytes"));
+            }
+            let kind = match kind {
+                "poke" => CheatKind::Poke,
+                "freeze" => CheatKind::Freeze,
+                "rom" => CheatKind::Rom,
+                _ => return Err(malformed("the kind is poke, freeze or rom")),
+            };
+            let address = address.strip_prefix("0x").or_else(|| address.strip_prefix("0X")).unwrap_or(address);
+            let address = u16::from_str_radix(address, 16).map_err(|_| malformed("invalid address"))?;
+            if bytes.len() % 2 != 0 || !bytes.is_ascii() {
+                return Err(malformed("bytes are two hex digits each"));
+            }
+            let bytes = (0..bytes.len())
+                .step_by(2)
+                .map(|at| u8::from_str_radix(&bytes[at..at + 2], 16))
+                .collect::<Result<Vec<u8>, _>>()
+                .map_err(|_| malformed("invalid bytes"))?;
+            cheats.push(Cheat { enabled, pending: enabled, ..Cheat::new(name.trim(), kind, address, bytes) });
+        }
+        Ok(Self { cheats })
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for cheat in &self.cheats {
+            let bytes: String = cheat.bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+            let disabled = if cheat.enabled { "" } else { "-" };
+            let _ = writeln!(text, "{}{} {:#05X} {} {}", disabled, cheat.kind.name(), cheat.address, bytes, cheat.name);
+        }
+        text
+    }
+
+    pub fn add(&mut self, cheat: Cheat) {
+        self.cheats.push(cheat);
+    }
+
+    //Every cheat called name, false if there was none
+    pub fn remove(&mut self, name: &str) -> bool {
+        let len = self.cheats.len();
+        self.cheats.retain(|cheat| cheat.name != name);
+        self.cheats.len() != len
+    }
+
+    //Turn every cheat called name on or off, false if there was none. A poke turned on is written again
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        let mut found = false;
+        for cheat in self.cheats.iter_mut().filter(|cheat| cheat.name == name) {
+            cheat.pending = enabled && (cheat.pending || !cheat.enabled);
+            cheat.enabled = enabled;
+            found = true;
+        }
+        found
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Cheat> {
+        self.cheats.iter().find(|cheat| cheat.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Cheat> {
+        self.cheats.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.cheats.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cheats.is_empty()
+    }
+
+    //End of a frame: pending pokes and every freeze
+    pub(crate) fn apply_frame(&mut self, emulator: &mut Emulator) {
+        for cheat in self.cheats.iter_mut().filter(|cheat| cheat.enabled) {
+            match cheat.kind {
+                CheatKind::Freeze => cheat.write(emulator),
+                CheatKind::Poke if cheat.pending => {
+                    cheat.write(emulator);
+                    cheat.pending = false;
+                },
+                _ => {},
+            }
+        }
+    }
+
+    //Just after the ROM is copied to RAM
+    pub(crate) fn apply_rom(&mut self, emulator: &mut Emulator) {
+        for cheat in self.cheats.iter().filter(|cheat| cheat.enabled && cheat.kind == CheatKind::Rom) {
+            cheat.write(emulator);
+        }
+    }
+}
+
+//The first word and what follows it
+fn split_field(text: &str) -> (&str, &str) {
+    let text = text.trim_start();
+    text.split_at(text.find(char::is_whitespace).unwrap_or(text.len()))
+}
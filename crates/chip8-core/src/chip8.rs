@@ -0,0 +1,2975 @@
+use crate::audio::{AudioSink, AudioState, DEFAULT_PITCH};
+use crate::builder::EmulatorBuilder;
+use crate::cheats::Cheats;
+use crate::clock::{SystemClock, TimeSource};
+use crate::debugger::{Break, Breakpoints, CallFrame};
+use crate::decode_cache::{DecodeCache, Decoded};
+use crate::disasm::{disassemble_as, Line};
+use crate::display::{Display, Frame};
+use crate::effects::Effects;
+use crate::error::{Chip8Error, FontError, MemoryMapError, RomError, StateError};
+#[cfg(feature = "std")]
+use crate::error::RomFileError;
+use crate::events::{Access, Event, Lifecycle, Violation, Warning};
+use crate::explain::explain;
+use crate::export::{export_state, Format};
+use crate::framebuffer::{FrameBuffer, Palette, ScaleOptions, ALL_PLANES, PLANE_COUNT, SCREEN_BUFFER_SIZE, SCREEN_HEIGHT, SCREEN_WIDTH};
+#[cfg(feature = "heatmap")]
+use crate::heatmap::Heatmap;
+use crate::input::{InputSource, Key};
+use crate::instruction::{decode_chip8x, decode_long, is_long, DecodeError, Instruction, Operands};
+use crate::megachip::{BlendMode, MegaChip, Sample, MEGA_MEMORY_SIZE};
+use crate::memory::MemoryMap;
+use crate::metrics::Metrics;
+use crate::profile::{Profile, ProfileReport};
+use crate::quirks::{Quirks, RandomModel};
+use crate::rom;
+#[cfg(feature = "std")]
+use crate::rom::RomFormat;
+use crate::romdb::rom_hash;
+use crate::rpl::{FlagStore, RPL_FLAGS_SIZE};
+use crate::screenshot::{screenshot, ImageFormat};
+use crate::state::State;
+use crate::tas::{InputEvent, InputLog};
+use crate::timing::{vip_cycles, vip_idle_cycles, Timing, VIP_FREE_CYCLES};
+
+use rand::RngCore;
+#[cfg(feature = "std")]
+use rand::random;
+
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use core::cell::RefCell;
+use core::fmt;
+use core::ops::Range;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::Read;
+#[cfg(feature = "std")]
+use std::panic::{self, AssertUnwindSafe};
+#[cfg(feature = "std")]
+use std::path::Path;
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+
+//XO-CHIP addresses 64 KB, classic programs only use the first 4 KB. The most a MemoryMap can have
+pub(crate) const RAM_SIZE: usize = 0x10000;
+pub(crate) const CLASSIC_RAM_SIZE: usize = 4096;
+pub const AUDIO_PATTERN_SIZE: usize = 16;
+pub const REGISTERS_SIZE: usize = 16;
+pub(crate) const STACK_SIZE: usize = 16;
+//Stack watchdog: warn when a call leaves this many entries in use, again once the stack is back under half
+const STACK_WARN_DEPTH: usize = STACK_SIZE - 2;
+//and when the per-frame low point of the stack rises this many times without falling
+const CALL_IMBALANCE_RISES: u32 = 3;
+//Program counters kept for recent_pcs and core dumps
+pub const PC_HISTORY_SIZE: usize = 64;
+pub(crate) const KEYS_SIZE: usize = 16;
+pub const FONTSET_SIZE: usize = 80;
+pub const BIG_FONTSET_SIZE: usize = 160;
+
+//Where programs load and start unless set_start_address moves them
+pub const START_ADDRESS: u16 = 0x200;
+//ETI-660 programs load higher, above the interpreter's larger work area
+pub const ETI_660_START_ADDRESS: u16 = 0x600;
+//Two page hires CHIP-8 programs begin with 1260, a jump into the interpreter extension that comes with them
+//and takes the screen to 64x64. The program proper starts after it
+pub(crate) const TWO_PAGE_SIGNATURE: [u8; 2] = [0x12, 0x60];
+pub const TWO_PAGE_ENTRY: u16 = 0x2C0;
+//Instructions per 60 Hz frame the frontend runs, roughly the speed of the original interpreter
+pub const TICKS_PER_FRAME: usize = 10;
+//How often the delay and sound timers count down
+const TIMER_PERIOD: Duration = Duration::from_nanos(1_000_000_000 / 60);
+//The VIP interpreter keeps its stack, variables and display buffer from here to the end of its 4 KB
+const RESERVED_HIGH_ADDRESS: u16 = 0xEA0;
+
+//Built-in 4x5 digits for FX29, 5 bytes each. Replace them with set_fontset
+pub const FONTSET: [u8; FONTSET_SIZE] = [
+    0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+    0x20, 0x60, 0x20, 0x20, 0x70, // 1
+    0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+    0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
+    0x90, 0x90, 0xF0, 0x10, 0x10, // 4
+    0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+    0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+    0xF0, 0x10, 0x20, 0x40, 0x40, // 7
+    0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+    0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+    0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+    0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
+    0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+    0xE0, 0x90, 0x90, 0x90, 0xE0, // D
+    0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+    0xF0, 0x80, 0xF0, 0x80, 0x80  // F
+];
+
+//SCHIP 8x10 digits for FX30, 10 bytes each. Replace them with set_big_fontset
+pub const BIG_FONTSET: [u8; BIG_FONTSET_SIZE] = [
+    0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // 0
+    0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // 1
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // 2
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 3
+    0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 5
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 6
+    0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // 7
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 8
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 9
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0  // F
+];
+
+//What tick() does next
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EmulatorState {
+    Running,
+    //FX0A is waiting, the next key pressed is stored in V[dest_register] and execution resumes
+    WaitingForKey { dest_register: u8 },
+    //FX0A with the release quirk: key was pressed during the wait, it is stored once released
+    WaitingForRelease { dest_register: u8, key: u8 },
+    //pause() was called: tick and the timers do nothing until resume() picks up where it left off
+    Paused,
+    //Stopped by a fault (e.g. a stack overflow) until reset, tick keeps returning the error
+    Halted { error: Chip8Error },
+    //The program can never get out of the loop at pc, tick does nothing until reset (see set_end_detection)
+    Finished { pc: u16, reason: EndReason },
+}
+
+//How a program was seen to be finished
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EndReason {
+    //1NNN (or BNNN) to its own address, how many ROMs end
+    JumpToSelf,
+    //A loop went round with the same registers, reading no input (keys, timers, random) and
+    //changing neither memory nor the screen, so it will do the same forever
+    EndlessLoop,
+}
+
+impl fmt::Display for EndReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EndReason::JumpToSelf => write!(f, "jump to self"),
+            EndReason::EndlessLoop => write!(f, "endless loop"),
+        }
+    }
+}
+
+//Runs one instruction from its operands, with its address for faults. Emulator::handler picks it at decode
+pub(crate) type Handler = fn(&mut Emulator, Operands, u16) -> Result<(), Chip8Error>;
+
+//set_rng's generator, behind a lock so clones can share it. No Mutex without std, the emulator is then not Send
+#[cfg(feature = "std")]
+type SharedRng = Arc<Mutex<dyn RngCore + Send>>;
+#[cfg(not(feature = "std"))]
+type SharedRng = Arc<RefCell<dyn RngCore + Send>>;
+
+#[derive(Clone)]
+pub struct Emulator {
+    state: EmulatorState,
+    program_counter: u16,
+    //RAM size, where the fonts go and the load address and entry point, see set_memory_map
+    memory_map: MemoryMap,
+    protection: MemoryProtection,
+    //Length of the loaded ROM, for MemoryProtection::rom
+    rom_len: usize,
+    //Written below the load address on every reset, see set_fontset
+    font: [u8; FONTSET_SIZE],
+    big_font: [u8; BIG_FONTSET_SIZE],
+    //memory_map.size long
+    ram: Box<[u8]>,
+    //MegaChip ROM bytes past the end of RAM, empty for anything else
+    extended: Vec<u8>,
+    screen: FrameBuffer,
+    //See set_megachip, kept through reset like the quirks
+    megachip_support: bool,
+    //Some in MegaChip mode, between 0011 and 0010 (or a reset)
+    megachip: Option<Box<MegaChip>>,
+    //See set_chip8x, kept through reset like the quirks
+    chip8x: bool,
+    //Planes drawn to by DXYN, 00E0 and the scroll instructions (XO-CHIP FN01)
+    planes: u8,
+    audio_pattern: [u8; AUDIO_PATTERN_SIZE],
+    //Playback rate of the audio pattern set by FX3A, see audio::playback_rate
+    pitch: u8,
+    exited: bool,
+    //Kept apart from state so resume() can go back to whatever it was
+    paused: bool,
+    v_registers: [u8; REGISTERS_SIZE],
+    i_register: u16,
+    //Top byte of MegaChip's 24 bit I, set by 01NN NNNN and cleared by any other write to I
+    i_high: u8,
+    stack_pointer: u16,
+    stack: [u16; STACK_SIZE],
+    //Subroutine each stack entry called, for call_stack()
+    subroutines: [u16; STACK_SIZE],
+    keys: [bool; KEYS_SIZE],
+    key_edges: KeyEdges,
+    delay_timer: u8,
+    sound_timer: u8,
+    //FX75/FX85, kept through reset like the calculator's and not part of save states
+    rpl_flags: [u8; RPL_FLAGS_SIZE],
+    flag_store: FlagStoreHook,
+    effects: Effects,
+    strict: bool,
+    invariant_checks: bool,
+    events: Vec<Event>,
+    lifecycle_events: bool,
+    end_detection: bool,
+    loop_check: LoopCheck,
+    explain: bool,
+    explanation: Option<String>,
+    quirks: Quirks,
+    //Random bytes drawn by CXNN are kept here while a Journal is recording
+    random_log: Option<Vec<u8>>,
+    //Bytes CXNN uses instead of fresh ones while a Journal replays
+    random_replay: VecDeque<u8>,
+    //Generator state of the Seeded and Vip random models
+    random_state: u64,
+    //Replaces the thread RNG in the Entropy model. Shared with clones
+    rng: Option<SharedRng>,
+    breakpoints: Breakpoints,
+    //tick() calls since the last timers(), for the frame aware stepping functions
+    frame_ticks: usize,
+    //Ticks since the last reset, what an InputLog counts in
+    tick_count: u64,
+    //Key changes of an InputLog still to deliver, tick already offset to tick_count
+    input_playback: VecDeque<InputEvent>,
+    //DXYN ran under Quirks::display_wait, the frame ends early
+    vblank_wait: bool,
+    timing: Timing,
+    //VIP machine cycles left this frame under Timing::Vip, negative when the last instruction ran over
+    cycle_credit: i64,
+    //Time given to update_timers short of a whole TIMER_PERIOD, carried to the next call
+    timer_time: Duration,
+    trace_hook: TraceHook,
+    pc_history: PcHistory,
+    audio_sink: AudioSinkHook,
+    display: DisplayHook,
+    //A clear happened this frame, for Display::clear
+    frame_cleared: bool,
+    input: InputHook,
+    watchdog: Watchdog,
+    profile: Option<Profile>,
+    metrics: Metrics,
+    //Bit per address of the largest RAM, set for the first byte of every instruction executed
+    coverage: Box<[u64; RAM_SIZE / 64]>,
+    #[cfg(feature = "heatmap")]
+    heatmap: Heatmap,
+    //None unless set_decode_cache turned it on
+    decode_cache: Option<DecodeCache>,
+    //Applied by load_rom and timers, see set_cheats
+    cheats: Cheats,
+    //Shared with clones
+    clock: Arc<dyn TimeSource>,
+    //Instructions run_frame runs
+    ticks_per_frame: usize,
+}
+
+//What happened during one run_frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FrameOutput {
+    //Anything was drawn, cleared or scrolled
+    pub screen_changed: bool,
+    //The sound timer was running, the frontend should beep for this frame
+    pub sound: bool,
+}
+
+//The registers and timers at one moment, from Emulator::cpu, for debug panels and test assertions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuView {
+    pub v_registers: [u8; REGISTERS_SIZE],
+    pub i_register: u16,
+    pub program_counter: u16,
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    pub stack_depth: usize,
+}
+
+//Memory program writes (FX33, FX55, 5XY2) may not touch, see Emulator::set_memory_protection
+//Off by default, as on the original interpreters where a stray write just corrupted the font
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MemoryProtection {
+    //0x000 up to the load address: the fonts and the interpreter's own area
+    pub interpreter: bool,
+    //The ROM image as loaded, for programs that never modify their own code
+    pub rom: bool,
+}
+
+//Stack depth history for the StackNearlyFull and CallImbalance warnings
+#[derive(Debug, Clone, Copy, Default)]
+struct Watchdog {
+    //Lowest depth seen this frame, and in the frame before
+    frame_low: usize,
+    last_low: usize,
+    //Times the low point went up since it last went down
+    rises: u32,
+    nearly_full_warned: bool,
+}
+
+//Keys that went down and up this frame, for keys_pressed_this_frame and keys_released_this_frame
+#[derive(Debug, Clone, Copy, Default)]
+struct KeyEdges {
+    pressed: u16,
+    released: u16,
+    //A frame ended, the next tick or key change starts the new frame's edges
+    stale: bool,
+}
+
+impl KeyEdges {
+    fn begin(&mut self) {
+        if self.stale {
+            *self = Self::default();
+        }
+    }
+
+    fn record(&mut self, key: usize, pressed: bool) {
+        self.begin();
+        if pressed {
+            self.pressed |= 1 << key;
+        } else {
+            self.released |= 1 << key;
+        }
+    }
+}
+
+//Ring of the last PC_HISTORY_SIZE instructions' addresses
+#[derive(Debug, Clone, Copy)]
+struct PcHistory {
+    pcs: [u16; PC_HISTORY_SIZE],
+    //Instructions recorded, the next one goes at executed % PC_HISTORY_SIZE
+    executed: u64,
+}
+
+impl Default for PcHistory {
+    fn default() -> Self {
+        Self { pcs: [0; PC_HISTORY_SIZE], executed: 0 }
+    }
+}
+
+impl PcHistory {
+    fn push(&mut self, pc: u16) {
+        self.pcs[(self.executed % PC_HISTORY_SIZE as u64) as usize] = pc;
+        self.executed += 1;
+    }
+
+    fn oldest_first(&self) -> Vec<u16> {
+        let len = self.executed.min(PC_HISTORY_SIZE as u64);
+        (self.executed - len..self.executed).map(|n| self.pcs[(n % PC_HISTORY_SIZE as u64) as usize]).collect()
+    }
+}
+
+//The last backward jump and the machine as it was then, for EndReason::EndlessLoop
+#[derive(Debug, Clone, Copy, Default)]
+struct LoopCheck {
+    head: Option<u16>,
+    v_registers: [u8; REGISTERS_SIZE],
+    i_register: u16,
+    stack_pointer: u16,
+    //Nothing since the jump read input or wrote memory or the screen
+    quiet: bool,
+}
+
+//Callback set with set_trace_hook. A cloned emulator starts without one, so snapshots do not trace
+#[derive(Default)]
+struct TraceHook(Option<Box<dyn FnMut(u16, u16) + Send>>);
+
+impl Clone for TraceHook {
+    fn clone(&self) -> Self {
+        Self(None)
+    }
+}
+
+//Sink set with set_audio_sink. Like the trace hook a clone starts without one, snapshots stay silent
+#[derive(Default)]
+struct AudioSinkHook(Option<Box<dyn AudioSink>>);
+
+impl Clone for AudioSinkHook {
+    fn clone(&self) -> Self {
+        Self(None)
+    }
+}
+
+//Backend set with set_display, clones start without one like the audio sink
+#[derive(Default)]
+struct DisplayHook(Option<Box<dyn Display + Send>>);
+
+impl Clone for DisplayHook {
+    fn clone(&self) -> Self {
+        Self(None)
+    }
+}
+
+//Store set with set_flag_store, clones start without one so snapshots never overwrite the saved flags
+#[derive(Default)]
+struct FlagStoreHook(Option<Box<dyn FlagStore>>);
+
+impl Clone for FlagStoreHook {
+    fn clone(&self) -> Self {
+        Self(None)
+    }
+}
+
+//Source set with set_input_source, clones start without one and keep the keys as they were
+#[derive(Default)]
+struct InputHook(Option<Box<dyn InputSource>>);
+
+impl Clone for InputHook {
+    fn clone(&self) -> Self {
+        Self(None)
+    }
+}
+
+impl Default for Emulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Emulator {
+    pub fn new() -> Self {
+        let mut new_emulator = Self {
+            state: EmulatorState::Running,
+            program_counter: START_ADDRESS,
+            memory_map: MemoryMap::default(),
+            protection: MemoryProtection::default(),
+            rom_len: 0,
+            font: FONTSET,
+            big_font: BIG_FONTSET,
+            ram: vec![0; MemoryMap::default().size].into_boxed_slice(),
+            extended: Vec::new(),
+            screen: FrameBuffer::new(),
+            megachip_support: false,
+            megachip: None,
+            chip8x: false,
+            planes: 1,
+            audio_pattern: [0; AUDIO_PATTERN_SIZE],
+            pitch: DEFAULT_PITCH,
+            exited: false,
+            paused: false,
+            v_registers: [0; REGISTERS_SIZE],
+            i_register: 0,
+            i_high: 0,
+            stack_pointer: 0,
+            stack: [0; STACK_SIZE],
+            subroutines: [0; STACK_SIZE],
+            keys: [false; KEYS_SIZE],
+            key_edges: KeyEdges::default(),
+            delay_timer: 0,
+            sound_timer: 0,
+            rpl_flags: [0; RPL_FLAGS_SIZE],
+            effects: Effects::default(),
+            strict: false,
+            invariant_checks: cfg!(debug_assertions),
+            events: Vec::new(),
+            lifecycle_events: false,
+            end_detection: false,
+            loop_check: LoopCheck::default(),
+            explain: false,
+            explanation: None,
+            quirks: Quirks::default(),
+            random_log: None,
+            random_replay: VecDeque::new(),
+            random_state: 0,
+            rng: None,
+            breakpoints: Breakpoints::default(),
+            frame_ticks: 0,
+            tick_count: 0,
+            input_playback: VecDeque::new(),
+            vblank_wait: false,
+            timing: Timing::default(),
+            cycle_credit: VIP_FREE_CYCLES as i64,
+            timer_time: Duration::ZERO,
+            trace_hook: TraceHook::default(),
+            pc_history: PcHistory::default(),
+            audio_sink: AudioSinkHook::default(),
+            display: DisplayHook::default(),
+            frame_cleared: false,
+            flag_store: FlagStoreHook::default(),
+            input: InputHook::default(),
+            watchdog: Watchdog::default(),
+            profile: None,
+            metrics: Metrics::default(),
+            coverage: Box::new([0; RAM_SIZE / 64]),
+            #[cfg(feature = "heatmap")]
+            heatmap: Heatmap::new(),
+            decode_cache: None,
+            cheats: Cheats::new(),
+            clock: Arc::new(SystemClock::new()),
+            ticks_per_frame: TICKS_PER_FRAME,
+        };
+        new_emulator.load_fonts();
+        new_emulator
+    }
+
+    fn load_fonts(&mut self) {
+        let (font, big_font) = (self.memory_map.font_address as usize, self.memory_map.big_font_address as usize);
+        self.ram[font..font + FONTSET_SIZE].copy_from_slice(&self.font);
+        self.ram[big_font..big_font + BIG_FONTSET_SIZE].copy_from_slice(&self.big_font);
+        self.clear_decode_cache();
+    }
+
+    //Digits FX29 points at, FONTSET unless replaced
+    pub fn fontset(&self) -> &[u8; FONTSET_SIZE] {
+        &self.font
+    }
+
+    //Replace the FX29 digits with FONTSET_SIZE bytes, 5 rows of 4 (or up to 8) pixels per digit
+    //Written to memory straight away and again on every reset
+    pub fn set_fontset(&mut self, font: &[u8]) -> Result<(), FontError> {
+        self.font = font.try_into().map_err(|_| FontError { len: font.len(), expected: FONTSET_SIZE })?;
+        self.load_fonts();
+        Ok(())
+    }
+
+    //Digits FX30 points at, BIG_FONTSET unless replaced
+    pub fn big_fontset(&self) -> &[u8; BIG_FONTSET_SIZE] {
+        &self.big_font
+    }
+
+    //Replace the SCHIP FX30 digits with BIG_FONTSET_SIZE bytes, 10 rows of 8 pixels per digit
+    pub fn set_big_fontset(&mut self, font: &[u8]) -> Result<(), FontError> {
+        self.big_font = font.try_into().map_err(|_| FontError { len: font.len(), expected: BIG_FONTSET_SIZE })?;
+        self.load_fonts();
+        Ok(())
+    }
+
+    //Pixels of the current resolution, row major (screen_width() pixels per row)
+    //Each pixel is a bitmask of the planes it is lit in: bit 0 is plane 1, bit 1 is plane 2 (XO-CHIP)
+    //Decoded from the packed frame_buffer() on every call
+    pub fn get_screen(&self) -> Vec<u8> {
+        self.screen.pixels()
+    }
+
+    pub fn frame_buffer(&self) -> &FrameBuffer {
+        &self.screen
+    }
+
+    //See FrameBuffer::render_rgba
+    pub fn render_rgba(&self, out: &mut [u8], palette: &Palette) {
+        self.screen.render_rgba(out, palette)
+    }
+
+    //See FrameBuffer::render_scaled
+    pub fn render_scaled(&self, out: &mut [u8], scale: usize, options: &ScaleOptions) {
+        self.screen.render_scaled(out, scale, options)
+    }
+
+    //See FrameBuffer::hash, e.g. assert_eq!(emulator.screen_hash(), 0x...) after running a ROM
+    pub fn screen_hash(&self) -> u64 {
+        self.screen.hash()
+    }
+
+    //SHA-1 of the snapshot (save_state), equal for two emulators in the same state. For checking runs
+    //against each other, see Session::verify
+    pub fn state_hash(&self) -> String {
+        rom_hash(&self.save_state().to_snapshot_bytes())
+    }
+
+    //Whether anything was drawn, cleared or scrolled since the last take_dirty_rows
+    pub fn screen_changed(&self) -> bool {
+        self.screen.dirty_rows() != 0
+    }
+
+    //Rows (of the current resolution) changed since the last call, in order
+    pub fn take_dirty_rows(&mut self) -> Vec<usize> {
+        let dirty = self.screen.take_dirty_rows();
+        (0..self.screen_height()).filter(|&row| dirty & (1 << row) != 0).collect()
+    }
+
+    //Run programs for CHIP-8X, the VIP with the VP-590 colour board: 02A0, 5XY1 and BXYN (instead of BNNN) set
+    //the colours in frame_buffer().color_zones(). Off by default. Kept through reset like the quirks, the colours
+    //are not: they start over on reset and are not part of save states
+    pub fn set_chip8x(&mut self, enabled: bool) {
+        self.chip8x = enabled;
+        self.screen.set_color_zones(enabled);
+        self.clear_decode_cache();
+    }
+
+    pub fn chip8x_enabled(&self) -> bool {
+        self.chip8x
+    }
+
+    //Let programs switch to MegaChip mode with 0011 and load ROMs up to 16 MB. Off by default, when 0011 is an
+    //unknown opcode. Turning it off leaves MegaChip mode. Kept through reset like the quirks
+    pub fn set_megachip(&mut self, enabled: bool) {
+        self.megachip_support = enabled;
+        if !enabled && self.megachip.take().is_some() {
+            self.screen.touch();
+            self.clear_decode_cache();
+        }
+    }
+
+    pub fn megachip_enabled(&self) -> bool {
+        self.megachip_support
+    }
+
+    //Keep the instructions tick decodes, by address, and run them from there the next time round instead of
+    //fetching and decoding again. Worth it at high ticks_per_frame, costs 32 bytes a byte of RAM (128 KB for
+    //4 KB, 2 MB for 64 KB). Off by default
+    //Writes to RAM by the program, poke and loads drop what they overwrite, so it never changes what runs
+    pub fn set_decode_cache(&mut self, enabled: bool) {
+        self.decode_cache = enabled.then(|| DecodeCache::new(self.memory_map.size));
+    }
+
+    pub fn decode_cache_enabled(&self) -> bool {
+        self.decode_cache.is_some()
+    }
+
+    fn clear_decode_cache(&mut self) {
+        if let Some(cache) = &mut self.decode_cache {
+            cache.clear();
+        }
+    }
+
+    //Cheats to apply from now on, replacing any set before: ROM patches on every load_rom, pokes at the end
+    //of the next frame and freezes at the end of every frame. Kept through reset and load_state
+    pub fn set_cheats(&mut self, cheats: Cheats) {
+        self.cheats = cheats;
+    }
+
+    pub fn cheats(&self) -> &Cheats {
+        &self.cheats
+    }
+
+    //To turn cheats on and off while the program runs
+    pub fn cheats_mut(&mut self) -> &mut Cheats {
+        &mut self.cheats
+    }
+
+    //The colour screen and digitised sound in MegaChip mode, None otherwise
+    //The classic screen (frame_buffer, render_rgba, ...) is blank meanwhile, and screen_changed reports 00E0
+    pub fn megachip(&self) -> Option<&MegaChip> {
+        self.megachip.as_deref()
+    }
+
+    //I with MegaChip's top byte, the full address the MegaChip instructions read from
+    pub(crate) fn mega_address(&self) -> u32 {
+        ((self.i_high as u32) << 16) | self.i_register as u32
+    }
+
+    fn mega_byte(&self, address: u32) -> u8 {
+        match (address as usize).checked_sub(self.ram.len()) {
+            None => self.ram[address as usize],
+            Some(offset) => self.extended.get(offset).copied().unwrap_or(0),
+        }
+    }
+
+    fn mega_bytes(&self, len: usize) -> Vec<u8> {
+        let start = self.mega_address();
+        (0..len as u32).map(|offset| self.mega_byte(start.wrapping_add(offset) % MEGA_MEMORY_SIZE as u32)).collect()
+    }
+
+    //Currently selected XO-CHIP planes, 1 (plane 1 only) unless the program ran FN01
+    pub fn selected_planes(&self) -> u8 {
+        self.planes
+    }
+
+    //XO-CHIP audio pattern loaded by F002, 128 one bit samples
+    pub fn audio_pattern(&self) -> &[u8; AUDIO_PATTERN_SIZE] {
+        &self.audio_pattern
+    }
+
+    //XO-CHIP pitch register set by FX3A, 64 (4000 Hz) until a program changes it
+    pub fn pitch(&self) -> u8 {
+        self.pitch
+    }
+
+    //SCHIP RPL user flags as FX75 left them
+    pub fn rpl_flags(&self) -> &[u8; RPL_FLAGS_SIZE] {
+        &self.rpl_flags
+    }
+
+    //Does not go to the flag store, it only hears about FX75
+    pub fn set_rpl_flags(&mut self, flags: [u8; RPL_FLAGS_SIZE]) {
+        self.rpl_flags = flags;
+    }
+
+    //Keep the RPL flags in store, e.g. a FlagFile, so high scores survive between runs
+    //The flags are loaded from it now and saved to it on every FX75
+    pub fn set_flag_store(&mut self, mut store: impl FlagStore + 'static) {
+        if let Some(flags) = store.load() {
+            self.rpl_flags = flags;
+        }
+        self.flag_store = FlagStoreHook(Some(Box::new(store)));
+    }
+
+    pub fn clear_flag_store(&mut self) {
+        self.flag_store = FlagStoreHook::default();
+    }
+
+    //Sound timer, pattern and pitch for audio::PatternPlayer
+    pub fn audio_state(&self) -> AudioState {
+        AudioState { sound: self.sound_timer > 0, pattern: self.audio_pattern, pitch: self.pitch }
+    }
+
+    //SCHIP high resolution (128x64) mode
+    pub fn is_hires(&self) -> bool {
+        self.screen.is_hires()
+    }
+
+    pub fn screen_width(&self) -> usize {
+        self.screen.width()
+    }
+
+    pub fn screen_height(&self) -> usize {
+        self.screen.height()
+    }
+
+    //Whether the pixel at (x, y) of the current resolution is lit, in any plane
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        self.screen.is_lit(x, y)
+    }
+
+    //Lit pixels row by row, screen_width() each, see FrameBuffer::rows
+    pub fn rows(&self) -> impl Iterator<Item = &[bool]> + '_ {
+        self.screen.rows()
+    }
+
+    //The program ran SCHIP's 00FD, tick() does nothing after this
+    pub fn has_exited(&self) -> bool {
+        self.exited
+    }
+
+    //What the most recently executed instruction changed (for the debugger effects panel)
+    pub fn last_effects(&self) -> &Effects {
+        &self.effects
+    }
+
+    //Paused while paused, unless the program has halted or finished
+    pub fn state(&self) -> EmulatorState {
+        match self.state {
+            EmulatorState::Halted { .. } | EmulatorState::Finished { .. } => self.state,
+            _ if self.paused => EmulatorState::Paused,
+            state => state,
+        }
+    }
+
+    //Stop tick and the timers without losing anything, e.g. while a menu is open or the window is hidden
+    //Keys still register, FX0A only takes presses made after resume()
+    pub fn pause(&mut self) {
+        #[cfg(feature = "tracing")]
+        tracing::debug!("paused");
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        #[cfg(feature = "tracing")]
+        tracing::debug!("resumed");
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    //A press while FX0A is waiting completes the instruction (or its release, with the release quirk)
+    //Only edges count, so a key held down since before FX0A or a repeated press event is ignored
+    pub fn keypress(&mut self, key: Key, pressed: bool) {
+        let idx = key.index();
+        let was_pressed = self.keys[idx];
+        self.keys[idx] = pressed;
+        if pressed != was_pressed {
+            self.key_edges.record(idx, pressed);
+        }
+        //Paused, so a press now does not complete FX0A
+        match self.state() {
+            EmulatorState::WaitingForKey { dest_register } if pressed && !was_pressed => {
+                if self.quirks.wait_key_on_release {
+                    self.set_state(EmulatorState::WaitingForRelease { dest_register, key: idx as u8 });
+                } else {
+                    self.set_v(dest_register, idx as u8);
+                    self.set_state(EmulatorState::Running);
+                }
+            },
+            EmulatorState::WaitingForRelease { dest_register, key } if !pressed && was_pressed && key as usize == idx => {
+                self.set_v(dest_register, key);
+                self.set_state(EmulatorState::Running);
+            },
+            _ => (),
+        }
+    }
+
+    //All 16 keys at once, bit n is key n. Changed keys go through keypress so FX0A sees them
+    pub fn set_keys(&mut self, keys: u16) {
+        for key in Key::ALL {
+            let pressed = keys & (1 << key.index()) != 0;
+            if self.keys[key.index()] != pressed {
+                self.keypress(key, pressed);
+            }
+        }
+    }
+
+    //Keys held down, bit n is key n
+    pub fn keys_bitmask(&self) -> u16 {
+        self.keys.iter().enumerate().fold(0, |keys, (key, &pressed)| keys | (pressed as u16) << key)
+    }
+
+    //Keys that went down this frame, bit n is key n, whether or not they are still held. A frame starts with
+    //the first key change or tick after timers() ends the one before, so between run_frame calls these
+    //are the frame just run's. For turbo buttons and repeat: a press and release within one frame is one tap
+    pub fn keys_pressed_this_frame(&self) -> u16 {
+        self.key_edges.pressed
+    }
+
+    //Keys that came up this frame, as keys_pressed_this_frame
+    pub fn keys_released_this_frame(&self) -> u16 {
+        self.key_edges.released
+    }
+
+    //Poll keys from source every frame instead of waiting for keypress calls
+    pub fn set_input_source(&mut self, source: impl InputSource + 'static) {
+        self.input = InputHook(Some(Box::new(source)));
+    }
+
+    pub fn clear_input_source(&mut self) {
+        self.input = InputHook::default();
+    }
+
+    //Ticks run since the last reset, waiting and paused ones included
+    pub fn tick_count(&self) -> u64 {
+        self.tick_count
+    }
+
+    //Where the last PC_HISTORY_SIZE instructions were fetched from, oldest first. A faulting one is the last
+    //Starts again at reset and load_state
+    pub fn recent_pcs(&self) -> Vec<u16> {
+        self.pc_history.oldest_first()
+    }
+
+    //A core dump's, oldest first
+    pub(crate) fn restore_pc_history(&mut self, pcs: &[u16]) {
+        self.pc_history = PcHistory::default();
+        for &pc in &pcs[pcs.len().saturating_sub(PC_HISTORY_SIZE)..] {
+            self.pc_history.push(pc);
+        }
+    }
+
+    //Deliver log's key changes at the ticks they were recorded on, counted from now
+    //Start right where recording started (e.g. straight after the same load_rom) for the same run
+    pub fn play_inputs(&mut self, log: &InputLog) {
+        let start = self.tick_count;
+        self.input_playback = log.events.iter().map(|&event| InputEvent { tick: start + event.tick, ..event }).collect();
+    }
+
+    //Whether key changes from play_inputs are still to come
+    pub fn is_playing_inputs(&self) -> bool {
+        !self.input_playback.is_empty()
+    }
+
+    pub fn stop_playing_inputs(&mut self) {
+        self.input_playback.clear();
+    }
+
+    //Apply the input source's keys through keypress so FX0A sees the edges
+    //run_frame does this before each frame, call it yourself when driving tick directly
+    pub fn poll_input(&mut self) {
+        let Some(source) = &mut self.input.0 else {
+            return;
+        };
+        let keys = source.poll();
+        for (key, pressed) in keys.into_iter().enumerate() {
+            if self.keys[key] != pressed {
+                self.keypress(Key::ALL[key], pressed);
+            }
+        }
+    }
+
+    //The ROM is copied to the load address, RAM is left untouched if it is refused
+    //A two page hires ROM (starting 1260) switches the screen to 64x64 and the PC to TWO_PAGE_ENTRY
+    //With MegaChip support whatever does not fit in RAM goes to the memory only a 24 bit I reaches
+    pub fn load_rom(&mut self, data: &[u8]) -> Result<(), RomError> {
+        self.check_rom(data)?;
+        let begin = self.memory_map.start_address as usize;
+        let (low, high) = data.split_at(data.len().min(self.ram.len() - begin));
+        self.ram[begin..begin + low.len()].copy_from_slice(low);
+        self.clear_decode_cache();
+        self.apply_cheats(Cheats::apply_rom);
+        self.extended = high.to_vec();
+        self.rom_len = data.len();
+        #[cfg(feature = "tracing")]
+        tracing::info!(size = data.len(), address = begin, "ROM loaded");
+        //Two page hires: the extension is VIP machine code, so switch to 64x64 here and start past it
+        if begin == START_ADDRESS as usize && rom::is_two_page(data) {
+            self.screen.set_two_page();
+            self.program_counter = TWO_PAGE_ENTRY;
+        }
+        Ok(())
+    }
+
+    fn check_rom(&self, data: &[u8]) -> Result<(), RomError> {
+        let max = self.rom_capacity();
+        if data.is_empty() {
+            return Err(RomError::Empty);
+        }
+        if data.len() > max {
+            return Err(RomError::TooLarge { size: data.len(), max });
+        }
+        Ok(())
+    }
+
+    //The most load_rom takes, what RAM (or MegaChip memory) holds from the load address up
+    fn rom_capacity(&self) -> usize {
+        let memory = if self.megachip_support { MEGA_MEMORY_SIZE } else { self.ram.len() };
+        memory - self.memory_map.start_address as usize
+    }
+
+    //Read the file and load_rom it, after checking its size (before reading, so a disk image is not read in
+    //whole) and that it is not obviously something else, see RomFormat::detect
+    #[cfg(feature = "std")]
+    pub fn load_rom_from_path(&mut self, path: impl AsRef<Path>) -> Result<RomFormat, RomFileError> {
+        let mut file = File::open(path)?;
+        let size = file.metadata()?.len();
+        let max = self.rom_capacity();
+        if size > max as u64 {
+            return Err(RomError::TooLarge { size: usize::try_from(size).unwrap_or(usize::MAX), max }.into());
+        }
+        let mut data = Vec::with_capacity(size as usize);
+        file.read_to_end(&mut data)?;
+        let format = RomFormat::detect(&data)?;
+        self.load_rom(&data)?;
+        Ok(format)
+    }
+
+    //Restart with data as the program: reset, then load_rom. What reset keeps stays, so the quirks,
+    //seed, timing, start address, hooks and RPL flags are as before. Nothing changes if data is refused
+    pub fn reload_rom(&mut self, data: &[u8]) -> Result<(), RomError> {
+        self.check_rom(data)?;
+        self.reset();
+        self.load_rom(data)
+    }
+
+    //set_start_address then load_rom, e.g. ETI_660_START_ADDRESS for ETI-660 programs
+    //Nothing changes if either refuses
+    pub fn load_rom_at(&mut self, address: u16, data: &[u8]) -> Result<(), RomError> {
+        let previous = (self.memory_map, self.program_counter);
+        self.set_start_address(address)?;
+        if let Err(err) = self.load_rom(data) {
+            (self.memory_map, self.program_counter) = previous;
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    //Where load_rom copies ROMs and where execution starts, START_ADDRESS unless changed
+    pub fn start_address(&self) -> u16 {
+        self.memory_map.start_address
+    }
+
+    //Also moves the PC there, so call it before running. Kept through reset like the quirks
+    //The memory map's start address, which has to be above the fonts and leave room for an instruction
+    pub fn set_start_address(&mut self, address: u16) -> Result<(), RomError> {
+        let map = self.memory_map.at(address);
+        if map.check().is_err() {
+            return Err(RomError::InvalidLoadAddress { address, min: map.fonts_end() });
+        }
+        self.memory_map = map;
+        self.program_counter = address;
+        Ok(())
+    }
+
+    //4 KB with the fonts from 0 and programs at START_ADDRESS unless changed, see RomSettings::detect for 64 KB
+    pub fn memory_map(&self) -> MemoryMap {
+        self.memory_map
+    }
+
+    //Resize RAM and move the fonts and load address, then reset, so call it before load_rom
+    //Addresses past the end wrap round to the start. Kept through reset like the quirks
+    pub fn set_memory_map(&mut self, map: MemoryMap) -> Result<(), MemoryMapError> {
+        map.check()?;
+        self.memory_map = map;
+        self.ram = vec![0; map.size].into_boxed_slice();
+        if self.decode_cache.is_some() {
+            self.decode_cache = Some(DecodeCache::new(map.size));
+        }
+        self.reset();
+        Ok(())
+    }
+
+    //Where address lands in RAM once wrapped round its size
+    fn ram_index(&self, address: u16) -> usize {
+        address as usize & self.memory_map.mask()
+    }
+    pub fn reset(&mut self){
+        #[cfg(feature = "tracing")]
+        tracing::info!("reset");
+        self.set_state(EmulatorState::Running);
+        self.program_counter = self.memory_map.start_address;
+        self.ram.fill(0);
+        self.clear_decode_cache();
+        self.extended.clear();
+        self.rom_len = 0;
+        self.screen = FrameBuffer::new();
+        self.frame_cleared = true;
+        self.screen.set_color_zones(self.chip8x);
+        self.megachip = None;
+        self.planes = 1;
+        self.audio_pattern = [0; AUDIO_PATTERN_SIZE];
+        self.pitch = DEFAULT_PITCH;
+        self.exited = false;
+        self.paused = false;
+        self.v_registers = [0; REGISTERS_SIZE];
+        self.i_register = 0;
+        self.i_high = 0;
+        self.stack_pointer = 0;
+        self.stack = [0; STACK_SIZE];
+        self.subroutines = [0; STACK_SIZE];
+        self.keys = [false; KEYS_SIZE];
+        self.key_edges = KeyEdges::default();
+        self.pc_history = PcHistory::default();
+        self.delay_timer = 0;
+        self.effects = Effects::default();
+        self.events.clear();
+        //After the clear so a stop or start is still reported
+        self.set_sound_timer(0);
+        self.explanation = None;
+        self.random_replay.clear();
+        self.take_random_log();
+        self.seed_random();
+        self.breakpoints.stopped_at = None;
+        self.frame_ticks = 0;
+        self.tick_count = 0;
+        self.input_playback.clear();
+        self.vblank_wait = false;
+        self.cycle_credit = VIP_FREE_CYCLES as i64;
+        self.timer_time = Duration::ZERO;
+        self.loop_check = LoopCheck::default();
+        self.watchdog = Watchdog::default();
+        self.coverage.fill(0);
+        #[cfg(feature = "heatmap")]
+        self.heatmap.clear();
+        self.load_fonts();
+    }
+
+    //Snapshot of the running program, see State
+    pub fn save_state(&self) -> State {
+        State {
+            state: self.state,
+            program_counter: self.program_counter,
+            ram: self.ram.to_vec(),
+            screen: self.screen.to_raw(),
+            hires: self.screen.is_hires(),
+            two_page: self.screen.is_two_page(),
+            planes: self.planes,
+            audio_pattern: self.audio_pattern,
+            pitch: self.pitch,
+            exited: self.exited,
+            v_registers: self.v_registers,
+            i_register: self.i_register,
+            stack: self.stack().to_vec(),
+            keys: self.keys,
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+            quirks: self.quirks,
+            random_state: self.random_state,
+            i_high: self.i_high,
+            extended: self.extended.clone(),
+            megachip: self.megachip.clone(),
+        }
+    }
+
+    //Resume from a snapshot. Strict, explain and journal settings are kept, nothing changes on error
+    pub fn load_state(&mut self, state: &State) -> Result<(), StateError> {
+        if state.ram.len() != self.ram.len() {
+            return Err(StateError::RamSize { len: state.ram.len(), expected: self.ram.len() });
+        }
+        let screen: [u8; SCREEN_BUFFER_SIZE] = state.screen.as_slice().try_into().map_err(|_| StateError::ScreenSize { len: state.screen.len() })?;
+        if state.stack.len() > STACK_SIZE {
+            return Err(StateError::StackTooDeep { depth: state.stack.len() });
+        }
+        state.check()?;
+        if !self.megachip_support && (state.megachip.is_some() || !state.extended.is_empty()) {
+            return Err(StateError::MegaChipOff);
+        }
+
+        //save_state never stores Paused, a hand made State with it loads paused
+        match state.state {
+            EmulatorState::Paused => {
+                self.set_state(EmulatorState::Running);
+                self.paused = true;
+            },
+            other => self.set_state(other),
+        }
+        self.program_counter = state.program_counter;
+        self.ram.copy_from_slice(&state.ram);
+        self.extended.clone_from(&state.extended);
+        self.clear_decode_cache();
+        self.screen = FrameBuffer::from_raw(&screen, state.hires, state.two_page);
+        self.screen.set_color_zones(self.chip8x);
+        self.megachip.clone_from(&state.megachip);
+        self.planes = state.planes;
+        self.audio_pattern = state.audio_pattern;
+        self.pitch = state.pitch;
+        self.exited = state.exited;
+        self.v_registers = state.v_registers;
+        self.i_register = state.i_register;
+        self.i_high = state.i_high;
+        self.stack = [0; STACK_SIZE];
+        self.subroutines = [0; STACK_SIZE];
+        self.stack[..state.stack.len()].copy_from_slice(&state.stack);
+        self.stack_pointer = state.stack.len() as u16;
+        //States only keep return addresses, the 2NNN before each one says what was called
+        for (depth, &return_address) in state.stack.iter().enumerate() {
+            let call = self.read_word(return_address.wrapping_sub(2));
+            self.subroutines[depth] = if call & 0xF000 == 0x2000 { call & 0xFFF } else { 0 };
+        }
+        self.keys = state.keys;
+        self.key_edges = KeyEdges::default();
+        self.pc_history = PcHistory::default();
+        self.delay_timer = state.delay_timer;
+        self.quirks = state.quirks;
+        self.random_state = state.random_state;
+        self.effects = Effects::default();
+        self.events.clear();
+        //After the clear so a stop or start is still reported
+        self.set_sound_timer(state.sound_timer);
+        self.explanation = None;
+        self.random_replay.clear();
+        self.frame_ticks = 0;
+        self.vblank_wait = false;
+        self.cycle_credit = VIP_FREE_CYCLES as i64;
+        self.timer_time = Duration::ZERO;
+        self.loop_check = LoopCheck::default();
+        self.watchdog = Watchdog { frame_low: self.stack_depth(), last_low: self.stack_depth(), ..Watchdog::default() };
+        #[cfg(feature = "tracing")]
+        tracing::info!(pc = self.program_counter, "state loaded");
+        Ok(())
+    }
+
+    //Push the address of a subroutine onto the stack, None if the stack is full
+    fn push(&mut self, address: u16, subroutine: u16) -> Option<()> {
+        *self.stack.get_mut(self.stack_pointer as usize)? = address;
+        self.subroutines[self.stack_pointer as usize] = subroutine;
+        self.stack_pointer += 1;
+        Some(())
+    }
+    //Pop the address of a subroutine off the stack and return its address, None if the stack is empty
+    fn pop(&mut self) -> Option<u16> {
+        self.stack_pointer = self.stack_pointer.checked_sub(1)?;
+        Some(self.stack[self.stack_pointer as usize])
+    }
+
+    pub fn quirks(&self) -> &Quirks {
+        &self.quirks
+    }
+
+    //Switching random model (or seed) restarts the generator
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        let reseed = quirks.random != self.quirks.random;
+        self.quirks = quirks;
+        if reseed {
+            self.seed_random();
+        }
+    }
+
+    //Same sequence of CXNN results on every run (and after every reset): the Seeded random model
+    pub fn set_seed(&mut self, seed: u64) {
+        self.quirks.random = RandomModel::Seeded(seed);
+        self.seed_random();
+    }
+
+    //Generator for CXNN under the Entropy model, instead of the thread RNG. A seeded rand generator
+    //(e.g. StdRng::seed_from_u64) makes runs reproducible without changing the quirks
+    pub fn set_rng(&mut self, rng: impl RngCore + Send + 'static) {
+        #[cfg(feature = "std")]
+        let rng = Mutex::new(rng);
+        #[cfg(not(feature = "std"))]
+        let rng = RefCell::new(rng);
+        self.rng = Some(Arc::new(rng));
+    }
+
+    //Back to the thread RNG
+    pub fn clear_rng(&mut self) {
+        self.rng = None;
+    }
+
+    //Every setting at once, checked together, see EmulatorBuilder
+    pub fn builder() -> EmulatorBuilder {
+        EmulatorBuilder::new()
+    }
+
+    pub fn with_rng(rng: impl RngCore + Send + 'static) -> Self {
+        let mut emulator = Self::new();
+        emulator.set_rng(rng);
+        emulator
+    }
+
+    //Strict (teaching) mode reports memory accesses into reserved areas as warnings
+    pub fn set_strict_mode(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    //Check the emulator's invariants after every tick and raise Event::Violation for any that broke, on in
+    //debug builds. Costs a few comparisons a tick
+    pub fn set_invariant_checks(&mut self, enabled: bool) {
+        self.invariant_checks = enabled;
+    }
+
+    //Also raise Event::Lifecycle for clears, draws, halts and the sound timer starting and stopping
+    pub fn set_lifecycle_events(&mut self, enabled: bool) {
+        self.lifecycle_events = enabled;
+    }
+
+    //Move to EmulatorState::Finished when the program ends in a loop it cannot leave. Off by default,
+    //such a program keeps running the loop as on real hardware. The frontends and the conformance tests
+    //turn it on to stop once nothing more can happen
+    pub fn set_end_detection(&mut self, enabled: bool) {
+        self.end_detection = enabled;
+    }
+
+    fn lifecycle(&mut self, event: Lifecycle) {
+        #[cfg(feature = "tracing")]
+        match event {
+            Lifecycle::Halted { error } => tracing::error!(%error, "halted"),
+            Lifecycle::SpriteDrawn { .. } => tracing::trace!(?event, "lifecycle"),
+            _ => tracing::debug!(?event, "lifecycle"),
+        }
+        if event == Lifecycle::ScreenCleared {
+            self.frame_cleared = true;
+        }
+        if self.lifecycle_events {
+            self.events.push(Event::Lifecycle(event));
+        }
+    }
+
+    //Warnings and violations, Lifecycle events go through lifecycle
+    fn raise(&mut self, event: Event) {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(?event, "emulator event");
+        self.events.push(event);
+    }
+
+    fn set_state(&mut self, state: EmulatorState) {
+        #[cfg(feature = "tracing")]
+        if state != self.state {
+            tracing::debug!(from = ?self.state, to = ?state, "state changed");
+        }
+        self.state = state;
+    }
+
+    //Drain the events raised since the last call, frontends should call this every frame
+    pub fn take_events(&mut self) -> Vec<Event> {
+        core::mem::take(&mut self.events)
+    }
+
+    //Explain mode builds a human readable description of every instruction as it executes
+    pub fn set_explain_mode(&mut self, explain: bool) {
+        self.explain = explain;
+        if !explain {
+            self.explanation = None;
+        }
+    }
+
+    //Explanation of the most recently executed instruction (explain mode only)
+    pub fn last_explanation(&self) -> Option<&str> {
+        self.explanation.as_deref()
+    }
+
+    //Only the low nibble counts, as with set_v_register
+    pub fn v_register(&self, register: u8) -> u8 {
+        self.v_registers[register as usize & 0xF]
+    }
+
+    //V0-VF
+    pub fn v_registers(&self) -> &[u8; REGISTERS_SIZE] {
+        &self.v_registers
+    }
+
+    //Everything a register panel shows, copied out in one go
+    pub fn cpu(&self) -> CpuView {
+        CpuView {
+            v_registers: self.v_registers,
+            i_register: self.i_register,
+            program_counter: self.program_counter,
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+            stack_depth: self.stack_pointer as usize,
+        }
+    }
+
+    //Debugger edit, not recorded in last_effects
+    pub fn set_v_register(&mut self, register: u8, value: u8) {
+        self.v_registers[register as usize & 0xF] = value;
+    }
+
+    //Debugger edit
+    pub fn set_i_register(&mut self, value: u16) {
+        self.i_register = value;
+    }
+
+    //Debugger edit, execution continues from address
+    pub fn set_program_counter(&mut self, address: u16) {
+        self.program_counter = address;
+    }
+
+    //Every u16 is a valid address, wrapped round the size of RAM (4 KB like the VIP unless set_memory_map
+    //makes it bigger)
+    pub fn peek(&self, address: u16) -> u8 {
+        self.ram[self.ram_index(address)]
+    }
+
+    //All of RAM, for memory viewers and cheat searches
+    pub fn memory(&self) -> &[u8] {
+        &self.ram[..]
+    }
+
+    //None if range runs past the end of RAM or is backwards
+    pub fn dump_range(&self, range: Range<usize>) -> Option<&[u8]> {
+        self.ram.get(range)
+    }
+
+    //Debugger edit of RAM, not recorded in last_effects and never a strict mode warning
+    pub fn poke(&mut self, address: u16, value: u8) {
+        let index = self.ram_index(address);
+        self.ram[index] = value;
+        if let Some(cache) = &mut self.decode_cache {
+            cache.invalidate(index as u16);
+        }
+    }
+
+    //poke for several bytes from address on, nothing is written if they run past the end of RAM
+    pub fn poke_range(&mut self, address: u16, data: &[u8]) -> bool {
+        let start = address as usize;
+        match self.ram.get_mut(start..start + data.len()) {
+            Some(memory) => {
+                memory.copy_from_slice(data);
+                if let Some(cache) = &mut self.decode_cache {
+                    cache.invalidate_range(address, data.len());
+                }
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn i_register(&self) -> u16 {
+        self.i_register
+    }
+
+    pub fn program_counter(&self) -> u16 {
+        self.program_counter
+    }
+
+    pub fn delay_timer(&self) -> u8 {
+        self.delay_timer
+    }
+
+    pub fn sound_timer(&self) -> u8 {
+        self.sound_timer
+    }
+
+    //Debugger edit, counts down from the next timers()
+    pub fn set_delay_timer(&mut self, value: u8) {
+        self.delay_timer = value;
+    }
+
+    //Return addresses currently on the stack, oldest first
+    pub fn stack(&self) -> &[u16] {
+        &self.stack[..self.stack_pointer as usize]
+    }
+
+    pub(crate) fn stack_top(&self) -> Option<u16> {
+        self.stack_pointer.checked_sub(1).map(|top| self.stack[top as usize])
+    }
+
+    //Subroutine calls in progress, outermost first
+    pub fn call_stack(&self) -> Vec<CallFrame> {
+        self.stack()
+            .iter()
+            .zip(&self.subroutines)
+            .map(|(&return_address, &subroutine)| CallFrame { subroutine, return_address })
+            .collect()
+    }
+
+    //Number of return addresses on the stack, at most 16
+    pub fn stack_depth(&self) -> usize {
+        self.stack_pointer as usize
+    }
+
+    pub(crate) fn read_byte(&self, address: u16) -> u8 {
+        self.ram[self.ram_index(address)]
+    }
+
+    pub(crate) fn is_key_pressed(&self, key: u8) -> bool {
+        self.keys[(key & 0xF) as usize]
+    }
+
+    //Disassemble a range of RAM using the same decoder execute() runs on
+    pub fn disassemble(&self, range: Range<u16>) -> Vec<Line> {
+        let end = (range.end as usize).min(self.ram.len());
+        let start = (range.start as usize).min(end);
+        disassemble_as(&self.ram[start..end], range.start, self.megachip.is_some())
+    }
+
+    //Readable TOML/JSON dump of the whole state (registers, stack, used RAM, screen) for bug reports
+    pub fn export_state(&self, format: Format) -> String {
+        export_state(self, format)
+    }
+
+    //The display as an image file, see screenshot::screenshot
+    pub fn screenshot(&self, format: ImageFormat, palette: &Palette, scale: usize) -> Vec<u8> {
+        screenshot(self, format, palette, scale)
+    }
+
+    //Register and memory writes go through these so they are recorded in the effects
+    fn set_v(&mut self, register: u8, value: u8) {
+        self.v_registers[register as usize] = value;
+        self.effects.record_register(register);
+    }
+
+    fn set_i(&mut self, value: u16) {
+        self.i_register = value;
+        self.i_high = 0;
+        self.effects.i_written = true;
+    }
+
+    fn write_ram(&mut self, address: u16, value: u8) {
+        let address = self.ram_index(address) as u16;
+        self.ram[address as usize] = value;
+        if let Some(cache) = &mut self.decode_cache {
+            cache.invalidate(address);
+        }
+        self.effects.record_memory(address);
+        #[cfg(feature = "heatmap")]
+        self.heatmap.write(address);
+    }
+
+    //Every random byte goes through here so a journal can record and replay them
+    fn random_byte(&mut self) -> u8 {
+        let byte = match self.random_replay.pop_front() {
+            Some(byte) => byte,
+            None => self.generate_random(),
+        };
+        if let Some(log) = self.random_log.as_mut() {
+            log.push(byte);
+        }
+        byte
+    }
+
+    fn generate_random(&mut self) -> u8 {
+        match self.quirks.random {
+            #[cfg(feature = "std")]
+            RandomModel::Entropy => match &self.rng {
+                Some(rng) => rng.lock().unwrap().next_u32() as u8,
+                None => random(),
+            },
+            //No OS random source without std, the Seeded generator stands in until an rng is set
+            #[cfg(not(feature = "std"))]
+            RandomModel::Entropy => match &self.rng {
+                Some(rng) => rng.borrow_mut().next_u32() as u8,
+                None => {
+                    self.random_state = self.random_state.max(1);
+                    self.xorshift()
+                },
+            },
+            RandomModel::Seeded(_) => self.xorshift(),
+            //Low byte is the counter, the next byte the previous result
+            RandomModel::Vip => {
+                let counter = self.random_state as u8;
+                let byte = ((self.random_state >> 8) as u8).wrapping_add(self.ram[counter as usize]);
+                self.random_state = (byte as u64) << 8 | counter.wrapping_add(1) as u64;
+                byte
+            },
+        }
+    }
+
+    //The Seeded model's generator
+    fn xorshift(&mut self) -> u8 {
+        let mut x = self.random_state;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.random_state = x;
+        (x.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 56) as u8
+    }
+
+    fn seed_random(&mut self) {
+        self.random_state = match self.quirks.random {
+            //xorshift gets stuck on 0
+            RandomModel::Seeded(seed) => (seed ^ 0x9E37_79B9_7F4A_7C15).max(1),
+            _ => 0,
+        };
+    }
+
+    pub(crate) fn set_random_logging(&mut self, logging: bool) {
+        self.random_log = if logging { Some(Vec::new()) } else { None };
+    }
+
+    //Random bytes drawn since the last call (while logging)
+    pub(crate) fn take_random_log(&mut self) -> Vec<u8> {
+        self.random_log.as_mut().map(core::mem::take).unwrap_or_default()
+    }
+
+    pub(crate) fn replay_random(&mut self, bytes: &[u8]) {
+        self.random_replay.extend(bytes);
+    }
+
+    //A write to protected memory halts the emulator with Chip8Error::ProtectedWrite before anything is written
+    //Debugger edits (poke, poke_range) are never refused. Kept through reset like the quirks
+    pub fn set_memory_protection(&mut self, protection: MemoryProtection) {
+        self.protection = protection;
+    }
+
+    pub fn memory_protection(&self) -> MemoryProtection {
+        self.protection
+    }
+
+    //The first protected address in [address, address+len), as the fault the write causes
+    fn check_write(&self, pc: u16, address: u16, len: u16) -> Result<(), Chip8Error> {
+        let start = self.memory_map.start_address as usize;
+        let protected = |a: usize| {
+            (self.protection.interpreter && a < start) || (self.protection.rom && a >= start && a < start + self.rom_len)
+        };
+        match (0..len).map(|offset| address.wrapping_add(offset)).find(|&a| protected(self.ram_index(a))) {
+            Some(address) => Err(Chip8Error::ProtectedWrite { pc, address }),
+            None => Ok(()),
+        }
+    }
+
+    //Invariant checks: [address, address+len) running past the end of RAM
+    fn check_wrap(&mut self, pc: u16, address: u16, len: u16) {
+        if self.invariant_checks && self.ram_index(address) + len as usize > self.ram.len() {
+            self.raise(Event::Violation(Violation::MemoryWrap { pc, address, len }));
+        }
+    }
+
+    //Strict mode: warn once per instruction if [address, address+len) overlaps a reserved area
+    fn check_access(&mut self, pc: u16, address: u16, len: u16, access: Access) {
+        self.check_wrap(pc, address, len);
+        #[cfg(feature = "heatmap")]
+        if access != Access::Write {
+            self.heatmap.read(self.ram_index(address) as u16, len);
+        }
+        if !self.strict {
+            return;
+        }
+        //Sprite reads from the built-in font are how FX29 is meant to be used
+        let reserved = (0..len).map(|offset| self.ram_index(address.wrapping_add(offset)) as u16).find(|&a| {
+            (a < self.memory_map.start_address && !(access == Access::Sprite && self.memory_map.in_font(a)))
+                || (RESERVED_HIGH_ADDRESS..CLASSIC_RAM_SIZE as u16).contains(&a)
+        });
+        if let Some(address) = reserved {
+            self.raise(Event::Warning(Warning::ReservedMemoryAccess { pc, address, access }));
+        }
+    }
+
+    //End of frame half of the watchdog: compare this frame's lowest stack depth with the last one
+    fn check_call_balance(&mut self) {
+        let watchdog = &mut self.watchdog;
+        if watchdog.frame_low > watchdog.last_low {
+            watchdog.rises += 1;
+        } else if watchdog.frame_low < watchdog.last_low {
+            watchdog.rises = 0;
+        }
+        watchdog.last_low = watchdog.frame_low;
+        if watchdog.rises >= CALL_IMBALANCE_RISES {
+            watchdog.rises = 0;
+            let depth = watchdog.frame_low as u8;
+            self.raise(Event::Warning(Warning::CallImbalance { depth }));
+        }
+        self.watchdog.frame_low = self.stack_depth();
+    }
+
+    //Skip the next instruction, which is 4 bytes long if it is XO-CHIP's F000 NNNN (or MegaChip's 01NN NNNN)
+    fn skip(&mut self) {
+        let next = self.read_word(self.program_counter);
+        let long = is_long(next, self.megachip.is_some());
+        self.program_counter = self.program_counter.wrapping_add(if long { 4 } else { 2 });
+    }
+
+    //decode_long, or decode_chip8x on CHIP-8X, with 01NN as long as skip takes it
+    fn decode(&self, opcode: u16, next: u16) -> Result<Instruction, DecodeError> {
+        let megachip = self.megachip.is_some();
+        if self.chip8x { decode_chip8x(opcode, next, megachip) } else { decode_long(opcode, next, megachip) }
+    }
+
+    fn read_word(&self, address: u16) -> u16 {
+        ((self.read_byte(address) as u16) << 8) | self.read_byte(address.wrapping_add(1)) as u16
+    }
+
+    //Logic quirk: the VIP's 8XY1/8XY2/8XY3 leave VF cleared
+    fn logic_vf_reset(&mut self) {
+        if self.quirks.vf_reset {
+            self.set_v(0xF, 0);
+        }
+    }
+
+    //The cheats are moved out while they write, as they write through poke
+    fn apply_cheats(&mut self, apply: impl FnOnce(&mut Cheats, &mut Self)) {
+        if self.cheats.is_empty() {
+            return;
+        }
+        let mut cheats = core::mem::take(&mut self.cheats);
+        apply(&mut cheats, self);
+        self.cheats = cheats;
+    }
+
+    //Timers
+    //Modified once every frame, left alone while paused
+    pub fn timers(&mut self) {
+        self.frame_ticks = 0;
+        self.key_edges.stale = true;
+        self.vblank_wait = false;
+        //Only an overrun carries over, time left unused is gone
+        self.cycle_credit = self.cycle_credit.min(0) + VIP_FREE_CYCLES as i64;
+        if self.paused {
+            return;
+        }
+        self.metrics.frames += 1;
+        self.apply_cheats(Cheats::apply_frame);
+        self.check_call_balance();
+        if self.delay_timer > 0 {
+            self.delay_timer -= 1;
+        }
+        if self.sound_timer > 0 {
+            //The frontend beeps while it is non zero, see audio::Beeper
+            self.set_sound_timer(self.sound_timer - 1);
+        }
+    }
+
+    //timers() as many times as 60 Hz fit in the time since the last call, for frontends running tick()
+    //themselves at whatever rate the host draws. What is left over carries to the next call, so the timers
+    //keep time over a 144 Hz or stuttering display instead of drifting with the frame rate
+    //Not together with run_frame, which calls timers() itself. Returns the number of timer updates
+    pub fn update_timers(&mut self, elapsed: Duration) -> u32 {
+        self.timer_time += elapsed;
+        let mut updates = 0;
+        while self.timer_time >= TIMER_PERIOD {
+            self.timer_time -= TIMER_PERIOD;
+            self.timers();
+            updates += 1;
+        }
+        updates
+    }
+
+    //CPU Execution per cycle (tick)
+    //1. Fetch instruction from RAM at memory address loaded into program counter
+    //2. Decode this instruction
+    //3. Execute
+    //4. Move program counter to next instruction
+    //Returns straight away while FX0A is waiting for a key, timers keep running meanwhile, and while paused
+    //A fault halts the emulator with the program counter left on the faulting instruction
+    pub fn tick(&mut self) -> Result<(), Chip8Error> {
+        while let Some(event) = self.input_playback.front().copied().filter(|event| event.tick <= self.tick_count) {
+            self.input_playback.pop_front();
+            self.keypress(event.key, event.pressed);
+        }
+        self.key_edges.begin();
+        self.tick_count += 1;
+        self.frame_ticks += 1;
+        if self.timing == Timing::Vip {
+            self.cycle_credit -= self.next_cycles() as i64;
+        }
+        if let EmulatorState::Halted { error } = self.state {
+            return Err(error);
+        }
+        if self.exited || self.paused || self.state != EmulatorState::Running {
+            return Ok(());
+        }
+        let pc = self.program_counter;
+        let result = self.step();
+        if let Err(error) = result {
+            self.set_state(EmulatorState::Halted { error });
+            self.lifecycle(Lifecycle::Halted { error });
+        } else if self.invariant_checks {
+            self.check_invariants(pc);
+        }
+        result
+    }
+
+    //After the instruction at pc ran, for set_invariant_checks
+    fn check_invariants(&mut self, pc: u16) {
+        let target = self.program_counter;
+        if !target.is_multiple_of(2) && pc.is_multiple_of(2) {
+            self.raise(Event::Violation(Violation::UnalignedPc { pc, target }));
+        }
+        if target as usize >= self.ram.len() - 1 {
+            self.raise(Event::Violation(Violation::PcPastMemory { pc, target }));
+        }
+        if self.stack_pointer as usize > STACK_SIZE {
+            self.raise(Event::Violation(Violation::StackPointer { pc, depth: self.stack_pointer }));
+        }
+    }
+
+    //tick for fuzzing and ROMs from anywhere: a panic inside the emulator, a bug no ROM should be able to
+    //cause, halts it with Chip8Error::Internal instead of unwinding into the caller
+    //Nothing is caught in builds with panic = "abort", and libFuzzer aborts on the panic before it gets here
+    #[cfg(feature = "std")]
+    pub fn tick_checked(&mut self) -> Result<(), Chip8Error> {
+        let pc = self.program_counter;
+        match panic::catch_unwind(AssertUnwindSafe(|| self.tick())) {
+            Ok(result) => result,
+            Err(_) => {
+                let error = Chip8Error::Internal { pc };
+                self.set_state(EmulatorState::Halted { error });
+                self.lifecycle(Lifecycle::Halted { error });
+                Err(error)
+            },
+        }
+    }
+
+    fn step(&mut self) -> Result<(), Chip8Error> {
+        let pc = self.program_counter;
+        self.pc_history.push(pc);
+        //Where pc lands in RAM, for everything kept by address
+        let index = self.ram_index(pc) as u16;
+        let (opcode, decoded) = match self.decode_cache.as_ref().and_then(|cache| cache.get(index)) {
+            Some(decoded) => {
+                self.program_counter = pc.wrapping_add(2);
+                (decoded.opcode, Ok(decoded))
+            },
+            None => {
+                let opcode = self.fetch();
+                let next = self.read_word(self.program_counter);
+                let decoded = self.decode(opcode, next).map(|instruction| Decoded {
+                    opcode,
+                    instruction,
+                    operands: Operands::new(opcode, next),
+                    handler: Self::handler(instruction),
+                    extension: instruction.is_megachip() || instruction.is_chip8x(),
+                });
+                if let (Some(cache), Ok(decoded)) = (&mut self.decode_cache, decoded) {
+                    cache.insert(index, decoded);
+                }
+                (opcode, decoded)
+            },
+        };
+        if let Some(hook) = &mut self.trace_hook.0 {
+            hook(pc, opcode);
+        }
+        #[cfg(feature = "tracing")]
+        if let Ok(Decoded { instruction, .. }) = decoded {
+            tracing::trace!(pc, opcode, %instruction, "instruction");
+        }
+        match decoded {
+            Ok(decoded) => {
+                let instruction = decoded.instruction;
+                self.coverage[index as usize / 64] |= 1 << (index % 64);
+                #[cfg(feature = "heatmap")]
+                self.heatmap.execute(index);
+                //The operand word of F000 NNNN and 01NN NNNN
+                self.program_counter = self.program_counter.wrapping_add(instruction.size() - 2);
+                if self.explain {
+                    self.explanation = Some(explain(instruction, self));
+                }
+                let draw = matches!(instruction, Instruction::Draw { .. });
+                let timed = draw || self.profile.as_ref().is_some_and(|profile| profile.timed);
+                let start = timed.then(|| self.clock.now());
+                let result = self.execute(decoded).map(|_| ());
+                if result.is_ok() && self.end_detection {
+                    self.check_end(pc, instruction);
+                }
+                let elapsed = start.map(|start| self.clock.now().saturating_sub(start));
+                self.metrics.instructions += 1;
+                if draw {
+                    self.metrics.draws += 1;
+                    self.metrics.draw_time += elapsed.unwrap_or_default();
+                }
+                if let Some(profile) = &mut self.profile {
+                    profile.record(instruction.pattern(), elapsed.filter(|_| profile.timed));
+                }
+                result
+            },
+            Err(err) => {
+                self.program_counter = pc;
+                Err(Chip8Error::UnknownOpcode { pc, opcode: err.opcode })
+            },
+        }
+    }
+
+    //After instruction at pc ran: finish on a jump to itself, or on a loop coming round unchanged
+    //Only 1NNN and BNNN count as loops, calls and returns come back by themselves
+    fn check_end(&mut self, pc: u16, instruction: Instruction) {
+        let reads_input = matches!(
+            instruction,
+            Instruction::Random { .. }
+                | Instruction::LoadDelay { .. }
+                | Instruction::SkipKeyPressed { .. }
+                | Instruction::SkipKeyNotPressed { .. }
+                | Instruction::WaitKey { .. }
+        );
+        if reads_input || self.effects.memory_written.is_some() || self.effects.screen_written {
+            self.loop_check.quiet = false;
+        }
+        if !matches!(instruction, Instruction::Jump(_) | Instruction::JumpV0(_)) || self.program_counter > pc {
+            return;
+        }
+        let head = self.program_counter;
+        let reason = if head == pc {
+            EndReason::JumpToSelf
+        } else {
+            let check = self.loop_check;
+            let same = check.v_registers == self.v_registers && check.i_register == self.i_register && check.stack_pointer == self.stack_pointer;
+            if check.head != Some(head) || !check.quiet || !same {
+                self.loop_check = LoopCheck {
+                    head: Some(head),
+                    v_registers: self.v_registers,
+                    i_register: self.i_register,
+                    stack_pointer: self.stack_pointer,
+                    quiet: true,
+                };
+                return;
+            }
+            EndReason::EndlessLoop
+        };
+        self.set_state(EmulatorState::Finished { pc: head, reason });
+        self.lifecycle(Lifecycle::Finished { pc: head, reason });
+    }
+
+    //Whether an instruction starting at address has run since the last reset (or clear_coverage)
+    pub fn was_executed(&self, address: u16) -> bool {
+        self.coverage[address as usize / 64] & (1 << (address % 64)) != 0
+    }
+
+    //Start addresses of every instruction run so far, ascending. Useful for finding dead code
+    pub fn executed_addresses(&self) -> Vec<u16> {
+        (0..=u16::MAX).filter(|&address| self.was_executed(address)).collect()
+    }
+
+    //The same as a bitmap: bit n of word n / 64 is address n
+    pub fn coverage_bitmap(&self) -> &[u64] {
+        &self.coverage[..]
+    }
+
+    pub fn clear_coverage(&mut self) {
+        self.coverage.fill(0);
+    }
+
+    //Reads, writes and executions per address since the last reset (or clear_heatmap)
+    #[cfg(feature = "heatmap")]
+    pub fn heatmap(&self) -> &Heatmap {
+        &self.heatmap
+    }
+
+    #[cfg(feature = "heatmap")]
+    pub fn clear_heatmap(&mut self) {
+        self.heatmap.clear();
+    }
+
+    //Count executions per opcode family (and time them, if timed) until stop_profiling
+    //Starting again throws away the counts so far
+    pub fn start_profiling(&mut self, timed: bool) {
+        self.profile = Some(Profile::new(timed));
+    }
+
+    //Counts so far, None when not profiling
+    pub fn profile_report(&self) -> Option<ProfileReport> {
+        self.profile.as_ref().map(Profile::report)
+    }
+
+    pub fn stop_profiling(&mut self) -> Option<ProfileReport> {
+        self.profile.take().map(|profile| profile.report())
+    }
+
+    //Totals since the emulator was made (or reset_metrics), a reset or new ROM does not clear them
+    pub fn metrics(&self) -> Metrics {
+        self.metrics
+    }
+
+    pub fn reset_metrics(&mut self) {
+        self.metrics = Metrics::default();
+    }
+
+    //Called with the PC and opcode of every fetched instruction, for execution traces and visualizers
+    pub fn set_trace_hook(&mut self, hook: impl FnMut(u16, u16) + Send + 'static) {
+        self.trace_hook = TraceHook(Some(Box::new(hook)));
+    }
+
+    pub fn clear_trace_hook(&mut self) {
+        self.trace_hook = TraceHook::default();
+    }
+
+    pub fn set_audio_sink(&mut self, sink: impl AudioSink + 'static) {
+        self.audio_sink = AudioSinkHook(Some(Box::new(sink)));
+    }
+
+    pub fn clear_audio_sink(&mut self) {
+        self.audio_sink = AudioSinkHook::default();
+    }
+
+    //Drawn to at the end of every run_frame that changed the screen, which also takes the dirty rows
+    pub fn set_display(&mut self, display: impl Display + Send + 'static) {
+        self.display = DisplayHook(Some(Box::new(display)));
+    }
+
+    pub fn clear_display(&mut self) {
+        self.display = DisplayHook::default();
+    }
+
+    //The screen as it is now, as a Display is handed it
+    pub fn frame(&self) -> Frame<'_> {
+        Frame {
+            screen: &self.screen,
+            megachip: self.megachip.as_deref(),
+            dirty_rows: self.screen.dirty_rows(),
+            sound: self.sound_timer > 0,
+        }
+    }
+
+    //run_frame drawing to display rather than the one set with set_display
+    pub fn run_frame_to(&mut self, display: &mut dyn Display) -> Result<FrameOutput, Chip8Error> {
+        let mut set = self.display.0.take();
+        let output = self.run_frame();
+        self.display.0 = set.take();
+        if let Ok(output) = output {
+            self.present(display, output);
+        }
+        output
+    }
+
+    //Hand the frame to a display if it changed, see set_display
+    fn present(&mut self, display: &mut dyn Display, output: FrameOutput) {
+        if core::mem::take(&mut self.frame_cleared) {
+            display.clear();
+        }
+        if output.screen_changed {
+            display.draw_frame(&self.frame());
+            self.screen.take_dirty_rows();
+        }
+    }
+
+    //Every write to the sound timer goes through here so the audio sink hears about starts and stops
+    //A debugger edit too, so setting it from outside starts and stops the sound the same way
+    pub fn set_sound_timer(&mut self, value: u8) {
+        let was_running = self.sound_timer > 0;
+        self.sound_timer = value;
+        match (was_running, value > 0) {
+            (false, true) => {
+                if let Some(sink) = &mut self.audio_sink.0 {
+                    sink.sound_start();
+                }
+                self.lifecycle(Lifecycle::SoundStarted);
+            },
+            (true, false) => {
+                if let Some(sink) = &mut self.audio_sink.0 {
+                    sink.sound_stop();
+                }
+                self.lifecycle(Lifecycle::SoundStopped);
+            },
+            _ => {},
+        }
+    }
+
+    //Clock for warm_up deadlines, timed profiling and the gdb server's pacing, the wall clock by default
+    pub fn set_time_source(&mut self, clock: impl TimeSource + 'static) {
+        self.clock = Arc::new(clock);
+    }
+
+    pub fn time_source(&self) -> &dyn TimeSource {
+        &*self.clock
+    }
+
+    //Breakpoints stop debug_step and debug_run, tick and run_frame ignore them
+    pub fn breakpoints(&self) -> &Breakpoints {
+        &self.breakpoints
+    }
+
+    pub fn set_breakpoint(&mut self, pc: u16) {
+        self.breakpoints.pcs.insert(pc);
+    }
+
+    pub fn clear_breakpoint(&mut self, pc: u16) {
+        self.breakpoints.pcs.remove(&pc);
+    }
+
+    //Stop after any instruction that writes address
+    pub fn set_watchpoint(&mut self, address: u16) {
+        self.breakpoints.watchpoints.insert(address);
+    }
+
+    pub fn clear_watchpoint(&mut self, address: u16) {
+        self.breakpoints.watchpoints.remove(&address);
+    }
+
+    //Stop after an instruction that makes V[register] equal to value
+    pub fn set_condition(&mut self, register: u8, value: u8) {
+        self.breakpoints.conditions.insert((register & 0xF, value));
+    }
+
+    pub fn clear_condition(&mut self, register: u8, value: u8) {
+        self.breakpoints.conditions.remove(&(register & 0xF, value));
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints = Breakpoints::default();
+    }
+
+    //tick() that reports breakpoints. A PC breakpoint stops before its instruction, calling again runs it
+    pub fn debug_step(&mut self) -> Result<Option<Break>, Chip8Error> {
+        let pc = self.program_counter;
+        let runs = !self.exited && !self.paused && self.state == EmulatorState::Running;
+        if runs && self.breakpoints.pcs.contains(&pc) && self.breakpoints.stopped_at != Some(pc) {
+            self.breakpoints.stopped_at = Some(pc);
+            return Ok(Some(Break::Breakpoint { pc }));
+        }
+        self.breakpoints.stopped_at = None;
+        let registers = self.v_registers;
+        self.tick()?;
+        if !runs {
+            return Ok(None);
+        }
+
+        let watched = self.breakpoints.watchpoints.iter().find(|&&address| self.effects.wrote_memory(address));
+        if let Some(&address) = watched {
+            return Ok(Some(Break::Watchpoint { pc, address, value: self.read_byte(address) }));
+        }
+        let condition = self.breakpoints.conditions.iter().find(|&&(register, value)| {
+            registers[register as usize] != value && self.v_registers[register as usize] == value
+        });
+        Ok(condition.map(|&(register, value)| Break::Condition { pc, register, value }))
+    }
+
+    //Run exactly one instruction, even one with a breakpoint on it
+    pub fn step_into(&mut self) -> Result<Option<Break>, Chip8Error> {
+        self.breakpoints.stopped_at = Some(self.program_counter);
+        self.debug_step()
+    }
+
+    //step_into, except a 2NNN runs until its subroutine returns
+    //Like the other stepping functions it stops early at a break or after max_ticks
+    pub fn step_over(&mut self, max_ticks: usize) -> Result<Option<Break>, Chip8Error> {
+        let pc = self.program_counter;
+        match self.decode(self.read_word(pc), self.read_word(pc.wrapping_add(2))) {
+            Ok(Instruction::Call(_)) => self.run_to_depth(self.stack_depth(), max_ticks),
+            _ => self.step_into(),
+        }
+    }
+
+    //Run until the current subroutine's 00EE has executed, nothing happens outside a subroutine
+    pub fn step_out(&mut self, max_ticks: usize) -> Result<Option<Break>, Chip8Error> {
+        match self.stack_depth().checked_sub(1) {
+            Some(depth) => self.run_to_depth(depth, max_ticks),
+            None => Ok(None),
+        }
+    }
+
+    fn run_to_depth(&mut self, depth: usize, max_ticks: usize) -> Result<Option<Break>, Chip8Error> {
+        for tick in 0..max_ticks {
+            let hit = if tick == 0 { self.step_into()? } else { self.debug_step()? };
+            if hit.is_some() {
+                return Ok(hit);
+            }
+            if self.stack_depth() <= depth {
+                break;
+            }
+        }
+        Ok(None)
+    }
+
+    //Run until the next DXYN, stopping before it. Frames of ticks_per_frame instructions keep the timers going
+    pub fn run_to_draw(&mut self, ticks_per_frame: usize, max_frames: usize) -> Result<Option<Break>, Chip8Error> {
+        self.run_frames_until(ticks_per_frame, max_frames, |instruction| matches!(instruction, Instruction::Draw { .. }))
+    }
+
+    //Run until the next 00E0, stopping before it
+    pub fn run_to_clear(&mut self, ticks_per_frame: usize, max_frames: usize) -> Result<Option<Break>, Chip8Error> {
+        self.run_frames_until(ticks_per_frame, max_frames, |instruction| instruction == Instruction::ClearScreen)
+    }
+
+    //Finish the current frame, including its timer update
+    pub fn run_to_frame_end(&mut self, ticks_per_frame: usize) -> Result<Option<Break>, Chip8Error> {
+        self.run_frames_until(ticks_per_frame, 1, |_| false)
+    }
+
+    fn run_frames_until(&mut self, ticks_per_frame: usize, max_frames: usize, target: impl Fn(Instruction) -> bool) -> Result<Option<Break>, Chip8Error> {
+        let mut first = true;
+        for _ in 0..max_frames {
+            while self.frame_ticks < ticks_per_frame {
+                let pc = self.program_counter;
+                let next = self.decode(self.read_word(pc), self.read_word(pc.wrapping_add(2)));
+                //The instruction under the PC when starting does not count, it is stepped over
+                if !first && self.state == EmulatorState::Running && next.is_ok_and(&target) {
+                    return Ok(Some(Break::Target { pc }));
+                }
+                let hit = if first { self.step_into()? } else { self.debug_step()? };
+                first = false;
+                if hit.is_some() {
+                    return Ok(hit);
+                }
+            }
+            self.timers();
+        }
+        Ok(None)
+    }
+
+    //debug_step up to max_ticks times, stopping at the first break. Timers are left alone
+    pub fn debug_run(&mut self, max_ticks: usize) -> Result<Option<Break>, Chip8Error> {
+        for _ in 0..max_ticks {
+            if let Some(hit) = self.debug_step()? {
+                return Ok(Some(hit));
+            }
+        }
+        Ok(None)
+    }
+
+    //One 60 Hz frame: ticks_per_frame() instructions, then a timer update
+    pub fn run_frame(&mut self) -> Result<FrameOutput, Chip8Error> {
+        self.run_frame_with(self.ticks_per_frame)
+    }
+
+    //run_frame with a one-off instruction count, e.g. a replay recorded at another speed
+    //A fault stops the frame before the timer update
+    pub fn run_frame_with(&mut self, ticks: usize) -> Result<FrameOutput, Chip8Error> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("frame", frame = self.metrics.frames, ticks).entered();
+        let start = self.clock.now();
+        let output = self.run_ticks(ticks);
+        if let (Ok(output), Some(mut display)) = (output, self.display.0.take()) {
+            self.present(display.as_mut(), output);
+            self.display.0 = Some(display);
+        }
+        let elapsed = self.clock.now().saturating_sub(start);
+        self.metrics.frame_time += elapsed;
+        self.metrics.last_frame_time = elapsed;
+        output
+    }
+
+    fn run_ticks(&mut self, ticks: usize) -> Result<FrameOutput, Chip8Error> {
+        self.poll_input();
+        let revision = self.screen.revision();
+        match self.timing {
+            Timing::Instructions => {
+                for _ in 0..ticks {
+                    self.tick()?;
+                    if self.vblank_wait {
+                        break;
+                    }
+                }
+            },
+            Timing::Vip => {
+                while !self.frame_over() {
+                    self.tick()?;
+                }
+            },
+        }
+        let output = FrameOutput {
+            screen_changed: self.screen.revision() != revision,
+            sound: self.sound_timer > 0,
+        };
+        self.timers();
+        Ok(output)
+    }
+
+    //Tick until predicate holds, checked before every instruction, for at most max_cycles instructions
+    //The timers update every ticks_per_frame() instructions as with run_frame. Returns the instructions
+    //run once predicate held, None if max_cycles ran out first
+    pub fn run_until(&mut self, max_cycles: usize, predicate: impl FnMut(&Emulator) -> bool) -> Result<Option<usize>, Chip8Error> {
+        let start = self.clock.now();
+        let result = self.tick_until(max_cycles, predicate);
+        self.metrics.frame_time += self.clock.now().saturating_sub(start);
+        result
+    }
+
+    fn tick_until(&mut self, max_cycles: usize, mut predicate: impl FnMut(&Emulator) -> bool) -> Result<Option<usize>, Chip8Error> {
+        for cycles in 0..max_cycles {
+            if predicate(self) {
+                return Ok(Some(cycles));
+            }
+            //End of a frame, the next one starts with fresh input as in run_frame
+            if self.frame_over() {
+                self.timers();
+                self.poll_input();
+            }
+            self.tick()?;
+        }
+        Ok(predicate(self).then_some(max_cycles))
+    }
+
+    //Whether this frame's instructions (or cycles) are used up, or a draw ended it under display_wait
+    fn frame_over(&self) -> bool {
+        let used_up = match self.timing {
+            Timing::Instructions => self.frame_ticks >= self.ticks_per_frame,
+            Timing::Vip => self.cycle_credit <= 0,
+        };
+        used_up || self.vblank_wait
+    }
+
+    //What the next tick costs under Timing::Vip
+    fn next_cycles(&self) -> u32 {
+        if self.exited || self.paused || self.state != EmulatorState::Running {
+            return vip_idle_cycles();
+        }
+        match self.decode(self.read_word(self.program_counter), self.read_word(self.program_counter.wrapping_add(2))) {
+            Ok(instruction) => vip_cycles(&instruction),
+            Err(_) => vip_idle_cycles(),
+        }
+    }
+
+    pub fn timing(&self) -> Timing {
+        self.timing
+    }
+
+    //Timing::Vip makes run_frame run a budget of VIP machine cycles and ignore its instruction count
+    //run_until ends its frames on the same budget
+    pub fn set_timing(&mut self, timing: Timing) {
+        self.timing = timing;
+        self.cycle_credit = VIP_FREE_CYCLES as i64;
+    }
+
+    //Instructions per frame for run_frame, TICKS_PER_FRAME unless changed
+    pub fn ticks_per_frame(&self) -> usize {
+        self.ticks_per_frame
+    }
+
+    pub fn set_ticks_per_frame(&mut self, ticks: usize) {
+        self.ticks_per_frame = ticks;
+    }
+
+    //Instructions are held in 16 bytes (HEX)
+    //RAM is 8 bytes, therefore each instruction is held side by side
+    fn fetch(&mut self) -> u16 {
+        let instruction = self.read_word(self.program_counter);
+        self.program_counter = self.program_counter.wrapping_add(2);
+        instruction
+    }
+
+    //The handler of each instruction, picked once when it is decoded and kept with it in the decode cache
+    //An extension adds its instructions here with a handler each, the match leaves none without one
+    fn handler(instruction: Instruction) -> Handler {
+        match instruction {
+            Instruction::Nop => |_, _, _| Ok(()),
+            Instruction::ClearScreen => Self::execute_clear_screen,
+            Instruction::Return => Self::execute_return,
+            Instruction::MegaOff => Self::execute_mega_off,
+            Instruction::MegaOn => Self::execute_mega_on,
+            Instruction::LoadIMega(_) => Self::execute_load_i_mega,
+            Instruction::LoadPalette(_) => Self::execute_load_palette,
+            Instruction::SpriteWidth(_) => Self::execute_sprite_width,
+            Instruction::SpriteHeight(_) => Self::execute_sprite_height,
+            Instruction::ScreenAlpha(_) => Self::execute_screen_alpha,
+            Instruction::PlaySample(_) => Self::execute_play_sample,
+            Instruction::StopSample => Self::execute_stop_sample,
+            Instruction::Blend(_) => Self::execute_blend,
+            Instruction::CollisionColor(_) => Self::execute_collision_color,
+            Instruction::ScrollUp(_) => Self::execute_scroll_up,
+            Instruction::CycleBackground => Self::execute_cycle_background,
+            Instruction::ScrollDown(_) => Self::execute_scroll_down,
+            Instruction::ScrollRight => Self::execute_scroll_right,
+            Instruction::ScrollLeft => Self::execute_scroll_left,
+            Instruction::Exit => Self::execute_exit,
+            Instruction::LowRes => Self::execute_low_res,
+            Instruction::HighRes => Self::execute_high_res,
+            Instruction::Jump(_) => Self::execute_jump,
+            Instruction::Call(_) => Self::execute_call,
+            Instruction::SkipEqImm { .. } => Self::execute_skip_eq_imm,
+            Instruction::SkipNeImm { .. } => Self::execute_skip_ne_imm,
+            Instruction::SkipEqReg { .. } => Self::execute_skip_eq_reg,
+            Instruction::AddNibbles { .. } => Self::execute_add_nibbles,
+            Instruction::SaveRange { .. } => Self::execute_save_range,
+            Instruction::LoadRange { .. } => Self::execute_load_range,
+            Instruction::LoadImm { .. } => Self::execute_load_imm,
+            Instruction::AddImm { .. } => Self::execute_add_imm,
+            Instruction::LoadReg { .. } => Self::execute_load_reg,
+            Instruction::Or { .. } => Self::execute_or,
+            Instruction::And { .. } => Self::execute_and,
+            Instruction::Xor { .. } => Self::execute_xor,
+            Instruction::AddReg { .. } => Self::execute_add_reg,
+            Instruction::SubReg { .. } => Self::execute_sub_reg,
+            Instruction::ShiftRight { .. } => Self::execute_shift_right,
+            Instruction::SubN { .. } => Self::execute_sub_n,
+            Instruction::ShiftLeft { .. } => Self::execute_shift_left,
+            Instruction::SkipNeReg { .. } => Self::execute_skip_ne_reg,
+            Instruction::LoadI(_) => Self::execute_load_i,
+            Instruction::JumpV0(_) => Self::execute_jump_v0,
+            Instruction::Foreground { .. } => Self::execute_foreground,
+            Instruction::Random { .. } => Self::execute_random,
+            Instruction::Draw { .. } => Self::execute_draw,
+            Instruction::SkipKeyPressed { .. } => Self::execute_skip_key_pressed,
+            Instruction::SkipKeyNotPressed { .. } => Self::execute_skip_key_not_pressed,
+            Instruction::LoadILong(_) => Self::execute_load_i_long,
+            Instruction::Plane(_) => Self::execute_plane,
+            Instruction::LoadAudio => Self::execute_load_audio,
+            Instruction::LoadDelay { .. } => Self::execute_load_delay,
+            Instruction::WaitKey { .. } => Self::execute_wait_key,
+            Instruction::SetDelay { .. } => Self::execute_set_delay,
+            Instruction::SetSound { .. } => Self::execute_set_sound,
+            Instruction::AddI { .. } => Self::execute_add_i,
+            Instruction::LoadFont { .. } => Self::execute_load_font,
+            Instruction::LoadBigFont { .. } => Self::execute_load_big_font,
+            Instruction::StoreBcd { .. } => Self::execute_store_bcd,
+            Instruction::SetPitch { .. } => Self::execute_set_pitch,
+            Instruction::StoreRegs { .. } => Self::execute_store_regs,
+            Instruction::LoadRegs { .. } => Self::execute_load_regs,
+            Instruction::SaveFlags { .. } => Self::execute_save_flags,
+            Instruction::LoadFlags { .. } => Self::execute_load_flags,
+        }
+    }
+
+    //Execute the instruction from decode with its handler
+    //Returns what the instruction changed, branch_taken is any PC other than the next instruction
+    //Faults are detected before anything is changed, apart from the program counter being put back
+    fn execute(&mut self, decoded: Decoded) -> Result<Effects, Chip8Error> {
+        let instruction = decoded.instruction;
+        self.effects = Effects::default();
+        let next_instruction = self.program_counter;
+        let pc = next_instruction.wrapping_sub(instruction.size());
+        let fault = |emulator: &mut Self, error: Chip8Error| {
+            emulator.program_counter = pc;
+            Err(error)
+        };
+        if decoded.extension && !self.extension_available(instruction) {
+            return fault(self, Chip8Error::UnknownOpcode { pc, opcode: instruction.encode() });
+        }
+
+        //Handlers return their faults, execute puts the program counter back
+        if let Err(error) = (decoded.handler)(self, decoded.operands, pc) {
+            return fault(self, error);
+        }
+
+        if self.program_counter != next_instruction {
+            self.effects.branch_taken = Some(self.program_counter);
+        }
+        Ok(self.effects)
+    }
+
+    //MegaChip instructions are unknown opcodes outside MegaChip mode, and 0011 without support for it
+    //CHIP-8X ones are outside CHIP-8X, apart from 02A0 which MegaChip mode has its own use for
+    fn extension_available(&self, instruction: Instruction) -> bool {
+        let mega_available = if instruction == Instruction::MegaOn { self.megachip_support } else { self.megachip.is_some() };
+        let chip8x_available = self.chip8x || (instruction == Instruction::CycleBackground && self.megachip.is_some());
+        let two_page_clear = instruction == Instruction::LoadPalette(0x30) && self.screen.is_two_page() && self.megachip.is_none();
+        (!instruction.is_megachip() || mega_available || two_page_clear) && (!instruction.is_chip8x() || chip8x_available)
+    }
+
+    //00E0:Clear screen
+    //XO-CHIP: only the selected planes are cleared
+    //MegaChip: Show the back buffer, then clear it
+    fn execute_clear_screen(&mut self, _: Operands, _: u16) -> Result<(), Chip8Error> {
+        match &mut self.megachip {
+            Some(mega) => {
+                mega.present();
+                self.screen.touch();
+            },
+            None => self.screen.clear(self.planes),
+        }
+        self.effects.screen_written = true;
+        self.lifecycle(Lifecycle::ScreenCleared);
+        Ok(())
+    }
+
+    //OOEE: Return from subroutine
+    fn execute_return(&mut self, _: Operands, pc: u16) -> Result<(), Chip8Error> {
+        let return_address = self.pop().ok_or(Chip8Error::StackUnderflow { pc })?;
+        self.program_counter = return_address;
+        let depth = self.stack_depth();
+        self.watchdog.frame_low = self.watchdog.frame_low.min(depth);
+        if depth < STACK_SIZE / 2 {
+            self.watchdog.nearly_full_warned = false;
+        }
+        Ok(())
+    }
+
+    //0010: Leave MegaChip mode, back to a blank 64x32 screen (MegaChip)
+    //01NN decodes differently in and out of MegaChip mode, so both switches drop the decode cache
+    fn execute_mega_off(&mut self, _: Operands, _: u16) -> Result<(), Chip8Error> {
+        self.megachip = None;
+        self.clear_decode_cache();
+        self.screen.set_hires(false);
+        self.effects.screen_written = true;
+        self.lifecycle(Lifecycle::ScreenCleared);
+        Ok(())
+    }
+
+    //0011: Switch to the 256x192 colour screen, the classic one is cleared (MegaChip)
+    fn execute_mega_on(&mut self, _: Operands, _: u16) -> Result<(), Chip8Error> {
+        self.megachip = Some(Box::new(MegaChip::new()));
+        self.clear_decode_cache();
+        self.screen.set_hires(false);
+        self.effects.screen_written = true;
+        self.lifecycle(Lifecycle::ScreenCleared);
+        Ok(())
+    }
+
+    //01NN NNNN: Set Iregister to a 24 bit address (MegaChip)
+    fn execute_load_i_mega(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        self.set_i(op.long as u16);
+        self.i_high = (op.long >> 16) as u8;
+        Ok(())
+    }
+
+    //02NN: Load NN colours from Iregister, 4 bytes each (MegaChip)
+    //0230 outside MegaChip mode: Clear the 64x64 screen, the extension's own clear. 00E0 clears all of it too
+    //(two page hires)
+    fn execute_load_palette(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        if op.nn == 0x30 && self.screen.is_two_page() && self.megachip.is_none() {
+            self.screen.clear(ALL_PLANES);
+            self.effects.screen_written = true;
+            self.lifecycle(Lifecycle::ScreenCleared);
+            return Ok(());
+        }
+        let colors = self.mega_bytes(op.nn as usize * 4);
+        if let Some(mega) = &mut self.megachip {
+            mega.load_palette(&colors);
+        }
+        Ok(())
+    }
+
+    //03NN/04NN: Set the sprite size (MegaChip)
+    fn execute_sprite_width(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        if let Some(mega) = &mut self.megachip {
+            mega.set_sprite_width(op.nn);
+        }
+        Ok(())
+    }
+
+    fn execute_sprite_height(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        if let Some(mega) = &mut self.megachip {
+            mega.set_sprite_height(op.nn);
+        }
+        Ok(())
+    }
+
+    //05NN: Set the screen alpha (MegaChip)
+    fn execute_screen_alpha(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        if let Some(mega) = &mut self.megachip {
+            mega.set_alpha(op.nn);
+        }
+        Ok(())
+    }
+
+    //060N: Play the digitised sound at Iregister (MegaChip)
+    //6 byte header: sample rate (2 bytes), length (3 bytes), one unused byte, then the samples
+    fn execute_play_sample(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        let header = self.mega_bytes(6);
+        let rate = u16::from_be_bytes([header[0], header[1]]);
+        let len = u32::from_be_bytes([0, header[2], header[3], header[4]]) as usize;
+        let data = self.mega_bytes(6 + len).split_off(6);
+        if let Some(mega) = &mut self.megachip {
+            mega.play(Some(Sample { rate, data, looping: op.n == 0 }));
+        }
+        Ok(())
+    }
+
+    //0700: Stop the digitised sound (MegaChip)
+    fn execute_stop_sample(&mut self, _: Operands, _: u16) -> Result<(), Chip8Error> {
+        if let Some(mega) = &mut self.megachip {
+            mega.play(None);
+        }
+        Ok(())
+    }
+
+    //080N: Set the sprite blend mode (MegaChip)
+    fn execute_blend(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        if let Some(mega) = &mut self.megachip {
+            mega.set_blend_mode(BlendMode::from_n(op.n));
+        }
+        Ok(())
+    }
+
+    //09NN: Set the collision colour (MegaChip)
+    fn execute_collision_color(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        if let Some(mega) = &mut self.megachip {
+            mega.set_collision_color(op.nn);
+        }
+        Ok(())
+    }
+
+    //00BN: Scroll up N pixels (MegaChip)
+    fn execute_scroll_up(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        if let Some(mega) = &mut self.megachip {
+            mega.scroll_up(op.n as usize);
+        }
+        self.effects.screen_written = true;
+        Ok(())
+    }
+
+    //02A0: Next background colour, blue, black, green, red and round again (CHIP-8X)
+    //In MegaChip mode it is 02NN loading 160 colours
+    fn execute_cycle_background(&mut self, op: Operands, pc: u16) -> Result<(), Chip8Error> {
+        if self.megachip.is_some() {
+            return self.execute_load_palette(op, pc);
+        }
+        self.screen.cycle_background();
+        self.effects.screen_written = true;
+        Ok(())
+    }
+
+    //00CN: Scroll down N pixels (SCHIP)
+    fn execute_scroll_down(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        self.screen.scroll(0, op.n as isize, self.planes);
+        self.effects.screen_written = true;
+        Ok(())
+    }
+
+    //00FB: Scroll right 4 pixels (SCHIP)
+    fn execute_scroll_right(&mut self, _: Operands, _: u16) -> Result<(), Chip8Error> {
+        self.screen.scroll(4, 0, self.planes);
+        self.effects.screen_written = true;
+        Ok(())
+    }
+
+    //00FC: Scroll left 4 pixels (SCHIP)
+    fn execute_scroll_left(&mut self, _: Operands, _: u16) -> Result<(), Chip8Error> {
+        self.screen.scroll(-4, 0, self.planes);
+        self.effects.screen_written = true;
+        Ok(())
+    }
+
+    //00FD: Exit the interpreter (SCHIP)
+    fn execute_exit(&mut self, _: Operands, _: u16) -> Result<(), Chip8Error> {
+        self.exited = true;
+        self.lifecycle(Lifecycle::Exited);
+        Ok(())
+    }
+
+    //00FE/00FF: Switch resolution (SCHIP), the display is cleared
+    fn execute_low_res(&mut self, _: Operands, _: u16) -> Result<(), Chip8Error> {
+        self.screen.set_hires(false);
+        self.effects.screen_written = true;
+        self.lifecycle(Lifecycle::ScreenCleared);
+        Ok(())
+    }
+
+    fn execute_high_res(&mut self, _: Operands, _: u16) -> Result<(), Chip8Error> {
+        self.screen.set_hires(true);
+        self.effects.screen_written = true;
+        self.lifecycle(Lifecycle::ScreenCleared);
+        Ok(())
+    }
+
+    //1NNN: Move to address program counter to NNN
+    fn execute_jump(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        self.program_counter = op.nnn;
+        Ok(())
+    }
+
+    //2NNN: Call subroutine. Place current PC into stack, then move PC to NNN
+    fn execute_call(&mut self, op: Operands, pc: u16) -> Result<(), Chip8Error> {
+        self.push(self.program_counter, op.nnn).ok_or(Chip8Error::StackOverflow { pc })?;
+        if self.stack_depth() >= STACK_WARN_DEPTH && !self.watchdog.nearly_full_warned {
+            self.watchdog.nearly_full_warned = true;
+            self.raise(Event::Warning(Warning::StackNearlyFull { pc, depth: self.stack_depth() as u8 }));
+        }
+        self.program_counter = op.nnn;
+        Ok(())
+    }
+
+    //3XNN: Skip if Vx = NN
+    fn execute_skip_eq_imm(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        if self.v_registers[op.x as usize] == op.nn {
+            self.skip();
+        }
+        Ok(())
+    }
+
+    //4XNN: Skip if Vx != NN
+    fn execute_skip_ne_imm(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        if self.v_registers[op.x as usize] != op.nn {
+            self.skip();
+        }
+        Ok(())
+    }
+
+    //5XY0 : Skip if Vx = Vy
+    fn execute_skip_eq_reg(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        if self.v_registers[op.x as usize] == self.v_registers[op.y as usize] {
+            self.skip();
+        }
+        Ok(())
+    }
+
+    //5XY1: Add Vy to Vx a nibble at a time, each wrapping at 8 (CHIP-8X, for colour zone coordinates)
+    fn execute_add_nibbles(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        let (vx, vy) = (self.v_registers[op.x as usize], self.v_registers[op.y as usize]);
+        self.set_v(op.x, ((vx & 0x77) + (vy & 0x77)) & 0x77);
+        Ok(())
+    }
+
+    //5XY2: Store Vx..=Vy at I, I is not changed (XO-CHIP)
+    fn execute_save_range(&mut self, op: Operands, pc: u16) -> Result<(), Chip8Error> {
+        let registers = register_range(op.x, op.y);
+        self.check_access(pc, self.i_register, registers.len() as u16, Access::Write);
+        self.check_write(pc, self.i_register, registers.len() as u16)?;
+        for (offset, register) in registers.into_iter().enumerate() {
+            self.write_ram(self.i_register.wrapping_add(offset as u16), self.v_registers[register as usize]);
+        }
+        Ok(())
+    }
+
+    //5XY3: Read Vx..=Vy from I, I is not changed (XO-CHIP)
+    fn execute_load_range(&mut self, op: Operands, pc: u16) -> Result<(), Chip8Error> {
+        let registers = register_range(op.x, op.y);
+        self.check_access(pc, self.i_register, registers.len() as u16, Access::Read);
+        for (offset, register) in registers.into_iter().enumerate() {
+            self.set_v(register, self.read_byte(self.i_register.wrapping_add(offset as u16)));
+        }
+        Ok(())
+    }
+
+    //6XNN: Vx = NN
+    fn execute_load_imm(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        self.set_v(op.x, op.nn);
+        Ok(())
+    }
+
+    //7XNN: Vx += NN
+    fn execute_add_imm(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        self.set_v(op.x, self.v_registers[op.x as usize].wrapping_add(op.nn));
+        Ok(())
+    }
+
+    //8XY0: Set Vx to Vy
+    fn execute_load_reg(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        self.set_v(op.x, self.v_registers[op.y as usize]);
+        Ok(())
+    }
+
+    //8XY1: Set Vx to Vx OR Vy (bitwise)
+    fn execute_or(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        self.set_v(op.x, self.v_registers[op.x as usize] | self.v_registers[op.y as usize]);
+        self.logic_vf_reset();
+        Ok(())
+    }
+
+    //8XY2: Set Vx to Vx AND Vy (bitwise)
+    fn execute_and(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        self.set_v(op.x, self.v_registers[op.x as usize] & self.v_registers[op.y as usize]);
+        self.logic_vf_reset();
+        Ok(())
+    }
+
+    //8XY3: Set Vx to Vx XOR Vy (bitwise)
+    fn execute_xor(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        self.set_v(op.x, self.v_registers[op.x as usize] ^ self.v_registers[op.y as usize]);
+        self.logic_vf_reset();
+        Ok(())
+    }
+
+    //8XY4: Vx += Vy. If there is overflow, put carry in Vf(0xF)
+    fn execute_add_reg(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        let (new_vx, carry) = self.v_registers[op.x as usize].overflowing_add(self.v_registers[op.y as usize]);
+
+        self.set_v(0xF, if carry {1} else {0});
+        self.set_v(op.x, new_vx);
+        Ok(())
+    }
+
+    //8XY5: Vx -= Vy. If Vx>Vy, put 1 in Vf(0xF)
+    fn execute_sub_reg(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        let (new_vx, borrow) = self.v_registers[op.x as usize].overflowing_sub(self.v_registers[op.y as usize]);
+
+        self.set_v(0xF, if borrow {0} else {1});
+        self.set_v(op.x, new_vx);
+        Ok(())
+    }
+
+    //8XY6: If LSB of Vx is 1, put in Vf(0xF). Right shift Vx by 1 bit.
+    //Shift quirk: shift Vy instead and store the result in Vx
+    fn execute_shift_right(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        let source = if self.quirks.shift_uses_vy { op.y } else { op.x };
+        self.set_v(0xF, self.v_registers[source as usize] & 1);
+        self.set_v(op.x, self.v_registers[source as usize] >> 1);
+        Ok(())
+    }
+
+    //8XY7: Vx = Vy-Vx. If Vy>Vx, put 1 in Vf(0xF)
+    fn execute_sub_n(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        let (new_vx, borrow) = self.v_registers[op.y as usize].overflowing_sub(self.v_registers[op.x as usize]);
+
+        self.set_v(0xF, if borrow {0} else {1});
+        self.set_v(op.x, new_vx);
+        Ok(())
+    }
+
+    //8XYE: If MSB of Vx is 1, put in Vf(0xF). Left shift Vx by 1 bit.
+    //Shift quirk: shift Vy instead and store the result in Vx
+    fn execute_shift_left(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        let source = if self.quirks.shift_uses_vy { op.y } else { op.x };
+        self.set_v(0xF, (self.v_registers[source as usize] >> 7) & 1);
+        self.set_v(op.x, self.v_registers[source as usize] << 1);
+        Ok(())
+    }
+
+    //9XY0: Skip of Vx != Vy
+    fn execute_skip_ne_reg(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        if self.v_registers[op.x as usize] != self.v_registers[op.y as usize] {
+            self.skip();
+        }
+        Ok(())
+    }
+
+    //ANNN: Set value of Iregister to nnn
+    fn execute_load_i(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        self.set_i(op.nnn);
+        Ok(())
+    }
+
+    //BNNN: Set Program Counter to V[0] + nnn
+    //Jump quirk (BXNN): use Vx, where x is the top digit of nnn
+    fn execute_jump_v0(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        let register = if self.quirks.jump_uses_vx { op.x as usize } else { 0 };
+        self.program_counter = (self.v_registers[register] as u16).wrapping_add(op.nnn);
+        Ok(())
+    }
+
+    //BXY0: Colour a block of zones in colour Vy (CHIP-8X), in BNNN's place
+    //Vx is the left column (low nibble, 8 pixels each) and the width less one (high nibble), Vx+1 the same
+    //for the top row and height, in rows of 4 pixels. Zones past the edge are left alone
+    //BXYN: Colour N rows of the column holding pixel (Vx, Vx+1) in colour Vy
+    fn execute_foreground(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        let (vx, vy) = (self.v_registers[op.x as usize] as usize, self.v_registers[(op.x as usize + 1) & 0xF] as usize);
+        let color = self.v_registers[op.y as usize];
+        if op.n == 0 {
+            let (left, top) = (vx & 0xF, vy & 0xF);
+            self.screen.set_foreground(left..left + (vx >> 4) + 1, top * 4..(top + (vy >> 4) + 1) * 4, color);
+        } else {
+            let (column, top) = (vx % SCREEN_WIDTH / 8, vy % SCREEN_HEIGHT);
+            self.screen.set_foreground(column..column + 1, top..top + op.n as usize, color);
+        }
+        self.effects.screen_written = true;
+        Ok(())
+    }
+
+    //CXKK: Set Vx to a random byte AND kk
+    fn execute_random(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        let random = self.random_byte();
+        self.set_v(op.x, op.nn & random);
+        Ok(())
+    }
+
+    //DXYN: Draw Sprite
+    //Sprite: 1 byte wide (8 bits long) starting at (x,y) (held in Vx, Vy)
+    //N: Number of pixels tall (starting from address Iregister)
+    //Drawing: XORed onto the screen. If there was any collision,Vf =1
+    //If sprite "spills" over screen, its wrapped around to the other side of the row
+    //DXY0 (SCHIP): 16x16 sprite, each row is 2 bytes
+    //XO-CHIP: the sprite is drawn to each selected plane in turn, plane 2's data follows plane 1's
+    fn execute_draw(&mut self, op: Operands, pc: u16) -> Result<(), Chip8Error> {
+        if self.megachip.is_some() {
+            return self.execute_draw_mega(op);
+        }
+        self.vblank_wait = self.quirks.display_wait;
+        let (width, rows) = if op.n == 0 { (16, 16) } else { (8, op.n as u16) };
+        let bytes_per_row = width / 8;
+        let sprite_size = rows * bytes_per_row;
+        let selected: Vec<u8> = (0..PLANE_COUNT as u8).map(|plane| 1 << plane).filter(|&bit| self.planes & bit != 0).collect();
+        self.check_access(pc, self.i_register, sprite_size * selected.len() as u16, Access::Sprite);
+        let x_coord = self.v_registers[op.x as usize] as u16;
+        let y_coord = self.v_registers[op.y as usize] as u16;
+        let screen_width = self.screen_width();
+        let screen_height = self.screen_height();
+        let mut collision = false;
+
+        for (index, plane) in selected.into_iter().enumerate() {
+            let sprite_address = self.i_register.wrapping_add(index as u16 * sprite_size);
+            for y_line in 0..rows {
+                let row_address = sprite_address.wrapping_add(y_line * bytes_per_row);
+                let row_pixels = if width == 16 {
+                    self.read_word(row_address)
+                } else {
+                    (self.read_byte(row_address) as u16) << 8
+                };
+                //The start wraps, the rest of the sprite wraps too or is clipped at the edges
+                let y = (y_coord as usize % screen_height) + y_line as usize;
+                if self.quirks.clip_sprites && y >= screen_height {
+                    break;
+                }
+                let x = x_coord as usize % screen_width;
+                collision |= self.screen.draw_row(x, y % screen_height, row_pixels, width as usize, plane, self.quirks.clip_sprites);
+            }
+        }
+        self.effects.screen_written = true;
+        self.set_v(0xF, if collision {1} else {0});
+        self.lifecycle(Lifecycle::SpriteDrawn {
+            x: (x_coord as usize % screen_width) as u8,
+            y: (y_coord as usize % screen_height) as u8,
+            rows: rows as u8,
+            collision,
+        });
+        Ok(())
+    }
+
+    //DXYN (MegaChip): Draw a sprite of the set size to the back buffer, N is not used
+    //Each byte is a palette index, VF = 1 if a pixel lands on the collision colour
+    fn execute_draw_mega(&mut self, op: Operands) -> Result<(), Chip8Error> {
+        let (width, height) = self.megachip.as_ref().map_or((0, 0), |mega| mega.sprite_size());
+        let sprite = self.mega_bytes(width * height);
+        let (x_coord, y_coord) = (self.v_registers[op.x as usize] as usize, self.v_registers[op.y as usize] as usize);
+        let collision = self.megachip.as_mut().is_some_and(|mega| mega.draw(x_coord, y_coord, &sprite));
+        self.effects.screen_written = true;
+        self.set_v(0xF, if collision {1} else {0});
+        Ok(())
+    }
+
+    //EX9E: Skip next instruction if key with the value of Vx is pressed
+    fn execute_skip_key_pressed(&mut self, op: Operands, pc: u16) -> Result<(), Chip8Error> {
+        if self.key_held(op.x, pc)? {
+            self.skip();
+        }
+        Ok(())
+    }
+
+    //ExA1: Skip next instruction if key with the value of Vx is NOT pressed
+    fn execute_skip_key_not_pressed(&mut self, op: Operands, pc: u16) -> Result<(), Chip8Error> {
+        if !self.key_held(op.x, pc)? {
+            self.skip();
+        }
+        Ok(())
+    }
+
+    //The key in Vx for EX9E and EXA1, a fault past key F
+    fn key_held(&self, x: u8, pc: u16) -> Result<bool, Chip8Error> {
+        let key = self.v_registers[x as usize];
+        self.keys.get(key as usize).copied().ok_or(Chip8Error::InvalidKey { pc, key })
+    }
+
+    //F000 NNNN: Set Iregister to a 16 bit address (XO-CHIP)
+    fn execute_load_i_long(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        self.set_i(op.long as u16);
+        Ok(())
+    }
+
+    //FN01: Select the planes to draw on (XO-CHIP)
+    fn execute_plane(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        self.planes = op.x & ALL_PLANES;
+        Ok(())
+    }
+
+    //F002: Copy 16 bytes from Iregister into the audio pattern buffer (XO-CHIP)
+    fn execute_load_audio(&mut self, _: Operands, pc: u16) -> Result<(), Chip8Error> {
+        self.check_access(pc, self.i_register, AUDIO_PATTERN_SIZE as u16, Access::Read);
+        for i in 0..AUDIO_PATTERN_SIZE {
+            self.audio_pattern[i] = self.read_byte(self.i_register.wrapping_add(i as u16));
+        }
+        Ok(())
+    }
+
+    //FX07: Set Vx as delay timer
+    fn execute_load_delay(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        self.set_v(op.x, self.delay_timer);
+        Ok(())
+    }
+
+    //FX0A: Wait for a keypress and store it into Vx
+    //Execution stops until keypress() delivers the next press (or release, see Quirks)
+    fn execute_wait_key(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        //Waiting for the release, a key that went down earlier this frame and is still held has done the
+        //press half: the frontend delivered it between frames, the VIP would have seen it held
+        let held = self.key_edges.pressed & self.keys_bitmask();
+        if self.quirks.wait_key_on_release && held != 0 {
+            self.set_state(EmulatorState::WaitingForRelease { dest_register: op.x, key: held.trailing_zeros() as u8 });
+        } else {
+            self.set_state(EmulatorState::WaitingForKey { dest_register: op.x });
+        }
+        Ok(())
+    }
+
+    //FX15: Set delay timer as Vx
+    fn execute_set_delay(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        self.delay_timer = self.v_registers[op.x as usize];
+        Ok(())
+    }
+
+    //FX18: Set sound timer as Vx
+    fn execute_set_sound(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        self.set_sound_timer(self.v_registers[op.x as usize]);
+        Ok(())
+    }
+
+    //FX1E: Iregister += Vx
+    //Overflow quirk: VF is set when I passes 0xFFF
+    fn execute_add_i(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        let i = self.i_register.wrapping_add(self.v_registers[op.x as usize] as u16);
+        self.set_i(i);
+        if self.quirks.add_i_overflow {
+            self.set_v(0xF, (i > 0xFFF) as u8);
+        }
+        Ok(())
+    }
+
+    //FX29: Load sprite into Iregister. E
+    //Each sprite is 5 bits long. (Starting at the memory map's font address, 0 unless moved)
+    //Only the low digit of Vx counts, as with FX30
+    fn execute_load_font(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        let sprite_index = self.memory_map.font_address + ((self.v_registers[op.x as usize] & 0xF) as u16) * 5;
+        self.set_i(sprite_index);
+        Ok(())
+    }
+
+    //FX30: Load big font sprite into Iregister (SCHIP)
+    //Each big sprite is 10 bytes long, stored after the small font unless the memory map moves it
+    fn execute_load_big_font(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        let sprite_index = self.memory_map.big_font_address + ((self.v_registers[op.x as usize] & 0xF) as u16) * 10;
+        self.set_i(sprite_index);
+        Ok(())
+    }
+
+    //FX33: Store BCD of Vx into memory starting from address Iregister
+    //Vx: 16 bits -> 2^8 (256)
+    //100 -> I, 10 -> I+1, 1 -> I+2
+    fn execute_store_bcd(&mut self, op: Operands, pc: u16) -> Result<(), Chip8Error> {
+        self.check_access(pc, self.i_register, 3, Access::Write);
+        self.check_write(pc, self.i_register, 3)?;
+        let vx = self.v_registers[op.x as usize];
+        self.write_ram(self.i_register, vx / 100);
+        self.write_ram(self.i_register.wrapping_add(1), (vx / 10) % 10);
+        self.write_ram(self.i_register.wrapping_add(2), vx % 10);
+        Ok(())
+    }
+
+    //FX3A: Set the audio pattern playback rate (XO-CHIP)
+    fn execute_set_pitch(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        self.pitch = self.v_registers[op.x as usize];
+        Ok(())
+    }
+
+    //FX55: Copy values of V0 to Vx into memory starting at address in Iregister
+    fn execute_store_regs(&mut self, op: Operands, pc: u16) -> Result<(), Chip8Error> {
+        self.check_access(pc, self.i_register, op.x as u16 + 1, Access::Write);
+        self.check_write(pc, self.i_register, op.x as u16 + 1)?;
+        for i in 0..=op.x {
+            self.write_ram(self.i_register.wrapping_add(i as u16), self.v_registers[i as usize]);
+        }
+        if self.quirks.load_store_increments_i {
+            self.set_i(self.i_register.wrapping_add(op.x as u16 + 1));
+        }
+        Ok(())
+    }
+
+    //FX65: Read values into V0 to Vx from memory starting at address in Iregister
+    fn execute_load_regs(&mut self, op: Operands, pc: u16) -> Result<(), Chip8Error> {
+        self.check_access(pc, self.i_register, op.x as u16 + 1, Access::Read);
+        for i in 0..=op.x {
+            self.set_v(i, self.read_byte(self.i_register.wrapping_add(i as u16)));
+        }
+        if self.quirks.load_store_increments_i {
+            self.set_i(self.i_register.wrapping_add(op.x as u16 + 1));
+        }
+        Ok(())
+    }
+
+    //FX75: Copy values of V0 to Vx into the RPL user flags (SCHIP), then hand them to the flag store
+    fn execute_save_flags(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        let count = op.x as usize + 1;
+        self.rpl_flags[..count].copy_from_slice(&self.v_registers[..count]);
+        if let Some(store) = &mut self.flag_store.0 {
+            store.save(&self.rpl_flags);
+        }
+        Ok(())
+    }
+
+    //FX85: Read values into V0 to Vx from the RPL user flags (SCHIP)
+    fn execute_load_flags(&mut self, op: Operands, _: u16) -> Result<(), Chip8Error> {
+        for i in 0..=op.x {
+            self.set_v(i, self.rpl_flags[i as usize]);
+        }
+        Ok(())
+    }
+}
+
+//Registers touched by 5XY2/5XY3, from x to y in either direction
+fn register_range(x: u8, y: u8) -> Vec<u8> {
+    if x <= y { (x..=y).collect() } else { (y..=x).rev().collect() }
+}
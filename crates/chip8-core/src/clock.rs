@@ -0,0 +1,88 @@
+#[cfg(target_has_atomic = "64")]
+use alloc::sync::Arc;
+#[cfg(target_has_atomic = "64")]
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::time::Duration;
+#[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+use std::{sync::OnceLock, thread, time::Instant};
+
+//Where an Emulator and the code driving it read the time: warm-up deadlines, profiler timings, real time pacing
+//Swap in a ManualClock to make anything timed deterministic
+pub trait TimeSource: Send + Sync {
+    //Time since some fixed point, only differences mean anything
+    fn now(&self) -> Duration;
+    fn sleep(&self, duration: Duration);
+}
+
+//The wall clock, what an Emulator uses unless told otherwise
+//Without std, and on wasm32-unknown-unknown which has no clock without JavaScript, it reads zero like a
+//ManualClock nobody advances and sleeping returns at once. The wasm feature's Chip8 class reads
+//performance.now() instead, embedded hosts set a TimeSource over their own timer
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+//Shared by every SystemClock, set on first use
+#[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+static START: OnceLock<Instant> = OnceLock::new();
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[cfg(all(feature = "std", not(all(target_arch = "wasm32", target_os = "unknown"))))]
+impl TimeSource for SystemClock {
+    fn now(&self) -> Duration {
+        START.get_or_init(Instant::now).elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+//Instant::now and thread::sleep panic on wasm32-unknown-unknown
+#[cfg(any(not(feature = "std"), all(target_arch = "wasm32", target_os = "unknown")))]
+impl TimeSource for SystemClock {
+    fn now(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    fn sleep(&self, _duration: Duration) {}
+}
+
+//Time that only moves when told to; sleeping moves it forward at once instead of waiting
+//Clones share the same time, so a test can keep one and hand the other to the emulator
+//Only on targets with 64-bit atomics, which leaves out 32-bit microcontrollers like the Cortex-M
+#[cfg(target_has_atomic = "64")]
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    nanos: Arc<AtomicU64>,
+}
+
+#[cfg(target_has_atomic = "64")]
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.nanos.fetch_add(duration.as_nanos() as u64, Ordering::SeqCst);
+    }
+
+    pub fn set(&self, now: Duration) {
+        self.nanos.store(now.as_nanos() as u64, Ordering::SeqCst);
+    }
+}
+
+#[cfg(target_has_atomic = "64")]
+impl TimeSource for ManualClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::SeqCst))
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+    }
+}
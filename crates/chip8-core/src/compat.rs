@@ -0,0 +1,180 @@
+use crate::chip8::{Emulator, CLASSIC_RAM_SIZE, STACK_SIZE, START_ADDRESS, TICKS_PER_FRAME};
+use crate::error::{Chip8Error, RomError};
+use crate::instruction::{decode_long, Instruction};
+use crate::quirks::Quirks;
+
+use core::fmt;
+
+//Sandbox limits for a compatibility run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub max_cycles: u64,
+    //Instructions between timer updates, matching the frontend's instructions per frame
+    pub cycles_per_frame: u64,
+    pub quirks: Quirks,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_cycles: 200_000,
+            cycles_per_frame: TICKS_PER_FRAME as u64,
+            quirks: Quirks::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    //Ran for the whole cycle budget without faulting
+    Ok,
+    //Stopped at FX0A since nobody is pressing keys in the sandbox, no faults until then
+    WaitingForInput { pc: u16 },
+    InvalidOpcode { pc: u16, opcode: u16 },
+    //Uses a SUPER-CHIP instruction
+    NeedsSchip { pc: u16, opcode: u16 },
+    //Uses an XO-CHIP instruction
+    NeedsXoChip { pc: u16, opcode: u16 },
+    //Uses a MegaChip instruction
+    NeedsMegaChip { pc: u16, opcode: u16 },
+    //Uses a CHIP-8X instruction
+    NeedsChip8x { pc: u16, opcode: u16 },
+    StackOverflow { pc: u16 },
+    StackUnderflow { pc: u16 },
+    MemoryOutOfBounds { pc: u16, address: u16 },
+    //EX9E/EXA1 with a key number above 0xF
+    InvalidKey { pc: u16, key: u8 },
+    //The emulator panicked, a bug in this crate
+    EmulatorBug { pc: u16 },
+    RomTooLarge { size: usize },
+    EmptyRom,
+}
+
+impl Verdict {
+    //Ran without any fault (waiting for input counts)
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Verdict::Ok | Verdict::WaitingForInput { .. })
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Verdict::Ok => write!(f, "ok"),
+            Verdict::WaitingForInput { pc } => write!(f, "ok (waits for input at {:#05X})", pc),
+            Verdict::InvalidOpcode { pc, opcode } => write!(f, "invalid opcode {:#06X} at {:#05X}", opcode, pc),
+            Verdict::NeedsSchip { pc, opcode } => write!(f, "needs SCHIP ({:#06X} at {:#05X})", opcode, pc),
+            Verdict::NeedsXoChip { pc, opcode } => write!(f, "needs XO-CHIP ({:#06X} at {:#05X})", opcode, pc),
+            Verdict::NeedsMegaChip { pc, opcode } => write!(f, "needs MegaChip ({:#06X} at {:#05X})", opcode, pc),
+            Verdict::NeedsChip8x { pc, opcode } => write!(f, "needs CHIP-8X ({:#06X} at {:#05X})", opcode, pc),
+            Verdict::StackOverflow { pc } => write!(f, "stack overflow at {:#05X}", pc),
+            Verdict::StackUnderflow { pc } => write!(f, "stack underflow at {:#05X}", pc),
+            Verdict::MemoryOutOfBounds { pc, address } => write!(f, "memory access out of bounds ({:#06X}) at {:#05X}", address, pc),
+            Verdict::InvalidKey { pc, key } => write!(f, "invalid key {:#04X} at {:#05X}", key, pc),
+            Verdict::EmulatorBug { pc } => write!(f, "emulator bug at {:#05X}", pc),
+            Verdict::RomTooLarge { size } => write!(f, "ROM too large ({} bytes, max {})", size, CLASSIC_RAM_SIZE - START_ADDRESS as usize),
+            Verdict::EmptyRom => write!(f, "empty ROM"),
+        }
+    }
+}
+
+impl From<Chip8Error> for Verdict {
+    fn from(error: Chip8Error) -> Self {
+        match error {
+            Chip8Error::UnknownOpcode { pc, opcode } => Verdict::InvalidOpcode { pc, opcode },
+            Chip8Error::StackOverflow { pc } => Verdict::StackOverflow { pc },
+            Chip8Error::StackUnderflow { pc } => Verdict::StackUnderflow { pc },
+            Chip8Error::InvalidKey { pc, key } => Verdict::InvalidKey { pc, key },
+            //Only with memory protection, which the sandbox leaves off
+            Chip8Error::ProtectedWrite { pc, address } => Verdict::MemoryOutOfBounds { pc, address },
+            Chip8Error::Internal { pc } => Verdict::EmulatorBug { pc },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompatReport {
+    pub verdict: Verdict,
+    pub cycles: u64,
+}
+
+//Run a ROM headless with no input until it faults or the limits are hit
+pub fn check_rom(rom: &[u8], limits: &Limits) -> CompatReport {
+    let mut emulator = Emulator::new();
+    emulator.set_quirks(limits.quirks);
+    let verdict = match emulator.load_rom(rom) {
+        Err(RomError::Empty) => Some(Verdict::EmptyRom),
+        //Anything past 4 KB needs XO-CHIP's larger memory
+        Err(RomError::TooLarge { size, .. }) => Some(Verdict::RomTooLarge { size }),
+        Ok(()) if rom.len() > CLASSIC_RAM_SIZE - START_ADDRESS as usize => Some(Verdict::RomTooLarge { size: rom.len() }),
+        Ok(()) => None,
+        //A fresh emulator loads at START_ADDRESS, which is always valid
+        Err(RomError::InvalidLoadAddress { .. }) => unreachable!(),
+    };
+    if let Some(verdict) = verdict {
+        return CompatReport { verdict, cycles: 0 };
+    }
+
+    let mut cycles = 0;
+    while cycles < limits.max_cycles {
+        if let Some(verdict) = fault(&emulator) {
+            return CompatReport { verdict, cycles };
+        }
+        //fault() catches these first, this only covers anything it misses
+        //Checked so one bad ROM is a verdict rather than the end of a batch, without std a panic still unwinds
+        #[cfg(feature = "std")]
+        let result = emulator.tick_checked();
+        #[cfg(not(feature = "std"))]
+        let result = emulator.tick();
+        if let Err(error) = result {
+            return CompatReport { verdict: error.into(), cycles };
+        }
+        cycles += 1;
+        if cycles % limits.cycles_per_frame.max(1) == 0 {
+            emulator.timers();
+        }
+    }
+    CompatReport { verdict: Verdict::Ok, cycles }
+}
+
+//Whether the instruction at PC would fault (or block) if executed
+//The sandbox models a classic 4 KB interpreter, XO-CHIP's larger address space counts as out of bounds
+fn fault(emulator: &Emulator) -> Option<Verdict> {
+    let pc = emulator.program_counter();
+    if pc as usize + 1 >= CLASSIC_RAM_SIZE {
+        return Some(Verdict::MemoryOutOfBounds { pc, address: pc });
+    }
+    let word = |address: u16| ((emulator.read_byte(address) as u16) << 8) | emulator.read_byte(address + 1) as u16;
+    let opcode = word(pc);
+    //Decoded with MegaChip's instructions, so a ROM using them is told it needs MegaChip
+    let instruction = match decode_long(opcode, word(pc + 2), true) {
+        Ok(instruction) if instruction.is_schip() => return Some(Verdict::NeedsSchip { pc, opcode }),
+        Ok(instruction) if instruction.is_xochip() => return Some(Verdict::NeedsXoChip { pc, opcode }),
+        Ok(instruction) if instruction.is_megachip() => return Some(Verdict::NeedsMegaChip { pc, opcode }),
+        Ok(instruction) if instruction.is_chip8x() => return Some(Verdict::NeedsChip8x { pc, opcode }),
+        Ok(instruction) => instruction,
+        Err(_) => return Some(Verdict::InvalidOpcode { pc, opcode }),
+    };
+
+    let i = emulator.i_register() as usize;
+    //Last address touched by a memory instruction
+    let last_address = match instruction {
+        Instruction::Draw { n, .. } => (n > 0).then(|| i + n as usize - 1),
+        Instruction::StoreBcd { .. } => Some(i + 2),
+        Instruction::StoreRegs { x } | Instruction::LoadRegs { x } => Some(i + x as usize),
+        _ => None,
+    };
+    if let Some(address) = last_address.filter(|&address| address >= CLASSIC_RAM_SIZE) {
+        return Some(Verdict::MemoryOutOfBounds { pc, address: address.min(u16::MAX as usize) as u16 });
+    }
+
+    match instruction {
+        Instruction::Call(_) if emulator.stack_depth() >= STACK_SIZE => Some(Verdict::StackOverflow { pc }),
+        Instruction::Return if emulator.stack_depth() == 0 => Some(Verdict::StackUnderflow { pc }),
+        Instruction::WaitKey { .. } => Some(Verdict::WaitingForInput { pc }),
+        Instruction::SkipKeyPressed { x } | Instruction::SkipKeyNotPressed { x } if emulator.v_register(x) > 0xF => {
+            Some(Verdict::InvalidKey { pc, key: emulator.v_register(x) })
+        },
+        _ => None,
+    }
+}
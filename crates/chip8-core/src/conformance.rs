@@ -0,0 +1,177 @@
+//Runs the community test ROMs (Timendus' chip8-test-suite: corax+, flags, quirks) and reads the
+//pass and fail marks off their result screens. The ROMs are not bundled, pass their bytes in
+//Marks are matched pixel for pixel, a report without any means the screen was not recognised
+
+use crate::chip8::{Emulator, EmulatorState};
+use crate::error::{Chip8Error, RomError};
+use crate::framebuffer::FrameBuffer;
+use crate::quirks::Quirks;
+
+use alloc::vec::Vec;
+use core::fmt;
+
+//Where the suite's menu reads the platform from, set before running to skip the menu
+const PLATFORM_ADDRESS: u16 = 0x1FF;
+
+//Platform the quirks test checks against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Chip8 = 1,
+    SuperChip = 2,
+    XoChip = 3,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestRom {
+    //3-corax+.ch8, every opcode's basic behaviour
+    CoraxPlus,
+    //4-flags.ch8, VF after the arithmetic opcodes
+    Flags,
+    //5-quirks.ch8, which quirks the emulator has against what the platform should have
+    Quirks(Platform),
+}
+
+//A mark as drawn by a test ROM: rows of pixels, most significant bit leftmost
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Glyph {
+    pub width: usize,
+    pub rows: &'static [u8],
+}
+
+//"OK" and "NO" from the original corax test
+pub const OK: Glyph = Glyph { width: 7, rows: &[0xEA, 0xAC, 0xAA, 0xEA] };
+pub const NO: Glyph = Glyph { width: 7, rows: &[0xCE, 0xAA, 0xAA, 0xAE] };
+//The check and cross the suite draws
+pub const CHECK: Glyph = Glyph { width: 6, rows: &[0x04, 0x08, 0x90, 0x60] };
+pub const CROSS: Glyph = Glyph { width: 5, rows: &[0x88, 0x50, 0x20, 0x50, 0x88] };
+
+impl Glyph {
+    //Whether the glyph's box at (x, y) holds exactly the glyph, lit and unlit pixels alike
+    pub fn matches(&self, screen: &FrameBuffer, x: usize, y: usize) -> bool {
+        if x + self.width > screen.width() || y + self.rows.len() > screen.height() {
+            return false;
+        }
+        self.rows.iter().enumerate().all(|(row, &bits)| {
+            (0..self.width).all(|column| (bits & (0x80 >> column) != 0) == (screen.pixel(x + column, y + row) != 0))
+        })
+    }
+}
+
+impl TestRom {
+    //Instructions to run at most, every test draws its results well within this
+    pub fn max_cycles(self) -> usize {
+        match self {
+            TestRom::CoraxPlus => 100_000,
+            TestRom::Flags => 200_000,
+            //The timing checks wait on the delay timer for several frames
+            TestRom::Quirks(_) => 1_000_000,
+        }
+    }
+
+    pub fn pass_glyphs(self) -> &'static [Glyph] {
+        match self {
+            TestRom::CoraxPlus => &[CHECK, OK],
+            TestRom::Flags | TestRom::Quirks(_) => &[CHECK],
+        }
+    }
+
+    pub fn fail_glyphs(self) -> &'static [Glyph] {
+        match self {
+            TestRom::CoraxPlus => &[CROSS, NO],
+            TestRom::Flags | TestRom::Quirks(_) => &[CROSS],
+        }
+    }
+}
+
+impl fmt::Display for TestRom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TestRom::CoraxPlus => write!(f, "corax+"),
+            TestRom::Flags => write!(f, "flags"),
+            TestRom::Quirks(platform) => write!(f, "quirks ({:?})", platform),
+        }
+    }
+}
+
+//One mark on the result screen, at the top left of its glyph
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mark {
+    pub x: usize,
+    pub y: usize,
+    pub passed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConformanceReport {
+    pub rom: TestRom,
+    //In reading order: top to bottom, left to right
+    pub marks: Vec<Mark>,
+    //Instructions run, 0 when the ROM faulted
+    pub cycles: usize,
+    //The ROM faulted before it finished
+    pub error: Option<Chip8Error>,
+}
+
+impl ConformanceReport {
+    pub fn passed(&self) -> usize {
+        self.marks.iter().filter(|mark| mark.passed).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.marks.iter().filter(|mark| !mark.passed).count()
+    }
+
+    //Some marks were found and none of them failed
+    pub fn is_pass(&self) -> bool {
+        self.error.is_none() && !self.marks.is_empty() && self.failed() == 0
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &Mark> {
+        self.marks.iter().filter(|mark| !mark.passed)
+    }
+}
+
+impl fmt::Display for ConformanceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} passed, {} failed", self.rom, self.passed(), self.failed())?;
+        match self.error {
+            Some(error) => write!(f, ", stopped by {}", error),
+            None if self.marks.is_empty() => write!(f, ", no results recognised"),
+            None => Ok(()),
+        }
+    }
+}
+
+//Run test headless with quirks until it settles in its final loop (or max_cycles), then read the marks
+pub fn run_test_rom(test: TestRom, rom: &[u8], quirks: Quirks) -> Result<ConformanceReport, RomError> {
+    let mut emulator = Emulator::new();
+    emulator.set_quirks(quirks);
+    emulator.set_end_detection(true);
+    emulator.load_rom(rom)?;
+    if let TestRom::Quirks(platform) = test {
+        emulator.poke(PLATFORM_ADDRESS, platform as u8);
+    }
+
+    let (cycles, error) = match emulator.run_until(test.max_cycles(), finished) {
+        Ok(cycles) => (cycles.unwrap_or(test.max_cycles()), None),
+        Err(error) => (0, Some(error)),
+    };
+    let screen = emulator.frame_buffer();
+    let mut marks = Vec::new();
+    for y in 0..screen.height() {
+        for x in 0..screen.width() {
+            let found = |glyphs: &[Glyph]| glyphs.iter().any(|glyph| glyph.matches(screen, x, y));
+            if found(test.pass_glyphs()) {
+                marks.push(Mark { x, y, passed: true });
+            } else if found(test.fail_glyphs()) {
+                marks.push(Mark { x, y, passed: false });
+            }
+        }
+    }
+    Ok(ConformanceReport { rom: test, marks, cycles, error })
+}
+
+//The suite ends every test on a jump to itself
+fn finished(emulator: &Emulator) -> bool {
+    matches!(emulator.state(), EmulatorState::Finished { .. })
+}
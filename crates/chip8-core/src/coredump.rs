@@ -0,0 +1,142 @@
+//Core dumps: everything left of a program when it halted, written to a file to look at later
+//(Emulator::core_dump, then load_core_dump into a fresh emulator for the debugger and disassembler)
+//File layout, numbers big-endian: "C8CD", version, ticks run (u64), the recent PCs (u16 count and entries,
+//oldest first), then the State in its snapshot form, which holds the error, RAM, registers and stack
+
+use crate::chip8::{Emulator, EmulatorState};
+use crate::differential::context;
+use crate::disasm::Line;
+use crate::error::{Chip8Error, CoreDumpError, StateError};
+use crate::memory::MemoryMap;
+use crate::state::State;
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+
+const CORE_DUMP_MAGIC: &[u8; 4] = b"C8CD";
+const CORE_DUMP_VERSION: u8 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoreDump {
+    pub state: State,
+    //Where the last instructions were fetched from, oldest first, see Emulator::recent_pcs
+    pub recent_pcs: Vec<u16>,
+    //Since the last reset, see Emulator::tick_count
+    pub ticks: u64,
+}
+
+impl CoreDump {
+    //The ROM's path with the extension swapped for .c8dump, like Cheats::path_for_rom
+    #[cfg(feature = "std")]
+    pub fn path_for_rom(rom: &Path) -> PathBuf {
+        rom.with_extension("c8dump")
+    }
+
+    //What halted the program, None for a dump taken while it still ran
+    pub fn error(&self) -> Option<Chip8Error> {
+        match self.state.state {
+            EmulatorState::Halted { error } => Some(error),
+            _ => None,
+        }
+    }
+
+    //Disassembly round the program counter, from the dumped RAM
+    pub fn code(&self) -> Vec<Line> {
+        context(&self.state)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = CORE_DUMP_MAGIC.to_vec();
+        bytes.push(CORE_DUMP_VERSION);
+        bytes.extend_from_slice(&self.ticks.to_be_bytes());
+        bytes.extend_from_slice(&(self.recent_pcs.len().min(u16::MAX as usize) as u16).to_be_bytes());
+        for pc in self.recent_pcs.iter().take(u16::MAX as usize) {
+            bytes.extend_from_slice(&pc.to_be_bytes());
+        }
+        bytes.extend(self.state.to_snapshot_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CoreDumpError> {
+        let rest = bytes.strip_prefix(CORE_DUMP_MAGIC).ok_or(CoreDumpError::NotACoreDump)?;
+        let (&version, rest) = rest.split_first().ok_or(CoreDumpError::Truncated)?;
+        if version > CORE_DUMP_VERSION {
+            return Err(CoreDumpError::NotACoreDump);
+        }
+        let (ticks, rest) = rest.split_first_chunk::<8>().ok_or(CoreDumpError::Truncated)?;
+        let (count, rest) = rest.split_first_chunk::<2>().ok_or(CoreDumpError::Truncated)?;
+        let (pcs, snapshot) = rest.split_at_checked(u16::from_be_bytes(*count) as usize * 2).ok_or(CoreDumpError::Truncated)?;
+        Ok(Self {
+            state: State::from_snapshot_bytes(snapshot)?,
+            recent_pcs: pcs.chunks_exact(2).map(|pc| u16::from_be_bytes([pc[0], pc[1]])).collect(),
+            ticks: u64::from_be_bytes(*ticks),
+        })
+    }
+
+    #[cfg(feature = "std")]
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CoreDumpError> {
+        fs::write(path, self.to_bytes())?;
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CoreDumpError> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+}
+
+//The report printed for a crash: the error, registers, stack, the recent PCs and the code at the PC
+impl fmt::Display for CoreDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = &self.state;
+        match self.error() {
+            Some(error) => writeln!(f, "Halted: {}", error)?,
+            None => writeln!(f, "Not halted: {:?}", state.state)?,
+        }
+        writeln!(f, "After {} ticks", self.ticks)?;
+        writeln!(f, "PC {:#05X}  I {:#05X}  DT {:#04X}  ST {:#04X}", state.program_counter, state.i_register, state.delay_timer, state.sound_timer)?;
+        for (row, registers) in state.v_registers.chunks(8).enumerate() {
+            let registers: Vec<String> = registers.iter().enumerate().map(|(n, v)| format!("V{:X} {:02X}", row * 8 + n, v)).collect();
+            writeln!(f, "{}", registers.join("  "))?;
+        }
+        let stack: Vec<String> = state.stack.iter().map(|address| format!("{:#05X}", address)).collect();
+        writeln!(f, "Stack: {}", if stack.is_empty() { "empty".to_string() } else { stack.join(" ") })?;
+        let pcs: Vec<String> = self.recent_pcs.iter().map(|pc| format!("{:03X}", pc)).collect();
+        writeln!(f, "Recent PCs, oldest first:")?;
+        for line in pcs.chunks(16) {
+            writeln!(f, "  {}", line.join(" "))?;
+        }
+        writeln!(f, "Code:")?;
+        for line in self.code() {
+            writeln!(f, "{} {}", if line.address == state.program_counter { ">" } else { " " }, line)?;
+        }
+        Ok(())
+    }
+}
+
+impl Emulator {
+    //Everything there is to know after a fault, see CoreDump. Works at any time, not only halted
+    pub fn core_dump(&self) -> CoreDump {
+        CoreDump { state: self.save_state(), recent_pcs: self.recent_pcs(), ticks: self.tick_count() }
+    }
+
+    //Open a dump to inspect it: the state loads as with load_state, still halted, and recent_pcs are the
+    //dumped ones. RAM is resized to the dump's first if it differs, the fonts and start address stay
+    pub fn load_core_dump(&mut self, dump: &CoreDump) -> Result<(), CoreDumpError> {
+        let len = dump.state.ram.len();
+        if len != self.memory_map().size {
+            let map = MemoryMap { size: len, ..self.memory_map() };
+            let expected = self.memory_map().size;
+            self.set_memory_map(map).map_err(|_| StateError::RamSize { len, expected })?;
+        }
+        self.load_state(&dump.state)?;
+        self.restore_pc_history(&dump.recent_pcs);
+        Ok(())
+    }
+}
@@ -0,0 +1,322 @@
+//Breakpoints, and the session file a debugger frontend keeps them in with its pane layout:
+//
+//  layout screen,registers | disassembly,trace | memory,sprite
+//  memory 0x300
+//  break 0x2A4
+//  watch 0x3F0
+//
+//The layout is the columns left to right, each one's panes top to bottom. Blank lines and lines starting
+//with # are skipped
+
+use crate::chip8::Emulator;
+use crate::error::DebugSessionError;
+
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::Write;
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+
+//Why Emulator::debug_step stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Break {
+    //PC reached a breakpoint, the instruction there has not run yet
+    Breakpoint { pc: u16 },
+    //The instruction at pc wrote a watched address, it has already run
+    Watchpoint { pc: u16, address: u16, value: u8 },
+    //The instruction at pc made V[register] equal to value
+    Condition { pc: u16, register: u8, value: u8 },
+    //run_to_draw/run_to_clear reached the instruction at pc, it has not run yet
+    Target { pc: u16 },
+}
+
+impl fmt::Display for Break {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Break::Breakpoint { pc } => write!(f, "breakpoint at {:#05X}", pc),
+            Break::Watchpoint { pc, address, value } => write!(f, "{:#05X} wrote {:#04X} to {:#06X}", pc, value, address),
+            Break::Condition { pc, register, value } => write!(f, "{:#05X} set V{:X} to {:#04X}", pc, register, value),
+            Break::Target { pc } => write!(f, "stopped at {:#05X}", pc),
+        }
+    }
+}
+
+//Breakpoints, watchpoints and register conditions set on an Emulator
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Breakpoints {
+    pub(crate) pcs: BTreeSet<u16>,
+    pub(crate) watchpoints: BTreeSet<u16>,
+    //(register, value) pairs
+    pub(crate) conditions: BTreeSet<(u8, u8)>,
+    //Breakpoint just reported, the next step runs its instruction instead of stopping again
+    pub(crate) stopped_at: Option<u16>,
+}
+
+impl Breakpoints {
+    pub fn pcs(&self) -> impl Iterator<Item = u16> + '_ {
+        self.pcs.iter().copied()
+    }
+
+    pub fn watchpoints(&self) -> impl Iterator<Item = u16> + '_ {
+        self.watchpoints.iter().copied()
+    }
+
+    pub fn conditions(&self) -> impl Iterator<Item = (u8, u8)> + '_ {
+        self.conditions.iter().copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pcs.is_empty() && self.watchpoints.is_empty() && self.conditions.is_empty()
+    }
+}
+
+//One subroutine call on the stack
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallFrame {
+    //Address the 2NNN jumped to
+    pub subroutine: u16,
+    pub return_address: u16,
+}
+
+impl CallFrame {
+    //Address of the 2NNN itself
+    pub fn call_site(&self) -> u16 {
+        self.return_address.wrapping_sub(2)
+    }
+}
+
+//What a debugger frontend can show
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Pane {
+    Screen,
+    //PC, I, V0-VF, the timers and the stack
+    Registers,
+    //Round the PC, with the breakpoints
+    Disassembly,
+    //Hex bytes from the session's memory address, editable
+    Memory,
+    //The last instructions run, oldest first (Emulator::recent_pcs)
+    Trace,
+    //The bytes from I drawn as a sprite
+    Sprite,
+}
+
+impl Pane {
+    pub const ALL: [Pane; 6] = [Pane::Screen, Pane::Registers, Pane::Disassembly, Pane::Memory, Pane::Trace, Pane::Sprite];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Pane::Screen => "screen",
+            Pane::Registers => "registers",
+            Pane::Disassembly => "disassembly",
+            Pane::Memory => "memory",
+            Pane::Trace => "trace",
+            Pane::Sprite => "sprite",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Pane> {
+        Pane::ALL.into_iter().find(|pane| pane.name() == name)
+    }
+}
+
+//Panes in columns, left to right and each top to bottom. Every pane is in it at most once, and a column
+//is never empty
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    columns: Vec<Vec<Pane>>,
+}
+
+//The screen and registers, the code and what ran, then memory and the sprite at I
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            columns: vec![
+                vec![Pane::Screen, Pane::Registers],
+                vec![Pane::Disassembly, Pane::Trace],
+                vec![Pane::Memory, Pane::Sprite],
+            ],
+        }
+    }
+}
+
+impl Layout {
+    pub fn columns(&self) -> &[Vec<Pane>] {
+        &self.columns
+    }
+
+    pub fn contains(&self, pane: Pane) -> bool {
+        self.position(pane).is_some()
+    }
+
+    //The panes not shown, in Pane::ALL order
+    pub fn hidden(&self) -> Vec<Pane> {
+        Pane::ALL.into_iter().filter(|&pane| !self.contains(pane)).collect()
+    }
+
+    //Column and row of pane
+    pub fn position(&self, pane: Pane) -> Option<(usize, usize)> {
+        self.columns.iter().enumerate().find_map(|(column, panes)| Some((column, panes.iter().position(|&p| p == pane)?)))
+    }
+
+    //Into the next column to the right (left for a negative step), a new one past either end
+    pub fn move_across(&mut self, pane: Pane, step: isize) {
+        let Some((column, row)) = self.position(pane) else { return };
+        if self.columns[column].len() == 1 && !(0..self.columns.len() as isize).contains(&(column as isize + step)) {
+            return;
+        }
+        self.columns[column].remove(row);
+        let target = column as isize + step;
+        let target = if target < 0 {
+            self.columns.insert(0, Vec::new());
+            0
+        } else if target as usize >= self.columns.len() {
+            self.columns.push(Vec::new());
+            self.columns.len() - 1
+        } else {
+            target as usize
+        };
+        let panes = &mut self.columns[target];
+        panes.insert(row.min(panes.len()), pane);
+        self.columns.retain(|panes| !panes.is_empty());
+    }
+
+    //Up (a negative step) or down its column, at most to either end
+    pub fn move_within(&mut self, pane: Pane, step: isize) {
+        let Some((column, row)) = self.position(pane) else { return };
+        let panes = &mut self.columns[column];
+        let target = (row as isize + step).clamp(0, panes.len() as isize - 1) as usize;
+        let pane = panes.remove(row);
+        panes.insert(target, pane);
+    }
+
+    //Stop showing pane, false for the last one left
+    pub fn close(&mut self, pane: Pane) -> bool {
+        let Some((column, row)) = self.position(pane) else { return false };
+        if self.columns.len() == 1 && self.columns[0].len() == 1 {
+            return false;
+        }
+        self.columns[column].remove(row);
+        self.columns.retain(|panes| !panes.is_empty());
+        true
+    }
+
+    //Show pane again at the bottom of column (the last one if there are fewer)
+    pub fn open(&mut self, pane: Pane, column: usize) {
+        if self.contains(pane) {
+            return;
+        }
+        let column = column.min(self.columns.len() - 1);
+        self.columns[column].push(pane);
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let columns: Vec<String> = self.columns.iter().map(|panes| {
+            panes.iter().map(|pane| pane.name()).collect::<Vec<_>>().join(",")
+        }).collect();
+        write!(f, "{}", columns.join(" | "))
+    }
+}
+
+//What a debugger frontend keeps between runs of a ROM: its layout, where the memory pane is and the
+//breakpoints and watchpoints
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DebugSession {
+    pub layout: Layout,
+    //The memory pane's cursor
+    pub memory_address: u16,
+    pub breakpoints: BTreeSet<u16>,
+    pub watchpoints: BTreeSet<u16>,
+}
+
+impl DebugSession {
+    //The ROM's path with the extension swapped for .c8debug, like Cheats::path_for_rom
+    #[cfg(feature = "std")]
+    pub fn path_for_rom(rom: &Path) -> PathBuf {
+        rom.with_extension("c8debug")
+    }
+
+    pub fn parse(text: &str) -> Result<Self, DebugSessionError> {
+        let mut session = Self::default();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let malformed = |message: &str| DebugSessionError { line: index + 1, message: message.to_string() };
+            let (key, value) = line.split_once(char::is_whitespace).ok_or_else(|| malformed("expected a setting and its value"))?;
+            let value = value.trim();
+            let address = || {
+                let digits = value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")).unwrap_or(value);
+                u16::from_str_radix(digits, 16).map_err(|_| malformed("invalid address"))
+            };
+            match key {
+                "layout" => session.layout = parse_layout(value).map_err(|message| malformed(&message))?,
+                "memory" => session.memory_address = address()?,
+                "break" => {
+                    session.breakpoints.insert(address()?);
+                },
+                "watch" => {
+                    session.watchpoints.insert(address()?);
+                },
+                _ => return Err(malformed("the settings are layout, memory, break and watch")),
+            }
+        }
+        Ok(session)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("layout {}\nmemory {:#05X}\n", self.layout, self.memory_address);
+        for address in &self.breakpoints {
+            let _ = writeln!(text, "break {:#05X}", address);
+        }
+        for address in &self.watchpoints {
+            let _ = writeln!(text, "watch {:#05X}", address);
+        }
+        text
+    }
+
+    //Set the session's breakpoints and watchpoints on emulator, on top of any it has
+    pub fn apply(&self, emulator: &mut Emulator) {
+        for &pc in &self.breakpoints {
+            emulator.set_breakpoint(pc);
+        }
+        for &address in &self.watchpoints {
+            emulator.set_watchpoint(address);
+        }
+    }
+
+    //Take emulator's breakpoints and watchpoints, as set while debugging, to save them
+    pub fn record(&mut self, emulator: &Emulator) {
+        self.breakpoints = emulator.breakpoints().pcs().collect();
+        self.watchpoints = emulator.breakpoints().watchpoints().collect();
+    }
+}
+
+fn parse_layout(text: &str) -> Result<Layout, String> {
+    let mut columns = Vec::new();
+    let mut seen = BTreeSet::new();
+    for column in text.split('|') {
+        let mut panes = Vec::new();
+        for name in column.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            let pane = Pane::from_name(name).ok_or_else(|| format!("unknown pane {}", name))?;
+            if !seen.insert(pane) {
+                return Err(format!("{} is in the layout twice", name));
+            }
+            panes.push(pane);
+        }
+        if !panes.is_empty() {
+            columns.push(panes);
+        }
+    }
+    if columns.is_empty() {
+        return Err("the layout has no panes".to_string());
+    }
+    Ok(Layout { columns })
+}
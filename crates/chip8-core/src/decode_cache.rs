@@ -0,0 +1,64 @@
+//Instructions already decoded, by address, so hot loops skip fetch and decode (see Emulator::set_decode_cache)
+//An entry goes when any byte it was decoded from is written, and the whole cache on loads, resets and
+//anything else that changes RAM or how it decodes
+
+use crate::chip8::Handler;
+use crate::instruction::{Instruction, Operands};
+
+use alloc::boxed::Box;
+use alloc::vec;
+
+//The longest instructions, F000 NNNN and 01NN NNNN
+const MAX_INSTRUCTION_SIZE: u16 = 4;
+
+//What an address decoded to: the opcode (for the trace hook), the instruction, its operands and the
+//handler that runs it
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Decoded {
+    pub(crate) opcode: u16,
+    pub(crate) instruction: Instruction,
+    pub(crate) operands: Operands,
+    pub(crate) handler: Handler,
+    //A MegaChip or CHIP-8X instruction, which only runs in its mode
+    pub(crate) extension: bool,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct DecodeCache {
+    //As long as RAM
+    entries: Box<[Option<Decoded>]>,
+}
+
+impl DecodeCache {
+    //size is the MemoryMap's, a power of two
+    pub(crate) fn new(size: usize) -> Self {
+        Self { entries: vec![None; size].into_boxed_slice() }
+    }
+
+    pub(crate) fn get(&self, address: u16) -> Option<Decoded> {
+        self.entries[address as usize]
+    }
+
+    pub(crate) fn insert(&mut self, address: u16, decoded: Decoded) {
+        self.entries[address as usize] = Some(decoded);
+    }
+
+    //The byte at address changed, drop every instruction that could have been decoded from it, those
+    //wrapping round from the end of RAM included
+    pub(crate) fn invalidate(&mut self, address: u16) {
+        let mask = self.entries.len() - 1;
+        for offset in 0..MAX_INSTRUCTION_SIZE as usize {
+            self.entries[(address as usize).wrapping_sub(offset) & mask] = None;
+        }
+    }
+
+    pub(crate) fn invalidate_range(&mut self, address: u16, len: usize) {
+        for offset in 0..len {
+            self.invalidate(address.wrapping_add(offset as u16));
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+}
@@ -0,0 +1,156 @@
+//What differs between two State snapshots, for golden state tests that fail with more than "not equal"
+//e.g. assert!(diff(&expected, &actual).is_empty(), "{}", diff(&expected, &actual))
+
+use crate::state::State;
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+//Bytes a memory difference shows before it is cut short with "..."
+const SHOWN_BYTES: usize = 16;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StateDiff {
+    //Registers, timers, flags and settings that differ, in State field order
+    pub registers: Vec<FieldDiff>,
+    //Runs of RAM (and MegaChip memory past it) that differ, in address order
+    pub memory: Vec<MemoryDiff>,
+    //Screen rows with a pixel that differs, at the resolution each snapshot was taken in
+    pub screen_rows: Vec<usize>,
+}
+
+//A value of the left and the right snapshot, formatted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldDiff {
+    //e.g. "PC", "V3", "quirks.clip_sprites"
+    pub name: String,
+    pub left: String,
+    pub right: String,
+}
+
+//Consecutive differing bytes from start. A snapshot with less RAM has fewer bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryDiff {
+    pub start: usize,
+    pub left: Vec<u8>,
+    pub right: Vec<u8>,
+}
+
+impl MemoryDiff {
+    pub fn len(&self) -> usize {
+        self.left.len().max(self.right.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl StateDiff {
+    pub fn is_empty(&self) -> bool {
+        self.registers.is_empty() && self.memory.is_empty() && self.screen_rows.is_empty()
+    }
+}
+
+pub fn diff(left: &State, right: &State) -> StateDiff {
+    let mut registers = Vec::new();
+    let mut field = |name: &str, l: String, r: String| {
+        if l != r {
+            registers.push(FieldDiff { name: name.to_string(), left: l, right: r });
+        }
+    };
+    field("state", format!("{:?}", left.state), format!("{:?}", right.state));
+    field("PC", format!("{:#05X}", left.program_counter), format!("{:#05X}", right.program_counter));
+    field("hires", left.hires.to_string(), right.hires.to_string());
+    field("two page", left.two_page.to_string(), right.two_page.to_string());
+    field("planes", left.planes.to_string(), right.planes.to_string());
+    field("audio pattern", hex(&left.audio_pattern), hex(&right.audio_pattern));
+    field("pitch", left.pitch.to_string(), right.pitch.to_string());
+    field("exited", left.exited.to_string(), right.exited.to_string());
+    for (register, (l, r)) in left.v_registers.iter().zip(&right.v_registers).enumerate() {
+        field(&format!("V{:X}", register), format!("{:#04X}", l), format!("{:#04X}", r));
+    }
+    field("I", format!("{:#05X}", left.i_register), format!("{:#05X}", right.i_register));
+    let stack = |stack: &[u16]| format!("{:X?}", stack);
+    field("stack", stack(&left.stack), stack(&right.stack));
+    let keys = |keys: &[bool; 16]| format!("{:X?}", (0..16).filter(|&key| keys[key]).collect::<Vec<_>>());
+    field("keys", keys(&left.keys), keys(&right.keys));
+    field("DT", left.delay_timer.to_string(), right.delay_timer.to_string());
+    field("ST", left.sound_timer.to_string(), right.sound_timer.to_string());
+    for ((name, l), (_, r)) in left.quirks.flags().into_iter().zip(right.quirks.flags()) {
+        field(&format!("quirks.{}", name), l.to_string(), r.to_string());
+    }
+    field("quirks.random", format!("{:?}", left.quirks.random), format!("{:?}", right.quirks.random));
+    field("random state", format!("{:#X}", left.random_state), format!("{:#X}", right.random_state));
+    field("I high", format!("{:#04X}", left.i_high), format!("{:#04X}", right.i_high));
+    //The colour screens and settings as a whole, too big to show
+    let megachip = |state: &State| match &state.megachip {
+        None => "off".to_string(),
+        Some(_) if left.megachip == right.megachip => "on".to_string(),
+        Some(_) => "on, screen or settings differ".to_string(),
+    };
+    field("MegaChip", megachip(left), megachip(right));
+    //Memory past RAM counts on from its end
+    let memory = |state: &State| [state.ram.as_slice(), &state.extended].concat();
+    StateDiff { registers, memory: memory_runs(&memory(left), &memory(right)), screen_rows: screen_rows(left, right) }
+}
+
+fn memory_runs(left: &[u8], right: &[u8]) -> Vec<MemoryDiff> {
+    let mut runs: Vec<MemoryDiff> = Vec::new();
+    for address in 0..left.len().max(right.len()) {
+        let (l, r) = (left.get(address), right.get(address));
+        if l == r {
+            continue;
+        }
+        match runs.last_mut() {
+            Some(run) if run.start + run.len() == address => {},
+            _ => runs.push(MemoryDiff { start: address, left: Vec::new(), right: Vec::new() }),
+        }
+        let run = runs.last_mut().expect("a run was just pushed");
+        run.left.extend(l);
+        run.right.extend(r);
+    }
+    runs
+}
+
+fn screen_rows(left: &State, right: &State) -> Vec<usize> {
+    let (left, right) = (rows(left), rows(right));
+    (0..left.len().max(right.len())).filter(|&y| left.get(y) != right.get(y)).collect()
+}
+
+//Rows as State::screen lays them out, as many as the snapshot's screen has
+fn rows(state: &State) -> Vec<&[u8]> {
+    let width = if state.hires { 128 } else { 64 };
+    let height = if state.hires || state.two_page { 64 } else { 32 };
+    state.screen.chunks(width).take(height).collect()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02X}", byte)).collect::<Vec<_>>().join(" ")
+}
+
+//One difference a line, left then right, e.g. "V3: 0x05 -> 0x07" and "RAM 0x300..0x302: 01 02 -> 01 03"
+impl fmt::Display for StateDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no differences");
+        }
+        for field in &self.registers {
+            writeln!(f, "{}: {} -> {}", field.name, field.left, field.right)?;
+        }
+        let shown = |bytes: &[u8]| {
+            let more = if bytes.len() > SHOWN_BYTES { " ..." } else { "" };
+            format!("{}{}", hex(&bytes[..bytes.len().min(SHOWN_BYTES)]), more)
+        };
+        for run in &self.memory {
+            writeln!(f, "RAM {:#05X}..{:#05X}: {} -> {}", run.start, run.start + run.len(), shown(&run.left), shown(&run.right))?;
+        }
+        if !self.screen_rows.is_empty() {
+            let rows: Vec<String> = self.screen_rows.iter().map(usize::to_string).collect();
+            writeln!(f, "screen rows: {}", rows.join(", "))?;
+        }
+        Ok(())
+    }
+}
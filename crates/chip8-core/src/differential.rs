@@ -0,0 +1,160 @@
+//Differential testing: the same ROM and keys on two cores, compared frame by frame. Two Emulators that
+//differ only inside (decode cache on and off, interpreter against a rewrite) or an Emulator against another
+//implementation behind the Core trait. Quirks and the seed are part of the state, so set them the same
+//The first frame they disagree on is reported with what differs and the code both were running, e.g.
+//
+//  let divergence = Differential::new(cached, plain).run(&rom, &inputs, 3600)?;
+//  assert!(divergence.is_none(), "{}", divergence.unwrap());
+
+use crate::chip8::Emulator;
+use crate::diff::{diff, StateDiff};
+use crate::disasm::{disassemble, Line};
+use crate::input::Key;
+use crate::romdb::rom_hash;
+use crate::state::State;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+//Bytes of code shown either side of the program counter
+const CONTEXT_BYTES: u16 = 8;
+
+//What the runner needs of an implementation. Faults and other errors are strings, so a core written
+//elsewhere (behind FFI, in another process) can report its own
+pub trait Core {
+    fn load_rom(&mut self, rom: &[u8]) -> Result<(), String>;
+    //Bit n is key n, held for the frames after until changed
+    fn set_keys(&mut self, keys: u16);
+    fn run_frame(&mut self) -> Result<(), String>;
+    //Everything compared, as an Emulator snapshot
+    fn state(&self) -> State;
+
+    //What is compared every frame, equal for equal states. Override with something cheaper only if it is
+    fn state_hash(&self) -> String {
+        rom_hash(&self.state().to_snapshot_bytes())
+    }
+}
+
+impl Core for Emulator {
+    fn load_rom(&mut self, rom: &[u8]) -> Result<(), String> {
+        Emulator::load_rom(self, rom).map_err(|err| err.to_string())
+    }
+
+    fn set_keys(&mut self, keys: u16) {
+        Emulator::set_keys(self, keys);
+    }
+
+    fn run_frame(&mut self) -> Result<(), String> {
+        Emulator::run_frame(self).map(drop).map_err(|err| err.to_string())
+    }
+
+    fn state(&self) -> State {
+        self.save_state()
+    }
+
+    fn state_hash(&self) -> String {
+        Emulator::state_hash(self)
+    }
+}
+
+//Where the two cores first disagreed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    //Frames both ran before it, 0 if they differed straight after loading
+    pub frame: u64,
+    //Of the left core and the right one. Empty when only their faults differ
+    pub diff: StateDiff,
+    //What each core's run_frame returned on that frame
+    pub left_fault: Option<String>,
+    pub right_fault: Option<String>,
+    //Disassembly round each core's program counter
+    pub left_code: Vec<Line>,
+    pub right_code: Vec<Line>,
+    pub left_pc: u16,
+    pub right_pc: u16,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Diverged after {} frames", self.frame)?;
+        if self.left_fault != self.right_fault {
+            let fault = |fault: &Option<String>| fault.clone().unwrap_or_else(|| "no fault".to_string());
+            writeln!(f, "left: {}, right: {}", fault(&self.left_fault), fault(&self.right_fault))?;
+        }
+        write!(f, "{}", self.diff)?;
+        for (side, pc, code) in [("left", self.left_pc, &self.left_code), ("right", self.right_pc, &self.right_code)] {
+            writeln!(f, "{} at {:#05X}:", side, pc)?;
+            for line in code {
+                writeln!(f, "{} {}", if line.address == pc { ">" } else { " " }, line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct Differential<L, R> {
+    pub left: L,
+    pub right: R,
+}
+
+impl<L: Core, R: Core> Differential<L, R> {
+    pub fn new(left: L, right: R) -> Self {
+        Self { left, right }
+    }
+
+    //Load rom into both and run frames, pressing and releasing keys as ScriptedInput does with the same
+    //(frame, key, pressed) list. None if they agreed all the way
+    //Stops at the first fault when both cores fault the same way, there is nothing to compare after it
+    pub fn run(&mut self, rom: &[u8], inputs: &[(u64, Key, bool)], frames: u64) -> Result<Option<Divergence>, String> {
+        self.left.load_rom(rom)?;
+        self.right.load_rom(rom)?;
+        if let Some(divergence) = self.compare(0, None, None) {
+            return Ok(Some(divergence));
+        }
+        let mut inputs = inputs.to_vec();
+        inputs.sort_by_key(|&(frame, _, _)| frame);
+        let mut inputs = inputs.into_iter().peekable();
+        let mut keys = 0u16;
+        for frame in 0..frames {
+            while let Some((_, key, pressed)) = inputs.next_if(|&(at, _, _)| at <= frame) {
+                keys = if pressed { keys | 1 << key.index() } else { keys & !(1 << key.index()) };
+            }
+            self.left.set_keys(keys);
+            self.right.set_keys(keys);
+            let (left, right) = (self.left.run_frame().err(), self.right.run_frame().err());
+            let faulted = left.is_some() || right.is_some();
+            if let Some(divergence) = self.compare(frame + 1, left, right) {
+                return Ok(Some(divergence));
+            }
+            if faulted {
+                break;
+            }
+        }
+        Ok(None)
+    }
+
+    fn compare(&self, frame: u64, left_fault: Option<String>, right_fault: Option<String>) -> Option<Divergence> {
+        if left_fault == right_fault && self.left.state_hash() == self.right.state_hash() {
+            return None;
+        }
+        let (left, right) = (self.left.state(), self.right.state());
+        Some(Divergence {
+            frame,
+            diff: diff(&left, &right),
+            left_fault,
+            right_fault,
+            left_code: context(&left),
+            right_code: context(&right),
+            left_pc: left.program_counter,
+            right_pc: right.program_counter,
+        })
+    }
+}
+
+//A few instructions either side of the program counter, decoded from the snapshot's RAM
+pub(crate) fn context(state: &State) -> Vec<Line> {
+    let start = state.program_counter.saturating_sub(CONTEXT_BYTES) as usize;
+    let end = (state.program_counter as usize + CONTEXT_BYTES as usize).min(state.ram.len());
+    disassemble(state.ram.get(start..end).unwrap_or_default(), start as u16)
+}
@@ -0,0 +1,230 @@
+use crate::graph::{successors, Flow};
+use crate::instruction::{decode_long, Instruction};
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+//One disassembled instruction (or data word). instruction is None when the word does not decode (usually sprite data)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Line {
+    pub address: u16,
+    pub opcode: u16,
+    pub instruction: Option<Instruction>,
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.instruction {
+            Some(instruction) => write!(f, "{:#05X}: {}", self.address, instruction),
+            None => write!(f, "{:#05X}: DW {:#06X}", self.address, self.opcode),
+        }
+    }
+}
+
+//Linear disassembly of a ROM image (or RAM slice) that starts at origin
+//Instructions are 2 bytes (4 for XO-CHIP's F000 NNNN and MegaChip's 01NN NNNN), a trailing odd byte is
+//emitted as a half word of data
+pub fn disassemble(bytes: &[u8], origin: u16) -> Vec<Line> {
+    disassemble_as(bytes, origin, true)
+}
+
+//disassemble for a program in or out of MegaChip mode, outside it 01NN is 2 bytes like the emulator runs it
+pub fn disassemble_as(bytes: &[u8], origin: u16, megachip: bool) -> Vec<Line> {
+    let word_at = |offset: usize| match bytes.get(offset..offset + 2) {
+        Some(&[left, right]) => Some(((left as u16) << 8) | right as u16),
+        _ => None,
+    };
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    while offset < bytes.len() {
+        let address = origin.wrapping_add(offset as u16);
+        let Some(opcode) = word_at(offset) else {
+            lines.push(Line { address, opcode: (bytes[offset] as u16) << 8, instruction: None });
+            break;
+        };
+        //A long instruction cut off by the end of the slice is shown as data
+        let instruction = decode_long(opcode, word_at(offset + 2).unwrap_or(0), megachip)
+            .ok()
+            .filter(|instruction| offset + instruction.size() as usize <= bytes.len());
+        offset += instruction.map_or(2, |instruction| instruction.size() as usize);
+        lines.push(Line { address, opcode, instruction });
+    }
+    lines
+}
+
+//Text listing, one line per word
+pub fn listing(lines: &[Line]) -> String {
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+//Addresses of listed lines that a listed instruction jumps to, calls or points I at
+pub fn label_targets(lines: &[Line]) -> BTreeSet<u16> {
+    lines
+        .iter()
+        .filter_map(target)
+        .filter(|&address| lines.iter().any(|line| line.address == address))
+        .collect()
+}
+
+fn target(line: &Line) -> Option<u16> {
+    match line.instruction? {
+        Instruction::Jump(address) | Instruction::Call(address) | Instruction::JumpV0(address) | Instruction::LoadI(address) => Some(address),
+        Instruction::LoadILong(address) => Some(address),
+        _ => None,
+    }
+}
+
+//Listing with a label line before every address in label_targets
+//Instructions using a label are annotated with it, e.g. "0x204: CALL 0x20A  ; L20A"
+pub fn labeled_listing(lines: &[Line]) -> String {
+    let labels = label_targets(lines);
+    let mut listing = String::new();
+    for line in lines {
+        if labels.contains(&line.address) {
+            listing.push_str(&format!("L{:03X}:\n", line.address));
+        }
+        match target(line).filter(|address| labels.contains(address)) {
+            Some(address) => listing.push_str(&format!("{}  ; L{:03X}\n", line, address)),
+            None => listing.push_str(&format!("{}\n", line)),
+        }
+    }
+    listing
+}
+
+//Recursive disassembly: what is reachable from the entry point by following jumps, calls and skips is code,
+//the rest is data. BNNN follows NNN only, the first entry of what is usually a jump table
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Analysis {
+    //In address order, data as words with instruction None
+    pub lines: Vec<Line>,
+    //sub_XXX for CALL targets, loop_XXX for backward jumps, label_XXX for other jump targets
+    //and data_XXX for data that ANNN points at
+    pub labels: BTreeMap<u16, String>,
+    //Address after the last byte
+    pub end: u32,
+}
+
+impl Analysis {
+    //Whether an instruction starts at address
+    pub fn is_code(&self, address: u16) -> bool {
+        self.lines.iter().any(|line| line.address == address && line.instruction.is_some())
+    }
+
+    //Byte ranges nothing reaches from the entry point: data, or code only a computed jump could get to
+    pub fn unreached(&self) -> Vec<Range<u32>> {
+        let mut ranges: Vec<Range<u32>> = Vec::new();
+        for (index, line) in self.lines.iter().enumerate() {
+            let end = self.lines.get(index + 1).map_or(self.end, |next| next.address as u32);
+            match ranges.last_mut() {
+                _ if line.instruction.is_some() => {},
+                Some(range) if range.end == line.address as u32 => range.end = end,
+                _ => ranges.push(line.address as u32..end),
+            }
+        }
+        ranges
+    }
+}
+
+pub fn analyze(bytes: &[u8], origin: u16) -> Analysis {
+    let offset = |address: u16| address.checked_sub(origin).map(usize::from).filter(|&offset| offset < bytes.len());
+    let word_at = |address: u16| {
+        let offset = offset(address)?;
+        Some(((bytes[offset] as u16) << 8) | *bytes.get(offset + 1)? as u16)
+    };
+    let decode_at = |address: u16| {
+        let instruction = decode_long(word_at(address)?, word_at(address.wrapping_add(2)).unwrap_or(0), true).ok()?;
+        offset(address.wrapping_add(instruction.size() - 1)).map(|_| instruction)
+    };
+
+    let mut code: BTreeMap<u16, Instruction> = BTreeMap::new();
+    let mut calls = BTreeSet::new();
+    let mut jumps = BTreeMap::new();
+    let mut pointers = BTreeSet::new();
+    let mut pending = vec![origin];
+    while let Some(address) = pending.pop() {
+        if code.contains_key(&address) {
+            continue;
+        }
+        let Some(instruction) = decode_at(address) else {
+            continue;
+        };
+        code.insert(address, instruction);
+        //The instruction a skip jumps over is 4 bytes if it is F000 NNNN
+        let skipped = decode_at(address.wrapping_add(instruction.size())).map_or(2, |instruction| instruction.size());
+        for (target, kind) in successors(instruction, address, skipped) {
+            match kind {
+                Flow::Jump => {
+                    //The furthest jump decides, any jump back to it makes it a loop
+                    let from = jumps.entry(target).or_insert(address);
+                    *from = address.max(*from);
+                },
+                Flow::Call => {
+                    calls.insert(target);
+                },
+                Flow::Next | Flow::Skip => {},
+            }
+            pending.push(target);
+        }
+        if let Instruction::LoadI(target) | Instruction::LoadILong(target) = instruction {
+            pointers.insert(target);
+        }
+    }
+
+    let mut labels = BTreeMap::new();
+    for (&target, &from) in &jumps {
+        let kind = if target <= from { "loop" } else { "label" };
+        labels.insert(target, format!("{}_{:03X}", kind, target));
+    }
+    for &target in &calls {
+        labels.insert(target, format!("sub_{:03X}", target));
+    }
+    let mut lines = Vec::new();
+    let mut address = origin;
+    while let Some(start) = offset(address) {
+        let (line, size) = match code.get(&address) {
+            Some(&instruction) => (Line { address, opcode: word_at(address).unwrap_or(0), instruction: Some(instruction) }, instruction.size()),
+            //A data word stops short of code or labelled data at the next byte, and at the end of the ROM
+            None if code.contains_key(&address.wrapping_add(1)) || pointers.contains(&address.wrapping_add(1)) || start + 1 == bytes.len() => {
+                (Line { address, opcode: (bytes[start] as u16) << 8, instruction: None }, 1)
+            },
+            None => (Line { address, opcode: word_at(address).unwrap_or(0), instruction: None }, 2),
+        };
+        if line.instruction.is_none() && pointers.contains(&address) {
+            labels.insert(address, format!("data_{:03X}", address));
+        }
+        lines.push(line);
+        address = match address.checked_add(size) {
+            Some(next) => next,
+            None => break,
+        };
+    }
+    Analysis { lines, labels, end: origin as u32 + bytes.len() as u32 }
+}
+
+//Listing of an analysis with a "name:" line before each labelled address and labels in place of the
+//addresses instructions use, e.g. "0x204: CALL sub_2A4"
+pub fn symbolic_listing(analysis: &Analysis) -> String {
+    let mut listing = String::new();
+    for (index, line) in analysis.lines.iter().enumerate() {
+        let next = analysis.lines.get(index + 1).map_or(analysis.end, |next| next.address as u32);
+        if let Some(label) = analysis.labels.get(&line.address) {
+            listing.push_str(&format!("{}:\n", label));
+        }
+        let text = match line.instruction {
+            Some(_) => line.to_string(),
+            None if next == line.address as u32 + 1 => format!("{:#05X}: DB {:#04X}", line.address, line.opcode >> 8),
+            None => format!("{:#05X}: DB {:#04X}, {:#04X}", line.address, line.opcode >> 8, line.opcode & 0xFF),
+        };
+        //Every instruction with a target has it as its last operand
+        match target(line).and_then(|address| analysis.labels.get(&address)).zip(text.rfind("0x")) {
+            Some((label, at)) => listing.push_str(&format!("{}{}\n", &text[..at], label)),
+            None => listing.push_str(&format!("{}\n", text)),
+        }
+    }
+    listing
+}
@@ -0,0 +1,47 @@
+//Push based display backends: the emulator hands each frame that changed the screen to a Display, instead of
+//the frontend checking screen_changed and pulling get_screen or frame_buffer (which keep working either way)
+
+use crate::framebuffer::FrameBuffer;
+use crate::megachip::MegaChip;
+
+//A finished frame, everything a backend needs to show it
+#[derive(Debug, Clone, Copy)]
+pub struct Frame<'a> {
+    pub screen: &'a FrameBuffer,
+    //Shown instead of screen while the program is in MegaChip mode
+    pub megachip: Option<&'a MegaChip>,
+    //Rows of screen changed since the frame before, bit n is row n
+    pub dirty_rows: u64,
+    //The sound timer is running
+    pub sound: bool,
+}
+
+impl Frame<'_> {
+    //Of the screen shown, 256x192 in MegaChip mode
+    pub fn width(&self) -> usize {
+        self.megachip.map_or(self.screen.width(), |mega| mega.width())
+    }
+
+    pub fn height(&self) -> usize {
+        self.megachip.map_or(self.screen.height(), |mega| mega.height())
+    }
+
+    //Lit in any plane, of the classic screen (MegaChip pixels are colours, see MegaChip::pixel)
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        self.screen.is_lit(x, y)
+    }
+
+    //The classic screen's lit pixels row by row, see FrameBuffer::rows
+    pub fn rows(&self) -> impl Iterator<Item = &[bool]> + '_ {
+        self.screen.rows()
+    }
+}
+
+//Set one with Emulator::set_display (which a Runner's thread then drives too), or pass one to
+//Emulator::run_frame_to when it cannot leave the thread, as with an SDL window
+pub trait Display {
+    //The program cleared the screen (00E0, a resolution or mode switch) or the emulator was reset, before
+    //the frame it happened in is drawn. For backends that only redraw dirty rows
+    fn clear(&mut self) {}
+    fn draw_frame(&mut self, frame: &Frame);
+}
@@ -0,0 +1,70 @@
+//Description of what a single executed instruction did
+//Filled in by the emulator while it executes, read back with Emulator::last_effects()
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Effects {
+    //Bitmask of written V registers (bit n = Vn)
+    pub registers_written: u16,
+    pub i_written: bool,
+    pub memory_written: Option<MemoryWrite>,
+    //The part of a write that ran off the end of RAM and carried on from 0, memory_written has the rest
+    pub memory_wrapped: Option<MemoryWrite>,
+    //New program counter when the instruction did not fall through (jump, call, return, skip)
+    pub branch_taken: Option<u16>,
+    pub screen_written: bool,
+}
+
+//Contiguous range of RAM written by FX33/FX55/5XY2
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryWrite {
+    pub address: u16,
+    pub len: u16,
+}
+
+impl MemoryWrite {
+    pub fn contains(&self, address: u16) -> bool {
+        (self.address as u32..self.end()).contains(&(address as u32))
+    }
+
+    fn end(&self) -> u32 {
+        self.address as u32 + self.len as u32
+    }
+
+    fn extend(&mut self, address: u16) {
+        let start = self.address.min(address);
+        self.len = (self.end().max(address as u32 + 1) - start as u32) as u16;
+        self.address = start;
+    }
+}
+
+impl Effects {
+    pub fn wrote_register(&self, register: u8) -> bool {
+        self.registers_written & (1 << register) != 0
+    }
+
+    //Iterate over the written V register indices in ascending order
+    pub fn registers(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..16).filter(move |&register| self.wrote_register(register))
+    }
+
+    pub(crate) fn record_register(&mut self, register: u8) {
+        self.registers_written |= 1 << register;
+    }
+
+    //Both written ranges, the wrapped part second
+    pub fn memory_writes(&self) -> impl Iterator<Item = MemoryWrite> {
+        self.memory_written.into_iter().chain(self.memory_wrapped)
+    }
+
+    pub fn wrote_memory(&self, address: u16) -> bool {
+        self.memory_writes().any(|write| write.contains(address))
+    }
+
+    //Writes go up from I, so a byte below where the write started means it ran off the end of RAM
+    pub(crate) fn record_memory(&mut self, address: u16) {
+        match (&mut self.memory_written, &mut self.memory_wrapped) {
+            (Some(write), None) if address < write.address => self.memory_wrapped = Some(MemoryWrite { address, len: 1 }),
+            (Some(write), None) | (Some(_), Some(write)) => write.extend(address),
+            _ => self.memory_written = Some(MemoryWrite { address, len: 1 }),
+        }
+    }
+}
@@ -0,0 +1,380 @@
+use alloc::string::String;
+use core::error::Error;
+use core::fmt;
+
+//Faults a ROM can cause while running, returned by Emulator::tick
+//The program counter is left on the faulting instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Chip8Error {
+    UnknownOpcode { pc: u16, opcode: u16 },
+    //2NNN with all 16 stack entries in use
+    StackOverflow { pc: u16 },
+    //00EE with an empty stack
+    StackUnderflow { pc: u16 },
+    //EX9E/EXA1 with a key number above 0xF
+    InvalidKey { pc: u16, key: u8 },
+    //FX33, FX55 or 5XY2 writing to memory made read-only with Emulator::set_memory_protection
+    ProtectedWrite { pc: u16, address: u16 },
+    //A panic inside the emulator that Emulator::tick_checked caught, a bug in this crate rather than the ROM
+    Internal { pc: u16 },
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Chip8Error::UnknownOpcode { pc, opcode } => write!(f, "Unknown opcode {:#06X} at {:#05X}", opcode, pc),
+            Chip8Error::StackOverflow { pc } => write!(f, "Stack overflow at {:#05X}", pc),
+            Chip8Error::StackUnderflow { pc } => write!(f, "Stack underflow at {:#05X}", pc),
+            Chip8Error::InvalidKey { pc, key } => write!(f, "Invalid key {:#04X} at {:#05X}", key, pc),
+            Chip8Error::ProtectedWrite { pc, address } => write!(f, "Write to protected memory {:#05X} at {:#05X}", address, pc),
+            Chip8Error::Internal { pc } => write!(f, "Emulator bug at {:#05X}", pc),
+        }
+    }
+}
+
+impl Error for Chip8Error {}
+
+//Why Emulator::load_rom refused a ROM image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RomError {
+    Empty,
+    //size is the length of the ROM, max the most that fits in RAM after the load address
+    TooLarge { size: usize, max: usize },
+    //Programs cannot load over the fonts or past the end of memory, min is the lowest allowed address
+    InvalidLoadAddress { address: u16, min: u16 },
+}
+
+impl fmt::Display for RomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            RomError::Empty => write!(f, "The ROM is empty"),
+            RomError::TooLarge { size, max } => write!(f, "The ROM is {} bytes, the most that fits in memory is {} bytes", size, max),
+            RomError::InvalidLoadAddress { address, min } => {
+                write!(f, "A ROM cannot load at {:#05X}, the lowest load address is {:#05X}", address, min)
+            },
+        }
+    }
+}
+
+impl Error for RomError {}
+
+//Why Emulator::set_fontset or set_big_fontset refused a font: 5 bytes a digit for the small one, 10 for the big one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FontError {
+    pub len: usize,
+    pub expected: usize,
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The font is {} bytes, expected {} (16 digits)", self.len, self.expected)
+    }
+}
+
+impl Error for FontError {}
+
+//Why EmulatorBuilder::build refused its settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildError {
+    //ticks_per_frame(0), nothing would ever run
+    NoTicks,
+    //A seed and an RNG both, only one can decide CXNN
+    SeedAndRng,
+    //The start address, or the ROM at it
+    Rom(RomError),
+    Font(FontError),
+    MemoryMap(MemoryMapError),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::NoTicks => write!(f, "At least one instruction a frame is needed"),
+            BuildError::SeedAndRng => write!(f, "A seed and an RNG cannot both be set"),
+            BuildError::Rom(error) => write!(f, "{}", error),
+            BuildError::Font(error) => write!(f, "{}", error),
+            BuildError::MemoryMap(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for BuildError {}
+
+//Why Emulator::set_memory_map refused a MemoryMap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryMapError {
+    //Not a power of two from MIN_RAM_SIZE to 64 KB
+    Size { size: usize },
+    FontsOverlap,
+    //Below the fonts or too close to the end of RAM for an instruction
+    StartAddress { address: u16, min: u16 },
+}
+
+impl fmt::Display for MemoryMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            MemoryMapError::Size { size } => write!(f, "RAM of {} bytes is not a power of two from {} to {}", size, crate::memory::MIN_RAM_SIZE, crate::chip8::RAM_SIZE),
+            MemoryMapError::FontsOverlap => write!(f, "The small and big fonts overlap"),
+            MemoryMapError::StartAddress { address, min } => {
+                write!(f, "Start address 0x{:03X} is below the fonts (0x{:03X}) or past the end of RAM", address, min)
+            },
+        }
+    }
+}
+
+impl Error for MemoryMapError {}
+
+//Why Emulator::load_state refused a State, or State::from_snapshot_bytes could not read one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateError {
+    //Not the size of the emulator's MemoryMap
+    RamSize { len: usize, expected: usize },
+    ScreenSize { len: usize },
+    StackTooDeep { depth: usize },
+    //No snapshot magic at the start
+    NotASnapshot,
+    //Written by a newer version of the crate
+    UnsupportedVersion { version: u16 },
+    Truncated,
+    Corrupt,
+    //Saved in MegaChip mode (or with a ROM past RAM) by an emulator with Emulator::set_megachip on
+    MegaChipOff,
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            StateError::RamSize { len, expected } => write!(f, "Saved RAM is {} bytes, expected {}", len, expected),
+            StateError::ScreenSize { len } => write!(f, "Saved screen is {} pixels, expected {}", len, crate::framebuffer::SCREEN_BUFFER_SIZE),
+            StateError::StackTooDeep { depth } => write!(f, "Saved stack has {} entries, the most is {}", depth, crate::chip8::STACK_SIZE),
+            StateError::NotASnapshot => write!(f, "Not a CHIP-8 snapshot"),
+            StateError::UnsupportedVersion { version } => write!(f, "Snapshot version {} is newer than this emulator supports ({})", version, crate::state::SNAPSHOT_VERSION),
+            StateError::Truncated => write!(f, "Snapshot is truncated"),
+            StateError::Corrupt => write!(f, "Snapshot is corrupt"),
+            StateError::MegaChipOff => write!(f, "Snapshot needs MegaChip support, which is turned off"),
+        }
+    }
+}
+
+impl Error for StateError {}
+
+//Problems reading or playing back a Replay
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayError {
+    Malformed { line: usize, message: String },
+    //The replay was recorded with a different ROM (SHA-1 of the expected ROM)
+    RomMismatch { expected: String },
+    Rom(RomError),
+    //The program faulted during playback
+    Fault(Chip8Error),
+    //A Session ended in another state than recorded (Emulator::state_hash of both)
+    Diverged { expected: String, actual: String },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Malformed { line, message } => write!(f, "Replay line {}: {}", line, message),
+            ReplayError::RomMismatch { expected } => write!(f, "Replay was recorded with another ROM (SHA-1 {})", expected),
+            ReplayError::Rom(error) => write!(f, "{}", error),
+            ReplayError::Fault(error) => write!(f, "Replay faulted: {}", error),
+            ReplayError::Diverged { expected, actual } => write!(f, "Replay ended in state {} instead of {}", actual, expected),
+        }
+    }
+}
+
+impl Error for ReplayError {}
+
+//Why Chip8::run_rom_file stopped early
+#[derive(Debug)]
+pub enum RunError {
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    Rom(RomError),
+    Fault(Chip8Error),
+    //The window could not be opened or drawn to
+    Frontend(String),
+}
+
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            RunError::Io(error) => write!(f, "{}", error),
+            RunError::Rom(error) => write!(f, "{}", error),
+            RunError::Fault(error) => write!(f, "{}", error),
+            RunError::Frontend(message) => write!(f, "Frontend error: {}", message),
+        }
+    }
+}
+
+impl Error for RunError {}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for RunError {
+    fn from(error: std::io::Error) -> Self {
+        RunError::Io(error)
+    }
+}
+
+impl From<RomError> for RunError {
+    fn from(error: RomError) -> Self {
+        RunError::Rom(error)
+    }
+}
+
+impl From<Chip8Error> for RunError {
+    fn from(error: Chip8Error) -> Self {
+        RunError::Fault(error)
+    }
+}
+
+//A line of a cheat file that could not be read, see Cheats::parse
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheatError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for CheatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cheat line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for CheatError {}
+
+//A line of a debugger session file that could not be read, see DebugSession::parse
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugSessionError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for DebugSessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Debugger session line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for DebugSessionError {}
+
+//Why Emulator::load_rom_from_path refused a file
+#[derive(Debug)]
+pub enum RomFileError {
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    //Obviously something else, kind says what it looks like ("a PNG image")
+    NotARom { kind: &'static str },
+    //Too big for RAM (found before reading it), empty or at a bad load address
+    Rom(RomError),
+}
+
+impl fmt::Display for RomFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            RomFileError::Io(error) => write!(f, "{}", error),
+            RomFileError::NotARom { kind } => write!(f, "Not a CHIP-8 ROM, the file looks like {}", kind),
+            RomFileError::Rom(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for RomFileError {}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for RomFileError {
+    fn from(error: std::io::Error) -> Self {
+        RomFileError::Io(error)
+    }
+}
+
+impl From<RomError> for RomFileError {
+    fn from(error: RomError) -> Self {
+        RomFileError::Rom(error)
+    }
+}
+
+//Why a StateSlots slot could not be saved or loaded
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub enum SlotError {
+    Io(std::io::Error),
+    //Nothing was saved in it
+    Empty { name: String },
+    //Slot names become file names: letters, digits, '-' and '_' only
+    BadName { name: String },
+    //Saved while another ROM was running (SHA-1 of that ROM)
+    RomMismatch { expected: String },
+    State(StateError),
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for SlotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SlotError::Io(error) => write!(f, "{}", error),
+            SlotError::Empty { name } => write!(f, "Slot {} is empty", name),
+            SlotError::BadName { name } => write!(f, "{:?} is not a slot name, use letters, digits, '-' and '_'", name),
+            SlotError::RomMismatch { expected } => write!(f, "Slot was saved with another ROM (SHA-1 {})", expected),
+            SlotError::State(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for SlotError {}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for SlotError {
+    fn from(error: std::io::Error) -> Self {
+        SlotError::Io(error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<StateError> for SlotError {
+    fn from(error: StateError) -> Self {
+        SlotError::State(error)
+    }
+}
+
+//Why a CoreDump could not be written or read back
+#[derive(Debug)]
+pub enum CoreDumpError {
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    //No core dump magic at the start, or from a newer version of the crate
+    NotACoreDump,
+    Truncated,
+    State(StateError),
+}
+
+impl fmt::Display for CoreDumpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            CoreDumpError::Io(error) => write!(f, "{}", error),
+            CoreDumpError::NotACoreDump => write!(f, "Not a CHIP-8 core dump"),
+            CoreDumpError::Truncated => write!(f, "Core dump is truncated"),
+            CoreDumpError::State(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for CoreDumpError {}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for CoreDumpError {
+    fn from(error: std::io::Error) -> Self {
+        CoreDumpError::Io(error)
+    }
+}
+
+impl From<StateError> for CoreDumpError {
+    fn from(error: StateError) -> Self {
+        CoreDumpError::State(error)
+    }
+}
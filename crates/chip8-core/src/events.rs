@@ -0,0 +1,133 @@
+use crate::chip8::EndReason;
+use crate::error::Chip8Error;
+
+use core::fmt;
+
+//Events raised by the emulator while it runs, drained by the frontend with Emulator::take_events()
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Warning(Warning),
+    //Only raised with invariant checks on, see Emulator::set_invariant_checks
+    Violation(Violation),
+    //Only raised after Emulator::set_lifecycle_events(true)
+    Lifecycle(Lifecycle),
+}
+
+//What the program did, for frontends that update their UI when something happens instead of every frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lifecycle {
+    //00E0, or a resolution switch (00FE/00FF) clearing the display
+    ScreenCleared,
+    //DXYN at (x, y) after wrapping, rows tall (16 for DXY0), collision is what it set VF to
+    SpriteDrawn { x: u8, y: u8, rows: u8, collision: bool },
+    //A fault stopped the program, tick keeps returning error until reset
+    Halted { error: Chip8Error },
+    //00FD (SCHIP)
+    Exited,
+    //The program ended in a loop it cannot leave, see EmulatorState::Finished
+    Finished { pc: u16, reason: EndReason },
+    //The sound timer went from zero to non zero, and back
+    SoundStarted,
+    SoundStopped,
+}
+
+//Suspicious but non-fatal behavior, execution carries on after these
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Warning {
+    //Strict mode: an instruction at pc touched memory reserved for the interpreter
+    //(0x000-0x1FF, outside the font when drawing) or the VIP stack/display area (0xEA0-0xFFF)
+    ReservedMemoryAccess { pc: u16, address: u16, access: Access },
+    //The 2NNN at pc left depth of the 16 stack entries in use, a few more calls overflow it
+    StackNearlyFull { pc: u16, depth: u8 },
+    //The lowest stack depth of each frame kept rising over many frames without ever dropping,
+    //usually a subroutine left with a jump instead of 00EE
+    CallImbalance { depth: u8 },
+}
+
+//An invariant that stopped holding during the tick of the instruction at pc. The emulator carries on,
+//these point at a program gone astray (or a bug in the emulator) before it corrupts anything further
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Violation {
+    //PC went from even to the odd target, legal but nearly always a jump into data
+    UnalignedPc { pc: u16, target: u16 },
+    //PC at the last byte of RAM, the instruction there would wrap around to 0x000
+    PcPastMemory { pc: u16, target: u16 },
+    //More return addresses than the 16 entry stack holds
+    StackPointer { pc: u16, depth: u16 },
+    //A memory instruction's [address, address+len) ran past the end of RAM and wrapped around to 0x000
+    MemoryWrap { pc: u16, address: u16, len: u16 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    //FX65
+    Read,
+    //FX55
+    Write,
+    //DXYN sprite data
+    Sprite,
+}
+
+//One line for a log or a status bar, e.g. "Warning: Instruction at 0x214 wrote reserved memory at 0x1F0"
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::Warning(warning) => write!(f, "Warning: {}", warning),
+            Event::Violation(violation) => write!(f, "Invariant broken: {}", violation),
+            Event::Lifecycle(lifecycle) => write!(f, "{}", lifecycle),
+        }
+    }
+}
+
+impl fmt::Display for Lifecycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Lifecycle::ScreenCleared => write!(f, "Screen cleared"),
+            Lifecycle::SpriteDrawn { x, y, rows, collision } => {
+                write!(f, "Sprite drawn at ({}, {}), {} rows{}", x, y, rows, if collision { ", collided" } else { "" })
+            },
+            Lifecycle::Halted { error } => write!(f, "Halted: {}", error),
+            Lifecycle::Exited => write!(f, "Exited"),
+            Lifecycle::Finished { pc, reason } => write!(f, "Finished: {} at {:#05X}", reason, pc),
+            Lifecycle::SoundStarted => write!(f, "Sound started"),
+            Lifecycle::SoundStopped => write!(f, "Sound stopped"),
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Warning::ReservedMemoryAccess { pc, address, access } => {
+                write!(f, "Instruction at {:#05X} {} reserved memory at {:#05X}", pc, access.verb(), address)
+            },
+            Warning::StackNearlyFull { pc, depth } => write!(f, "Call at {:#05X} left {} of 16 stack entries in use", pc, depth),
+            Warning::CallImbalance { depth } => {
+                write!(f, "Stack depth keeps rising ({} now), a subroutine may be left with a jump instead of 00EE", depth)
+            },
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Violation::UnalignedPc { pc, target } => write!(f, "Jump at {:#05X} to odd address {:#05X}", pc, target),
+            Violation::PcPastMemory { pc, target } => write!(f, "PC at the end of RAM ({:#05X}) after {:#05X}", target, pc),
+            Violation::StackPointer { pc, depth } => write!(f, "Stack {} deep after {:#05X}, the most is 16", depth, pc),
+            Violation::MemoryWrap { pc, address, len } => {
+                write!(f, "{} bytes from {:#05X} at {:#05X} wrapped round the end of RAM", len, address, pc)
+            },
+        }
+    }
+}
+
+impl Access {
+    fn verb(self) -> &'static str {
+        match self {
+            Access::Read => "read",
+            Access::Write => "wrote",
+            Access::Sprite => "drew from",
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use crate::audio::playback_rate;
+use crate::chip8::Emulator;
+use crate::instruction::Instruction;
+use crate::megachip::BlendMode;
+
+use alloc::format;
+use alloc::string::{String, ToString};
+
+//Human readable explanation of what instruction is about to do, using the register values in
+//emulator before it executes (the program counter already points at the next instruction)
+pub fn explain(instruction: Instruction, emulator: &Emulator) -> String {
+    let v = |register: u8| emulator.v_register(register);
+    let quirks = emulator.quirks();
+    let vf_reset = if quirks.vf_reset { "; VF reset to 0" } else { "" };
+    let display_wait = if quirks.display_wait { "; then waits for the next frame" } else { "" };
+    let i_increment = if quirks.load_store_increments_i { ", then I moves past the last register" } else { "" };
+    let skip = |taken: bool| if taken { "so the next instruction is skipped" } else { "so execution continues" };
+
+    match instruction {
+        Instruction::Nop => "Do nothing".to_string(),
+        Instruction::ClearScreen if emulator.megachip().is_some() => {
+            "Show the frame drawn so far and start the next one on a cleared buffer".to_string()
+        },
+        Instruction::ClearScreen if emulator.selected_planes() != 1 => {
+            format!("Clear the screen (selected planes {:#04b})", emulator.selected_planes())
+        },
+        Instruction::ClearScreen => "Clear the screen".to_string(),
+        Instruction::Return => match emulator.stack_top() {
+            Some(address) => format!("Return from subroutine to {:#05X}", address),
+            None => "Return from subroutine, but the stack is empty".to_string(),
+        },
+        Instruction::MegaOff => "Leave MegaChip mode for the 64x32 screen".to_string(),
+        Instruction::MegaOn => "Switch to the MegaChip 256x192 colour screen".to_string(),
+        Instruction::LoadIMega(nnnnnn) => format!("I = {:#08X}", nnnnnn),
+        Instruction::LoadPalette(0x30) if emulator.frame_buffer().is_two_page() && emulator.megachip().is_none() => {
+            "Clear the screen".to_string()
+        },
+        Instruction::LoadPalette(nn) => format!(
+            "Load {} ARGB colours from I = {:#08X} into palette entries 1 to {}",
+            nn, emulator.mega_address(), nn
+        ),
+        Instruction::SpriteWidth(nn) => format!("Sprites are {} pixels wide", if nn == 0 { 256 } else { nn as u16 }),
+        Instruction::SpriteHeight(nn) => format!("Sprites are {} pixels high", if nn == 0 { 256 } else { nn as u16 }),
+        Instruction::ScreenAlpha(nn) => format!("Screen alpha = {:#04X}", nn),
+        Instruction::PlaySample(n) => format!(
+            "Play the digitised sound at I = {:#08X}{}",
+            emulator.mega_address(), if n == 0 { " on a loop" } else { " once" }
+        ),
+        Instruction::StopSample => "Stop the digitised sound".to_string(),
+        Instruction::Blend(n) => format!("Blend sprites with {:?}", BlendMode::from_n(n)),
+        Instruction::CollisionColor(nn) => format!("Drawing over palette entry {} sets VF", nn),
+        Instruction::ScrollUp(n) => format!("Scroll the display up {} pixels", n),
+        Instruction::CycleBackground if emulator.megachip().is_some() => format!(
+            "Load 160 ARGB colours from I = {:#08X} into palette entries 1 to 160",
+            emulator.mega_address()
+        ),
+        Instruction::CycleBackground => "Switch to the next background colour".to_string(),
+        Instruction::AddNibbles { x, y } => format!(
+            "V{:X} += V{:X} nibble by nibble, each mod 8: {:#04X} + {:#04X}",
+            x, y, v(x), v(y)
+        ),
+        Instruction::Foreground { x, y, n: 0 } => format!(
+            "Colour the zones at V{:X}, V{:X} = ({:#04X}, {:#04X}) in colour V{:X} = {}",
+            x, (x + 1) & 0xF, v(x), v((x + 1) & 0xF), y, v(y) & 0b111
+        ),
+        Instruction::Foreground { x, y, n } => format!(
+            "Colour {} rows of the column at (V{:X}, V{:X}) = ({}, {}) in colour V{:X} = {}",
+            n, x, (x + 1) & 0xF, v(x), v((x + 1) & 0xF), y, v(y) & 0b111
+        ),
+        Instruction::ScrollDown(n) => format!("Scroll the display down {} pixels", n),
+        Instruction::ScrollRight => "Scroll the display right 4 pixels".to_string(),
+        Instruction::ScrollLeft => "Scroll the display left 4 pixels".to_string(),
+        Instruction::Exit => "Exit the interpreter, the program stops here".to_string(),
+        Instruction::LowRes => "Switch to 64x32 low resolution and clear the screen".to_string(),
+        Instruction::HighRes => "Switch to 128x64 high resolution and clear the screen".to_string(),
+        Instruction::Jump(nnn) => {
+            if nnn + 2 == emulator.program_counter() {
+                format!("Jump to {:#05X}, which is this instruction: the program loops here forever", nnn)
+            } else {
+                format!("Jump to {:#05X}", nnn)
+            }
+        },
+        Instruction::Call(nnn) => format!(
+            "Call subroutine at {:#05X}, pushing return address {:#05X}",
+            nnn, emulator.program_counter()
+        ),
+        Instruction::SkipEqImm { x, nn } => format!(
+            "Skip if V{:X} == {:#04X}: V{:X} is {:#04X}, {}",
+            x, nn, x, v(x), skip(v(x) == nn)
+        ),
+        Instruction::SkipNeImm { x, nn } => format!(
+            "Skip if V{:X} != {:#04X}: V{:X} is {:#04X}, {}",
+            x, nn, x, v(x), skip(v(x) != nn)
+        ),
+        Instruction::SkipEqReg { x, y } => format!(
+            "Skip if V{:X} == V{:X}: {:#04X} vs {:#04X}, {}",
+            x, y, v(x), v(y), skip(v(x) == v(y))
+        ),
+        Instruction::SkipNeReg { x, y } => format!(
+            "Skip if V{:X} != V{:X}: {:#04X} vs {:#04X}, {}",
+            x, y, v(x), v(y), skip(v(x) != v(y))
+        ),
+        Instruction::SaveRange { x, y } => format!(
+            "Store V{:X}..=V{:X} in memory starting at I = {:#05X}; I is not changed",
+            x, y, emulator.i_register()
+        ),
+        Instruction::LoadRange { x, y } => format!(
+            "Load V{:X}..=V{:X} from memory starting at I = {:#05X}; I is not changed",
+            x, y, emulator.i_register()
+        ),
+        Instruction::LoadImm { x, nn } => format!("V{:X} = {:#04X}", x, nn),
+        Instruction::AddImm { x, nn } => format!(
+            "V{:X} += {:#04X} ({:#04X} + {:#04X} = {:#04X}); VF is not changed",
+            x, nn, v(x), nn, v(x).wrapping_add(nn)
+        ),
+        Instruction::LoadReg { x, y } => format!("V{:X} = V{:X} ({:#04X})", x, y, v(y)),
+        Instruction::Or { x, y } => format!(
+            "V{:X} |= V{:X} ({:#04X} | {:#04X} = {:#04X}){}",
+            x, y, v(x), v(y), v(x) | v(y), vf_reset
+        ),
+        Instruction::And { x, y } => format!(
+            "V{:X} &= V{:X} ({:#04X} & {:#04X} = {:#04X}){}",
+            x, y, v(x), v(y), v(x) & v(y), vf_reset
+        ),
+        Instruction::Xor { x, y } => format!(
+            "V{:X} ^= V{:X} ({:#04X} ^ {:#04X} = {:#04X}){}",
+            x, y, v(x), v(y), v(x) ^ v(y), vf_reset
+        ),
+        Instruction::AddReg { x, y } => {
+            let (result, carry) = v(x).overflowing_add(v(y));
+            let reason = if carry { "carry set because" } else { "carry cleared because" };
+            let outcome = if carry { "overflows" } else { "fits in a byte" };
+            format!(
+                "V{:X} += V{:X} ({:#04X} + {:#04X} = {:#04X}); {} {:#04X} + {:#04X} {}",
+                x, y, v(x), v(y), result, reason, v(x), v(y), outcome
+            )
+        },
+        Instruction::SubReg { x, y } => {
+            let (result, borrow) = v(x).overflowing_sub(v(y));
+            format!(
+                "V{:X} -= V{:X} ({:#04X} - {:#04X} = {:#04X}); {}",
+                x, y, v(x), v(y), result, borrow_reason(v(x), v(y), borrow)
+            )
+        },
+        Instruction::SubN { x, y } => {
+            let (result, borrow) = v(y).overflowing_sub(v(x));
+            format!(
+                "V{:X} = V{:X} - V{:X} ({:#04X} - {:#04X} = {:#04X}); {}",
+                x, y, x, v(y), v(x), result, borrow_reason(v(y), v(x), borrow)
+            )
+        },
+        Instruction::ShiftRight { x, y } => {
+            let source = if quirks.shift_uses_vy { y } else { x };
+            format!(
+                "V{:X} = V{:X} >> 1 ({:#04X} -> {:#04X}); VF = {}, the bit shifted out",
+                x, source, v(source), v(source) >> 1, v(source) & 1
+            )
+        },
+        Instruction::ShiftLeft { x, y } => {
+            let source = if quirks.shift_uses_vy { y } else { x };
+            format!(
+                "V{:X} = V{:X} << 1 ({:#04X} -> {:#04X}); VF = {}, the bit shifted out",
+                x, source, v(source), v(source) << 1, v(source) >> 7
+            )
+        },
+        Instruction::LoadI(nnn) => format!("I = {:#05X}", nnn),
+        Instruction::JumpV0(nnn) => {
+            let register = if quirks.jump_uses_vx { (nnn >> 8) as u8 } else { 0 };
+            format!(
+                "Jump to V{:X} + {:#05X} ({:#04X} + {:#05X} = {:#05X})",
+                register, nnn, v(register), nnn, v(register) as u16 + nnn
+            )
+        },
+        Instruction::Random { x, nn } => format!("V{:X} = random byte AND {:#04X}", x, nn),
+        Instruction::Draw { x, y, .. } if emulator.megachip().is_some() => format!(
+            "Draw the colour sprite from I = {:#08X} at (V{:X}, V{:X}) = ({}, {}); VF = 1 if it covers the collision colour",
+            emulator.mega_address(), x, y, v(x), v(y)
+        ),
+        Instruction::Draw { x, y, n: 0 } => format!(
+            "Draw 16x16 sprite from I = {:#05X} at (V{:X}, V{:X}) = ({}, {}); VF = 1 if any lit pixel is erased{}",
+            emulator.i_register(), x, y, v(x), v(y), display_wait
+        ),
+        Instruction::Draw { x, y, n } => format!(
+            "Draw {} row sprite from I = {:#05X} at (V{:X}, V{:X}) = ({}, {}); VF = 1 if any lit pixel is erased{}",
+            n, emulator.i_register(), x, y, v(x), v(y), display_wait
+        ),
+        Instruction::SkipKeyPressed { x } => {
+            let pressed = emulator.is_key_pressed(v(x));
+            format!(
+                "Skip if key V{:X} ({:X}) is pressed: it is {}, {}",
+                x, v(x), if pressed { "down" } else { "up" }, skip(pressed)
+            )
+        },
+        Instruction::SkipKeyNotPressed { x } => {
+            let pressed = emulator.is_key_pressed(v(x));
+            format!(
+                "Skip if key V{:X} ({:X}) is not pressed: it is {}, {}",
+                x, v(x), if pressed { "down" } else { "up" }, skip(!pressed)
+            )
+        },
+        Instruction::LoadILong(nnnn) => format!("I = {:#06X}", nnnn),
+        Instruction::Plane(n) => match n & 0b11 {
+            0 => "Select no planes: drawing, clearing and scrolling do nothing".to_string(),
+            3 => "Select both planes for drawing, clearing and scrolling".to_string(),
+            plane => format!("Select plane {} for drawing, clearing and scrolling", plane),
+        },
+        Instruction::LoadAudio => format!("Load the 16 byte audio pattern from I = {:#05X}", emulator.i_register()),
+        Instruction::SetPitch { x } => {
+            format!("Pitch = V{:X} ({}), the audio pattern plays at {:.0} Hz", x, v(x), playback_rate(v(x)))
+        },
+        Instruction::LoadDelay { x } => format!("V{:X} = delay timer ({})", x, emulator.delay_timer()),
+        Instruction::WaitKey { x } if quirks.wait_key_on_release => {
+            format!("Wait for a key to be pressed and released and store the key in V{:X}; execution pauses until then", x)
+        },
+        Instruction::WaitKey { x } => format!("Wait for a key press and store the key in V{:X}; execution pauses until then", x),
+        Instruction::SetDelay { x } => format!("Delay timer = V{:X} ({})", x, v(x)),
+        Instruction::SetSound { x } => format!("Sound timer = V{:X} ({}), the buzzer sounds while it is non-zero", x, v(x)),
+        Instruction::AddI { x } => {
+            let i = emulator.i_register().wrapping_add(v(x) as u16);
+            let overflow = match quirks.add_i_overflow {
+                true if i > 0xFFF => "; VF = 1 because I passed 0xFFF (overflow quirk)",
+                true => "; VF = 0 because I stays within 0xFFF (overflow quirk)",
+                false => "",
+            };
+            format!("I += V{:X} ({:#05X} + {:#04X} = {:#05X}){}", x, emulator.i_register(), v(x), i, overflow)
+        },
+        Instruction::LoadFont { x } => format!("I = address of the font sprite for digit {:X} (V{:X})", v(x) & 0xF, x),
+        Instruction::LoadBigFont { x } => format!("I = address of the big font sprite for digit {:X} (V{:X})", v(x) & 0xF, x),
+        Instruction::StoreBcd { x } => format!(
+            "Store the decimal digits of V{:X} ({}) at I = {:#05X}, I+1 and I+2",
+            x, v(x), emulator.i_register()
+        ),
+        Instruction::StoreRegs { x } => format!(
+            "Store V0..=V{:X} in memory starting at I = {:#05X}{}",
+            x, emulator.i_register(), i_increment
+        ),
+        Instruction::LoadRegs { x } => format!(
+            "Load V0..=V{:X} from memory starting at I = {:#05X}{}",
+            x, emulator.i_register(), i_increment
+        ),
+        Instruction::SaveFlags { x } => format!("Store V0..=V{:X} in the RPL user flags, they outlast the program", x),
+        Instruction::LoadFlags { x } => format!("Load V0..=V{:X} from the RPL user flags", x),
+    }
+}
+
+fn borrow_reason(minuend: u8, subtrahend: u8, borrow: bool) -> String {
+    if borrow {
+        format!("VF cleared because {:#04X} > {:#04X} (borrow)", subtrahend, minuend)
+    } else {
+        format!("VF set because {:#04X} >= {:#04X} (no borrow)", minuend, subtrahend)
+    }
+}
@@ -0,0 +1,188 @@
+use crate::chip8::{Emulator, EmulatorState};
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+//Human readable dump of the full emulator state, for bug reports and diffing with text tools
+//Not meant to be loaded back, the binary savestate is for that
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Toml,
+    Json,
+}
+
+//Bytes per line in the RAM dump
+const ROW_SIZE: usize = 16;
+//Runs of zeros shorter than this do not split a RAM range
+const MIN_ZERO_GAP: usize = 16;
+
+pub fn export_state(emulator: &Emulator, format: Format) -> String {
+    let state = collect(emulator);
+    match format {
+        Format::Toml => to_toml(&state),
+        Format::Json => to_json(&state),
+    }
+}
+
+//A field value, kept simple enough to print as either format
+enum Value {
+    Bool(bool),
+    Number(u32),
+    //Printed as hex in TOML, JSON has no hex literals so it gets decimal
+    Hex(u32),
+    Text(String),
+    List(Vec<Value>),
+}
+
+type Table = Vec<(&'static str, Value)>;
+
+struct State {
+    tables: Vec<(&'static str, Table)>,
+    //Contiguous non-zero areas of RAM: start address and hex rows
+    ram: Vec<(u16, Vec<String>)>,
+}
+
+fn collect(emulator: &Emulator) -> State {
+    let quirks = emulator.quirks();
+    let cpu = vec![
+        ("pc", Value::Hex(emulator.program_counter() as u32)),
+        ("i", Value::Hex(emulator.i_register() as u32)),
+        ("v", Value::List((0..16).map(|x| Value::Hex(emulator.v_register(x) as u32)).collect())),
+        ("stack", Value::List(emulator.stack().iter().map(|&address| Value::Hex(address as u32)).collect())),
+        ("delay_timer", Value::Number(emulator.delay_timer() as u32)),
+        ("sound_timer", Value::Number(emulator.sound_timer() as u32)),
+        ("keys_down", Value::List((0..16).filter(|&key| emulator.is_key_pressed(key)).map(|key| Value::Hex(key as u32)).collect())),
+        ("exited", Value::Bool(emulator.has_exited())),
+        ("state", Value::Text(match emulator.state() {
+            EmulatorState::Running => "running".to_string(),
+            EmulatorState::WaitingForKey { dest_register } => format!("waiting for key into V{:X}", dest_register),
+            EmulatorState::WaitingForRelease { dest_register, key } => {
+                format!("waiting for key {:X} to be released into V{:X}", key, dest_register)
+            },
+            EmulatorState::Paused => "paused".to_string(),
+            EmulatorState::Halted { error } => format!("halted: {}", error),
+            EmulatorState::Finished { pc, reason } => format!("finished: {} at {:#05X}", reason, pc),
+        })),
+    ];
+    let display = vec![
+        ("hires", Value::Bool(emulator.is_hires())),
+        ("width", Value::Number(emulator.screen_width() as u32)),
+        ("height", Value::Number(emulator.screen_height() as u32)),
+        ("planes", Value::Number(emulator.selected_planes() as u32)),
+        ("screen", Value::Text(screen_ascii(emulator))),
+    ];
+    let mut quirks: Vec<_> = quirks.flags().iter().map(|&(name, value)| (name, Value::Bool(value))).collect();
+    quirks.push(("random", Value::Text(emulator.quirks().random.name())));
+    let audio = vec![
+        ("pattern", Value::Text(hex_bytes(emulator.audio_pattern()))),
+        ("pitch", Value::Number(emulator.pitch() as u32)),
+    ];
+    State {
+        tables: vec![("cpu", cpu), ("display", display), ("quirks", quirks), ("audio", audio)],
+        ram: ram_ranges(emulator.memory()),
+    }
+}
+
+//One text row per screen row: . is off, # plane 1, + plane 2, @ both planes
+fn screen_ascii(emulator: &Emulator) -> String {
+    emulator.frame_buffer().to_ascii_with(|pixel| ['.', '#', '+', '@'][pixel as usize & 0b11])
+}
+
+fn hex_bytes(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02X}", byte)).collect::<Vec<_>>().join(" ")
+}
+
+fn ram_ranges(ram: &[u8]) -> Vec<(u16, Vec<String>)> {
+    let mut ranges = Vec::new();
+    let mut address = 0;
+    while address < ram.len() {
+        if ram[address] == 0 {
+            address += 1;
+            continue;
+        }
+        let start = address;
+        let mut end = address + 1;
+        //Extend past short runs of zeros so sprites and code stay in one piece
+        while end < ram.len() {
+            let zeros = ram[end..].iter().take_while(|&&byte| byte == 0).count();
+            if zeros >= MIN_ZERO_GAP || end + zeros == ram.len() {
+                break;
+            }
+            end += zeros + ram[end + zeros..].iter().take_while(|&&byte| byte != 0).count();
+        }
+        let rows = ram[start..end].chunks(ROW_SIZE).map(hex_bytes).collect();
+        ranges.push((start as u16, rows));
+        address = end;
+    }
+    ranges
+}
+
+fn to_toml(state: &State) -> String {
+    let mut out = String::new();
+    for (name, table) in &state.tables {
+        let _ = writeln!(out, "[{}]", name);
+        for (key, value) in table {
+            let _ = writeln!(out, "{} = {}", key, toml_value(value));
+        }
+        out.push('\n');
+    }
+    for (address, rows) in &state.ram {
+        let _ = writeln!(out, "[[ram]]");
+        let _ = writeln!(out, "address = {:#06X}", address);
+        let _ = writeln!(out, "bytes = [");
+        for row in rows {
+            let _ = writeln!(out, "    \"{}\",", row);
+        }
+        let _ = writeln!(out, "]\n");
+    }
+    out
+}
+
+fn toml_value(value: &Value) -> String {
+    match value {
+        Value::Bool(value) => value.to_string(),
+        Value::Number(value) => value.to_string(),
+        Value::Hex(value) => format!("{:#X}", value),
+        //Multi-line literal strings keep the ASCII art readable without escapes
+        Value::Text(text) if text.contains('\n') => format!("'''\n{}'''", text),
+        Value::Text(text) => format!("\"{}\"", text),
+        Value::List(values) => format!("[{}]", values.iter().map(toml_value).collect::<Vec<_>>().join(", ")),
+    }
+}
+
+fn to_json(state: &State) -> String {
+    let mut out = String::from("{\n");
+    for (name, table) in &state.tables {
+        let _ = writeln!(out, "  \"{}\": {{", name);
+        for (index, (key, value)) in table.iter().enumerate() {
+            let comma = if index + 1 < table.len() { "," } else { "" };
+            let _ = writeln!(out, "    \"{}\": {}{}", key, json_value(value), comma);
+        }
+        out.push_str("  },\n");
+    }
+    out.push_str("  \"ram\": [\n");
+    for (index, (address, rows)) in state.ram.iter().enumerate() {
+        let rows: Vec<String> = rows.iter().map(|row| format!("      \"{}\"", row)).collect();
+        let comma = if index + 1 < state.ram.len() { "," } else { "" };
+        let _ = writeln!(out, "    {{\"address\": {}, \"bytes\": [\n{}\n    ]}}{}", address, rows.join(",\n"), comma);
+    }
+    out.push_str("  ]\n}\n");
+    out
+}
+
+fn json_value(value: &Value) -> String {
+    match value {
+        Value::Bool(value) => value.to_string(),
+        Value::Number(value) | Value::Hex(value) => value.to_string(),
+        //The screen becomes a list of rows so it still lines up in a diff
+        Value::Text(text) if text.contains('\n') => {
+            let rows: Vec<String> = text.lines().map(|row| format!("      \"{}\"", row)).collect();
+            format!("[\n{}\n    ]", rows.join(",\n"))
+        },
+        Value::Text(text) => format!("\"{}\"", text),
+        Value::List(values) => format!("[{}]", values.iter().map(json_value).collect::<Vec<_>>().join(", ")),
+    }
+}
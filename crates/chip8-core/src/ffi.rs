@@ -0,0 +1,196 @@
+//C API for embedding the emulator in C, C++ or C# hosts, see include/chip8.h
+//The host owns every handle from chip8_new and gives it back with chip8_free. Functions taking a
+//handle return a status code (CHIP8_OK or a negative error) and never unwind into the caller,
+//a panic is caught and reported as CHIP8_PANIC
+#![allow(clippy::missing_safety_doc)]
+
+use crate::chip8::Emulator;
+use crate::error::RomError;
+use crate::input::Key;
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use std::ffi::{c_char, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+pub const CHIP8_OK: i32 = 0;
+//A handle or buffer argument was null
+pub const CHIP8_NULL_POINTER: i32 = -1;
+pub const CHIP8_ROM_EMPTY: i32 = -2;
+pub const CHIP8_ROM_TOO_LARGE: i32 = -3;
+//The program faulted and the emulator halted, chip8_last_error says why. Stays halted until chip8_reset
+pub const CHIP8_FAULT: i32 = -4;
+//Keys are 0-15
+pub const CHIP8_INVALID_KEY: i32 = -5;
+pub const CHIP8_PANIC: i32 = -6;
+//chip8_load_rom_at was given an address below the fonts or past the end of memory
+pub const CHIP8_INVALID_ADDRESS: i32 = -7;
+
+//Opaque to C, only ever handled through a pointer
+pub struct Chip8 {
+    emulator: Emulator,
+    //Decoded screen handed out by chip8_framebuffer
+    pixels: Vec<u8>,
+    last_error: Option<CString>,
+}
+
+impl Chip8 {
+    fn fail(&mut self, code: i32, message: impl ToString) -> i32 {
+        self.last_error = CString::new(message.to_string()).ok();
+        code
+    }
+
+    fn rom_status(&mut self, result: Result<(), RomError>) -> i32 {
+        match result {
+            Ok(()) => CHIP8_OK,
+            Err(err @ RomError::Empty) => self.fail(CHIP8_ROM_EMPTY, err),
+            Err(err @ RomError::TooLarge { .. }) => self.fail(CHIP8_ROM_TOO_LARGE, err),
+            Err(err @ RomError::InvalidLoadAddress { .. }) => self.fail(CHIP8_INVALID_ADDRESS, err),
+        }
+    }
+}
+
+//Run f on the handle, mapping a null handle and panics to status codes
+unsafe fn with_handle(handle: *mut Chip8, f: impl FnOnce(&mut Chip8) -> i32) -> i32 {
+    let Some(chip8) = handle.as_mut() else {
+        return CHIP8_NULL_POINTER;
+    };
+    match panic::catch_unwind(AssertUnwindSafe(|| f(&mut *chip8))) {
+        Ok(code) => code,
+        Err(_) => chip8.fail(CHIP8_PANIC, "The emulator panicked"),
+    }
+}
+
+//A new emulator, free it with chip8_free. Null only if allocation failed
+#[no_mangle]
+pub extern "C" fn chip8_new() -> *mut Chip8 {
+    let chip8 = Chip8 { emulator: Emulator::new(), pixels: Vec::new(), last_error: None };
+    Box::into_raw(Box::new(chip8))
+}
+
+//Null is ignored. The handle and every pointer it gave out are invalid afterwards
+#[no_mangle]
+pub unsafe extern "C" fn chip8_free(handle: *mut Chip8) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+//Copies len bytes from data to the load address, the caller keeps ownership of data
+#[no_mangle]
+pub unsafe extern "C" fn chip8_load_rom(handle: *mut Chip8, data: *const u8, len: usize) -> i32 {
+    if data.is_null() {
+        return CHIP8_NULL_POINTER;
+    }
+    let rom = slice::from_raw_parts(data, len);
+    with_handle(handle, |chip8| {
+        let result = chip8.emulator.load_rom(rom);
+        chip8.rom_status(result)
+    })
+}
+
+//chip8_load_rom to another address, e.g. 0x600 for ETI-660 programs. Execution starts there too
+#[no_mangle]
+pub unsafe extern "C" fn chip8_load_rom_at(handle: *mut Chip8, address: u16, data: *const u8, len: usize) -> i32 {
+    if data.is_null() {
+        return CHIP8_NULL_POINTER;
+    }
+    let rom = slice::from_raw_parts(data, len);
+    with_handle(handle, |chip8| {
+        let result = chip8.emulator.load_rom_at(address, rom);
+        chip8.rom_status(result)
+    })
+}
+
+//chip8_reset then chip8_load_rom, for restarting a game. Nothing changes if the ROM is refused
+#[no_mangle]
+pub unsafe extern "C" fn chip8_reload_rom(handle: *mut Chip8, data: *const u8, len: usize) -> i32 {
+    if data.is_null() {
+        return CHIP8_NULL_POINTER;
+    }
+    let rom = slice::from_raw_parts(data, len);
+    with_handle(handle, |chip8| {
+        let result = chip8.emulator.reload_rom(rom);
+        if result.is_ok() {
+            chip8.last_error = None;
+        }
+        chip8.rom_status(result)
+    })
+}
+
+//Back to a blank machine, load the ROM again afterwards
+#[no_mangle]
+pub unsafe extern "C" fn chip8_reset(handle: *mut Chip8) -> i32 {
+    with_handle(handle, |chip8| {
+        chip8.emulator.reset();
+        chip8.last_error = None;
+        CHIP8_OK
+    })
+}
+
+//One instruction
+#[no_mangle]
+pub unsafe extern "C" fn chip8_tick(handle: *mut Chip8) -> i32 {
+    with_handle(handle, |chip8| match chip8.emulator.tick() {
+        Ok(()) => CHIP8_OK,
+        Err(err) => chip8.fail(CHIP8_FAULT, err),
+    })
+}
+
+//One 60 Hz frame: the configured instructions, then the timers
+#[no_mangle]
+pub unsafe extern "C" fn chip8_run_frame(handle: *mut Chip8) -> i32 {
+    with_handle(handle, |chip8| match chip8.emulator.run_frame() {
+        Ok(_) => CHIP8_OK,
+        Err(err) => chip8.fail(CHIP8_FAULT, err),
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn chip8_key(handle: *mut Chip8, key: u8, pressed: bool) -> i32 {
+    with_handle(handle, |chip8| match Key::from_index(key as usize) {
+        Some(key) => {
+            chip8.emulator.keypress(key, pressed);
+            CHIP8_OK
+        },
+        None => chip8.fail(CHIP8_INVALID_KEY, format!("Key {} is not 0-15", key)),
+    })
+}
+
+//The screen, one byte per pixel row major, each a bitmask of the planes it is lit in (0 is off)
+//width and height (either may be null) receive the current resolution. The pointer belongs to the
+//handle and stays valid until the next chip8_framebuffer or chip8_free on it. Null if handle is null
+#[no_mangle]
+pub unsafe extern "C" fn chip8_framebuffer(handle: *mut Chip8, width: *mut usize, height: *mut usize) -> *const u8 {
+    let Some(chip8) = handle.as_mut() else {
+        return ptr::null();
+    };
+    if !width.is_null() {
+        *width = chip8.emulator.screen_width();
+    }
+    if !height.is_null() {
+        *height = chip8.emulator.screen_height();
+    }
+    chip8.pixels = chip8.emulator.get_screen();
+    chip8.pixels.as_ptr()
+}
+
+//Whether the sound timer is running, false for a null handle
+#[no_mangle]
+pub unsafe extern "C" fn chip8_sound(handle: *const Chip8) -> bool {
+    handle.as_ref().is_some_and(|chip8| chip8.emulator.sound_timer() > 0)
+}
+
+//What the last failing call on the handle went wrong with, null if nothing has failed since chip8_reset
+//Owned by the handle, valid until the next call on it
+#[no_mangle]
+pub unsafe extern "C" fn chip8_last_error(handle: *const Chip8) -> *const c_char {
+    match handle.as_ref().and_then(|chip8| chip8.last_error.as_ref()) {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    }
+}
@@ -0,0 +1,434 @@
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::OnceCell;
+use core::ops::Range;
+
+pub const SCREEN_WIDTH: usize = 64;
+pub const SCREEN_HEIGHT: usize = 32;
+//SCHIP high resolution mode
+pub const HIRES_SCREEN_WIDTH: usize = 128;
+pub const HIRES_SCREEN_HEIGHT: usize = 64;
+//The screen buffer is sized for hires, low resolution only uses the first 64x32 pixels
+pub(crate) const SCREEN_BUFFER_SIZE: usize = HIRES_SCREEN_WIDTH * HIRES_SCREEN_HEIGHT;
+//XO-CHIP display planes, each pixel holds one bit per plane
+pub const PLANE_COUNT: usize = 2;
+pub(crate) const ALL_PLANES: u8 = 0b11;
+
+//Every row of the current resolution changed
+const ALL_ROWS: u64 = u64::MAX;
+
+//RGBA colour for each combination of planes a pixel is lit in: none, plane 1, plane 2, both
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    pub colors: [[u8; 4]; 4],
+}
+
+impl Palette {
+    //Two colours for classic programs, XO-CHIP's plane 2 and overlap get shades between them
+    pub fn new(background: [u8; 4], foreground: [u8; 4]) -> Self {
+        let mix = |weight: u16| -> [u8; 4] {
+            core::array::from_fn(|i| ((background[i] as u16 * (3 - weight) + foreground[i] as u16 * weight) / 3) as u8)
+        };
+        Self { colors: [background, foreground, mix(2), mix(1)] }
+    }
+}
+
+//White on black
+impl Default for Palette {
+    fn default() -> Self {
+        Self::new([0, 0, 0, 255], [255, 255, 255, 255])
+    }
+}
+
+//How FrameBuffer::render_scaled draws each CHIP-8 pixel's block
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScaleOptions {
+    pub palette: Palette,
+    //The bottom line of every block at half brightness, like the gaps between a CRT's lines
+    pub scanlines: bool,
+    //The bottom line and right column of every block at half brightness, like an LCD's pixel grid
+    pub grid: bool,
+}
+
+//CHIP-8X's VP-590 colour board, RGBA for each 3 bit colour (red 1, blue 2, green 4)
+pub const CHIP8X_COLORS: [[u8; 4]; 8] = [
+    [0, 0, 0, 255],
+    [255, 0, 0, 255],
+    [0, 0, 255, 255],
+    [255, 0, 255, 255],
+    [0, 255, 0, 255],
+    [255, 255, 0, 255],
+    [0, 255, 255, 255],
+    [255, 255, 255, 255],
+];
+//Background colours 02A0 steps through in turn, blue first
+const CHIP8X_BACKGROUNDS: [u8; 4] = [2, 0, 4, 1];
+//Foreground colours are set per 8 pixel wide column of each low resolution row
+const ZONE_COLUMNS: usize = SCREEN_WIDTH / 8;
+const CHIP8X_RED: u8 = 1;
+
+//CHIP-8X colours: one background for the screen and a foreground for lit pixels in each zone, an 8 pixel
+//wide column of one row. Zones start red. In high resolution each zone covers 2x2 as many pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColorZones {
+    //Index into CHIP8X_BACKGROUNDS
+    background: usize,
+    foreground: [[u8; ZONE_COLUMNS]; SCREEN_HEIGHT],
+}
+
+impl ColorZones {
+    fn new() -> Self {
+        Self { background: 0, foreground: [[CHIP8X_RED; ZONE_COLUMNS]; SCREEN_HEIGHT] }
+    }
+
+    //Colour of unlit pixels, an index into CHIP8X_COLORS
+    pub fn background(&self) -> u8 {
+        CHIP8X_BACKGROUNDS[self.background]
+    }
+
+    //Colour of lit pixels at low resolution column x, row y, an index into CHIP8X_COLORS
+    pub fn foreground(&self, x: usize, y: usize) -> u8 {
+        self.foreground[y][x / 8]
+    }
+
+    //02A0
+    fn cycle_background(&mut self) {
+        self.background = (self.background + 1) % CHIP8X_BACKGROUNDS.len();
+    }
+}
+
+//Bit of pixel x in a row word, column 0 is the most significant bit
+fn column_bit(x: usize) -> u128 {
+    1 << (HIRES_SCREEN_WIDTH - 1 - x)
+}
+
+//The display, packed: one 128 bit word per scanline and plane, the leftmost pixel in the top bit
+//Low resolution uses the left 64 columns of the top 32 rows, the rest stays blank, two page mode all 64 rows
+#[derive(Debug, Clone, Eq)]
+pub struct FrameBuffer {
+    planes: [[u128; HIRES_SCREEN_HEIGHT]; PLANE_COUNT],
+    hires: bool,
+    //64x64, see Emulator::load_rom
+    two_page: bool,
+    //Bit per row changed since the last take_dirty_rows, frontends use it for partial redraws
+    dirty_rows: u64,
+    //Bumped by every change, for telling whether a stretch of execution touched the screen
+    revision: u64,
+    //Some on CHIP-8X, see Emulator::set_chip8x
+    colors: Option<ColorZones>,
+    //Lit pixels of the current resolution for rows(), decoded on first use after a change
+    lit: OnceCell<Box<[bool]>>,
+}
+
+//Two buffers showing the same picture are equal, whoever has redrawn them
+impl PartialEq for FrameBuffer {
+    fn eq(&self, other: &Self) -> bool {
+        self.planes == other.planes && self.hires == other.hires && self.two_page == other.two_page && self.colors == other.colors
+    }
+}
+
+impl Default for FrameBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameBuffer {
+    pub fn new() -> Self {
+        Self {
+            planes: [[0; HIRES_SCREEN_HEIGHT]; PLANE_COUNT],
+            hires: false,
+            two_page: false,
+            dirty_rows: ALL_ROWS,
+            revision: 0,
+            colors: None,
+            lit: OnceCell::new(),
+        }
+    }
+
+    //Decoded pixels of the current resolution, row major (width() pixels per row)
+    //Each is a bitmask of the planes it is lit in (bit 0 is plane 1, bit 1 is plane 2 for XO-CHIP)
+    pub fn pixels(&self) -> Vec<u8> {
+        (0..self.height()).flat_map(|y| (0..self.width()).map(move |x| self.pixel(x, y))).collect()
+    }
+
+    //64 bit FNV-1a of the resolution and pixels(), the same in every build and crate version
+    //so tests can compare against a hash written down once
+    pub fn hash(&self) -> u64 {
+        let size = [self.width() as u8, self.height() as u8];
+        size.iter().chain(&self.pixels()).fold(0xCBF2_9CE4_8422_2325, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01B3)
+        })
+    }
+
+    //0 off the screen, and for the columns and rows of the buffer the current resolution does not show
+    pub fn pixel(&self, x: usize, y: usize) -> u8 {
+        if x >= self.width() || y >= self.height() {
+            return 0;
+        }
+        let bit = column_bit(x);
+        (0..PLANE_COUNT).filter(|&plane| self.planes[plane][y] & bit != 0).fold(0, |pixel, plane| pixel | 1 << plane)
+    }
+
+    //Lit in any plane
+    pub fn is_lit(&self, x: usize, y: usize) -> bool {
+        self.pixel(x, y) != 0
+    }
+
+    //Whether each pixel is lit in any plane, a row at a time, top to bottom, so callers need not work out
+    //x + width * y. Decoded once after each change to the screen, plane_rows has the planes
+    pub fn rows(&self) -> impl Iterator<Item = &[bool]> + '_ {
+        let lit = self.lit.get_or_init(|| {
+            (0..self.height()).flat_map(|y| (0..self.width()).map(move |x| self.is_lit(x, y))).collect()
+        });
+        lit.chunks_exact(self.width())
+    }
+
+    //Packed rows of one plane (0 or 1) at the current resolution
+    //Bit 127 of a row is column 0, in low resolution only the top 64 bits are used
+    pub fn plane_rows(&self, plane: usize) -> &[u128] {
+        &self.planes[plane][..self.height()]
+    }
+
+    //The CHIP-8X colours, None unless the emulator runs CHIP-8X programs
+    pub fn color_zones(&self) -> Option<&ColorZones> {
+        self.colors.as_ref()
+    }
+
+    pub(crate) fn set_color_zones(&mut self, enabled: bool) {
+        if enabled != self.colors.is_some() {
+            self.colors = enabled.then(ColorZones::new);
+            self.mark_dirty(ALL_ROWS);
+        }
+    }
+
+    //02A0 (CHIP-8X): the next background colour
+    pub(crate) fn cycle_background(&mut self) {
+        if let Some(colors) = &mut self.colors {
+            colors.cycle_background();
+            self.mark_dirty(ALL_ROWS);
+        }
+    }
+
+    //BXYN (CHIP-8X): colour the zones in columns (8 pixels each) and low resolution rows, clipped to the screen
+    pub(crate) fn set_foreground(&mut self, columns: Range<usize>, rows: Range<usize>, color: u8) {
+        //Screen rows a zone row covers
+        let zoom = self.height() / SCREEN_HEIGHT;
+        let Some(colors) = &mut self.colors else {
+            return;
+        };
+        let columns = columns.start.min(ZONE_COLUMNS)..columns.end.min(ZONE_COLUMNS);
+        let rows = rows.start.min(SCREEN_HEIGHT)..rows.end.min(SCREEN_HEIGHT);
+        let mut dirty = 0;
+        for y in rows {
+            colors.foreground[y][columns.clone()].fill(color & 0b111);
+            dirty |= ((1 << zoom) - 1) << (y * zoom);
+        }
+        self.mark_dirty(dirty);
+    }
+
+    //SCHIP high resolution (128x64) mode
+    pub fn is_hires(&self) -> bool {
+        self.hires
+    }
+
+    pub fn width(&self) -> usize {
+        if self.hires { HIRES_SCREEN_WIDTH } else { SCREEN_WIDTH }
+    }
+
+    pub fn height(&self) -> usize {
+        if self.hires || self.two_page { HIRES_SCREEN_HEIGHT } else { SCREEN_HEIGHT }
+    }
+
+    //The 64x64 screen of two page hires CHIP-8
+    pub fn is_two_page(&self) -> bool {
+        self.two_page
+    }
+
+    //Bitmask of the rows changed since the last take_dirty_rows (bit n is row n), all of them at first
+    pub fn dirty_rows(&self) -> u64 {
+        self.dirty_rows & (ALL_ROWS >> (64 - self.height()))
+    }
+
+    pub(crate) fn take_dirty_rows(&mut self) -> u64 {
+        let dirty = self.dirty_rows();
+        self.dirty_rows = 0;
+        dirty
+    }
+
+    pub(crate) fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    //Every change to the pixels or resolution comes through here
+    fn mark_dirty(&mut self, rows: u64) {
+        if rows != 0 {
+            self.dirty_rows |= rows;
+            self.revision += 1;
+            self.lit.take();
+        }
+    }
+
+    //Every row counts as changed, for a screen drawn outside the buffer (MegaChip)
+    pub(crate) fn touch(&mut self) {
+        self.mark_dirty(ALL_ROWS);
+    }
+
+    //One line per row, on for pixels lit in any plane
+    pub fn to_ascii(&self, on: char, off: char) -> String {
+        self.to_ascii_with(|pixel| if pixel != 0 { on } else { off })
+    }
+
+    //One line per row, with the character for each pixel's plane mask chosen by glyph
+    pub fn to_ascii_with(&self, glyph: impl Fn(u8) -> char) -> String {
+        (0..self.height())
+            .map(|y| (0..self.width()).map(|x| glyph(self.pixel(x, y))).collect::<String>() + "\n")
+            .collect()
+    }
+
+    //Write the current resolution as RGBA, 4 bytes per pixel row major, into the start of out
+    //CHIP-8X screens are drawn in their zone colours instead of the palette's
+    //Panics if out is shorter than width() * height() * 4
+    pub fn render_rgba(&self, out: &mut [u8], palette: &Palette) {
+        let (width, height) = (self.width(), self.height());
+        assert!(out.len() >= width * height * 4, "RGBA buffer of {} bytes is too small for {}x{}", out.len(), width, height);
+        for (y, line) in out.chunks_exact_mut(width * 4).take(height).enumerate() {
+            let (plane1, plane2) = (self.planes[0][y], self.planes[1][y]);
+            for (x, pixel) in line.chunks_exact_mut(4).enumerate() {
+                let shift = HIRES_SCREEN_WIDTH - 1 - x;
+                let index = ((plane1 >> shift) & 1) | ((plane2 >> shift) & 1) << 1;
+                let color = match &self.colors {
+                    Some(colors) if index != 0 => {
+                        CHIP8X_COLORS[colors.foreground(x * SCREEN_WIDTH / width, y * SCREEN_HEIGHT / height) as usize]
+                    },
+                    Some(colors) => CHIP8X_COLORS[colors.background() as usize],
+                    None => palette.colors[index as usize],
+                };
+                pixel.copy_from_slice(&color);
+            }
+        }
+    }
+
+    //render_rgba with every pixel a scale x scale block (scale 0 is taken as 1), into the start of out
+    //The effects need a scale of 2 or more to have room. Scale 10 gives 640x320 in low resolution
+    //Panics if out is shorter than width() * height() * scale * scale * 4
+    pub fn render_scaled(&self, out: &mut [u8], scale: usize, options: &ScaleOptions) {
+        let scale = scale.max(1);
+        let (width, height) = (self.width(), self.height());
+        let len = width * height * scale * scale * 4;
+        assert!(out.len() >= len, "RGBA buffer of {} bytes is too small for {}x{} at scale {}", out.len(), width, height, scale);
+        let mut rgba = vec![0; width * height * 4];
+        self.render_rgba(&mut rgba, &options.palette);
+
+        let dim = |color: &[u8]| [color[0] / 2, color[1] / 2, color[2] / 2, color[3]];
+        let effects = scale > 1 && (options.scanlines || options.grid);
+        for (y, line) in out[..len].chunks_exact_mut(width * scale * 4).enumerate() {
+            let source = &rgba[y / scale * width * 4..][..width * 4];
+            let dark_line = effects && y % scale == scale - 1;
+            for (x, pixel) in line.chunks_exact_mut(4).enumerate() {
+                let color = &source[x / scale * 4..][..4];
+                let dark_column = effects && options.grid && x % scale == scale - 1;
+                if dark_line || dark_column {
+                    pixel.copy_from_slice(&dim(color));
+                } else {
+                    pixel.copy_from_slice(color);
+                }
+            }
+        }
+    }
+
+    //The whole 128x64 buffer for save states, one byte per pixel
+    //Laid out like pixels(), followed by zeros in low resolution (pixels off screen are always blank)
+    pub(crate) fn to_raw(&self) -> Vec<u8> {
+        let mut raw = self.pixels();
+        raw.resize(SCREEN_BUFFER_SIZE, 0);
+        raw
+    }
+
+    pub(crate) fn from_raw(raw: &[u8; SCREEN_BUFFER_SIZE], hires: bool, two_page: bool) -> Self {
+        let mut screen = Self { hires, two_page: two_page && !hires, ..Self::new() };
+        let width = screen.width();
+        for (y, row) in raw.chunks(width).take(screen.height()).enumerate() {
+            for (x, &pixel) in row.iter().enumerate() {
+                for (plane, rows) in screen.planes.iter_mut().enumerate() {
+                    if pixel & 1 << plane != 0 {
+                        rows[y] |= column_bit(x);
+                    }
+                }
+            }
+        }
+        screen
+    }
+
+    //Switching resolution clears every plane, and leaves two page mode
+    pub(crate) fn set_hires(&mut self, hires: bool) {
+        self.hires = hires;
+        self.two_page = false;
+        self.clear(ALL_PLANES);
+        self.mark_dirty(ALL_ROWS);
+    }
+
+    //Switch to the 64x64 two page screen, clearing it
+    pub(crate) fn set_two_page(&mut self) {
+        self.set_hires(false);
+        self.two_page = true;
+        self.mark_dirty(ALL_ROWS);
+    }
+
+    //Clear the given planes, other planes keep their pixels
+    //Hires pixels left over from before a switch to lores are not visible but are cleared all the same
+    pub(crate) fn clear(&mut self, planes: u8) {
+        let mut dirty = 0;
+        for rows in self.selected(planes) {
+            for (y, row) in rows.iter_mut().enumerate() {
+                if *row != 0 {
+                    *row = 0;
+                    dirty |= 1 << y;
+                }
+            }
+        }
+        self.mark_dirty(dirty);
+    }
+
+    //XOR a sprite row into row y of plane (a single plane bit), wrapping around the right edge or cut off there with clip
+    //bits holds the sprite's width pixels, leftmost in the top bit. Returns whether any lit pixel was hit (a collision)
+    pub(crate) fn draw_row(&mut self, x: usize, y: usize, bits: u16, width: usize, plane: u8, clip: bool) -> bool {
+        let mask = (bits as u128) << (HIRES_SCREEN_WIDTH - 16) & !(u128::MAX >> width);
+        let mask = if self.hires {
+            if clip { mask >> x } else { mask.rotate_right(x as u32) }
+        } else {
+            let row = (mask >> 64) as u64;
+            (if clip { row >> x } else { row.rotate_right(x as u32) } as u128) << 64
+        };
+        let row = &mut self.planes[plane.trailing_zeros() as usize][y];
+        let collision = *row & mask != 0;
+        *row ^= mask;
+        if mask != 0 {
+            self.mark_dirty(1 << y);
+        }
+        collision
+    }
+
+    //Move the given planes of the current resolution by (dx, dy), pixels scrolled in are blank
+    pub(crate) fn scroll(&mut self, dx: isize, dy: isize, planes: u8) {
+        let height = self.height();
+        let visible = u128::MAX << (HIRES_SCREEN_WIDTH - self.width());
+        for rows in self.selected(planes) {
+            let source = *rows;
+            for (y, row) in rows[..height].iter_mut().enumerate() {
+                let source_y = y as isize - dy;
+                let line = if (0..height as isize).contains(&source_y) { source[source_y as usize] } else { 0 };
+                let moved = if dx >= 0 { line >> dx } else { line << -dx };
+                *row = moved & visible;
+            }
+        }
+        self.mark_dirty(ALL_ROWS);
+    }
+
+    //Rows of each plane whose bit is in planes
+    fn selected(&mut self, planes: u8) -> impl Iterator<Item = &mut [u128; HIRES_SCREEN_HEIGHT]> {
+        self.planes.iter_mut().enumerate().filter(move |(plane, _)| planes & 1 << plane != 0).map(|(_, rows)| rows)
+    }
+}
@@ -0,0 +1,48 @@
+//A ROM and the settings to run it with, made by Arbitrary from a fuzzer's bytes. FuzzInput::run is the whole
+//fuzz target (fuzz/ at the repository root, run with cargo fuzz run rom): a fault is the ROM's problem,
+//Chip8Error::Internal or a panic is a bug in the emulator
+
+use crate::chip8::{Emulator, TICKS_PER_FRAME};
+use crate::error::Chip8Error;
+use crate::quirks::{Quirks, RandomModel};
+
+use arbitrary::Arbitrary;
+
+use alloc::vec::Vec;
+
+#[derive(Debug, Clone, Arbitrary)]
+pub struct FuzzInput {
+    pub rom: Vec<u8>,
+    //Entropy runs on seed 0 instead, so a crash replays
+    pub quirks: Quirks,
+    pub megachip: bool,
+    pub chip8x: bool,
+    //Instructions to run
+    pub ticks: u16,
+    //Keys held down (a bit each) frame by frame, none after the last
+    pub keys: Vec<u16>,
+}
+
+impl FuzzInput {
+    //Ok if the ROM was refused or ran its ticks, the fault it stopped on if not
+    pub fn run(&self) -> Result<(), Chip8Error> {
+        let mut emulator = Emulator::new();
+        emulator.set_quirks(self.quirks);
+        if self.quirks.random == RandomModel::Entropy {
+            emulator.set_seed(0);
+        }
+        emulator.set_megachip(self.megachip);
+        emulator.set_chip8x(self.chip8x);
+        if emulator.load_rom(&self.rom).is_err() {
+            return Ok(());
+        }
+        for tick in 0..self.ticks as usize {
+            if tick % TICKS_PER_FRAME == 0 {
+                emulator.set_keys(self.keys.get(tick / TICKS_PER_FRAME).copied().unwrap_or(0));
+                emulator.timers();
+            }
+            emulator.tick_checked()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,164 @@
+//The control flow graph of a ROM from disasm::analyze: basic blocks of the code reachable from the entry
+//point and the jumps, calls and skips between them, as an adjacency list or Graphviz DOT
+
+use crate::disasm::{Analysis, Line};
+use crate::instruction::Instruction;
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+//How control gets from one instruction to another
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Flow {
+    //On to the next instruction
+    Next,
+    //1NNN, or BNNN to the first entry of its table
+    Jump,
+    //Over the next instruction when the skip's condition holds
+    Skip,
+    //2NNN, which carries on with the next instruction after 00EE
+    Call,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Edge {
+    //Start of the block it leads to
+    pub target: u16,
+    pub flow: Flow,
+}
+
+//Instructions run one after the other, only the first is jumped to and only the last jumps away
+//(calls aside, which come back)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub start: u16,
+    pub lines: Vec<Line>,
+    pub edges: Vec<Edge>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Graph {
+    //By start address
+    pub blocks: BTreeMap<u16, Block>,
+    //Labels from the analysis, used to name blocks
+    pub labels: BTreeMap<u16, String>,
+}
+
+//Where control can go after the instruction at address, skipped is the size of the instruction after it
+pub(crate) fn successors(instruction: Instruction, address: u16, skipped: u16) -> Vec<(u16, Flow)> {
+    let next = address.wrapping_add(instruction.size());
+    match instruction {
+        Instruction::Jump(target) | Instruction::JumpV0(target) => vec![(target, Flow::Jump)],
+        Instruction::Call(target) => vec![(target, Flow::Call), (next, Flow::Next)],
+        Instruction::Return | Instruction::Exit => Vec::new(),
+        Instruction::SkipEqImm { .. }
+        | Instruction::SkipNeImm { .. }
+        | Instruction::SkipEqReg { .. }
+        | Instruction::SkipNeReg { .. }
+        | Instruction::SkipKeyPressed { .. }
+        | Instruction::SkipKeyNotPressed { .. } => vec![(next, Flow::Next), (next.wrapping_add(skipped), Flow::Skip)],
+        _ => vec![(next, Flow::Next)],
+    }
+}
+
+pub fn graph(analysis: &Analysis) -> Graph {
+    let code: BTreeMap<u16, (Line, Instruction)> =
+        analysis.lines.iter().filter_map(|&line| Some((line.address, (line, line.instruction?)))).collect();
+    let flows = |address: u16, instruction: Instruction| {
+        let next = address.wrapping_add(instruction.size());
+        let skipped = code.get(&next).map_or(2, |(_, instruction)| instruction.size());
+        successors(instruction, address, skipped)
+    };
+
+    let carries_on = |address: u16, instruction: Instruction| {
+        flows(address, instruction).iter().all(|&(_, flow)| matches!(flow, Flow::Next | Flow::Call))
+    };
+
+    //Blocks start at the entry point, wherever something jumps, calls or skips to, and after anything
+    //that does not just carry on
+    let mut starts: BTreeSet<u16> = code.keys().next().copied().into_iter().collect();
+    for (&address, &(_, instruction)) in &code {
+        let plain = carries_on(address, instruction);
+        for (target, flow) in flows(address, instruction) {
+            if flow != Flow::Next || !plain {
+                starts.insert(target);
+            }
+        }
+    }
+
+    let mut blocks: BTreeMap<u16, Block> = BTreeMap::new();
+    let mut current: Option<Block> = None;
+    let mut previous: Option<(u16, Instruction)> = None;
+    for (&address, &(line, instruction)) in &code {
+        let continues = previous.is_some_and(|(last, instruction)| {
+            last.wrapping_add(instruction.size()) == address && carries_on(last, instruction)
+        });
+        if !continues || starts.contains(&address) {
+            blocks.extend(current.take().map(|block| (block.start, block)));
+            current = Some(Block { start: address, lines: Vec::new(), edges: Vec::new() });
+        }
+        let block = current.as_mut().expect("a block was just started");
+        block.lines.push(line);
+        for (target, flow) in flows(address, instruction) {
+            let edge = Edge { target, flow };
+            if flow == Flow::Call && !block.edges.contains(&edge) {
+                block.edges.push(edge);
+            }
+        }
+        previous = Some((address, instruction));
+    }
+    blocks.extend(current.map(|block| (block.start, block)));
+
+    //The last instruction of each block leads on to the blocks after it
+    for block in blocks.values_mut() {
+        let last = *block.lines.last().expect("blocks are never empty");
+        let instruction = last.instruction.expect("blocks only hold instructions");
+        block.edges.extend(
+            flows(last.address, instruction)
+                .into_iter()
+                .filter(|&(target, flow)| flow != Flow::Call && code.contains_key(&target))
+                .map(|(target, flow)| Edge { target, flow }),
+        );
+    }
+    Graph { blocks, labels: analysis.labels.clone() }
+}
+
+impl Graph {
+    //The label of the block at address, else its address
+    pub fn name(&self, address: u16) -> String {
+        self.labels.get(&address).cloned().unwrap_or_else(|| format!("{:#05X}", address))
+    }
+
+    //Block start addresses each block leads to
+    pub fn adjacency(&self) -> BTreeMap<u16, Vec<u16>> {
+        self.blocks.iter().map(|(&start, block)| (start, block.edges.iter().map(|edge| edge.target).collect())).collect()
+    }
+
+    //A box per block with its instructions, calls dashed and skips labelled
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph rom {\n    node [shape=box, fontname=monospace];\n");
+        for block in self.blocks.values() {
+            let mut label = format!("{}:\\l", self.name(block.start));
+            for line in &block.lines {
+                let _ = write!(label, "{}\\l", line.to_string().replace('"', "\\\""));
+            }
+            let _ = writeln!(dot, "    \"{}\" [label=\"{}\"];", self.name(block.start), label);
+        }
+        for block in self.blocks.values() {
+            for edge in &block.edges {
+                let style = match edge.flow {
+                    Flow::Next | Flow::Jump => "",
+                    Flow::Skip => " [label=\"skip\"]",
+                    Flow::Call => " [style=dashed]",
+                };
+                let _ = writeln!(dot, "    \"{}\" -> \"{}\"{};", self.name(block.start), self.name(edge.target), style);
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
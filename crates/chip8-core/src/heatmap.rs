@@ -0,0 +1,80 @@
+//Reads, writes and executions per RAM address since the last reset, see Emulator::heatmap
+//Only with the heatmap feature, without it the emulator counts nothing. Executions count the first byte of
+//each instruction, reads are FX65, 5XY3, F002 and sprite data
+
+use crate::chip8::RAM_SIZE;
+
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AccessCounts {
+    pub reads: u32,
+    pub writes: u32,
+    pub executes: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heatmap {
+    //RAM_SIZE long each, saturating
+    reads: Box<[u32]>,
+    writes: Box<[u32]>,
+    executes: Box<[u32]>,
+}
+
+impl Heatmap {
+    pub(crate) fn new() -> Self {
+        Self {
+            reads: vec![0; RAM_SIZE].into_boxed_slice(),
+            writes: vec![0; RAM_SIZE].into_boxed_slice(),
+            executes: vec![0; RAM_SIZE].into_boxed_slice(),
+        }
+    }
+
+    pub fn counts(&self, address: u16) -> AccessCounts {
+        let address = address as usize;
+        AccessCounts { reads: self.reads[address], writes: self.writes[address], executes: self.executes[address] }
+    }
+
+    //By address, for drawing the whole map
+    pub fn reads(&self) -> &[u32] {
+        &self.reads
+    }
+
+    pub fn writes(&self) -> &[u32] {
+        &self.writes
+    }
+
+    pub fn executes(&self) -> &[u32] {
+        &self.executes
+    }
+
+    //Addresses that were both executed and written, self-modifying code or stores running over the program
+    pub fn written_code(&self) -> Vec<u16> {
+        (0..RAM_SIZE).filter(|&address| self.executes[address] > 0 && self.writes[address] > 0).map(|address| address as u16).collect()
+    }
+
+    pub(crate) fn read(&mut self, address: u16, len: u16) {
+        for offset in 0..len {
+            let count = &mut self.reads[address.wrapping_add(offset) as usize];
+            *count = count.saturating_add(1);
+        }
+    }
+
+    pub(crate) fn write(&mut self, address: u16) {
+        let count = &mut self.writes[address as usize];
+        *count = count.saturating_add(1);
+    }
+
+    pub(crate) fn execute(&mut self, address: u16) {
+        let count = &mut self.executes[address as usize];
+        *count = count.saturating_add(1);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        for counts in [&mut self.reads, &mut self.writes, &mut self.executes] {
+            counts.fill(0);
+        }
+    }
+}
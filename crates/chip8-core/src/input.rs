@@ -0,0 +1,183 @@
+use crate::chip8::KEYS_SIZE;
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::fmt;
+
+//The 16 keys of the hex keypad, laid out on the COSMAC VIP as
+//1 2 3 C / 4 5 6 D / 7 8 9 E / A 0 B F
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Key {
+    Key0,
+    Key1,
+    Key2,
+    Key3,
+    Key4,
+    Key5,
+    Key6,
+    Key7,
+    Key8,
+    Key9,
+    KeyA,
+    KeyB,
+    KeyC,
+    KeyD,
+    KeyE,
+    KeyF,
+}
+
+impl Key {
+    pub const ALL: [Key; KEYS_SIZE] = [
+        Key::Key0, Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5, Key::Key6, Key::Key7,
+        Key::Key8, Key::Key9, Key::KeyA, Key::KeyB, Key::KeyC, Key::KeyD, Key::KeyE, Key::KeyF,
+    ];
+
+    //None above 0xF
+    pub fn from_index(index: usize) -> Option<Key> {
+        Key::ALL.get(index).copied()
+    }
+
+    //The key's hex digit, either case
+    pub fn from_digit(digit: char) -> Option<Key> {
+        Key::from_index(digit.to_digit(16)? as usize)
+    }
+
+    //The value EX9E, EXA1 and FX0A use
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+//The hex digit, as written in replays and chat commands
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:X}", self.index())
+    }
+}
+
+//Host keys (the character a key types, lowercase) to keypad keys. Frontends look keys up here
+//instead of each hard coding a layout
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyMap {
+    keys: BTreeMap<char, Key>,
+}
+
+impl KeyMap {
+    //No keys bound
+    pub fn empty() -> Self {
+        Self { keys: BTreeMap::new() }
+    }
+
+    //The usual layout: the 4x4 block under 1234 stands in for the keypad
+    pub fn qwerty() -> Self {
+        Self::from_rows(["1234", "qwer", "asdf", "zxcv"])
+    }
+
+    //The same block on a French keyboard, where the top row types & é " ' unshifted (and SDL names the keys
+    //by those). The digits it types with shift are bound too
+    pub fn azerty() -> Self {
+        let mut map = Self::from_rows(["&é\"'", "azer", "qsdf", "wxcv"]);
+        for (digit, key) in "1234".chars().zip([Key::Key1, Key::Key2, Key::Key3, Key::KeyC]) {
+            map.bind(digit, key);
+        }
+        map
+    }
+
+    //Four rows of four host keys, in the keypad's layout
+    pub fn from_rows(rows: [&str; 4]) -> Self {
+        const KEYPAD: [[Key; 4]; 4] = [
+            [Key::Key1, Key::Key2, Key::Key3, Key::KeyC],
+            [Key::Key4, Key::Key5, Key::Key6, Key::KeyD],
+            [Key::Key7, Key::Key8, Key::Key9, Key::KeyE],
+            [Key::KeyA, Key::Key0, Key::KeyB, Key::KeyF],
+        ];
+        let mut map = Self::empty();
+        for (row, keys) in rows.iter().zip(KEYPAD) {
+            for (host, key) in row.chars().zip(keys) {
+                map.bind(host, key);
+            }
+        }
+        map
+    }
+
+    pub fn bind(&mut self, host: char, key: Key) {
+        self.keys.insert(host.to_ascii_lowercase(), key);
+    }
+
+    pub fn unbind(&mut self, host: char) {
+        self.keys.remove(&host.to_ascii_lowercase());
+    }
+
+    pub fn get(&self, host: char) -> Option<Key> {
+        self.keys.get(&host.to_ascii_lowercase()).copied()
+    }
+
+    //Every host key with the keypad key it is bound to, in no particular order
+    //For frontends that poll key state instead of receiving key events
+    pub fn bindings(&self) -> impl Iterator<Item = (char, Key)> + '_ {
+        self.keys.iter().map(|(&host, &key)| (host, key))
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self::qwerty()
+    }
+}
+
+//Where key state comes from, polled by the emulator once a frame (Emulator::set_input_source)
+//Without one the frontend pushes key changes with Emulator::keypress as before
+pub trait InputSource: Send {
+    //Whether each of the 16 keys is down right now
+    fn poll(&mut self) -> [bool; KEYS_SIZE];
+}
+
+//A closure works as a source, e.g. one reading a gamepad
+impl<F: FnMut() -> [bool; KEYS_SIZE] + Send> InputSource for F {
+    fn poll(&mut self) -> [bool; KEYS_SIZE] {
+        self()
+    }
+}
+
+//Presses and releases at fixed frames, for demos, tests and bots
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedInput {
+    //(frame, key, pressed), sorted by frame
+    script: Vec<(u64, Key, bool)>,
+    next: usize,
+    frame: u64,
+    keys: [bool; KEYS_SIZE],
+}
+
+impl ScriptedInput {
+    //Frames count polls, the first poll is frame 0
+    pub fn new(mut script: Vec<(u64, Key, bool)>) -> Self {
+        script.sort_by_key(|&(frame, _, _)| frame);
+        Self { script, ..Self::default() }
+    }
+
+    //Hold key down from frame for frames polls
+    pub fn tap(mut self, key: Key, frame: u64, frames: u64) -> Self {
+        self.script.push((frame, key, true));
+        self.script.push((frame + frames.max(1), key, false));
+        Self::new(self.script)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next == self.script.len()
+    }
+}
+
+impl InputSource for ScriptedInput {
+    fn poll(&mut self) -> [bool; KEYS_SIZE] {
+        while let Some(&(frame, key, pressed)) = self.script.get(self.next) {
+            if frame > self.frame {
+                break;
+            }
+            self.keys[key.index()] = pressed;
+            self.next += 1;
+        }
+        self.frame += 1;
+        self.keys
+    }
+}
@@ -0,0 +1,628 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+
+//Register indices (x, y) are 0x0-0xF, nn is a byte, nnn is a 12 bit address
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    //0000: NOP (Do nothing)
+    Nop,
+    //00E0: Clear screen
+    ClearScreen,
+    //00EE: Return from subroutine
+    Return,
+    //0010: Leave MegaChip mode (MegaChip)
+    MegaOff,
+    //0011: Switch to the 256x192 colour screen (MegaChip)
+    MegaOn,
+    //01NN NNNN: I = NNNNNN, a 24 bit address, NN and the word after the opcode (MegaChip)
+    LoadIMega(u32),
+    //02NN: Load NN colours from I into the palette (MegaChip), 02A0 decodes as CycleBackground
+    LoadPalette(u8),
+    //03NN: Sprite width = NN, 0 is 256 (MegaChip)
+    SpriteWidth(u8),
+    //04NN: Sprite height = NN, 0 is 256 (MegaChip)
+    SpriteHeight(u8),
+    //05NN: Screen alpha = NN (MegaChip)
+    ScreenAlpha(u8),
+    //060N: Play the digitised sound at I, once if N is 1 or looping if N is 0 (MegaChip)
+    PlaySample(u8),
+    //0700: Stop the digitised sound (MegaChip)
+    StopSample,
+    //080N: Sprite blend mode N, see megachip::BlendMode (MegaChip)
+    Blend(u8),
+    //09NN: Collision colour = palette index NN (MegaChip)
+    CollisionColor(u8),
+    //00BN: Scroll the display up N pixels (MegaChip)
+    ScrollUp(u8),
+    //02A0: Step to the next background colour (CHIP-8X), loads a 160 colour palette in MegaChip mode
+    CycleBackground,
+    //00CN: Scroll the display down N pixels (SCHIP)
+    ScrollDown(u8),
+    //00FB: Scroll the display right 4 pixels (SCHIP)
+    ScrollRight,
+    //00FC: Scroll the display left 4 pixels (SCHIP)
+    ScrollLeft,
+    //00FD: Exit the interpreter (SCHIP)
+    Exit,
+    //00FE: Switch to 64x32 low resolution (SCHIP)
+    LowRes,
+    //00FF: Switch to 128x64 high resolution (SCHIP)
+    HighRes,
+    //1NNN: Jump to NNN
+    Jump(u16),
+    //2NNN: Call subroutine at NNN
+    Call(u16),
+    //3XNN: Skip if Vx = NN
+    SkipEqImm { x: u8, nn: u8 },
+    //4XNN: Skip if Vx != NN
+    SkipNeImm { x: u8, nn: u8 },
+    //5XY0: Skip if Vx = Vy
+    SkipEqReg { x: u8, y: u8 },
+    //5XY1: Vx += Vy, each nibble separately and mod 8 (CHIP-8X)
+    AddNibbles { x: u8, y: u8 },
+    //5XY2: Store Vx..=Vy at I, in descending order if x > y. I is not changed (XO-CHIP)
+    SaveRange { x: u8, y: u8 },
+    //5XY3: Read Vx..=Vy from I, in descending order if x > y. I is not changed (XO-CHIP)
+    LoadRange { x: u8, y: u8 },
+    //6XNN: Vx = NN
+    LoadImm { x: u8, nn: u8 },
+    //7XNN: Vx += NN
+    AddImm { x: u8, nn: u8 },
+    //8XY0: Vx = Vy
+    LoadReg { x: u8, y: u8 },
+    //8XY1: Vx |= Vy
+    Or { x: u8, y: u8 },
+    //8XY2: Vx &= Vy
+    And { x: u8, y: u8 },
+    //8XY3: Vx ^= Vy
+    Xor { x: u8, y: u8 },
+    //8XY4: Vx += Vy, Vf = carry
+    AddReg { x: u8, y: u8 },
+    //8XY5: Vx -= Vy, Vf = not borrow
+    SubReg { x: u8, y: u8 },
+    //8XY6: Vx >>= 1, Vf = shifted out bit
+    ShiftRight { x: u8, y: u8 },
+    //8XY7: Vx = Vy - Vx, Vf = not borrow
+    SubN { x: u8, y: u8 },
+    //8XYE: Vx <<= 1, Vf = shifted out bit
+    ShiftLeft { x: u8, y: u8 },
+    //9XY0: Skip if Vx != Vy
+    SkipNeReg { x: u8, y: u8 },
+    //ANNN: I = NNN
+    LoadI(u16),
+    //BNNN: Jump to V0 + NNN
+    JumpV0(u16),
+    //BXYN: Colour N rows of the 8 pixel column at (Vx, Vx+1) in colour Vy, BXY0 a block of zones (CHIP-8X)
+    //Takes BNNN's place, so only decode_chip8x produces it
+    Foreground { x: u8, y: u8, n: u8 },
+    //CXNN: Vx = random byte AND NN
+    Random { x: u8, nn: u8 },
+    //DXYN: Draw N rows of sprite data from I at (Vx, Vy), DXY0 draws a 16x16 sprite (SCHIP)
+    Draw { x: u8, y: u8, n: u8 },
+    //EX9E: Skip if key Vx is pressed
+    SkipKeyPressed { x: u8 },
+    //EXA1: Skip if key Vx is not pressed
+    SkipKeyNotPressed { x: u8 },
+    //F000 NNNN: I = NNNN, a 16 bit address in the word after the opcode (XO-CHIP)
+    LoadILong(u16),
+    //FN01: Select the drawing planes, bit 0 is plane 1 and bit 1 is plane 2 (XO-CHIP)
+    Plane(u8),
+    //F002: Load the 16 byte audio pattern buffer from I (XO-CHIP)
+    LoadAudio,
+    //FX07: Vx = delay timer
+    LoadDelay { x: u8 },
+    //FX0A: Wait for a keypress and store it in Vx
+    WaitKey { x: u8 },
+    //FX15: Delay timer = Vx
+    SetDelay { x: u8 },
+    //FX18: Sound timer = Vx
+    SetSound { x: u8 },
+    //FX1E: I += Vx
+    AddI { x: u8 },
+    //FX29: I = address of font sprite for Vx
+    LoadFont { x: u8 },
+    //FX30: I = address of the 10 byte big font sprite for Vx (SCHIP)
+    LoadBigFont { x: u8 },
+    //FX33: Store BCD of Vx at I, I+1, I+2
+    StoreBcd { x: u8 },
+    //FX3A: Audio pattern playback rate = 4000 * 2^((Vx - 64) / 48) Hz (XO-CHIP)
+    SetPitch { x: u8 },
+    //FX55: Store V0..=Vx at I
+    StoreRegs { x: u8 },
+    //FX65: Read V0..=Vx from I
+    LoadRegs { x: u8 },
+    //FX75: Store V0..=Vx in the RPL user flags (SCHIP)
+    SaveFlags { x: u8 },
+    //FX85: Read V0..=Vx from the RPL user flags (SCHIP)
+    LoadFlags { x: u8 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeError {
+    pub opcode: u16,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown opcode: {:#06X}", self.opcode)
+    }
+}
+
+impl Error for DecodeError {}
+
+//The fields an opcode's hex digits can hold, most instructions use one or two. Emulator's handlers read
+//the ones of the instruction they run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Operands {
+    pub(crate) x: u8,
+    pub(crate) y: u8,
+    pub(crate) n: u8,
+    pub(crate) nn: u8,
+    pub(crate) nnn: u16,
+    //NN and the word after the opcode, the address of F000 NNNN and 01NN NNNN
+    pub(crate) long: u32,
+}
+
+impl Operands {
+    pub(crate) fn new(opcode: u16, next: u16) -> Self {
+        Self {
+            x: ((opcode & 0x0F00) >> 8) as u8,
+            y: ((opcode & 0x00F0) >> 4) as u8,
+            n: (opcode & 0x000F) as u8,
+            nn: (opcode & 0xFF) as u8,
+            nnn: opcode & 0xFFF,
+            long: ((opcode as u32 & 0xFF) << 16) | next as u32,
+        }
+    }
+}
+
+//Decodes the opcodes with one first hex digit, None for the ones it does not know
+type Family = fn(Operands) -> Option<Instruction>;
+
+//Indexed by the first hex digit. Each family matches the rest of the opcode itself, so extensions (SCHIP,
+//XO-CHIP, MegaChip) add arms to the family they live in, and decode_chip8x swaps a whole family out
+const FAMILIES: [Family; 16] = [
+    system,
+    |op| Some(Instruction::Jump(op.nnn)),
+    |op| Some(Instruction::Call(op.nnn)),
+    |Operands { x, nn, .. }| Some(Instruction::SkipEqImm { x, nn }),
+    |Operands { x, nn, .. }| Some(Instruction::SkipNeImm { x, nn }),
+    registers,
+    |Operands { x, nn, .. }| Some(Instruction::LoadImm { x, nn }),
+    |Operands { x, nn, .. }| Some(Instruction::AddImm { x, nn }),
+    arithmetic,
+    |Operands { x, y, n, .. }| (n == 0).then_some(Instruction::SkipNeReg { x, y }),
+    |op| Some(Instruction::LoadI(op.nnn)),
+    |op| Some(Instruction::JumpV0(op.nnn)),
+    |Operands { x, nn, .. }| Some(Instruction::Random { x, nn }),
+    |Operands { x, y, n, .. }| Some(Instruction::Draw { x, y, n }),
+    keys,
+    misc,
+];
+
+//Split the 16 bit opcode into its hex "digits" and hand it to the family of its first one
+//F000 and 01NN are not decoded here since their operand is the following word, see decode_long
+pub fn decode(opcode: u16) -> Result<Instruction, DecodeError> {
+    FAMILIES[(opcode >> 12) as usize](Operands::new(opcode, 0)).ok_or(DecodeError { opcode })
+}
+
+//0NNN: CHIP-8 screen and subroutine return, SCHIP scrolling and resolution, MegaChip's colour screen
+fn system(Operands { x, y, n, nn, .. }: Operands) -> Option<Instruction> {
+    let instruction = match (x, y, n) {
+        (0,0,0) => Instruction::Nop,
+        (0,0xE,0) => Instruction::ClearScreen,
+        (0,0xE,0xE) => Instruction::Return,
+        (0,1,0) => Instruction::MegaOff,
+        (0,1,1) => Instruction::MegaOn,
+        (2,0xA,0) => Instruction::CycleBackground,
+        (2,_,_) => Instruction::LoadPalette(nn),
+        (3,_,_) => Instruction::SpriteWidth(nn),
+        (4,_,_) => Instruction::SpriteHeight(nn),
+        (5,_,_) => Instruction::ScreenAlpha(nn),
+        (6,0,_) => Instruction::PlaySample(n),
+        (7,0,0) => Instruction::StopSample,
+        (8,0,_) => Instruction::Blend(n),
+        (9,_,_) => Instruction::CollisionColor(nn),
+        (0,0xB,_) => Instruction::ScrollUp(n),
+        (0,0xC,_) => Instruction::ScrollDown(n),
+        (0,0xF,0xB) => Instruction::ScrollRight,
+        (0,0xF,0xC) => Instruction::ScrollLeft,
+        (0,0xF,0xD) => Instruction::Exit,
+        (0,0xF,0xE) => Instruction::LowRes,
+        (0,0xF,0xF) => Instruction::HighRes,
+        _ => return None,
+    };
+    Some(instruction)
+}
+
+//5XYN: the CHIP-8 skip, XO-CHIP's register ranges and CHIP-8X's nibble add
+fn registers(Operands { x, y, n, .. }: Operands) -> Option<Instruction> {
+    let instruction = match n {
+        0 => Instruction::SkipEqReg { x, y },
+        1 => Instruction::AddNibbles { x, y },
+        2 => Instruction::SaveRange { x, y },
+        3 => Instruction::LoadRange { x, y },
+        _ => return None,
+    };
+    Some(instruction)
+}
+
+//8XYN
+fn arithmetic(Operands { x, y, n, .. }: Operands) -> Option<Instruction> {
+    let instruction = match n {
+        0 => Instruction::LoadReg { x, y },
+        1 => Instruction::Or { x, y },
+        2 => Instruction::And { x, y },
+        3 => Instruction::Xor { x, y },
+        4 => Instruction::AddReg { x, y },
+        5 => Instruction::SubReg { x, y },
+        6 => Instruction::ShiftRight { x, y },
+        7 => Instruction::SubN { x, y },
+        0xE => Instruction::ShiftLeft { x, y },
+        _ => return None,
+    };
+    Some(instruction)
+}
+
+//EXNN
+fn keys(Operands { x, nn, .. }: Operands) -> Option<Instruction> {
+    match nn {
+        0x9E => Some(Instruction::SkipKeyPressed { x }),
+        0xA1 => Some(Instruction::SkipKeyNotPressed { x }),
+        _ => None,
+    }
+}
+
+//FXNN: timers, keys, I, memory and the SCHIP and XO-CHIP additions
+fn misc(Operands { x, y, n, .. }: Operands) -> Option<Instruction> {
+    let instruction = match (x, y, n) {
+        (0,0,2) => Instruction::LoadAudio,
+        (_,0,1) => Instruction::Plane(x),
+        (_,0,7) => Instruction::LoadDelay { x },
+        (_,0,0xA) => Instruction::WaitKey { x },
+        (_,1,5) => Instruction::SetDelay { x },
+        (_,1,8) => Instruction::SetSound { x },
+        (_,1,0xE) => Instruction::AddI { x },
+        (_,2,9) => Instruction::LoadFont { x },
+        (_,3,0) => Instruction::LoadBigFont { x },
+        (_,3,3) => Instruction::StoreBcd { x },
+        (_,3,0xA) => Instruction::SetPitch { x },
+        (_,5,5) => Instruction::StoreRegs { x },
+        (_,6,5) => Instruction::LoadRegs { x },
+        (_,7,5) => Instruction::SaveFlags { x },
+        (_,8,5) => Instruction::LoadFlags { x },
+        _ => return None,
+    };
+    Some(instruction)
+}
+
+//Decode the instruction at an address given its first word and the word after it
+//Only the XO-CHIP F000 NNNN and, in MegaChip mode, 01NN NNNN use the second word, see is_long
+pub fn decode_long(opcode: u16, next: u16, megachip: bool) -> Result<Instruction, DecodeError> {
+    if !is_long(opcode, megachip) {
+        return decode(opcode);
+    }
+    if opcode == LONG_PREFIX {
+        Ok(Instruction::LoadILong(next))
+    } else {
+        Ok(Instruction::LoadIMega(((opcode as u32 & 0xFF) << 16) | next as u32))
+    }
+}
+
+//decode_long for CHIP-8X programs, where BXYN sets colours instead of jumping
+pub fn decode_chip8x(opcode: u16, next: u16, megachip: bool) -> Result<Instruction, DecodeError> {
+    if opcode >> 12 == 0xB {
+        let [x, y, n] = [opcode >> 8 & 0xF, opcode >> 4 & 0xF, opcode & 0xF].map(|digit| digit as u8);
+        return Ok(Instruction::Foreground { x, y, n });
+    }
+    decode_long(opcode, next, megachip)
+}
+
+//Whether the instruction starting with opcode is 4 bytes, for decoding and for skips over it: F000 NNNN
+//always, 01NN NNNN only in MegaChip mode (outside it 01NN is a 2 byte unknown opcode, apart from 0010/0011)
+pub fn is_long(opcode: u16, megachip: bool) -> bool {
+    opcode == LONG_PREFIX || (megachip && opcode >> 8 == MEGA_LONG_PREFIX)
+}
+
+//First word of the XO-CHIP 4 byte instruction
+pub(crate) const LONG_PREFIX: u16 = 0xF000;
+//First byte of the MegaChip one
+pub(crate) const MEGA_LONG_PREFIX: u16 = 0x01;
+
+//Mnemonics follow Cowgod's Chip-8 technical reference (e.g. JP 0x22A, LD V0, 0x05)
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Instruction::Nop => write!(f, "NOP"),
+            Instruction::ClearScreen => write!(f, "CLS"),
+            Instruction::Return => write!(f, "RET"),
+            Instruction::MegaOff => write!(f, "MEGAOFF"),
+            Instruction::MegaOn => write!(f, "MEGAON"),
+            Instruction::LoadIMega(nnnnnn) => write!(f, "LDHI I, {:#08X}", nnnnnn),
+            Instruction::LoadPalette(nn) => write!(f, "LDPAL {}", nn),
+            Instruction::SpriteWidth(nn) => write!(f, "SPRW {}", nn),
+            Instruction::SpriteHeight(nn) => write!(f, "SPRH {}", nn),
+            Instruction::ScreenAlpha(nn) => write!(f, "ALPHA {:#04X}", nn),
+            Instruction::PlaySample(n) => write!(f, "DIGISND {}", n),
+            Instruction::StopSample => write!(f, "STOPSND"),
+            Instruction::Blend(n) => write!(f, "BMODE {}", n),
+            Instruction::CollisionColor(nn) => write!(f, "CCOL {}", nn),
+            Instruction::ScrollUp(n) => write!(f, "SCU {}", n),
+            Instruction::CycleBackground => write!(f, "BGCOL"),
+            Instruction::ScrollDown(n) => write!(f, "SCD {}", n),
+            Instruction::ScrollRight => write!(f, "SCR"),
+            Instruction::ScrollLeft => write!(f, "SCL"),
+            Instruction::Exit => write!(f, "EXIT"),
+            Instruction::LowRes => write!(f, "LOW"),
+            Instruction::HighRes => write!(f, "HIGH"),
+            Instruction::Jump(nnn) => write!(f, "JP {:#05X}", nnn),
+            Instruction::Call(nnn) => write!(f, "CALL {:#05X}", nnn),
+            Instruction::SkipEqImm { x, nn } => write!(f, "SE V{:X}, {:#04X}", x, nn),
+            Instruction::SkipNeImm { x, nn } => write!(f, "SNE V{:X}, {:#04X}", x, nn),
+            Instruction::SkipEqReg { x, y } => write!(f, "SE V{:X}, V{:X}", x, y),
+            Instruction::AddNibbles { x, y } => write!(f, "ADDN V{:X}, V{:X}", x, y),
+            Instruction::SaveRange { x, y } => write!(f, "LD [I], V{:X}-V{:X}", x, y),
+            Instruction::LoadRange { x, y } => write!(f, "LD V{:X}-V{:X}, [I]", x, y),
+            Instruction::LoadImm { x, nn } => write!(f, "LD V{:X}, {:#04X}", x, nn),
+            Instruction::AddImm { x, nn } => write!(f, "ADD V{:X}, {:#04X}", x, nn),
+            Instruction::LoadReg { x, y } => write!(f, "LD V{:X}, V{:X}", x, y),
+            Instruction::Or { x, y } => write!(f, "OR V{:X}, V{:X}", x, y),
+            Instruction::And { x, y } => write!(f, "AND V{:X}, V{:X}", x, y),
+            Instruction::Xor { x, y } => write!(f, "XOR V{:X}, V{:X}", x, y),
+            Instruction::AddReg { x, y } => write!(f, "ADD V{:X}, V{:X}", x, y),
+            Instruction::SubReg { x, y } => write!(f, "SUB V{:X}, V{:X}", x, y),
+            Instruction::ShiftRight { x, y } => write!(f, "SHR V{:X}, V{:X}", x, y),
+            Instruction::SubN { x, y } => write!(f, "SUBN V{:X}, V{:X}", x, y),
+            Instruction::ShiftLeft { x, y } => write!(f, "SHL V{:X}, V{:X}", x, y),
+            Instruction::SkipNeReg { x, y } => write!(f, "SNE V{:X}, V{:X}", x, y),
+            Instruction::LoadI(nnn) => write!(f, "LD I, {:#05X}", nnn),
+            Instruction::JumpV0(nnn) => write!(f, "JP V0, {:#05X}", nnn),
+            Instruction::Foreground { x, y, n } => write!(f, "COL V{:X}, V{:X}, {}", x, y, n),
+            Instruction::Random { x, nn } => write!(f, "RND V{:X}, {:#04X}", x, nn),
+            Instruction::Draw { x, y, n } => write!(f, "DRW V{:X}, V{:X}, {}", x, y, n),
+            Instruction::SkipKeyPressed { x } => write!(f, "SKP V{:X}", x),
+            Instruction::SkipKeyNotPressed { x } => write!(f, "SKNP V{:X}", x),
+            Instruction::LoadILong(nnnn) => write!(f, "LD I, LONG {:#06X}", nnnn),
+            Instruction::Plane(n) => write!(f, "PLANE {}", n),
+            Instruction::LoadAudio => write!(f, "AUDIO"),
+            Instruction::LoadDelay { x } => write!(f, "LD V{:X}, DT", x),
+            Instruction::WaitKey { x } => write!(f, "LD V{:X}, K", x),
+            Instruction::SetDelay { x } => write!(f, "LD DT, V{:X}", x),
+            Instruction::SetSound { x } => write!(f, "LD ST, V{:X}", x),
+            Instruction::AddI { x } => write!(f, "ADD I, V{:X}", x),
+            Instruction::LoadFont { x } => write!(f, "LD F, V{:X}", x),
+            Instruction::LoadBigFont { x } => write!(f, "LD HF, V{:X}", x),
+            Instruction::StoreBcd { x } => write!(f, "LD B, V{:X}", x),
+            Instruction::SetPitch { x } => write!(f, "PITCH V{:X}", x),
+            Instruction::StoreRegs { x } => write!(f, "LD [I], V{:X}", x),
+            Instruction::LoadRegs { x } => write!(f, "LD V{:X}, [I]", x),
+            Instruction::SaveFlags { x } => write!(f, "LD R, V{:X}", x),
+            Instruction::LoadFlags { x } => write!(f, "LD V{:X}, R", x),
+        }
+    }
+}
+
+impl Instruction {
+    //Instructions that only exist in SUPER-CHIP (DXY0 included)
+    pub fn is_schip(&self) -> bool {
+        matches!(
+            self,
+            Instruction::ScrollDown(_)
+                | Instruction::ScrollRight
+                | Instruction::ScrollLeft
+                | Instruction::Exit
+                | Instruction::LowRes
+                | Instruction::HighRes
+                | Instruction::LoadBigFont { .. }
+                | Instruction::SaveFlags { .. }
+                | Instruction::LoadFlags { .. }
+                | Instruction::Draw { n: 0, .. }
+        )
+    }
+
+    //Instructions that only exist in XO-CHIP
+    pub fn is_xochip(&self) -> bool {
+        matches!(
+            self,
+            Instruction::SaveRange { .. }
+                | Instruction::LoadRange { .. }
+                | Instruction::LoadILong(_)
+                | Instruction::Plane(_)
+                | Instruction::LoadAudio
+                | Instruction::SetPitch { .. }
+        )
+    }
+
+    //Instructions that only exist in CHIP-8X
+    pub fn is_chip8x(&self) -> bool {
+        matches!(self, Instruction::CycleBackground | Instruction::AddNibbles { .. } | Instruction::Foreground { .. })
+    }
+
+    //Instructions that only exist in MegaChip
+    pub fn is_megachip(&self) -> bool {
+        matches!(
+            self,
+            Instruction::MegaOff
+                | Instruction::MegaOn
+                | Instruction::LoadIMega(_)
+                | Instruction::LoadPalette(_)
+                | Instruction::SpriteWidth(_)
+                | Instruction::SpriteHeight(_)
+                | Instruction::ScreenAlpha(_)
+                | Instruction::PlaySample(_)
+                | Instruction::StopSample
+                | Instruction::Blend(_)
+                | Instruction::CollisionColor(_)
+                | Instruction::ScrollUp(_)
+        )
+    }
+
+    //Length in bytes, F000 NNNN and 01NN NNNN are 4 bytes and everything else is 2
+    pub fn size(&self) -> u16 {
+        match self {
+            Instruction::LoadILong(_) | Instruction::LoadIMega(_) => 4,
+            _ => 2,
+        }
+    }
+
+    //Opcode family in the usual notation, e.g. "8XY4" or "DXYN"
+    pub fn pattern(&self) -> &'static str {
+        match self {
+            Instruction::Nop => "0000",
+            Instruction::ClearScreen => "00E0",
+            Instruction::Return => "00EE",
+            Instruction::MegaOff => "0010",
+            Instruction::MegaOn => "0011",
+            Instruction::LoadIMega(_) => "01NN",
+            Instruction::LoadPalette(_) => "02NN",
+            Instruction::SpriteWidth(_) => "03NN",
+            Instruction::SpriteHeight(_) => "04NN",
+            Instruction::ScreenAlpha(_) => "05NN",
+            Instruction::PlaySample(_) => "060N",
+            Instruction::StopSample => "0700",
+            Instruction::Blend(_) => "080N",
+            Instruction::CollisionColor(_) => "09NN",
+            Instruction::ScrollUp(_) => "00BN",
+            Instruction::CycleBackground => "02A0",
+            Instruction::ScrollDown(_) => "00CN",
+            Instruction::ScrollRight => "00FB",
+            Instruction::ScrollLeft => "00FC",
+            Instruction::Exit => "00FD",
+            Instruction::LowRes => "00FE",
+            Instruction::HighRes => "00FF",
+            Instruction::Jump(_) => "1NNN",
+            Instruction::Call(_) => "2NNN",
+            Instruction::SkipEqImm { .. } => "3XNN",
+            Instruction::SkipNeImm { .. } => "4XNN",
+            Instruction::SkipEqReg { .. } => "5XY0",
+            Instruction::AddNibbles { .. } => "5XY1",
+            Instruction::SaveRange { .. } => "5XY2",
+            Instruction::LoadRange { .. } => "5XY3",
+            Instruction::LoadImm { .. } => "6XNN",
+            Instruction::AddImm { .. } => "7XNN",
+            Instruction::LoadReg { .. } => "8XY0",
+            Instruction::Or { .. } => "8XY1",
+            Instruction::And { .. } => "8XY2",
+            Instruction::Xor { .. } => "8XY3",
+            Instruction::AddReg { .. } => "8XY4",
+            Instruction::SubReg { .. } => "8XY5",
+            Instruction::ShiftRight { .. } => "8XY6",
+            Instruction::SubN { .. } => "8XY7",
+            Instruction::ShiftLeft { .. } => "8XYE",
+            Instruction::SkipNeReg { .. } => "9XY0",
+            Instruction::LoadI(_) => "ANNN",
+            Instruction::JumpV0(_) => "BNNN",
+            Instruction::Foreground { .. } => "BXYN",
+            Instruction::Random { .. } => "CXNN",
+            Instruction::Draw { .. } => "DXYN",
+            Instruction::SkipKeyPressed { .. } => "EX9E",
+            Instruction::SkipKeyNotPressed { .. } => "EXA1",
+            Instruction::LoadILong(_) => "F000",
+            Instruction::Plane(_) => "FN01",
+            Instruction::LoadAudio => "F002",
+            Instruction::LoadDelay { .. } => "FX07",
+            Instruction::WaitKey { .. } => "FX0A",
+            Instruction::SetDelay { .. } => "FX15",
+            Instruction::SetSound { .. } => "FX18",
+            Instruction::AddI { .. } => "FX1E",
+            Instruction::LoadFont { .. } => "FX29",
+            Instruction::LoadBigFont { .. } => "FX30",
+            Instruction::StoreBcd { .. } => "FX33",
+            Instruction::SetPitch { .. } => "FX3A",
+            Instruction::StoreRegs { .. } => "FX55",
+            Instruction::LoadRegs { .. } => "FX65",
+            Instruction::SaveFlags { .. } => "FX75",
+            Instruction::LoadFlags { .. } => "FX85",
+        }
+    }
+
+    //Encoded bytes in memory order, including the operand word of F000 NNNN and 01NN NNNN
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let word = self.encode();
+        let mut bytes = vec![(word >> 8) as u8, word as u8];
+        match *self {
+            Instruction::LoadILong(nnnn) => bytes.extend_from_slice(&nnnn.to_be_bytes()),
+            Instruction::LoadIMega(nnnnnn) => bytes.extend_from_slice(&(nnnnnn as u16).to_be_bytes()),
+            _ => (),
+        }
+        bytes
+    }
+
+    //Inverse of decode, used by the assembler
+    //F000 NNNN and 01NN NNNN encode to their first word only, to_bytes includes the operand
+    pub fn encode(&self) -> u16 {
+        let xy = |op: u16, x: u8, y: u8, n: u16| op | ((x as u16) << 8) | ((y as u16) << 4) | n;
+        let xnn = |op: u16, x: u8, nn: u8| op | ((x as u16) << 8) | nn as u16;
+        let fx = |x: u8, low: u16| 0xF000 | ((x as u16) << 8) | low;
+
+        match *self {
+            Instruction::Nop => 0x0000,
+            Instruction::ClearScreen => 0x00E0,
+            Instruction::Return => 0x00EE,
+            Instruction::MegaOff => 0x0010,
+            Instruction::MegaOn => 0x0011,
+            Instruction::LoadIMega(nnnnnn) => (MEGA_LONG_PREFIX << 8) | (nnnnnn >> 16 & 0xFF) as u16,
+            Instruction::LoadPalette(nn) => 0x0200 | nn as u16,
+            Instruction::SpriteWidth(nn) => 0x0300 | nn as u16,
+            Instruction::SpriteHeight(nn) => 0x0400 | nn as u16,
+            Instruction::ScreenAlpha(nn) => 0x0500 | nn as u16,
+            Instruction::PlaySample(n) => 0x0600 | (n & 0xF) as u16,
+            Instruction::StopSample => 0x0700,
+            Instruction::Blend(n) => 0x0800 | (n & 0xF) as u16,
+            Instruction::CollisionColor(nn) => 0x0900 | nn as u16,
+            Instruction::ScrollUp(n) => 0x00B0 | (n & 0xF) as u16,
+            Instruction::CycleBackground => 0x02A0,
+            Instruction::ScrollDown(n) => 0x00C0 | (n & 0xF) as u16,
+            Instruction::ScrollRight => 0x00FB,
+            Instruction::ScrollLeft => 0x00FC,
+            Instruction::Exit => 0x00FD,
+            Instruction::LowRes => 0x00FE,
+            Instruction::HighRes => 0x00FF,
+            Instruction::Jump(nnn) => 0x1000 | (nnn & 0xFFF),
+            Instruction::Call(nnn) => 0x2000 | (nnn & 0xFFF),
+            Instruction::SkipEqImm { x, nn } => xnn(0x3000, x, nn),
+            Instruction::SkipNeImm { x, nn } => xnn(0x4000, x, nn),
+            Instruction::SkipEqReg { x, y } => xy(0x5000, x, y, 0),
+            Instruction::AddNibbles { x, y } => xy(0x5000, x, y, 1),
+            Instruction::SaveRange { x, y } => xy(0x5000, x, y, 2),
+            Instruction::LoadRange { x, y } => xy(0x5000, x, y, 3),
+            Instruction::LoadImm { x, nn } => xnn(0x6000, x, nn),
+            Instruction::AddImm { x, nn } => xnn(0x7000, x, nn),
+            Instruction::LoadReg { x, y } => xy(0x8000, x, y, 0),
+            Instruction::Or { x, y } => xy(0x8000, x, y, 1),
+            Instruction::And { x, y } => xy(0x8000, x, y, 2),
+            Instruction::Xor { x, y } => xy(0x8000, x, y, 3),
+            Instruction::AddReg { x, y } => xy(0x8000, x, y, 4),
+            Instruction::SubReg { x, y } => xy(0x8000, x, y, 5),
+            Instruction::ShiftRight { x, y } => xy(0x8000, x, y, 6),
+            Instruction::SubN { x, y } => xy(0x8000, x, y, 7),
+            Instruction::ShiftLeft { x, y } => xy(0x8000, x, y, 0xE),
+            Instruction::SkipNeReg { x, y } => xy(0x9000, x, y, 0),
+            Instruction::LoadI(nnn) => 0xA000 | (nnn & 0xFFF),
+            Instruction::JumpV0(nnn) => 0xB000 | (nnn & 0xFFF),
+            Instruction::Foreground { x, y, n } => xy(0xB000, x, y, (n & 0xF) as u16),
+            Instruction::Random { x, nn } => xnn(0xC000, x, nn),
+            Instruction::Draw { x, y, n } => xy(0xD000, x, y, (n & 0xF) as u16),
+            Instruction::SkipKeyPressed { x } => xnn(0xE000, x, 0x9E),
+            Instruction::SkipKeyNotPressed { x } => xnn(0xE000, x, 0xA1),
+            Instruction::LoadILong(_) => LONG_PREFIX,
+            Instruction::Plane(n) => fx(n & 0xF, 0x01),
+            Instruction::LoadAudio => 0xF002,
+            Instruction::LoadDelay { x } => fx(x, 0x07),
+            Instruction::WaitKey { x } => fx(x, 0x0A),
+            Instruction::SetDelay { x } => fx(x, 0x15),
+            Instruction::SetSound { x } => fx(x, 0x18),
+            Instruction::AddI { x } => fx(x, 0x1E),
+            Instruction::LoadFont { x } => fx(x, 0x29),
+            Instruction::LoadBigFont { x } => fx(x, 0x30),
+            Instruction::StoreBcd { x } => fx(x, 0x33),
+            Instruction::SetPitch { x } => fx(x, 0x3A),
+            Instruction::StoreRegs { x } => fx(x, 0x55),
+            Instruction::LoadRegs { x } => fx(x, 0x65),
+            Instruction::SaveFlags { x } => fx(x, 0x75),
+            Instruction::LoadFlags { x } => fx(x, 0x85),
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use crate::chip8::{Emulator, FrameOutput, TICKS_PER_FRAME};
+use crate::error::{Chip8Error, ReplayError};
+use crate::input::Key;
+use crate::quirks::Quirks;
+use crate::romdb::rom_hash;
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+//Event-sourced recording: instead of a snapshot every frame, keep the key presses and random bytes of
+//each frame plus a keyframe snapshot every keyframe_interval frames. Any past frame is rebuilt by
+//re-running from the keyframe before it, so memory stays small for long recordings at the cost of CPU
+//Route the frontend's keypress() and frame calls through the journal while recording
+pub struct Journal {
+    keyframe_interval: u64,
+    ticks_per_frame: usize,
+    //Snapshot taken before the frame with the same number ran
+    keyframes: Vec<(u64, Emulator)>,
+    frames: Vec<FrameLog>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct FrameLog {
+    //Key changes delivered before the frame ran, in order
+    inputs: Vec<(Key, bool)>,
+    randoms: Vec<u8>,
+}
+
+impl Journal {
+    //Start recording emulator from its current state (frame 0)
+    //A small interval makes reconstruct() fast, a large one keeps fewer snapshots
+    pub fn new(emulator: &mut Emulator, keyframe_interval: u64) -> Self {
+        emulator.set_random_logging(true);
+        Self {
+            keyframe_interval: keyframe_interval.max(1),
+            ticks_per_frame: TICKS_PER_FRAME,
+            keyframes: vec![(0, emulator.clone())],
+            frames: vec![FrameLog::default()],
+        }
+    }
+
+    pub fn set_ticks_per_frame(&mut self, ticks: usize) {
+        self.ticks_per_frame = ticks;
+    }
+
+    //Number of completed frames
+    pub fn frame(&self) -> u64 {
+        self.frames.len() as u64 - 1
+    }
+
+    pub fn keypress(&mut self, emulator: &mut Emulator, key: Key, pressed: bool) {
+        self.current().inputs.push((key, pressed));
+        emulator.keypress(key, pressed);
+    }
+
+    //Run one frame and record the random bytes it drew
+    pub fn run_frame(&mut self, emulator: &mut Emulator) -> Result<FrameOutput, Chip8Error> {
+        let result = emulator.run_frame_with(self.ticks_per_frame);
+        self.current().randoms = emulator.take_random_log();
+        self.frames.push(FrameLog::default());
+        let frame = self.frame();
+        if frame.is_multiple_of(self.keyframe_interval) {
+            self.keyframes.push((frame, emulator.clone()));
+        }
+        result
+    }
+
+    //The emulator as it was after `frame` frames, None if that frame has not been recorded yet
+    //A frame that faulted while recording faults the same way here, the state is returned as it was
+    pub fn reconstruct(&self, frame: u64) -> Option<Emulator> {
+        if frame > self.frame() {
+            return None;
+        }
+        let (start, keyframe) = self.keyframes.iter().rev().find(|(start, _)| *start <= frame)?;
+        let mut emulator = keyframe.clone();
+        emulator.set_random_logging(false);
+        for log in &self.frames[*start as usize..frame as usize] {
+            for &(key, pressed) in &log.inputs {
+                emulator.keypress(key, pressed);
+            }
+            emulator.replay_random(&log.randoms);
+            let _ = emulator.run_frame_with(self.ticks_per_frame);
+        }
+        Some(emulator)
+    }
+
+    //Everything needed to play the recording back from a fresh emulator
+    //Only meaningful if the journal was started straight after loading rom
+    pub fn to_replay(&self, rom: &[u8]) -> Replay {
+        Replay {
+            rom_sha1: rom_hash(rom),
+            quirks: *self.keyframes[0].1.quirks(),
+            ticks_per_frame: self.ticks_per_frame,
+            frames: self.frames[..self.frames.len() - 1].to_vec(),
+        }
+    }
+
+    //Stop recording, the emulator draws random bytes without logging them again
+    pub fn finish(self, emulator: &mut Emulator) {
+        emulator.set_random_logging(false);
+    }
+
+    fn current(&mut self) -> &mut FrameLog {
+        self.frames.last_mut().unwrap()
+    }
+}
+
+const REPLAY_HEADER: &str = "chip8-replay 1";
+
+//A recorded session: the inputs and random bytes of every frame since the ROM was loaded
+//Text format, one line per frame after a short header: "3+ 3- | A1 07" presses and releases key 3
+//before the frame and draws two random bytes during it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replay {
+    pub rom_sha1: String,
+    pub quirks: Quirks,
+    pub ticks_per_frame: usize,
+    frames: Vec<FrameLog>,
+}
+
+impl Replay {
+    pub fn frame_count(&self) -> u64 {
+        self.frames.len() as u64
+    }
+
+    //Run the whole replay on a fresh emulator and return it as it was after the last frame
+    pub fn play(&self, rom: &[u8]) -> Result<Emulator, ReplayError> {
+        if rom_hash(rom) != self.rom_sha1 {
+            return Err(ReplayError::RomMismatch { expected: self.rom_sha1.clone() });
+        }
+        let mut emulator = Emulator::new();
+        emulator.set_quirks(self.quirks);
+        emulator.load_rom(rom).map_err(ReplayError::Rom)?;
+        for log in &self.frames {
+            for &(key, pressed) in &log.inputs {
+                emulator.keypress(key, pressed);
+            }
+            emulator.replay_random(&log.randoms);
+            emulator.run_frame_with(self.ticks_per_frame).map_err(ReplayError::Fault)?;
+        }
+        Ok(emulator)
+    }
+
+    pub fn to_text(&self) -> String {
+        let quirks: Vec<&str> = self.quirks.flags().iter().filter(|(_, on)| *on).map(|(name, _)| *name).collect();
+        let mut text = format!(
+            "{}\nrom {}\nticks {}\nquirks {}\nframes {}\n",
+            REPLAY_HEADER, self.rom_sha1, self.ticks_per_frame, quirks.join(","), self.frames.len()
+        );
+        for log in &self.frames {
+            let inputs: Vec<String> = log.inputs.iter().map(|&(key, pressed)| format!("{}{}", key, if pressed { '+' } else { '-' })).collect();
+            let randoms: Vec<String> = log.randoms.iter().map(|byte| format!("{:02X}", byte)).collect();
+            let _ = writeln!(text, "{} | {}", inputs.join(" "), randoms.join(" "));
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ReplayError> {
+        let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line.trim()));
+        let mut header = |name: &str| -> Result<(usize, String), ReplayError> {
+            match lines.next() {
+                Some((line, text)) if name.is_empty() => Ok((line, text.to_string())),
+                Some((line, text)) => match text.strip_prefix(name).and_then(|rest| rest.strip_prefix(' ').or(Some(rest).filter(|r| r.is_empty()))) {
+                    Some(value) => Ok((line, value.to_string())),
+                    None => Err(malformed(line, format!("expected '{}'", name))),
+                },
+                None => Err(malformed(0, "unexpected end of replay".to_string())),
+            }
+        };
+        let (line, magic) = header("")?;
+        if magic != REPLAY_HEADER {
+            return Err(malformed(line, "not a replay (or an unsupported version)".to_string()));
+        }
+        let (_, rom_sha1) = header("rom")?;
+        let (line, ticks) = header("ticks")?;
+        let ticks_per_frame = ticks.parse().map_err(|_| malformed(line, format!("invalid tick count '{}'", ticks)))?;
+        let (line, names) = header("quirks")?;
+        let mut quirks = Quirks::default();
+        for name in names.split(',').filter(|name| !name.is_empty()) {
+            if !quirks.set_flag(name, true) {
+                return Err(malformed(line, format!("unknown quirk '{}'", name)));
+            }
+        }
+        let (line, count) = header("frames")?;
+        let count: usize = count.parse().map_err(|_| malformed(line, format!("invalid frame count '{}'", count)))?;
+
+        let mut frames = Vec::with_capacity(count);
+        for (line, text) in lines {
+            let (inputs, randoms) = text.split_once('|').ok_or_else(|| malformed(line, "expected 'inputs | random bytes'".to_string()))?;
+            let inputs = inputs
+                .split_whitespace()
+                .map(|input| {
+                    let (key, pressed) = input.split_at(input.len().saturating_sub(1));
+                    let key = usize::from_str_radix(key, 16).ok().and_then(Key::from_index);
+                    match (key, pressed) {
+                        (Some(key), "+") => Ok((key, true)),
+                        (Some(key), "-") => Ok((key, false)),
+                        _ => Err(malformed(line, format!("invalid input '{}'", input))),
+                    }
+                })
+                .collect::<Result<_, _>>()?;
+            let randoms = randoms
+                .split_whitespace()
+                .map(|byte| u8::from_str_radix(byte, 16).map_err(|_| malformed(line, format!("invalid random byte '{}'", byte))))
+                .collect::<Result<_, _>>()?;
+            frames.push(FrameLog { inputs, randoms });
+        }
+        if frames.len() != count {
+            return Err(malformed(0, format!("expected {} frames, found {}", count, frames.len())));
+        }
+        Ok(Self { rom_sha1, quirks, ticks_per_frame, frames })
+    }
+}
+
+fn malformed(line: usize, message: String) -> ReplayError {
+    ReplayError::Malformed { line, message }
+}
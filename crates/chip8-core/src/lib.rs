@@ -0,0 +1,125 @@
+//The emulator itself: CPU, display, timers, debugging, save states and the tooling that needs no I/O
+//Frontends (SDL) and networked tools live in chip8-frontends and chip8-tools, the chip8 crate bundles all three
+//no_std with alloc unless the std feature (on by default) adds files, threads and the OS random source
+#![no_std]
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "archive")]
+pub mod archive;
+pub mod asm;
+pub mod audio;
+mod builder;
+mod cheats;
+mod chip8;
+mod clock;
+pub mod compat;
+pub mod conformance;
+mod coredump;
+mod debugger;
+mod decode_cache;
+mod diff;
+pub mod differential;
+mod display;
+pub mod disasm;
+pub mod effects;
+mod error;
+pub mod events;
+pub mod explain;
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod framebuffer;
+#[cfg(feature = "fuzz")]
+pub mod fuzz;
+pub mod graph;
+#[cfg(feature = "heatmap")]
+mod heatmap;
+pub mod input;
+pub mod instruction;
+mod journal;
+#[cfg(feature = "libretro")]
+pub mod libretro;
+pub mod megachip;
+mod memory;
+mod metrics;
+#[cfg(feature = "clock")]
+mod pacing;
+mod patch;
+mod phosphor;
+pub mod power;
+pub mod prelude;
+mod profile;
+pub mod program;
+pub mod quirks;
+pub mod recorder;
+mod rewind;
+mod rom;
+pub mod rpl;
+#[cfg(feature = "clock")]
+mod runner;
+pub mod romdb;
+pub mod score;
+pub mod screenshot;
+#[cfg(feature = "scripting")]
+pub mod script;
+#[cfg(feature = "crypto")]
+pub mod signing;
+#[cfg(feature = "std")]
+mod slots;
+pub mod sprites;
+mod state;
+mod tas;
+pub mod timing;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+mod warmup;
+
+pub use crate::audio::{AudioSink, AudioState, Beeper, PatternPlayer};
+pub use crate::builder::EmulatorBuilder;
+pub use crate::cheats::{Cheat, CheatKind, Cheats};
+pub use crate::chip8::*;
+pub use crate::clock::{SystemClock, TimeSource};
+#[cfg(target_has_atomic = "64")]
+pub use crate::clock::ManualClock;
+pub use crate::coredump::CoreDump;
+pub use crate::debugger::{Break, Breakpoints, CallFrame, DebugSession, Layout, Pane};
+pub use crate::diff::{diff, FieldDiff, MemoryDiff, StateDiff};
+pub use crate::display::{Display, Frame};
+pub use crate::effects::{Effects, MemoryWrite};
+pub use crate::error::{BuildError, CheatError, Chip8Error, CoreDumpError, DebugSessionError, FontError, MemoryMapError, ReplayError, RomError, RomFileError, RunError, StateError};
+#[cfg(feature = "std")]
+pub use crate::error::SlotError;
+pub use crate::events::{Access, Event, Lifecycle, Violation, Warning};
+pub use crate::explain::explain;
+pub use crate::framebuffer::*;
+#[cfg(feature = "heatmap")]
+pub use crate::heatmap::{AccessCounts, Heatmap};
+pub use crate::input::{InputSource, Key, KeyMap, ScriptedInput};
+pub use crate::instruction::{decode, DecodeError, Instruction};
+pub use crate::journal::{Journal, Replay};
+pub use crate::memory::{MemoryMap, MIN_RAM_SIZE};
+pub use crate::metrics::Metrics;
+#[cfg(feature = "clock")]
+pub use crate::pacing::Clock;
+pub use crate::patch::{Patch, PatchLog};
+pub use crate::phosphor::Phosphor;
+pub use crate::profile::{ProfileEntry, ProfileReport};
+pub use crate::program::Program;
+pub use crate::rewind::Rewind;
+pub use crate::rom::RomFormat;
+#[cfg(feature = "clock")]
+pub use crate::runner::{Command, Output, Runner};
+pub use crate::quirks::{Quirks, RandomModel};
+pub use crate::romdb::{rom_hash, RomDb, RomEntry, RomSettings};
+pub use crate::rpl::{FlagStore, RPL_FLAGS_SIZE};
+#[cfg(feature = "std")]
+pub use crate::rpl::FlagFile;
+#[cfg(feature = "std")]
+pub use crate::slots::{Slot, StateSlots, QUICK_SLOTS};
+pub use crate::state::State;
+pub use crate::tas::{InputEvent, InputLog, InputRecorder, Session};
+pub use crate::timing::Timing;
+pub use crate::warmup::{WarmUp, WarmUpOutcome};
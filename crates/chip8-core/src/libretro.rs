@@ -0,0 +1,393 @@
+//libretro core for RetroArch and other libretro frontends, with the libretro feature
+//Build the cdylib with cargo build -p chip8-core --release --features libretro and install
+//libchip8_core.so as chip8_libretro.so in the frontend's cores directory
+//A frontend runs one core instance per process, so the emulator lives in a global
+#![allow(clippy::missing_safety_doc)]
+
+use crate::audio::{Beeper, PatternPlayer};
+use crate::chip8::{Emulator, RAM_SIZE};
+use crate::framebuffer::{Palette, HIRES_SCREEN_HEIGHT, HIRES_SCREEN_WIDTH, SCREEN_BUFFER_SIZE, SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::input::{Key, KeyMap};
+use crate::romdb::RomSettings;
+use crate::state::State;
+
+use alloc::vec;
+use alloc::vec::Vec;
+use std::ffi::{c_char, c_uint, c_void, CStr};
+use std::{fs, ptr, slice};
+use std::sync::Mutex;
+
+const RETRO_API_VERSION: c_uint = 1;
+const RETRO_REGION_NTSC: c_uint = 0;
+const RETRO_ENVIRONMENT_SET_PIXEL_FORMAT: c_uint = 10;
+const RETRO_PIXEL_FORMAT_XRGB8888: c_uint = 1;
+const RETRO_DEVICE_JOYPAD: c_uint = 1;
+const RETRO_DEVICE_KEYBOARD: c_uint = 3;
+
+const RETRO_DEVICE_ID_JOYPAD_B: c_uint = 0;
+const RETRO_DEVICE_ID_JOYPAD_Y: c_uint = 1;
+const RETRO_DEVICE_ID_JOYPAD_SELECT: c_uint = 2;
+const RETRO_DEVICE_ID_JOYPAD_START: c_uint = 3;
+const RETRO_DEVICE_ID_JOYPAD_UP: c_uint = 4;
+const RETRO_DEVICE_ID_JOYPAD_DOWN: c_uint = 5;
+const RETRO_DEVICE_ID_JOYPAD_LEFT: c_uint = 6;
+const RETRO_DEVICE_ID_JOYPAD_RIGHT: c_uint = 7;
+const RETRO_DEVICE_ID_JOYPAD_A: c_uint = 8;
+const RETRO_DEVICE_ID_JOYPAD_X: c_uint = 9;
+
+//Most programs steer with 2/4/6/8 and act with 5, the rest covers common menu keys
+const JOYPAD: [(c_uint, Key); 10] = [
+    (RETRO_DEVICE_ID_JOYPAD_UP, Key::Key2),
+    (RETRO_DEVICE_ID_JOYPAD_DOWN, Key::Key8),
+    (RETRO_DEVICE_ID_JOYPAD_LEFT, Key::Key4),
+    (RETRO_DEVICE_ID_JOYPAD_RIGHT, Key::Key6),
+    (RETRO_DEVICE_ID_JOYPAD_A, Key::Key5),
+    (RETRO_DEVICE_ID_JOYPAD_B, Key::Key0),
+    (RETRO_DEVICE_ID_JOYPAD_X, Key::KeyF),
+    (RETRO_DEVICE_ID_JOYPAD_Y, Key::KeyE),
+    (RETRO_DEVICE_ID_JOYPAD_START, Key::Key1),
+    (RETRO_DEVICE_ID_JOYPAD_SELECT, Key::Key3),
+];
+
+const FPS: f64 = 60.0;
+const SAMPLE_RATE: u32 = 44_100;
+const SAMPLES_PER_FRAME: usize = (SAMPLE_RATE / 60) as usize;
+//Room for the largest snapshot (packing at worst doubles ram and screen) behind a u32 length,
+//libretro wants one size for every state
+const SERIALIZE_SIZE: usize = 4 + 2 * (RAM_SIZE + SCREEN_BUFFER_SIZE) + 256;
+
+type EnvironmentFn = unsafe extern "C" fn(cmd: c_uint, data: *mut c_void) -> bool;
+type VideoRefreshFn = unsafe extern "C" fn(data: *const c_void, width: c_uint, height: c_uint, pitch: usize);
+type AudioSampleFn = unsafe extern "C" fn(left: i16, right: i16);
+type AudioSampleBatchFn = unsafe extern "C" fn(data: *const i16, frames: usize) -> usize;
+type InputPollFn = unsafe extern "C" fn();
+type InputStateFn = unsafe extern "C" fn(port: c_uint, device: c_uint, index: c_uint, id: c_uint) -> i16;
+
+#[repr(C)]
+pub struct RetroSystemInfo {
+    library_name: *const c_char,
+    library_version: *const c_char,
+    valid_extensions: *const c_char,
+    need_fullpath: bool,
+    block_extract: bool,
+}
+
+#[repr(C)]
+pub struct RetroGameGeometry {
+    base_width: c_uint,
+    base_height: c_uint,
+    max_width: c_uint,
+    max_height: c_uint,
+    aspect_ratio: f32,
+}
+
+#[repr(C)]
+pub struct RetroSystemTiming {
+    fps: f64,
+    sample_rate: f64,
+}
+
+#[repr(C)]
+pub struct RetroSystemAvInfo {
+    geometry: RetroGameGeometry,
+    timing: RetroSystemTiming,
+}
+
+#[repr(C)]
+pub struct RetroGameInfo {
+    path: *const c_char,
+    data: *const c_void,
+    size: usize,
+    meta: *const c_char,
+}
+
+#[derive(Clone, Copy)]
+struct Callbacks {
+    environment: Option<EnvironmentFn>,
+    video_refresh: Option<VideoRefreshFn>,
+    audio_sample_batch: Option<AudioSampleBatchFn>,
+    input_poll: Option<InputPollFn>,
+    input_state: Option<InputStateFn>,
+}
+
+static CALLBACKS: Mutex<Callbacks> = Mutex::new(Callbacks {
+    environment: None,
+    video_refresh: None,
+    audio_sample_batch: None,
+    input_poll: None,
+    input_state: None,
+});
+
+//Between retro_load_game and retro_unload_game
+static CORE: Mutex<Option<Core>> = Mutex::new(None);
+
+struct Core {
+    emulator: Emulator,
+    //For retro_reset
+    rom: Vec<u8>,
+    keymap: KeyMap,
+    palette: Palette,
+    beeper: Beeper,
+    player: PatternPlayer,
+    rgba: Vec<u8>,
+    video: Vec<u32>,
+    audio: Vec<f32>,
+    samples: Vec<i16>,
+}
+
+impl Core {
+    fn new(rom: Vec<u8>) -> Option<Self> {
+        let mut emulator = Emulator::new();
+        RomSettings::detect(&rom).apply(&mut emulator);
+        emulator.load_rom(&rom).ok()?;
+        Some(Self {
+            emulator,
+            rom,
+            keymap: KeyMap::default(),
+            palette: Palette::default(),
+            beeper: Beeper::new(SAMPLE_RATE),
+            player: PatternPlayer::new(SAMPLE_RATE),
+            rgba: Vec::new(),
+            video: Vec::new(),
+            audio: vec![0.0; SAMPLES_PER_FRAME],
+            samples: Vec::new(),
+        })
+    }
+
+    fn reset(&mut self) {
+        //The ROM loaded once already
+        let _ = self.emulator.reload_rom(&self.rom);
+    }
+
+    //Keyboard through the default KeyMap plus the JOYPAD layout, both on port 0
+    unsafe fn read_keys(&mut self, input_state: InputStateFn) {
+        let mut keys = 0u16;
+        for (host, key) in self.keymap.bindings() {
+            //RETROK codes for letters and digits are their ASCII values
+            if input_state(0, RETRO_DEVICE_KEYBOARD, 0, host as c_uint) != 0 {
+                keys |= 1 << key.index();
+            }
+        }
+        for (button, key) in JOYPAD {
+            if input_state(0, RETRO_DEVICE_JOYPAD, 0, button) != 0 {
+                keys |= 1 << key.index();
+            }
+        }
+        self.emulator.set_keys(keys);
+    }
+
+    unsafe fn present(&mut self, video_refresh: VideoRefreshFn) {
+        let (width, height) = (self.emulator.screen_width(), self.emulator.screen_height());
+        self.rgba.resize(width * height * 4, 0);
+        self.emulator.render_rgba(&mut self.rgba, &self.palette);
+        self.video.clear();
+        self.video.extend(self.rgba.chunks_exact(4).map(|p| (p[0] as u32) << 16 | (p[1] as u32) << 8 | p[2] as u32));
+        video_refresh(self.video.as_ptr().cast(), width as c_uint, height as c_uint, width * 4);
+    }
+
+    //One frame of stereo samples, a pattern once the program loads one and a beep otherwise
+    unsafe fn play(&mut self, audio_sample_batch: AudioSampleBatchFn) {
+        let audio = self.emulator.audio_state();
+        if audio.has_pattern() {
+            self.player.fill(&audio, &mut self.audio);
+        } else {
+            self.beeper.fill(audio.sound, &mut self.audio);
+        }
+        self.samples.clear();
+        for &sample in &self.audio {
+            let sample = (sample * i16::MAX as f32) as i16;
+            self.samples.extend_from_slice(&[sample, sample]);
+        }
+        audio_sample_batch(self.samples.as_ptr(), SAMPLES_PER_FRAME);
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_api_version() -> c_uint {
+    RETRO_API_VERSION
+}
+
+#[no_mangle]
+pub extern "C" fn retro_init() {}
+
+#[no_mangle]
+pub extern "C" fn retro_deinit() {
+    *CORE.lock().unwrap() = None;
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn retro_get_system_info(info: *mut RetroSystemInfo) {
+    let Some(info) = info.as_mut() else { return };
+    *info = RetroSystemInfo {
+        library_name: c"CHIP-8".as_ptr(),
+        library_version: concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast(),
+        valid_extensions: c"ch8|c8|sc8|xo8".as_ptr(),
+        need_fullpath: false,
+        block_extract: false,
+    };
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn retro_get_system_av_info(info: *mut RetroSystemAvInfo) {
+    let Some(info) = info.as_mut() else { return };
+    *info = RetroSystemAvInfo {
+        geometry: RetroGameGeometry {
+            base_width: SCREEN_WIDTH as c_uint,
+            base_height: SCREEN_HEIGHT as c_uint,
+            max_width: HIRES_SCREEN_WIDTH as c_uint,
+            max_height: HIRES_SCREEN_HEIGHT as c_uint,
+            aspect_ratio: 2.0,
+        },
+        timing: RetroSystemTiming { fps: FPS, sample_rate: SAMPLE_RATE as f64 },
+    };
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_environment(callback: Option<EnvironmentFn>) {
+    CALLBACKS.lock().unwrap().environment = callback;
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_video_refresh(callback: Option<VideoRefreshFn>) {
+    CALLBACKS.lock().unwrap().video_refresh = callback;
+}
+
+//Samples go through the batch callback
+#[no_mangle]
+pub extern "C" fn retro_set_audio_sample(_callback: Option<AudioSampleFn>) {}
+
+#[no_mangle]
+pub extern "C" fn retro_set_audio_sample_batch(callback: Option<AudioSampleBatchFn>) {
+    CALLBACKS.lock().unwrap().audio_sample_batch = callback;
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_input_poll(callback: Option<InputPollFn>) {
+    CALLBACKS.lock().unwrap().input_poll = callback;
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_input_state(callback: Option<InputStateFn>) {
+    CALLBACKS.lock().unwrap().input_state = callback;
+}
+
+//Keyboard and joypad are both always read
+#[no_mangle]
+pub extern "C" fn retro_set_controller_port_device(_port: c_uint, _device: c_uint) {}
+
+#[no_mangle]
+pub extern "C" fn retro_reset() {
+    if let Some(core) = CORE.lock().unwrap().as_mut() {
+        core.reset();
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn retro_run() {
+    let callbacks = *CALLBACKS.lock().unwrap();
+    let mut core = CORE.lock().unwrap();
+    let Some(core) = core.as_mut() else { return };
+    if let Some(input_poll) = callbacks.input_poll {
+        input_poll();
+    }
+    if let Some(input_state) = callbacks.input_state {
+        core.read_keys(input_state);
+    }
+    //A fault halts the emulator, the last screen stays up until a reset
+    let _ = core.emulator.run_frame();
+    if let Some(video_refresh) = callbacks.video_refresh {
+        core.present(video_refresh);
+    }
+    if let Some(audio_sample_batch) = callbacks.audio_sample_batch {
+        core.play(audio_sample_batch);
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_serialize_size() -> usize {
+    SERIALIZE_SIZE
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn retro_serialize(data: *mut c_void, size: usize) -> bool {
+    let core = CORE.lock().unwrap();
+    let (Some(core), false) = (core.as_ref(), data.is_null()) else { return false };
+    let snapshot = core.emulator.save_state().to_snapshot_bytes();
+    if size < 4 + snapshot.len() {
+        return false;
+    }
+    let out = slice::from_raw_parts_mut(data.cast::<u8>(), size);
+    out.fill(0);
+    out[..4].copy_from_slice(&(snapshot.len() as u32).to_be_bytes());
+    out[4..4 + snapshot.len()].copy_from_slice(&snapshot);
+    true
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn retro_unserialize(data: *const c_void, size: usize) -> bool {
+    let mut core = CORE.lock().unwrap();
+    let (Some(core), false) = (core.as_mut(), data.is_null() || size < 4) else { return false };
+    let bytes = slice::from_raw_parts(data.cast::<u8>(), size);
+    let len = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+    let Some(snapshot) = bytes[4..].get(..len) else { return false };
+    match State::from_snapshot_bytes(snapshot) {
+        Ok(state) => core.emulator.load_state(&state).is_ok(),
+        Err(_) => false,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_cheat_reset() {}
+
+#[no_mangle]
+pub extern "C" fn retro_cheat_set(_index: c_uint, _enabled: bool, _code: *const c_char) {}
+
+//The ROM comes as data, or from path when the frontend gives none
+#[no_mangle]
+pub unsafe extern "C" fn retro_load_game(game: *const RetroGameInfo) -> bool {
+    let Some(game) = game.as_ref() else { return false };
+    let rom = if !game.data.is_null() {
+        slice::from_raw_parts(game.data.cast::<u8>(), game.size).to_vec()
+    } else if !game.path.is_null() {
+        let Ok(path) = CStr::from_ptr(game.path).to_str() else { return false };
+        let Ok(rom) = fs::read(path) else { return false };
+        rom
+    } else {
+        return false;
+    };
+
+    let Some(environment) = CALLBACKS.lock().unwrap().environment else { return false };
+    let mut format = RETRO_PIXEL_FORMAT_XRGB8888;
+    if !environment(RETRO_ENVIRONMENT_SET_PIXEL_FORMAT, ptr::from_mut(&mut format).cast()) {
+        return false;
+    }
+    let Some(core) = Core::new(rom) else { return false };
+    *CORE.lock().unwrap() = Some(core);
+    true
+}
+
+#[no_mangle]
+pub extern "C" fn retro_load_game_special(_game_type: c_uint, _info: *const RetroGameInfo, _num_info: usize) -> bool {
+    false
+}
+
+#[no_mangle]
+pub extern "C" fn retro_unload_game() {
+    *CORE.lock().unwrap() = None;
+}
+
+#[no_mangle]
+pub extern "C" fn retro_get_region() -> c_uint {
+    RETRO_REGION_NTSC
+}
+
+//No memory is exposed for achievements or cheats
+#[no_mangle]
+pub extern "C" fn retro_get_memory_data(_id: c_uint) -> *mut c_void {
+    ptr::null_mut()
+}
+
+#[no_mangle]
+pub extern "C" fn retro_get_memory_size(_id: c_uint) -> usize {
+    0
+}
@@ -0,0 +1,240 @@
+//MegaChip8 (Martijn Wanting, 2007): 0011 switches to a 256x192 screen of palette colours, drawn to a back
+//buffer that 00E0 shows, with sprites of any size whose bytes are colour indices and 8 bit digitised sound
+//Its ROMs outgrow 64K, memory past that is only reachable through the 24 bit I that 01NN NNNN sets
+//Enable it with Emulator::set_megachip. Save states keep the colour screens, palette, sound and memory past 64K
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+pub const MEGA_SCREEN_WIDTH: usize = 256;
+pub const MEGA_SCREEN_HEIGHT: usize = 192;
+//All a 24 bit I can reach, the most a MegaChip ROM can be
+pub const MEGA_MEMORY_SIZE: usize = 0x100_0000;
+
+pub(crate) const SCREEN_SIZE: usize = MEGA_SCREEN_WIDTH * MEGA_SCREEN_HEIGHT;
+
+//How 080N mixes a sprite's colours with what is already on screen
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BlendMode {
+    #[default]
+    Normal,
+    //The sprite at 25% and 50% opacity
+    Quarter,
+    Half,
+    Add,
+    Multiply,
+}
+
+impl BlendMode {
+    //080N, unknown modes draw normally
+    pub fn from_n(n: u8) -> Self {
+        match n {
+            1 => BlendMode::Quarter,
+            2 => BlendMode::Half,
+            3 => BlendMode::Add,
+            4 => BlendMode::Multiply,
+            _ => BlendMode::Normal,
+        }
+    }
+
+    //The N of 080N that selects it
+    pub fn n(self) -> u8 {
+        match self {
+            BlendMode::Normal => 0,
+            BlendMode::Quarter => 1,
+            BlendMode::Half => 2,
+            BlendMode::Add => 3,
+            BlendMode::Multiply => 4,
+        }
+    }
+
+    fn blend(self, under: [u8; 4], over: [u8; 4]) -> [u8; 4] {
+        let mix = |f: &dyn Fn(u16, u16) -> u16| {
+            let [r, g, b] = [0, 1, 2].map(|c| f(under[c] as u16, over[c] as u16).min(255) as u8);
+            [r, g, b, 0xFF]
+        };
+        match self {
+            BlendMode::Normal => over,
+            BlendMode::Quarter => mix(&|under, over| (under * 3 + over) / 4),
+            BlendMode::Half => mix(&|under, over| (under + over) / 2),
+            BlendMode::Add => mix(&|under, over| under + over),
+            BlendMode::Multiply => mix(&|under, over| under * over / 255),
+        }
+    }
+}
+
+//A digitised sound started by 060N: unsigned 8 bit mono samples
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sample {
+    pub rate: u16,
+    pub data: Vec<u8>,
+    //060N with N = 0 repeats the sound until 0700 stops it, N = 1 plays it once
+    pub looping: bool,
+}
+
+//Fields are read and written directly by the snapshot format in state.rs
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MegaChip {
+    //RGBA, what 00E0 last showed and what is being drawn
+    pub(crate) front: Vec<[u8; 4]>,
+    pub(crate) back: Vec<[u8; 4]>,
+    //Palette index last drawn at each pixel of back, for collisions
+    pub(crate) indices: Vec<u8>,
+    //RGBA, index 0 is transparent
+    #[cfg_attr(feature = "serde", serde(with = "palette_serde"))]
+    pub(crate) palette: [[u8; 4]; 256],
+    //1 to 256
+    pub(crate) sprite_width: usize,
+    pub(crate) sprite_height: usize,
+    pub(crate) alpha: u8,
+    pub(crate) blend: BlendMode,
+    pub(crate) collision_color: u8,
+    pub(crate) sample: Option<Sample>,
+}
+
+//serde only derives arrays up to 32 long
+#[cfg(feature = "serde")]
+mod palette_serde {
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+    use alloc::vec::Vec;
+
+    pub fn serialize<S: Serializer>(palette: &[[u8; 4]; 256], serializer: S) -> Result<S::Ok, S::Error> {
+        palette.as_slice().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[[u8; 4]; 256], D::Error> {
+        let colors = Vec::<[u8; 4]>::deserialize(deserializer)?;
+        colors.try_into().map_err(|colors: Vec<_>| de::Error::invalid_length(colors.len(), &"256 colours"))
+    }
+}
+
+impl MegaChip {
+    pub(crate) fn new() -> Self {
+        let black = [0, 0, 0, 0xFF];
+        Self {
+            front: vec![black; SCREEN_SIZE],
+            back: vec![black; SCREEN_SIZE],
+            indices: vec![0; SCREEN_SIZE],
+            palette: [black; 256],
+            sprite_width: 1,
+            sprite_height: 1,
+            alpha: 0xFF,
+            blend: BlendMode::Normal,
+            collision_color: 0,
+            sample: None,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        MEGA_SCREEN_WIDTH
+    }
+
+    pub fn height(&self) -> usize {
+        MEGA_SCREEN_HEIGHT
+    }
+
+    //RGBA of the pixel on screen
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        self.front[y * MEGA_SCREEN_WIDTH + x]
+    }
+
+    //The shown screen as RGBA rows, faded by the screen alpha (05NN). out needs 256 * 192 * 4 bytes
+    pub fn render_rgba(&self, out: &mut [u8]) {
+        assert!(out.len() >= SCREEN_SIZE * 4, "RGBA buffer of {} bytes is too small for 256x192", out.len());
+        let alpha = self.alpha as u16;
+        for (pixel, color) in out.chunks_exact_mut(4).zip(&self.front) {
+            let [r, g, b] = [0, 1, 2].map(|c| (color[c] as u16 * alpha / 255) as u8);
+            pixel.copy_from_slice(&[r, g, b, 0xFF]);
+        }
+    }
+
+    pub fn palette(&self) -> &[[u8; 4]; 256] {
+        &self.palette
+    }
+
+    pub fn blend_mode(&self) -> BlendMode {
+        self.blend
+    }
+
+    //The digitised sound playing, None after 0700 or once a sound played once would have ended
+    //The frontend plays it, the emulator only keeps it
+    pub fn sample(&self) -> Option<&Sample> {
+        self.sample.as_ref()
+    }
+
+    //02NN: NN colours from memory, 4 bytes each (alpha, red, green, blue), into palette indices 1..=NN
+    pub(crate) fn load_palette(&mut self, colors: &[u8]) {
+        for (index, argb) in colors.chunks_exact(4).enumerate().take(255) {
+            self.palette[index + 1] = [argb[1], argb[2], argb[3], argb[0]];
+        }
+    }
+
+    //03NN and 04NN, 0 is 256
+    pub(crate) fn set_sprite_width(&mut self, n: u8) {
+        self.sprite_width = if n == 0 { 256 } else { n as usize };
+    }
+
+    pub(crate) fn set_sprite_height(&mut self, n: u8) {
+        self.sprite_height = if n == 0 { 256 } else { n as usize };
+    }
+
+    pub(crate) fn sprite_size(&self) -> (usize, usize) {
+        (self.sprite_width, self.sprite_height)
+    }
+
+    pub(crate) fn set_alpha(&mut self, alpha: u8) {
+        self.alpha = alpha;
+    }
+
+    pub(crate) fn set_blend_mode(&mut self, blend: BlendMode) {
+        self.blend = blend;
+    }
+
+    pub(crate) fn set_collision_color(&mut self, index: u8) {
+        self.collision_color = index;
+    }
+
+    pub(crate) fn play(&mut self, sample: Option<Sample>) {
+        self.sample = sample;
+    }
+
+    //00E0: show what was drawn and start the next frame on a cleared buffer
+    pub(crate) fn present(&mut self) {
+        self.front.copy_from_slice(&self.back);
+        self.back.fill([0, 0, 0, 0xFF]);
+        self.indices.fill(0);
+    }
+
+    //00BN: move the back buffer up N rows, blank rows come in at the bottom
+    pub(crate) fn scroll_up(&mut self, rows: usize) {
+        let shift = rows.min(MEGA_SCREEN_HEIGHT) * MEGA_SCREEN_WIDTH;
+        self.back.copy_within(shift.., 0);
+        self.back[SCREEN_SIZE - shift..].fill([0, 0, 0, 0xFF]);
+        self.indices.copy_within(shift.., 0);
+        self.indices[SCREEN_SIZE - shift..].fill(0);
+    }
+
+    //DXYN in MegaChip mode: a sprite of the set size, one palette index a pixel, index 0 left transparent
+    //Clipped at the edges. True if a pixel landed on one of the collision colour
+    pub(crate) fn draw(&mut self, x: usize, y: usize, sprite: &[u8]) -> bool {
+        let mut collision = false;
+        for row in 0..self.sprite_height {
+            for column in 0..self.sprite_width {
+                let index = sprite[row * self.sprite_width + column];
+                let (px, py) = (x + column, y + row);
+                if index == 0 || px >= MEGA_SCREEN_WIDTH || py >= MEGA_SCREEN_HEIGHT {
+                    continue;
+                }
+                let at = py * MEGA_SCREEN_WIDTH + px;
+                collision |= self.indices[at] == self.collision_color && self.collision_color != 0;
+                self.indices[at] = index;
+                self.back[at] = self.blend.blend(self.back[at], self.palette[index as usize]);
+            }
+        }
+        collision
+    }
+}
@@ -0,0 +1,82 @@
+//How big RAM is and where the fonts and the program go in it, see Emulator::set_memory_map
+//Addresses past the end wrap round to the start, as on a machine with fewer address lines
+
+use crate::chip8::{BIG_FONTSET_SIZE, FONTSET_SIZE, RAM_SIZE, START_ADDRESS};
+use crate::error::MemoryMapError;
+
+//The smallest RAM that still holds both fonts and a program at 0x200
+pub const MIN_RAM_SIZE: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MemoryMap {
+    //Bytes of RAM, a power of two from MIN_RAM_SIZE to 64 KB
+    pub size: usize,
+    //The 4x5 digits FX29 points at, FONTSET_SIZE bytes
+    pub font_address: u16,
+    //SCHIP's 8x10 digits for FX30, BIG_FONTSET_SIZE bytes
+    pub big_font_address: u16,
+    //Where ROMs are loaded and execution starts, above both fonts
+    pub start_address: u16,
+}
+
+impl MemoryMap {
+    //The COSMAC VIP's 4 KB, what most CHIP-8 and SCHIP programs were written for
+    pub fn chip8() -> Self {
+        Self::with_size(4096)
+    }
+
+    //XO-CHIP's 64 KB, which a 16 bit I (F000 NNNN) can reach all of
+    pub fn xochip() -> Self {
+        Self::with_size(RAM_SIZE)
+    }
+
+    //The usual layout, fonts from 0 and programs at 0x200, in size bytes
+    pub fn with_size(size: usize) -> Self {
+        Self { size, font_address: 0, big_font_address: FONTSET_SIZE as u16, start_address: START_ADDRESS }
+    }
+
+    //The map with programs loaded at address instead, e.g. ETI_660_START_ADDRESS
+    pub fn at(self, address: u16) -> Self {
+        Self { start_address: address, ..self }
+    }
+
+    //First address after both fonts, the lowest a program may start at
+    pub fn fonts_end(&self) -> u16 {
+        (self.font_address as usize + FONTSET_SIZE).max(self.big_font_address as usize + BIG_FONTSET_SIZE) as u16
+    }
+
+    //Whether the address is taken by one of the fonts
+    pub fn in_font(&self, address: u16) -> bool {
+        let within = |start: u16, len: usize| (start as usize..start as usize + len).contains(&(address as usize));
+        within(self.font_address, FONTSET_SIZE) || within(self.big_font_address, BIG_FONTSET_SIZE)
+    }
+
+    pub(crate) fn mask(&self) -> usize {
+        self.size - 1
+    }
+
+    //The first thing about the map that does not hold
+    pub fn check(&self) -> Result<(), MemoryMapError> {
+        if !self.size.is_power_of_two() || !(MIN_RAM_SIZE..=RAM_SIZE).contains(&self.size) {
+            return Err(MemoryMapError::Size { size: self.size });
+        }
+        let (small, big) = (self.font_address as usize, self.big_font_address as usize);
+        if small < big + BIG_FONTSET_SIZE && big < small + FONTSET_SIZE {
+            return Err(MemoryMapError::FontsOverlap);
+        }
+        //Room for at least one instruction
+        if self.start_address < self.fonts_end() || self.start_address as usize + 2 > self.size {
+            return Err(MemoryMapError::StartAddress { address: self.start_address, min: self.fonts_end() });
+        }
+        Ok(())
+    }
+}
+
+//The 4 KB CHIP-8 map. XO-CHIP programs get 64 KB from RomSettings (by detect or the xochip platform)
+//or EmulatorBuilder with the XO-CHIP quirks
+impl Default for MemoryMap {
+    fn default() -> Self {
+        Self::chip8()
+    }
+}
@@ -0,0 +1,68 @@
+//Running totals for a perf overlay and for tuning ticks_per_frame, see Emulator::metrics
+//Wall times come from the emulator's TimeSource, so they read zero under a ManualClock nobody advances
+
+use core::fmt;
+use core::time::Duration;
+
+//Frames a second the timers run at, what ips scales by
+const FRAME_HZ: f64 = 60.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Metrics {
+    //Instructions executed, ticks spent waiting on FX0A or paused not included
+    pub instructions: u64,
+    //Timer updates, one a frame
+    pub frames: u64,
+    //DXYN instructions executed and the wall time spent in them
+    pub draws: u64,
+    pub draw_time: Duration,
+    //Wall time spent in run_frame and run_until, in total and for the last run_frame
+    pub frame_time: Duration,
+    pub last_frame_time: Duration,
+}
+
+impl Metrics {
+    //Instructions per emulated second, 60 frames of them. What ticks_per_frame works out to in practice
+    pub fn ips(&self) -> f64 {
+        if self.frames == 0 {
+            return 0.0;
+        }
+        self.instructions as f64 * FRAME_HZ / self.frames as f64
+    }
+
+    //Instructions per second of host time spent in run_frame, how fast the host could go unthrottled
+    pub fn host_ips(&self) -> f64 {
+        if self.frame_time.is_zero() {
+            return 0.0;
+        }
+        self.instructions as f64 / self.frame_time.as_secs_f64()
+    }
+
+    pub fn average_frame_time(&self) -> Duration {
+        match u32::try_from(self.frames) {
+            Ok(frames) if frames > 0 => self.frame_time / frames,
+            _ => Duration::ZERO,
+        }
+    }
+
+    //Share of frame time spent drawing, 0.0 to 1.0
+    pub fn draw_share(&self) -> f64 {
+        if self.frame_time.is_zero() {
+            return 0.0;
+        }
+        (self.draw_time.as_secs_f64() / self.frame_time.as_secs_f64()).min(1.0)
+    }
+}
+
+//One line for an overlay, e.g. "660 IPS, 0.12 ms/frame, 3% drawing"
+impl fmt::Display for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.0} IPS, {:.2} ms/frame, {:.0}% drawing",
+            self.ips(),
+            self.average_frame_time().as_secs_f64() * 1000.0,
+            self.draw_share() * 100.0
+        )
+    }
+}
@@ -0,0 +1,77 @@
+use crate::chip8::{Emulator, FrameOutput, TICKS_PER_FRAME};
+use crate::error::Chip8Error;
+
+use std::time::Duration;
+
+const FRAME_HZ: u32 = 60;
+const FRAME: Duration = Duration::from_nanos(1_000_000_000 / FRAME_HZ as u64);
+const MAX_CATCH_UP: u32 = 5;
+
+//Runs an Emulator in real time: cpu_hz instructions a second spread over 60 Hz frames, each ending
+//with a timer update, sleeping until each frame is due. Time comes from the emulator's TimeSource, so a ManualClock makes it deterministic
+//If the host falls behind, frames run back to back until caught up; past MAX_CATCH_UP frames the lost time is skipped
+#[derive(Debug, Clone)]
+pub struct Clock {
+    cpu_hz: u32,
+    //Instruction counts that did not divide into whole frames, carried to the next
+    carry: u32,
+    //When the next frame is due, None until the first frame
+    next_frame: Option<Duration>,
+    skipped: u64,
+}
+
+impl Clock {
+    pub fn new(cpu_hz: u32) -> Self {
+        Self { cpu_hz, carry: 0, next_frame: None, skipped: 0 }
+    }
+
+    pub fn cpu_hz(&self) -> u32 {
+        self.cpu_hz
+    }
+
+    pub fn set_cpu_hz(&mut self, cpu_hz: u32) {
+        self.cpu_hz = cpu_hz;
+        self.carry = 0;
+    }
+
+    //Frames dropped because the host could not keep up
+    pub fn skipped_frames(&self) -> u64 {
+        self.skipped
+    }
+
+    //Wait until the next frame is due, then run it
+    pub fn frame(&mut self, emulator: &mut Emulator) -> Result<FrameOutput, Chip8Error> {
+        self.wait(emulator);
+        let ticks = (self.cpu_hz + self.carry) / FRAME_HZ;
+        self.carry = (self.cpu_hz + self.carry) % FRAME_HZ;
+        emulator.run_frame_with(ticks as usize)
+    }
+
+    //Only the waiting part of frame, for loops that run the frame themselves: the emulator's own
+    //ticks_per_frame, a power profile's count, a netplay session, or nothing while halted or paused
+    pub fn wait(&mut self, emulator: &Emulator) {
+        let clock = emulator.time_source();
+        let now = clock.now();
+        let due = *self.next_frame.get_or_insert(now);
+        if due > now {
+            clock.sleep(due - now);
+        } else if now - due > FRAME * MAX_CATCH_UP {
+            self.skipped += ((now - due).as_nanos() / FRAME.as_nanos()) as u64;
+            self.next_frame = Some(now);
+        }
+        self.next_frame = self.next_frame.map(|due| due + FRAME);
+    }
+
+    //Forget the schedule, e.g. after a pause, so the next frame runs at once instead of catching up
+    pub fn reset(&mut self) {
+        self.next_frame = None;
+        self.carry = 0;
+    }
+}
+
+//TICKS_PER_FRAME instructions a frame, like run_frame
+impl Default for Clock {
+    fn default() -> Self {
+        Self::new(TICKS_PER_FRAME as u32 * FRAME_HZ)
+    }
+}
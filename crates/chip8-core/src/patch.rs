@@ -0,0 +1,107 @@
+use crate::asm::{assemble_at, AsmError, Assembly};
+use crate::chip8::Emulator;
+use crate::instruction::Instruction;
+
+use alloc::format;
+use alloc::vec::Vec;
+
+//One in-place edit made from the debugger
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Patch {
+    Memory { address: u16, old: u8, new: u8 },
+    Register { register: u8, old: u8, new: u8 },
+    //Edits undone and redone together, e.g. the bytes of a hot patch
+    Group(Vec<Patch>),
+}
+
+impl Patch {
+    fn apply(&self, emulator: &mut Emulator, undo: bool) {
+        match self {
+            &Patch::Memory { address, old, new } => emulator.poke(address, if undo { old } else { new }),
+            &Patch::Register { register, old, new } => emulator.set_v_register(register, if undo { old } else { new }),
+            //Undone in reverse so overlapping edits end at the oldest value
+            Patch::Group(patches) if undo => patches.iter().rev().for_each(|patch| patch.apply(emulator, true)),
+            Patch::Group(patches) => patches.iter().for_each(|patch| patch.apply(emulator, false)),
+        }
+    }
+}
+
+//Edits to RAM and V registers with undo and redo, for experimenting with game state
+//Undo puts back the value from before the edit, even if the program changed it since
+#[derive(Debug, Clone, Default)]
+pub struct PatchLog {
+    undo: Vec<Patch>,
+    redo: Vec<Patch>,
+}
+
+impl PatchLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn poke(&mut self, emulator: &mut Emulator, address: u16, value: u8) {
+        self.push(emulator, Patch::Memory { address, old: emulator.peek(address), new: value });
+    }
+
+    pub fn set_register(&mut self, emulator: &mut Emulator, register: u8, value: u8) {
+        let register = register & 0xF;
+        self.push(emulator, Patch::Register { register, old: emulator.v_register(register), new: value });
+    }
+
+    //Assemble source to run from address and write it into RAM while the program runs, as one edit
+    //With redirect, a jump to the new code is written over the instruction there, so jumps and calls
+    //to an old function land in its replacement. Labels in the snippet are local to it
+    pub fn hot_patch(&mut self, emulator: &mut Emulator, address: u16, source: &str, redirect: Option<u16>) -> Result<Assembly, AsmError> {
+        let assembly = assemble_at(source, address)?;
+        //1NNN only reaches the first 4K
+        if redirect.is_some() && address > 0xFFF {
+            return Err(AsmError { line: 0, message: format!("a jump cannot reach {:#06X}", address) });
+        }
+        let mut writes: Vec<(u16, u8)> = assembly.bytes.iter().enumerate().map(|(offset, &byte)| (address.wrapping_add(offset as u16), byte)).collect();
+        if let Some(from) = redirect {
+            let jump = Instruction::Jump(address).to_bytes();
+            writes.extend(jump.into_iter().enumerate().map(|(offset, byte)| (from.wrapping_add(offset as u16), byte)));
+        }
+        //Old values are read as each byte is written, so a redirect inside the snippet still undoes cleanly
+        let mut patches = Vec::with_capacity(writes.len());
+        for (address, value) in writes {
+            let patch = Patch::Memory { address, old: emulator.peek(address), new: value };
+            patch.apply(emulator, false);
+            patches.push(patch);
+        }
+        self.undo.push(Patch::Group(patches));
+        self.redo.clear();
+        Ok(assembly)
+    }
+
+    fn push(&mut self, emulator: &mut Emulator, patch: Patch) {
+        patch.apply(emulator, false);
+        self.undo.push(patch);
+        self.redo.clear();
+    }
+
+    //Revert the latest edit, None if there is nothing to undo
+    pub fn undo(&mut self, emulator: &mut Emulator) -> Option<Patch> {
+        let patch = self.undo.pop()?;
+        patch.apply(emulator, true);
+        self.redo.push(patch.clone());
+        Some(patch)
+    }
+
+    pub fn redo(&mut self, emulator: &mut Emulator) -> Option<Patch> {
+        let patch = self.redo.pop()?;
+        patch.apply(emulator, false);
+        self.undo.push(patch.clone());
+        Some(patch)
+    }
+
+    //Edits that can be undone, oldest first
+    pub fn history(&self) -> &[Patch] {
+        &self.undo
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
@@ -0,0 +1,65 @@
+//CRT phosphor persistence for RGBA output: pixels light up at once but fade out over a few frames, so
+//sprites a program erases and redraws every frame (XOR drawing) glow steadily instead of flickering
+//Call render_rgba once every frame, drawn or not, or the fading stalls
+
+use crate::framebuffer::{FrameBuffer, Palette};
+
+use alloc::vec::Vec;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Phosphor {
+    decay: f32,
+    //RGBA of the last frame rendered, as it faded
+    glow: Vec<f32>,
+    //Resolution glow is for, a switch starts over
+    size: (usize, usize),
+}
+
+impl Phosphor {
+    //decay is how much of a pixel's light is left a frame after it goes dark, 0.0 (no persistence, plain
+    //render_rgba) to 1.0 (never fades)
+    pub fn new(decay: f32) -> Self {
+        Self { decay: decay.clamp(0.0, 1.0), glow: Vec::new(), size: (0, 0) }
+    }
+
+    pub fn decay(&self) -> f32 {
+        self.decay
+    }
+
+    pub fn set_decay(&mut self, decay: f32) {
+        self.decay = decay.clamp(0.0, 1.0);
+    }
+
+    //Forget the frames before, e.g. after loading another ROM
+    pub fn clear(&mut self) {
+        self.glow.clear();
+    }
+
+    //FrameBuffer::render_rgba blended with the frames rendered before: lit pixels get their colour, dark ones
+    //fade from what they showed last frame towards theirs
+    //Panics if out is shorter than width() * height() * 4
+    pub fn render_rgba(&mut self, screen: &FrameBuffer, palette: &Palette, out: &mut [u8]) {
+        screen.render_rgba(out, palette);
+        let size = (screen.width(), screen.height());
+        let len = size.0 * size.1 * 4;
+        if self.size != size || self.glow.len() != len {
+            self.size = size;
+            self.glow = out[..len].iter().map(|&channel| channel as f32).collect();
+            return;
+        }
+        for ((lit, pixel), glow) in screen.pixels().into_iter().zip(out.chunks_exact_mut(4)).zip(self.glow.chunks_exact_mut(4)) {
+            for (channel, glow) in pixel.iter_mut().zip(glow) {
+                let target = *channel as f32;
+                *glow = if lit != 0 { target } else { target + (*glow - target) * self.decay };
+                *channel = libm::roundf(*glow) as u8;
+            }
+        }
+    }
+}
+
+//Half the light left after a frame, gone in a few
+impl Default for Phosphor {
+    fn default() -> Self {
+        Self::new(0.5)
+    }
+}
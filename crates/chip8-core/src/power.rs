@@ -0,0 +1,120 @@
+use crate::chip8::TICKS_PER_FRAME;
+
+//Settings for hosts that cannot afford full speed (handhelds, microcontrollers, phones on battery)
+//The frontend asks PowerGovernor each frame how much to run and whether to draw
+
+//What the frontend should do for sound
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioMode {
+    //XO-CHIP audio patterns and pitch as the program sets them
+    Full,
+    //A fixed square wave while the sound timer runs, whatever the pattern
+    Beep,
+    Off,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PowerProfile {
+    pub ticks_per_frame: usize,
+    //Draw every render_interval frames (1 draws every frame), the emulation itself still runs every frame
+    pub render_interval: u32,
+    pub audio: AudioMode,
+}
+
+impl PowerProfile {
+    pub fn full() -> Self {
+        Self { ticks_per_frame: TICKS_PER_FRAME, render_interval: 1, audio: AudioMode::Full }
+    }
+
+    //Fewer instructions a frame, every other frame drawn, plain beeps
+    //Most classic games still play at this speed, some feel sluggish
+    pub fn low_power() -> Self {
+        Self { ticks_per_frame: TICKS_PER_FRAME * 7 / 10, render_interval: 2, audio: AudioMode::Beep }
+    }
+
+    //Barely running: a third of the speed, 15 FPS, silent
+    pub fn minimal() -> Self {
+        Self { ticks_per_frame: (TICKS_PER_FRAME / 3).max(1), render_interval: 4, audio: AudioMode::Off }
+    }
+}
+
+impl Default for PowerProfile {
+    fn default() -> Self {
+        Self::full()
+    }
+}
+
+//How hard the host is being pushed, as reported by its thermal or battery monitoring
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Pressure {
+    #[default]
+    Normal,
+    //Getting warm or the battery is low
+    Elevated,
+    //Throttling or about to shut down
+    Critical,
+}
+
+//Picks the profile in effect: the one selected, or something cheaper while the host reports pressure
+#[derive(Debug, Clone)]
+pub struct PowerGovernor {
+    selected: PowerProfile,
+    pressure: Pressure,
+    frame: u64,
+}
+
+impl PowerGovernor {
+    pub fn new(selected: PowerProfile) -> Self {
+        Self { selected, pressure: Pressure::Normal, frame: 0 }
+    }
+
+    //Switch profile at runtime, e.g. from a menu
+    pub fn select(&mut self, profile: PowerProfile) {
+        self.selected = profile;
+    }
+
+    pub fn selected(&self) -> PowerProfile {
+        self.selected
+    }
+
+    //Hook for the host, call whenever its thermal or battery state changes
+    pub fn report(&mut self, pressure: Pressure) {
+        self.pressure = pressure;
+    }
+
+    pub fn pressure(&self) -> Pressure {
+        self.pressure
+    }
+
+    //Profile in effect: pressure only ever makes the selected one cheaper
+    pub fn profile(&self) -> PowerProfile {
+        let floor = match self.pressure {
+            Pressure::Normal => return self.selected,
+            Pressure::Elevated => PowerProfile::low_power(),
+            Pressure::Critical => PowerProfile::minimal(),
+        };
+        PowerProfile {
+            ticks_per_frame: self.selected.ticks_per_frame.min(floor.ticks_per_frame),
+            render_interval: self.selected.render_interval.max(floor.render_interval),
+            audio: match (self.selected.audio, floor.audio) {
+                (AudioMode::Off, _) | (_, AudioMode::Off) => AudioMode::Off,
+                (AudioMode::Beep, _) | (_, AudioMode::Beep) => AudioMode::Beep,
+                _ => AudioMode::Full,
+            },
+        }
+    }
+
+    //Call once per frame: instructions to run this frame and whether to draw it
+    pub fn frame(&mut self) -> (usize, bool) {
+        let profile = self.profile();
+        let render = self.frame.is_multiple_of(profile.render_interval.max(1) as u64);
+        self.frame += 1;
+        (profile.ticks_per_frame, render)
+    }
+}
+
+impl Default for PowerGovernor {
+    fn default() -> Self {
+        Self::new(PowerProfile::full())
+    }
+}
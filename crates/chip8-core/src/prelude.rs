@@ -0,0 +1,9 @@
+//What most programs need in one import: use chip8::prelude::*;
+pub use crate::audio::Beeper;
+pub use crate::chip8::{Emulator, EmulatorState, FrameOutput, TICKS_PER_FRAME};
+pub use crate::error::{Chip8Error, RomError, RunError, StateError};
+pub use crate::events::{Event, Warning};
+pub use crate::framebuffer::{FrameBuffer, Palette};
+pub use crate::input::{Key, KeyMap};
+pub use crate::quirks::{Quirks, RandomModel};
+pub use crate::state::State;
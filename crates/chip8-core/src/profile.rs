@@ -0,0 +1,70 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::fmt;
+use core::time::Duration;
+
+//Executions per opcode family while profiling is on, see Emulator::start_profiling
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Profile {
+    //Whether execution is timed as well (costs a clock read per instruction)
+    pub(crate) timed: bool,
+    counts: BTreeMap<&'static str, (u64, Duration)>,
+}
+
+impl Profile {
+    pub(crate) fn new(timed: bool) -> Self {
+        Self { timed, counts: BTreeMap::new() }
+    }
+
+    pub(crate) fn record(&mut self, pattern: &'static str, time: Option<Duration>) {
+        let (count, total) = self.counts.entry(pattern).or_default();
+        *count += 1;
+        *total += time.unwrap_or_default();
+    }
+
+    pub(crate) fn report(&self) -> ProfileReport {
+        let mut entries: Vec<ProfileEntry> = self
+            .counts
+            .iter()
+            .map(|(&pattern, &(count, time))| ProfileEntry { pattern, count, time: self.timed.then_some(time) })
+            .collect();
+        entries.sort_by(|a, b| b.count.cmp(&a.count).then(a.pattern.cmp(b.pattern)));
+        ProfileReport { entries }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProfileEntry {
+    //Opcode family, e.g. "DXYN"
+    pub pattern: &'static str,
+    pub count: u64,
+    //Wall time spent executing, None unless profiling was started with timing
+    pub time: Option<Duration>,
+}
+
+//Most executed opcode families first
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileReport {
+    pub entries: Vec<ProfileEntry>,
+}
+
+impl ProfileReport {
+    pub fn total(&self) -> u64 {
+        self.entries.iter().map(|entry| entry.count).sum()
+    }
+}
+
+//One line per family: count, share of all instructions and time if measured
+impl fmt::Display for ProfileReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.total().max(1);
+        for entry in &self.entries {
+            write!(f, "{}  {:>10}  {:>5.1}%", entry.pattern, entry.count, entry.count as f64 * 100.0 / total as f64)?;
+            if let Some(time) = entry.time {
+                write!(f, "  {:>10.3} ms", time.as_secs_f64() * 1000.0)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+use crate::asm::ORIGIN;
+use crate::instruction::Instruction;
+
+use alloc::vec::Vec;
+
+//Fluent builder for small programs in tests and tutorials, encoded with the same
+//Instruction::encode the assembler uses:
+//Program::new().ld_v(0, 5).add_v(0, 3).jump_self().build()
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Program {
+    bytes: Vec<u8>,
+}
+
+impl Program {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //Address the next instruction will be placed at
+    pub fn here(&self) -> u16 {
+        ORIGIN + self.bytes.len() as u16
+    }
+
+    pub fn instruction(mut self, instruction: Instruction) -> Self {
+        self.bytes.extend_from_slice(&instruction.to_bytes());
+        self
+    }
+
+    //Raw data, e.g. sprite rows
+    pub fn bytes(mut self, data: &[u8]) -> Self {
+        self.bytes.extend_from_slice(data);
+        self
+    }
+
+    pub fn build(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub fn cls(self) -> Self { self.instruction(Instruction::ClearScreen) }
+    pub fn ret(self) -> Self { self.instruction(Instruction::Return) }
+    pub fn jp(self, address: u16) -> Self { self.instruction(Instruction::Jump(address)) }
+    //Jump to this very instruction, the usual way a CHIP-8 program ends
+    pub fn jump_self(self) -> Self {
+        let here = self.here();
+        self.jp(here)
+    }
+    pub fn call(self, address: u16) -> Self { self.instruction(Instruction::Call(address)) }
+    pub fn se_v(self, x: u8, nn: u8) -> Self { self.instruction(Instruction::SkipEqImm { x, nn }) }
+    pub fn sne_v(self, x: u8, nn: u8) -> Self { self.instruction(Instruction::SkipNeImm { x, nn }) }
+    pub fn se_vv(self, x: u8, y: u8) -> Self { self.instruction(Instruction::SkipEqReg { x, y }) }
+    //5XY2/5XY3 (XO-CHIP): store/load Vx..=Vy at I
+    pub fn save_range(self, x: u8, y: u8) -> Self { self.instruction(Instruction::SaveRange { x, y }) }
+    pub fn load_range(self, x: u8, y: u8) -> Self { self.instruction(Instruction::LoadRange { x, y }) }
+    pub fn sne_vv(self, x: u8, y: u8) -> Self { self.instruction(Instruction::SkipNeReg { x, y }) }
+    pub fn ld_v(self, x: u8, nn: u8) -> Self { self.instruction(Instruction::LoadImm { x, nn }) }
+    pub fn add_v(self, x: u8, nn: u8) -> Self { self.instruction(Instruction::AddImm { x, nn }) }
+    pub fn ld_vv(self, x: u8, y: u8) -> Self { self.instruction(Instruction::LoadReg { x, y }) }
+    pub fn or_vv(self, x: u8, y: u8) -> Self { self.instruction(Instruction::Or { x, y }) }
+    pub fn and_vv(self, x: u8, y: u8) -> Self { self.instruction(Instruction::And { x, y }) }
+    pub fn xor_vv(self, x: u8, y: u8) -> Self { self.instruction(Instruction::Xor { x, y }) }
+    pub fn add_vv(self, x: u8, y: u8) -> Self { self.instruction(Instruction::AddReg { x, y }) }
+    pub fn sub_vv(self, x: u8, y: u8) -> Self { self.instruction(Instruction::SubReg { x, y }) }
+    pub fn subn_vv(self, x: u8, y: u8) -> Self { self.instruction(Instruction::SubN { x, y }) }
+    pub fn shr(self, x: u8, y: u8) -> Self { self.instruction(Instruction::ShiftRight { x, y }) }
+    pub fn shl(self, x: u8, y: u8) -> Self { self.instruction(Instruction::ShiftLeft { x, y }) }
+    pub fn ld_i(self, address: u16) -> Self { self.instruction(Instruction::LoadI(address)) }
+    //F000 NNNN (XO-CHIP)
+    pub fn ld_i_long(self, address: u16) -> Self { self.instruction(Instruction::LoadILong(address)) }
+    pub fn plane(self, n: u8) -> Self { self.instruction(Instruction::Plane(n)) }
+    pub fn audio(self) -> Self { self.instruction(Instruction::LoadAudio) }
+    //FX3A (XO-CHIP)
+    pub fn pitch(self, x: u8) -> Self { self.instruction(Instruction::SetPitch { x }) }
+    pub fn jp_v0(self, address: u16) -> Self { self.instruction(Instruction::JumpV0(address)) }
+    pub fn rnd(self, x: u8, nn: u8) -> Self { self.instruction(Instruction::Random { x, nn }) }
+    pub fn drw(self, x: u8, y: u8, n: u8) -> Self { self.instruction(Instruction::Draw { x, y, n }) }
+    pub fn skp(self, x: u8) -> Self { self.instruction(Instruction::SkipKeyPressed { x }) }
+    pub fn sknp(self, x: u8) -> Self { self.instruction(Instruction::SkipKeyNotPressed { x }) }
+    pub fn ld_v_dt(self, x: u8) -> Self { self.instruction(Instruction::LoadDelay { x }) }
+    pub fn ld_v_k(self, x: u8) -> Self { self.instruction(Instruction::WaitKey { x }) }
+    pub fn ld_dt(self, x: u8) -> Self { self.instruction(Instruction::SetDelay { x }) }
+    pub fn ld_st(self, x: u8) -> Self { self.instruction(Instruction::SetSound { x }) }
+    pub fn add_i(self, x: u8) -> Self { self.instruction(Instruction::AddI { x }) }
+    pub fn ld_f(self, x: u8) -> Self { self.instruction(Instruction::LoadFont { x }) }
+    pub fn ld_b(self, x: u8) -> Self { self.instruction(Instruction::StoreBcd { x }) }
+    //FX55: store V0..=Vx at I
+    pub fn store(self, x: u8) -> Self { self.instruction(Instruction::StoreRegs { x }) }
+    //FX65: load V0..=Vx from I
+    pub fn load(self, x: u8) -> Self { self.instruction(Instruction::LoadRegs { x }) }
+    //FX75 (SCHIP): store V0..=Vx in the RPL user flags
+    pub fn ld_r(self, x: u8) -> Self { self.instruction(Instruction::SaveFlags { x }) }
+    //FX85 (SCHIP): load V0..=Vx from the RPL user flags
+    pub fn ld_v_r(self, x: u8) -> Self { self.instruction(Instruction::LoadFlags { x }) }
+}
@@ -0,0 +1,138 @@
+//Behaviors that differ between CHIP-8 interpreters
+//The default matches this emulator's original behavior, use a preset for ROMs that expect another platform
+
+use alloc::format;
+use alloc::string::{String, ToString};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub struct Quirks {
+    //8XY6/8XYE: shift Vy and store the result in Vx, instead of shifting Vx in place
+    pub shift_uses_vy: bool,
+    //FX55/FX65: leave I pointing after the last register stored/loaded (I += X + 1)
+    pub load_store_increments_i: bool,
+    //BNNN behaves as BXNN: jump to XNN + Vx instead of NNN + V0
+    pub jump_uses_vx: bool,
+    //8XY1/8XY2/8XY3: reset VF to 0 after the logic operation
+    pub vf_reset: bool,
+    //FX0A: wait for a key to be pressed and released, the key is stored on release
+    pub wait_key_on_release: bool,
+    //DXYN: wait for the next 60 Hz frame after drawing, so at most one sprite is drawn a frame
+    //run_frame and run_until end the frame early, a bare tick does not know about frames and never waits
+    pub display_wait: bool,
+    //DXYN: drop the parts of a sprite past the right and bottom edges instead of wrapping them to the
+    //other side. The start coordinate wraps either way
+    pub clip_sprites: bool,
+    //FX1E: set VF to 1 when I + Vx passes 0xFFF and to 0 when it does not, as the Amiga interpreter did
+    //Spacefight 2091! relies on it, no preset turns it on. Missing from older serialized quirks, so off then
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub add_i_overflow: bool,
+    //CXNN: where the random bytes come from
+    pub random: RandomModel,
+}
+
+//Random number generators for CXNN, a few ROMs behave differently depending on the distribution
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub enum RandomModel {
+    //Fresh unpredictable bytes, uniformly distributed
+    #[default]
+    Entropy,
+    //xorshift64* from a fixed seed, the same sequence on every run
+    Seeded(u64),
+    //After the COSMAC VIP interpreter: a counter walks the first page of interpreter memory and each byte
+    //found is added to the previous result. Not bit exact (the page holds this emulator's fonts, not the
+    //VIP's code), but like the VIP's the numbers are far from uniform and repeat after 256 calls
+    Vip,
+}
+
+impl RandomModel {
+    //Text form for state exports: "entropy", "seeded 1234" or "vip"
+    pub fn name(&self) -> String {
+        match self {
+            RandomModel::Entropy => "entropy".to_string(),
+            RandomModel::Seeded(seed) => format!("seeded {}", seed),
+            RandomModel::Vip => "vip".to_string(),
+        }
+    }
+}
+
+impl Quirks {
+    //Every on/off quirk by name, in declaration order (random is not one). Used for text formats (state export, replays)
+    pub fn flags(&self) -> [(&'static str, bool); 8] {
+        [
+            ("shift_uses_vy", self.shift_uses_vy),
+            ("load_store_increments_i", self.load_store_increments_i),
+            ("jump_uses_vx", self.jump_uses_vx),
+            ("vf_reset", self.vf_reset),
+            ("wait_key_on_release", self.wait_key_on_release),
+            ("display_wait", self.display_wait),
+            ("clip_sprites", self.clip_sprites),
+            ("add_i_overflow", self.add_i_overflow),
+        ]
+    }
+
+    //Set a quirk by the name flags() uses, false if there is no such quirk
+    pub fn set_flag(&mut self, name: &str, value: bool) -> bool {
+        let flag = match name {
+            "shift_uses_vy" => &mut self.shift_uses_vy,
+            "load_store_increments_i" => &mut self.load_store_increments_i,
+            "jump_uses_vx" => &mut self.jump_uses_vx,
+            "vf_reset" => &mut self.vf_reset,
+            "wait_key_on_release" => &mut self.wait_key_on_release,
+            "display_wait" => &mut self.display_wait,
+            "clip_sprites" => &mut self.clip_sprites,
+            "add_i_overflow" => &mut self.add_i_overflow,
+            _ => return false,
+        };
+        *flag = value;
+        true
+    }
+
+    //The original COSMAC VIP interpreter
+    pub fn cosmac_vip() -> Self {
+        Self {
+            shift_uses_vy: true,
+            load_store_increments_i: true,
+            jump_uses_vx: false,
+            vf_reset: true,
+            wait_key_on_release: true,
+            display_wait: true,
+            clip_sprites: true,
+            add_i_overflow: false,
+            random: RandomModel::Vip,
+        }
+    }
+
+    //SUPER-CHIP 1.1 on the HP-48
+    pub fn schip() -> Self {
+        Self {
+            shift_uses_vy: false,
+            load_store_increments_i: false,
+            jump_uses_vx: true,
+            vf_reset: false,
+            wait_key_on_release: false,
+            display_wait: false,
+            clip_sprites: true,
+            add_i_overflow: false,
+            random: RandomModel::Entropy,
+        }
+    }
+
+    //XO-CHIP as Octo runs it: VIP arithmetic and key waits, but sprites wrap and nothing waits for the display
+    pub fn xochip() -> Self {
+        Self {
+            shift_uses_vy: true,
+            load_store_increments_i: true,
+            jump_uses_vx: false,
+            vf_reset: false,
+            wait_key_on_release: true,
+            display_wait: false,
+            clip_sprites: false,
+            add_i_overflow: false,
+            random: RandomModel::Entropy,
+        }
+    }
+}
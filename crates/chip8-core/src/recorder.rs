@@ -0,0 +1,216 @@
+use crate::chip8::Emulator;
+use crate::framebuffer::Palette;
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+//Screen recordings of gameplay: call capture() once per frame (after run_frame) and encode the result
+//as an animated GIF, or take the frames and encode them some other way
+//Frames that look the same as the one before are merged into it, so idle screens cost nothing
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecorderConfig {
+    //Frames left out after each captured one, 0 captures every frame
+    pub frame_skip: u32,
+    //Each CHIP-8 pixel becomes a scale x scale block in the GIF
+    pub scale: usize,
+    pub palette: Palette,
+    //Distinct frames kept, capture stops adding once this many are held
+    pub max_frames: usize,
+}
+
+impl Default for RecorderConfig {
+    fn default() -> Self {
+        Self {
+            frame_skip: 1,
+            scale: 4,
+            palette: Palette::default(),
+            //A minute of constant motion at 30 captures a second
+            max_frames: 1800,
+        }
+    }
+}
+
+//One captured picture and how long it stays up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub width: usize,
+    pub height: usize,
+    //Plane mask per pixel, as Emulator::get_screen
+    pub pixels: Vec<u8>,
+    //Emulated frames (1/60 s) it is shown for
+    pub duration: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    config: RecorderConfig,
+    frames: Vec<Frame>,
+    //Frames seen by capture, captured or skipped
+    seen: u64,
+}
+
+impl Recorder {
+    pub fn new(config: RecorderConfig) -> Self {
+        Self { config, frames: Vec::new(), seen: 0 }
+    }
+
+    pub fn capture(&mut self, emulator: &Emulator) {
+        let skip = self.config.frame_skip as u64 + 1;
+        let step = self.seen.is_multiple_of(skip);
+        self.seen += 1;
+        if !step {
+            return;
+        }
+        let pixels = emulator.get_screen();
+        let full = self.frames.len() >= self.config.max_frames;
+        match self.frames.last_mut() {
+            //Once full the final picture just stays up longer
+            Some(last) if full || last.pixels == pixels && last.width == emulator.screen_width() => last.duration += skip as u32,
+            _ if full => {},
+            _ => self.frames.push(Frame {
+                width: emulator.screen_width(),
+                height: emulator.screen_height(),
+                pixels,
+                duration: skip as u32,
+            }),
+        }
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.seen = 0;
+    }
+
+    //Looping GIF of everything captured, None if nothing was
+    //A recording that switches resolution is sized for hires, low resolution frames are doubled to fit
+    pub fn to_gif(&self) -> Option<Vec<u8>> {
+        let width = self.frames.iter().map(|frame| frame.width).max()?;
+        let height = self.frames.iter().map(|frame| frame.height).max()?;
+        let scale = self.config.scale.max(1);
+        let (gif_width, gif_height) = ((width * scale) as u16, (height * scale) as u16);
+
+        let mut gif = b"GIF89a".to_vec();
+        gif.extend(gif_width.to_le_bytes());
+        gif.extend(gif_height.to_le_bytes());
+        //Global colour table of 4 entries, 8 bit colour resolution
+        gif.extend([0xF1, 0, 0]);
+        for color in self.config.palette.colors {
+            gif.extend(&color[..3]);
+        }
+        //Loop forever
+        gif.extend(b"\x21\xFF\x0BNETSCAPE2.0\x03\x01\x00\x00\x00");
+
+        //GIF delays are in 1/100 s, rounding is carried over so the total stays in step with 60 FPS
+        let mut elapsed = 0;
+        let mut shown = 0;
+        for frame in &self.frames {
+            elapsed += frame.duration as u64;
+            let delay = (elapsed * 100 / 60 - shown) as u16;
+            shown += delay as u64;
+            gif.extend([0x21, 0xF9, 0x04, 0x00]);
+            gif.extend(delay.to_le_bytes());
+            gif.extend([0x00, 0x00]);
+
+            gif.push(0x2C);
+            gif.extend([0, 0, 0, 0]);
+            gif.extend(gif_width.to_le_bytes());
+            gif.extend(gif_height.to_le_bytes());
+            gif.push(0x00);
+
+            let factor = scale * width / frame.width;
+            let indices: Vec<u8> = (0..gif_height as usize)
+                .flat_map(|y| (0..gif_width as usize).map(move |x| (x / factor, y / factor)))
+                .map(|(x, y)| frame.pixels.get(x + frame.width * y).copied().unwrap_or(0) & 0b11)
+                .collect();
+            gif.push(MIN_CODE_SIZE);
+            for block in lzw(&indices).chunks(255) {
+                gif.push(block.len() as u8);
+                gif.extend(block);
+            }
+            gif.push(0x00);
+        }
+        gif.push(0x3B);
+        Some(gif)
+    }
+}
+
+//Two bit pixels
+const MIN_CODE_SIZE: u8 = 2;
+const MAX_CODE: u16 = 4095;
+
+//GIF flavoured LZW: variable width codes packed least significant bit first
+fn lzw(indices: &[u8]) -> Vec<u8> {
+    let clear = 1 << MIN_CODE_SIZE;
+    let end = clear + 1;
+    let mut bits = BitWriter::default();
+    let mut codes: BTreeMap<(u16, u8), u16> = BTreeMap::new();
+    let mut size = MIN_CODE_SIZE + 1;
+    let mut next = end + 1;
+    bits.write(clear, size);
+
+    let mut current: Option<u16> = None;
+    for &index in indices {
+        let Some(prefix) = current else {
+            current = Some(index as u16);
+            continue;
+        };
+        if let Some(&code) = codes.get(&(prefix, index)) {
+            current = Some(code);
+            continue;
+        }
+        bits.write(prefix, size);
+        if next <= MAX_CODE {
+            codes.insert((prefix, index), next);
+            if next == 1 << size {
+                size += 1;
+            }
+            next += 1;
+        } else {
+            bits.write(clear, size);
+            codes.clear();
+            size = MIN_CODE_SIZE + 1;
+            next = end + 1;
+        }
+        current = Some(index as u16);
+    }
+    if let Some(prefix) = current {
+        bits.write(prefix, size);
+    }
+    bits.write(end, size);
+    bits.finish()
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    len: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= (code as u32) << self.len;
+        self.len += size;
+        while self.len >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.len -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.len > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
@@ -0,0 +1,62 @@
+use crate::chip8::Emulator;
+use crate::error::StateError;
+use crate::state::State;
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+//Ring buffer of recent states for stepping back in time
+//Call record() once per frame before running it; states are kept as snapshot bytes, each a little over the
+//size of RAM (4 KB, 64 KB with XO-CHIP's memory map)
+#[derive(Debug, Clone)]
+pub struct Rewind {
+    depth: usize,
+    snapshots: VecDeque<Vec<u8>>,
+}
+
+impl Rewind {
+    //Keep the last depth frames
+    pub fn new(depth: usize) -> Self {
+        Self { depth, snapshots: VecDeque::with_capacity(depth) }
+    }
+
+    pub fn record(&mut self, emulator: &Emulator) {
+        if self.depth == 0 {
+            return;
+        }
+        if self.snapshots.len() == self.depth {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(emulator.save_state().to_snapshot_bytes());
+    }
+
+    //Go back to the start of the frame recorded frames ago (or as far as the buffer reaches)
+    //Returns how many frames were rewound, the rewound frames are dropped from the buffer
+    //Snapshots recorded before the memory map or MegaChip support changed do not fit the emulator: then the
+    //buffer is cleared and the emulator left as it was
+    pub fn rewind(&mut self, emulator: &mut Emulator, frames: usize) -> Result<usize, StateError> {
+        let frames = frames.min(self.snapshots.len());
+        if frames == 0 {
+            return Ok(0);
+        }
+        let snapshot = self.snapshots.drain(self.snapshots.len() - frames..).next().unwrap();
+        if let Err(error) = State::from_snapshot_bytes(&snapshot).and_then(|state| emulator.load_state(&state)) {
+            self.snapshots.clear();
+            return Err(error);
+        }
+        Ok(frames)
+    }
+
+    //Frames that can be rewound
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+}
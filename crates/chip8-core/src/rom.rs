@@ -0,0 +1,49 @@
+//Telling ROMs apart before loading them, for Emulator::load_rom_from_path: the two page hires CHIP-8
+//programs load_rom treats specially, and files that are obviously something else picked by mistake
+
+use crate::chip8::{START_ADDRESS, TWO_PAGE_ENTRY, TWO_PAGE_SIGNATURE};
+use crate::error::RomFileError;
+
+//Files a ROM picker is likely to be pointed at by mistake, by their first bytes. None of these begin a
+//program in practice
+const NOT_ROMS: [(&[u8], &str); 11] = [
+    (b"C8ST", "a save state"),
+    (b"C8SL", "a save slot"),
+    (b"C8SG", "a signed save or replay"),
+    (b"C8CD", "a core dump"),
+    (b"\x89PNG", "a PNG image"),
+    (b"GIF8", "a GIF image"),
+    (b"\xFF\xD8\xFF", "a JPEG image"),
+    (b"PK\x03\x04", "a ZIP archive"),
+    (b"\x1F\x8B\x08", "a gzip archive"),
+    (b"%PDF", "a PDF document"),
+    (b"\x7FELF", "an ELF executable"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RomFormat {
+    //Loaded at the start address and run from there
+    Chip8,
+    //Starts 1260 with its interpreter extension, load_rom switches to 64x64 and starts at TWO_PAGE_ENTRY
+    HiresChip8,
+}
+
+impl RomFormat {
+    //What data is as a ROM, or what it looks like instead when it is clearly not one
+    pub fn detect(data: &[u8]) -> Result<Self, RomFileError> {
+        if let Some((_, kind)) = NOT_ROMS.iter().find(|(magic, _)| data.starts_with(magic)) {
+            return Err(RomFileError::NotARom { kind });
+        }
+        //Octo source, a README or the like. Real programs have zero bytes (00E0, V0) or other control codes
+        let text = |byte: &u8| byte.is_ascii_graphic() || b" \t\r\n".contains(byte);
+        if data.contains(&b'\n') && data.iter().all(text) {
+            return Err(RomFileError::NotARom { kind: "a text file" });
+        }
+        Ok(if is_two_page(data) { RomFormat::HiresChip8 } else { RomFormat::Chip8 })
+    }
+}
+
+//Longer than the extension, so there is a program after it to start
+pub(crate) fn is_two_page(data: &[u8]) -> bool {
+    data.starts_with(&TWO_PAGE_SIGNATURE) && data.len() > (TWO_PAGE_ENTRY - START_ADDRESS) as usize
+}
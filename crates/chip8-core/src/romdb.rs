@@ -0,0 +1,156 @@
+use crate::chip8::{Emulator, CLASSIC_RAM_SIZE, RAM_SIZE, START_ADDRESS};
+use crate::disasm::analyze;
+use crate::memory::MemoryMap;
+use crate::quirks::Quirks;
+use crate::score::ScoreRule;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::str::FromStr;
+
+//Database of known ROMs keyed by the SHA-1 of the ROM image
+//The built-in table lives in romdb.tsv: one "sha1<TAB>title" line per ROM, # starts a comment
+//An optional third column holds the scoring rule for leaderboards, e.g. "0x2F0:bcd:3"
+//An optional fourth the settings the ROM needs to run right, e.g. "schip,-clip_sprites,ticks=20"
+const BUILTIN: &str = include_str!("romdb.tsv");
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RomEntry {
+    pub sha1: String,
+    pub title: String,
+    pub score: Option<ScoreRule>,
+    pub settings: RomSettings,
+}
+
+//What a ROM needs from the emulator, nothing when the defaults run it fine
+//Written as comma separated words: a platform (chip8, vip, schip or xochip) for its quirks, then
+//+quirk or -quirk to turn one on or off (named as in Quirks::flags), and ticks=N for the instructions a frame
+//xochip also asks for XO-CHIP's 64 KB of RAM
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RomSettings {
+    //None leaves the emulator's quirks alone
+    pub quirks: Option<Quirks>,
+    pub ticks_per_frame: Option<usize>,
+    //Bytes of RAM, None leaves the memory map alone
+    pub memory_size: Option<usize>,
+}
+
+impl RomSettings {
+    //What the ROM itself gives away: it needs 64 KB if it is too big for 4 KB or the code reachable from its
+    //start uses XO-CHIP or MegaChip instructions. Nothing else, unknown ROMs keep the emulator's quirks
+    pub fn detect(rom: &[u8]) -> Self {
+        let too_big = rom.len() > CLASSIC_RAM_SIZE - START_ADDRESS as usize;
+        let extended = || {
+            analyze(rom, START_ADDRESS).lines.iter().filter_map(|line| line.instruction).any(|instruction| instruction.is_xochip() || instruction.is_megachip())
+        };
+        Self { memory_size: (too_big || extended()).then_some(RAM_SIZE), ..Self::default() }
+    }
+
+    //Set up emulator for the ROM, before loading it, as a new memory size resets it. The random model
+    //stays as it was, it is not a matter of compatibility and may be a seed the player chose
+    pub fn apply(&self, emulator: &mut Emulator) {
+        //Only the size changes, a size the map does not allow leaves RAM as it was
+        if let Some(size) = self.memory_size.filter(|&size| size != emulator.memory_map().size) {
+            let _ = emulator.set_memory_map(MemoryMap { size, ..emulator.memory_map() });
+        }
+        if let Some(quirks) = self.quirks {
+            emulator.set_quirks(Quirks { random: emulator.quirks().random, ..quirks });
+        }
+        if let Some(ticks) = self.ticks_per_frame {
+            emulator.set_ticks_per_frame(ticks);
+        }
+    }
+}
+
+//Err names the word that was not understood
+impl FromStr for RomSettings {
+    type Err = String;
+
+    fn from_str(settings: &str) -> Result<Self, Self::Err> {
+        let mut result = Self::default();
+        for word in settings.split(',').map(str::trim).filter(|word| !word.is_empty()) {
+            let preset = match word {
+                "chip8" => Some(Quirks::default()),
+                "vip" => Some(Quirks::cosmac_vip()),
+                "schip" => Some(Quirks::schip()),
+                "xochip" => Some(Quirks::xochip()),
+                _ => None,
+            };
+            if let Some(preset) = preset {
+                result.quirks = Some(preset);
+                if word == "xochip" {
+                    result.memory_size = Some(RAM_SIZE);
+                }
+            } else if let Some(ticks) = word.strip_prefix("ticks=") {
+                result.ticks_per_frame = Some(ticks.parse().ok().filter(|&ticks| ticks > 0).ok_or_else(|| word.to_string())?);
+            } else {
+                let (value, name) = match (word.strip_prefix('+'), word.strip_prefix('-')) {
+                    (Some(name), _) => (true, name),
+                    (_, Some(name)) => (false, name),
+                    _ => return Err(word.to_string()),
+                };
+                if !result.quirks.get_or_insert_with(Quirks::default).set_flag(name, value) {
+                    return Err(word.to_string());
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RomDb {
+    entries: Vec<RomEntry>,
+}
+
+impl RomDb {
+    pub fn builtin() -> Self {
+        Self::parse(BUILTIN)
+    }
+
+    //Parse a database in the romdb.tsv format, malformed lines are skipped
+    pub fn parse(text: &str) -> Self {
+        let entries = text
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let mut columns = line.split('\t');
+                let sha1 = columns.next()?.trim().to_lowercase();
+                let title = columns.next()?.trim().to_string();
+                let score = match columns.next().map(str::trim).filter(|rule| !rule.is_empty()) {
+                    Some(rule) => Some(rule.parse().ok()?),
+                    None => None,
+                };
+                let settings = columns.next().map_or(Ok(RomSettings::default()), str::parse).ok()?;
+                Some(RomEntry { sha1, title, score, settings })
+            })
+            .collect();
+        Self { entries }
+    }
+
+    pub fn entries(&self) -> &[RomEntry] {
+        &self.entries
+    }
+
+    pub fn lookup(&self, sha1: &str) -> Option<&RomEntry> {
+        self.entries.iter().find(|entry| entry.sha1.eq_ignore_ascii_case(sha1))
+    }
+
+    pub fn lookup_rom(&self, rom: &[u8]) -> Option<&RomEntry> {
+        self.lookup(&rom_hash(rom))
+    }
+
+    //Apply the settings of rom's entry to emulator if it has one, call before loading it
+    //The memory RomSettings::detect finds the ROM needs is set up known or not
+    pub fn configure(&self, emulator: &mut Emulator, rom: &[u8]) -> Option<&RomEntry> {
+        RomSettings::detect(rom).apply(emulator);
+        let entry = self.lookup_rom(rom)?;
+        entry.settings.apply(emulator);
+        Some(entry)
+    }
+}
+
+//Lowercase hex SHA-1 of a ROM image
+pub fn rom_hash(rom: &[u8]) -> String {
+    sha1_smol::Sha1::from(rom).digest().to_string()
+}
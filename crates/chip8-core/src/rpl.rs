@@ -0,0 +1,58 @@
+//The HP-48 RPL user flags SCHIP games keep high scores in: FX75 saves V0..=Vx to them, FX85 reads them back
+//On the calculator they outlived the program, a FlagStore does the same between runs
+
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+
+//SCHIP has 8, XO-CHIP extends them to 16
+pub const RPL_FLAGS_SIZE: usize = 16;
+
+//Where the flags live between runs. Set with Emulator::set_flag_store, which loads them straight away
+pub trait FlagStore: Send {
+    //None when nothing was saved yet, the flags then start at zero
+    fn load(&mut self) -> Option<[u8; RPL_FLAGS_SIZE]>;
+    //After every FX75
+    fn save(&mut self, flags: &[u8; RPL_FLAGS_SIZE]);
+}
+
+//Flags kept in a file of RPL_FLAGS_SIZE bytes, e.g. next to the ROM
+//Read and write errors are ignored: the game still runs, its scores are just not kept
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlagFile {
+    path: PathBuf,
+}
+
+#[cfg(feature = "std")]
+impl FlagFile {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    //The ROM's path with the extension swapped for .flags
+    pub fn for_rom(rom: &Path) -> Self {
+        Self::new(rom.with_extension("flags"))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(feature = "std")]
+impl FlagStore for FlagFile {
+    //A shorter file (SCHIP's 8 flags) fills the start, the rest stay zero
+    fn load(&mut self) -> Option<[u8; RPL_FLAGS_SIZE]> {
+        let bytes = fs::read(&self.path).ok()?;
+        let mut flags = [0; RPL_FLAGS_SIZE];
+        let length = bytes.len().min(RPL_FLAGS_SIZE);
+        flags[..length].copy_from_slice(&bytes[..length]);
+        Some(flags)
+    }
+
+    fn save(&mut self, flags: &[u8; RPL_FLAGS_SIZE]) {
+        let _ = fs::write(&self.path, flags);
+    }
+}
@@ -0,0 +1,155 @@
+use crate::chip8::{Emulator, EmulatorState, EndReason};
+use crate::error::{Chip8Error, RomError};
+use crate::events::Event;
+use crate::framebuffer::FrameBuffer;
+use crate::input::Key;
+use crate::metrics::Metrics;
+use crate::pacing::Clock;
+use crate::state::State;
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError, TryIter};
+use std::thread::{self, JoinHandle};
+
+//Sent to the worker thread, handled before the next frame
+#[derive(Debug, Clone)]
+pub enum Command {
+    //Emulator::reload_rom, settings such as the quirks stay
+    LoadRom(Vec<u8>),
+    KeyEvent(Key, bool),
+    Pause,
+    Resume,
+    //Answered with Output::State
+    SaveState,
+    //Answered with Output::Metrics, e.g. once a second for a perf overlay
+    Metrics,
+}
+
+//What the worker reports back, drain it with Runner::outputs once a frame
+#[derive(Debug, Clone)]
+pub enum Output {
+    //A frame changed the screen. sound is whether the sound timer was running
+    Frame { screen: Box<FrameBuffer>, sound: bool },
+    //Raised by the emulator during a frame
+    Event(Event),
+    State(State),
+    Metrics(Metrics),
+    //LoadRom was refused, the previous program keeps running
+    RomError(RomError),
+    //The program faulted, nothing runs until the next LoadRom
+    Halted(Chip8Error),
+    //The program ended in a loop it cannot leave, nothing runs until the next LoadRom
+    Finished { pc: u16, reason: EndReason },
+}
+
+//Owns an Emulator on a worker thread that runs it in real time with a Clock, so a GUI thread only
+//sends commands and draws what comes back. The worker stops when the Runner is dropped
+pub struct Runner {
+    commands: Option<Sender<Command>>,
+    outputs: Receiver<Output>,
+    worker: Option<JoinHandle<Emulator>>,
+}
+
+impl Runner {
+    //Run emulator at the default speed (TICKS_PER_FRAME a frame)
+    pub fn spawn(emulator: Emulator) -> Self {
+        Self::with_clock(emulator, Clock::default())
+    }
+
+    pub fn with_clock(emulator: Emulator, clock: Clock) -> Self {
+        let (commands, command_receiver) = mpsc::channel();
+        let (output_sender, outputs) = mpsc::channel();
+        let worker = thread::spawn(move || work(emulator, clock, command_receiver, output_sender));
+        Self { commands: Some(commands), outputs, worker: Some(worker) }
+    }
+
+    //false once the worker has stopped, e.g. because the emulator panicked
+    pub fn send(&self, command: Command) -> bool {
+        self.commands.as_ref().is_some_and(|commands| commands.send(command).is_ok())
+    }
+
+    //Everything reported since the last call, without waiting
+    pub fn outputs(&self) -> TryIter<'_, Output> {
+        self.outputs.try_iter()
+    }
+
+    //Stop the worker and take the emulator back as it was after its last frame
+    //None if the worker panicked
+    pub fn stop(mut self) -> Option<Emulator> {
+        self.shut_down()
+    }
+
+    fn shut_down(&mut self) -> Option<Emulator> {
+        //The worker stops once the channel is closed
+        self.commands = None;
+        self.worker.take()?.join().ok()
+    }
+}
+
+impl Drop for Runner {
+    fn drop(&mut self) {
+        self.shut_down();
+    }
+}
+
+fn work(mut emulator: Emulator, mut clock: Clock, commands: Receiver<Command>, outputs: Sender<Output>) -> Emulator {
+    //The Runner (and its receiver) may go away at any point, sends are allowed to fail
+    let send = |output: Output| {
+        let _ = outputs.send(output);
+    };
+    loop {
+        //Nothing to run, sleep until told otherwise
+        let idle = matches!(emulator.state(), EmulatorState::Paused | EmulatorState::Halted { .. } | EmulatorState::Finished { .. });
+        let mut next = if idle {
+            match commands.recv() {
+                Ok(command) => Some(command),
+                Err(_) => return emulator,
+            }
+        } else {
+            None
+        };
+        loop {
+            let command = match next.take() {
+                Some(command) => command,
+                None => match commands.try_recv() {
+                    Ok(command) => command,
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => return emulator,
+                },
+            };
+            match command {
+                //A bad ROM leaves the running one alone
+                Command::LoadRom(rom) => match emulator.reload_rom(&rom) {
+                    Ok(()) => clock.reset(),
+                    Err(err) => send(Output::RomError(err)),
+                },
+                Command::KeyEvent(key, pressed) => emulator.keypress(key, pressed),
+                Command::Pause => emulator.pause(),
+                Command::Resume => {
+                    emulator.resume();
+                    //Run on from now instead of catching up on the paused time
+                    clock.reset();
+                },
+                Command::SaveState => send(Output::State(emulator.save_state())),
+                Command::Metrics => send(Output::Metrics(emulator.metrics())),
+            }
+        }
+        if matches!(emulator.state(), EmulatorState::Paused | EmulatorState::Halted { .. } | EmulatorState::Finished { .. }) {
+            continue;
+        }
+
+        let frame = clock.frame(&mut emulator);
+        for event in emulator.take_events() {
+            send(Output::Event(event));
+        }
+        match frame {
+            Ok(frame) if frame.screen_changed => send(Output::Frame { screen: Box::new(emulator.frame_buffer().clone()), sound: frame.sound }),
+            Ok(_) => {},
+            Err(err) => send(Output::Halted(err)),
+        }
+        if let EmulatorState::Finished { pc, reason } = emulator.state() {
+            send(Output::Finished { pc, reason });
+        }
+    }
+}
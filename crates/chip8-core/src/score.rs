@@ -0,0 +1,174 @@
+use crate::chip8::Emulator;
+use crate::error::ReplayError;
+use crate::journal::Replay;
+use crate::romdb::RomDb;
+#[cfg(feature = "crypto")]
+use crate::signing::{self, KeyProvider, SignError};
+
+use alloc::format;
+use alloc::string::{String, ToString};
+#[cfg(feature = "crypto")]
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+use core::str::FromStr;
+
+//Deterministic score extraction for leaderboards: replay a recorded session and read the score out of RAM
+//A scoring rule names where the game keeps its score, e.g. "0x2F0:bcd:3" is three BCD digits at 0x2F0
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoreEncoding {
+    //One decimal digit per byte, most significant first (what FX33 writes)
+    Bcd,
+    //Big-endian unsigned integer
+    Binary,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScoreRule {
+    pub address: u16,
+    //Length in bytes, at most 8
+    pub len: u8,
+    pub encoding: ScoreEncoding,
+}
+
+impl ScoreRule {
+    pub fn extract(&self, emulator: &Emulator) -> u64 {
+        (0..self.len as u16)
+            .map(|offset| emulator.read_byte(self.address.wrapping_add(offset)))
+            .fold(0, |score, byte| match self.encoding {
+                ScoreEncoding::Bcd => score * 10 + byte.min(9) as u64,
+                ScoreEncoding::Binary => (score << 8) | byte as u64,
+            })
+    }
+}
+
+impl FromStr for ScoreRule {
+    type Err = ScoreError;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let invalid = || ScoreError::InvalidRule(rule.to_string());
+        let mut parts = rule.split(':');
+        let (Some(address), Some(encoding), Some(len), None) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+            return Err(invalid());
+        };
+        let address = address.strip_prefix("0x").or_else(|| address.strip_prefix("0X")).ok_or_else(invalid)?;
+        let address = u16::from_str_radix(address, 16).map_err(|_| invalid())?;
+        let encoding = match encoding {
+            "bcd" => ScoreEncoding::Bcd,
+            "bin" => ScoreEncoding::Binary,
+            _ => return Err(invalid()),
+        };
+        let len = len.parse().ok().filter(|len| (1..=8).contains(len)).ok_or_else(invalid)?;
+        Ok(Self { address, len, encoding })
+    }
+}
+
+impl fmt::Display for ScoreRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let encoding = match self.encoding {
+            ScoreEncoding::Bcd => "bcd",
+            ScoreEncoding::Binary => "bin",
+        };
+        write!(f, "{:#05X}:{}:{}", self.address, encoding, self.len)
+    }
+}
+
+//A recomputed score, the body of a certificate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Score {
+    pub rom_sha1: String,
+    pub title: String,
+    pub frames: u64,
+    pub score: u64,
+}
+
+impl Score {
+    pub fn to_text(&self) -> String {
+        format!("chip8-score 1\nrom {}\ntitle {}\nframes {}\nscore {}\n", self.rom_sha1, self.title, self.frames, self.score)
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut lines = text.lines();
+        if lines.next()? != "chip8-score 1" {
+            return None;
+        }
+        let mut field = |name: &str| lines.next()?.strip_prefix(name)?.strip_prefix(' ').map(str::to_string);
+        Some(Self {
+            rom_sha1: field("rom")?,
+            title: field("title")?,
+            frames: field("frames")?.parse().ok()?,
+            score: field("score")?.parse().ok()?,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum ScoreError {
+    InvalidRule(String),
+    UnknownRom,
+    //The ROM is in the database but has no scoring rule
+    NoRule,
+    Replay(ReplayError),
+    #[cfg(feature = "crypto")]
+    Signature(SignError),
+    MalformedCertificate,
+}
+
+impl fmt::Display for ScoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScoreError::InvalidRule(rule) => write!(f, "Invalid scoring rule '{}'", rule),
+            ScoreError::UnknownRom => write!(f, "ROM is not in the database"),
+            ScoreError::NoRule => write!(f, "ROM has no scoring rule"),
+            ScoreError::Replay(error) => write!(f, "{}", error),
+            #[cfg(feature = "crypto")]
+            ScoreError::Signature(error) => write!(f, "{}", error),
+            ScoreError::MalformedCertificate => write!(f, "Malformed score certificate"),
+        }
+    }
+}
+
+impl Error for ScoreError {}
+
+impl From<ReplayError> for ScoreError {
+    fn from(error: ReplayError) -> Self {
+        ScoreError::Replay(error)
+    }
+}
+
+#[cfg(feature = "crypto")]
+impl From<SignError> for ScoreError {
+    fn from(error: SignError) -> Self {
+        ScoreError::Signature(error)
+    }
+}
+
+//Play the replay from power-on and read the final score with the ROM's rule from db
+pub fn recompute(rom: &[u8], replay: &Replay, db: &RomDb) -> Result<Score, ScoreError> {
+    let entry = db.lookup_rom(rom).ok_or(ScoreError::UnknownRom)?;
+    let rule = entry.score.ok_or(ScoreError::NoRule)?;
+    let emulator = replay.play(rom)?;
+    Ok(Score {
+        rom_sha1: entry.sha1.clone(),
+        title: entry.title.clone(),
+        frames: replay.frame_count(),
+        score: rule.extract(&emulator),
+    })
+}
+
+//Check a signed replay, recompute its score and sign the result with key_id
+#[cfg(feature = "crypto")]
+pub fn certify(rom: &[u8], signed_replay: &[u8], db: &RomDb, key_id: &str, keys: &dyn KeyProvider) -> Result<Vec<u8>, ScoreError> {
+    let (_, replay) = signing::verify(signed_replay, keys)?;
+    let replay = core::str::from_utf8(replay).map_err(|_| ReplayError::Malformed { line: 0, message: "not UTF-8".to_string() })?;
+    let score = recompute(rom, &Replay::parse(replay)?, db)?;
+    Ok(signing::sign(score.to_text().as_bytes(), key_id, keys)?)
+}
+
+//Check a certificate from certify() and read the score back
+#[cfg(feature = "crypto")]
+pub fn read_certificate(certificate: &[u8], keys: &dyn KeyProvider) -> Result<Score, ScoreError> {
+    let (_, text) = signing::verify(certificate, keys)?;
+    core::str::from_utf8(text).ok().and_then(Score::parse).ok_or(ScoreError::MalformedCertificate)
+}
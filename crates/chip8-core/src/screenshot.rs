@@ -0,0 +1,51 @@
+use crate::chip8::Emulator;
+use crate::framebuffer::{Palette, ScaleOptions};
+
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    //Binary PPM (P6), readable by most image tools and trivial to diff
+    Ppm,
+    #[cfg(feature = "png")]
+    Png,
+}
+
+impl ImageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Ppm => "ppm",
+            #[cfg(feature = "png")]
+            ImageFormat::Png => "png",
+        }
+    }
+}
+
+//The display as an image file, every CHIP-8 pixel a scale x scale block (scale 0 is taken as 1)
+pub fn screenshot(emulator: &Emulator, format: ImageFormat, palette: &Palette, scale: usize) -> Vec<u8> {
+    let scale = scale.max(1);
+    let (width, height) = (emulator.screen_width() * scale, emulator.screen_height() * scale);
+    let mut rgba = vec![0; width * height * 4];
+    emulator.render_scaled(&mut rgba, scale, &ScaleOptions { palette: *palette, ..ScaleOptions::default() });
+    match format {
+        ImageFormat::Ppm => {
+            let mut image = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+            image.extend(rgba.chunks_exact(4).flat_map(|pixel| &pixel[..3]));
+            image
+        },
+        #[cfg(feature = "png")]
+        ImageFormat::Png => {
+            let mut image = Vec::new();
+            let mut encoder = png::Encoder::new(&mut image, width as u32, height as u32);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            //Only writing to a Vec, which cannot fail
+            let mut writer = encoder.write_header().expect("PNG header");
+            writer.write_image_data(&rgba).expect("PNG data");
+            writer.finish().expect("PNG end");
+            image
+        },
+    }
+}
@@ -0,0 +1,292 @@
+//Automation scripts in Rhai (rhai.rs) run once a frame, for bots and regression checks that can change
+//without a rebuild. The whole script runs every frame, e.g. for a Pong bot and a check:
+//
+//  // follow the ball
+//  if v(1) > v(3) { press(1) } else { release(1) }
+//  if peek(0x2F0) != vars.last { vars.misses += 1 }
+//  vars.last = peek(0x2F0);
+//  assert(vars.misses < 3, "missed three times");
+//  if frame == 3600 { stop() }
+//
+//  fn init() { #{ misses: 0, last: 0 } }
+//
+//The machine: v(N) and set_v(N, X), i() and set_i(X), pc() and set_pc(X), dt() and set_dt(X), st() and
+//set_st(X), peek(ADDR) and poke(ADDR, X), key(K) (true while held), press(K), release(K), tap(K) (held for
+//one frame), pixel(X, Y) (true if lit, false off screen), width() and height() of the screen
+//Writes take the low bits of the value, like the instructions that write the same things
+//The script: frame (counted by the script from 0), vars, a map kept from frame to frame that starts as what
+//fn init() returns or empty, print(X), assert(CONDITION) or assert(CONDITION, MESSAGE), and stop()
+//let variables only last the frame they are made in
+
+use crate::chip8::Emulator;
+use crate::error::Chip8Error;
+use crate::input::Key;
+
+use rhai::{CallFnOptions, Engine, EvalAltResult, Map, Scope, AST};
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use std::cell::{Cell, RefCell};
+use std::error::Error;
+use std::fmt;
+use std::mem;
+use std::rc::Rc;
+
+//Rhai operations a frame may take before the script is taken to be stuck in a loop
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+//What Script::run_frame wants next
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    Continue,
+    //stop() was called
+    Stop,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptError {
+    Parse { line: usize, message: String },
+    //An assert failed, or the script did something it cannot (a key above F, a type mismatch, a throw)
+    Failed { line: usize, frame: u64, message: String },
+    //The program faulted while Script::run was running it
+    Fault(Chip8Error),
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptError::Parse { line, message } => write!(f, "Script line {}: {}", line, message),
+            ScriptError::Failed { line, frame, message } => write!(f, "Script line {}, frame {}: {}", line, frame, message),
+            ScriptError::Fault(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for ScriptError {}
+
+impl From<Chip8Error> for ScriptError {
+    fn from(error: Chip8Error) -> Self {
+        ScriptError::Fault(error)
+    }
+}
+
+//What the functions registered with the engine share with the Script
+#[derive(Default)]
+struct Shared {
+    //Moved in for the length of run_frame, so the functions can reach it
+    emulator: RefCell<Option<Emulator>>,
+    frame: Cell<u64>,
+    //Tapped this frame, released before the next one runs
+    taps: RefCell<Vec<Key>>,
+    stop: Cell<bool>,
+    output: RefCell<Vec<String>>,
+}
+
+impl Shared {
+    fn with<T>(&self, f: impl FnOnce(&mut Emulator) -> Result<T, String>) -> Result<T, Box<EvalAltResult>> {
+        let mut emulator = self.emulator.borrow_mut();
+        let emulator = emulator.as_mut().ok_or("the machine is only there while a frame runs")?;
+        Ok(f(emulator)?)
+    }
+}
+
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    //vars and frame
+    scope: Scope<'static>,
+    shared: Rc<Shared>,
+    //fn init() is yet to run
+    init: bool,
+}
+
+impl Script {
+    pub fn parse(text: &str) -> Result<Self, ScriptError> {
+        let shared = Rc::new(Shared::default());
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        register(&mut engine, &shared);
+        let ast = engine.compile(text).map_err(|error| ScriptError::Parse { line: error.1.line().unwrap_or(0), message: error.0.to_string() })?;
+        let init = ast.iter_functions().any(|function| function.name == "init" && function.params.is_empty());
+        let mut scope = Scope::new();
+        scope.push("vars", Map::new());
+        scope.push("frame", 0 as rhai::INT);
+        Ok(Self { engine, ast, scope, shared, init })
+    }
+
+    //The script's part of a frame, before Emulator::run_frame so the keys it presses count for it
+    pub fn run_frame(&mut self, emulator: &mut Emulator) -> Result<Flow, ScriptError> {
+        let frame = self.shared.frame.get();
+        *self.shared.emulator.borrow_mut() = Some(mem::take(emulator));
+        let result = self.run_script();
+        *emulator = self.shared.emulator.borrow_mut().take().expect("the emulator is put back once");
+        result.map_err(|error| failed(*error, frame))?;
+        self.shared.frame.set(frame + 1);
+        Ok(if self.shared.stop.replace(false) { Flow::Stop } else { Flow::Continue })
+    }
+
+    fn run_script(&mut self) -> Result<(), Box<EvalAltResult>> {
+        self.shared.with(|emulator| {
+            for key in self.shared.taps.borrow_mut().drain(..) {
+                emulator.keypress(key, false);
+            }
+            Ok(())
+        })?;
+        if mem::take(&mut self.init) {
+            let options = CallFnOptions::new().eval_ast(false);
+            let vars: Map = self.engine.call_fn_with_options(options, &mut self.scope, &self.ast, "init", ())?;
+            self.scope.set_value("vars", vars);
+        }
+        self.scope.set_value("frame", self.shared.frame.get() as rhai::INT);
+        //Dropping the frame's let variables
+        let len = self.scope.len();
+        let result = self.engine.run_ast_with_scope(&mut self.scope, &self.ast);
+        self.scope.rewind(len);
+        result
+    }
+
+    //run_frame then Emulator::run_frame, until the script stops or frames have run. Returns the frames run
+    pub fn run(&mut self, emulator: &mut Emulator, frames: u64) -> Result<u64, ScriptError> {
+        for frame in 0..frames {
+            if self.run_frame(emulator)? == Flow::Stop {
+                return Ok(frame);
+            }
+            emulator.run_frame()?;
+        }
+        Ok(frames)
+    }
+
+    //Frames run_frame has been through
+    pub fn frame(&self) -> u64 {
+        self.shared.frame.get()
+    }
+
+    //vars.name, 0 if it is not there or not a number
+    pub fn variable(&self, name: &str) -> i64 {
+        let vars = self.scope.get_value::<Map>("vars").unwrap_or_default();
+        vars.get(name).and_then(|value| value.as_int().ok()).unwrap_or(0)
+    }
+
+    //What print printed, oldest first, as "frame N: value"
+    pub fn output(&self) -> Vec<String> {
+        self.shared.output.borrow().clone()
+    }
+
+    pub fn take_output(&mut self) -> Vec<String> {
+        mem::take(&mut self.shared.output.borrow_mut())
+    }
+}
+
+//Where and what, without the position Rhai adds to the message
+fn failed(mut error: EvalAltResult, frame: u64) -> ScriptError {
+    let line = error.take_position().line().unwrap_or(0);
+    ScriptError::Failed { line, frame, message: error.to_string() }
+}
+
+fn register(engine: &mut Engine, shared: &Rc<Shared>) {
+    let register_index = |register: i64| u8::try_from(register).ok().filter(|&register| register < 16).ok_or_else(|| format!("no register v{}", register));
+    let key_index = |key: i64| usize::try_from(key).ok().and_then(Key::from_index).ok_or_else(|| format!("no key {}", key));
+
+    let s = shared.clone();
+    engine.register_fn("v", move |register: i64| s.with(|emulator| Ok(emulator.v_register(register_index(register)?) as i64)));
+    let s = shared.clone();
+    engine.register_fn("set_v", move |register: i64, value: i64| {
+        s.with(|emulator| {
+            emulator.set_v_register(register_index(register)?, value as u8);
+            Ok(())
+        })
+    });
+    let s = shared.clone();
+    engine.register_fn("i", move || s.with(|emulator| Ok(emulator.i_register() as i64)));
+    let s = shared.clone();
+    engine.register_fn("set_i", move |value: i64| {
+        s.with(|emulator| {
+            emulator.set_i_register(value as u16);
+            Ok(())
+        })
+    });
+    let s = shared.clone();
+    engine.register_fn("pc", move || s.with(|emulator| Ok(emulator.program_counter() as i64)));
+    let s = shared.clone();
+    engine.register_fn("set_pc", move |value: i64| {
+        s.with(|emulator| {
+            emulator.set_program_counter(value as u16);
+            Ok(())
+        })
+    });
+    let s = shared.clone();
+    engine.register_fn("dt", move || s.with(|emulator| Ok(emulator.delay_timer() as i64)));
+    let s = shared.clone();
+    engine.register_fn("set_dt", move |value: i64| {
+        s.with(|emulator| {
+            emulator.set_delay_timer(value as u8);
+            Ok(())
+        })
+    });
+    let s = shared.clone();
+    engine.register_fn("st", move || s.with(|emulator| Ok(emulator.sound_timer() as i64)));
+    let s = shared.clone();
+    engine.register_fn("set_st", move |value: i64| {
+        s.with(|emulator| {
+            emulator.set_sound_timer(value as u8);
+            Ok(())
+        })
+    });
+    let s = shared.clone();
+    engine.register_fn("peek", move |address: i64| s.with(|emulator| Ok(emulator.peek(address as u16) as i64)));
+    let s = shared.clone();
+    engine.register_fn("poke", move |address: i64, value: i64| {
+        s.with(|emulator| {
+            emulator.poke(address as u16, value as u8);
+            Ok(())
+        })
+    });
+
+    let s = shared.clone();
+    engine.register_fn("key", move |key: i64| s.with(|emulator| Ok(emulator.keys_bitmask() >> key_index(key)?.index() & 1 != 0)));
+    let s = shared.clone();
+    engine.register_fn("press", move |key: i64| {
+        s.with(|emulator| {
+            emulator.keypress(key_index(key)?, true);
+            Ok(())
+        })
+    });
+    let s = shared.clone();
+    engine.register_fn("release", move |key: i64| {
+        s.with(|emulator| {
+            emulator.keypress(key_index(key)?, false);
+            Ok(())
+        })
+    });
+    let s = shared.clone();
+    engine.register_fn("tap", move |key: i64| {
+        s.with(|emulator| {
+            let key = key_index(key)?;
+            emulator.keypress(key, true);
+            s.taps.borrow_mut().push(key);
+            Ok(())
+        })
+    });
+
+    let s = shared.clone();
+    engine.register_fn("pixel", move |x: i64, y: i64| {
+        s.with(|emulator| {
+            let screen = emulator.frame_buffer();
+            let inside = (0..screen.width() as i64).contains(&x) && (0..screen.height() as i64).contains(&y);
+            Ok(inside && screen.is_lit(x as usize, y as usize))
+        })
+    });
+    let s = shared.clone();
+    engine.register_fn("width", move || s.with(|emulator| Ok(emulator.frame_buffer().width() as i64)));
+    let s = shared.clone();
+    engine.register_fn("height", move || s.with(|emulator| Ok(emulator.frame_buffer().height() as i64)));
+
+    let s = shared.clone();
+    engine.register_fn("stop", move || s.stop.set(true));
+    engine.register_fn("assert", |condition: bool| if condition { Ok(()) } else { Err(Box::<EvalAltResult>::from("assertion failed")) });
+    engine.register_fn("assert", |condition: bool, message: &str| if condition { Ok(()) } else { Err(Box::<EvalAltResult>::from(format!("assertion failed: {}", message))) });
+    let s = shared.clone();
+    engine.on_print(move |text| s.output.borrow_mut().push(format!("frame {}: {}", s.frame.get(), text)));
+}
@@ -0,0 +1,104 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+//HMAC-SHA256 signing for shared save and replay files, so a server can check they were not edited
+//Signed layout: "C8SG", key id length (1 byte), key id, 32 byte tag, payload
+//The tag covers the key id and the payload
+const MAGIC: &[u8; 4] = b"C8SG";
+const TAG_SIZE: usize = 32;
+
+type HmacSha256 = Hmac<Sha256>;
+
+//Where signing keys come from (a config file, a secrets service, ...), looked up by id so keys can be rotated
+pub trait KeyProvider {
+    fn key(&self, key_id: &str) -> Option<Vec<u8>>;
+}
+
+//Keys held in memory
+#[derive(Debug, Clone, Default)]
+pub struct StaticKeys {
+    keys: HashMap<String, Vec<u8>>,
+}
+
+impl StaticKeys {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_key(mut self, key_id: &str, key: &[u8]) -> Self {
+        self.keys.insert(key_id.to_string(), key.to_vec());
+        self
+    }
+}
+
+impl KeyProvider for StaticKeys {
+    fn key(&self, key_id: &str) -> Option<Vec<u8>> {
+        self.keys.get(key_id).cloned()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignError {
+    UnknownKey(String),
+    //Key ids are at most 255 bytes
+    KeyIdTooLong,
+    //Not a signed file, or cut short
+    Malformed,
+    BadSignature,
+}
+
+impl fmt::Display for SignError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignError::UnknownKey(key_id) => write!(f, "Unknown signing key '{}'", key_id),
+            SignError::KeyIdTooLong => write!(f, "Signing key id is longer than 255 bytes"),
+            SignError::Malformed => write!(f, "Not a signed file"),
+            SignError::BadSignature => write!(f, "Signature does not match, the file was modified or signed with another key"),
+        }
+    }
+}
+
+impl Error for SignError {}
+
+pub fn sign(payload: &[u8], key_id: &str, keys: &dyn KeyProvider) -> Result<Vec<u8>, SignError> {
+    if key_id.len() > u8::MAX as usize {
+        return Err(SignError::KeyIdTooLong);
+    }
+    let tag = tag(keys, key_id, payload)?.finalize().into_bytes();
+    let mut signed = Vec::with_capacity(MAGIC.len() + 1 + key_id.len() + TAG_SIZE + payload.len());
+    signed.extend_from_slice(MAGIC);
+    signed.push(key_id.len() as u8);
+    signed.extend_from_slice(key_id.as_bytes());
+    signed.extend_from_slice(&tag);
+    signed.extend_from_slice(payload);
+    Ok(signed)
+}
+
+//Check a signed file and return its key id and payload
+pub fn verify<'a>(signed: &'a [u8], keys: &dyn KeyProvider) -> Result<(&'a str, &'a [u8]), SignError> {
+    let rest = signed.strip_prefix(MAGIC.as_slice()).ok_or(SignError::Malformed)?;
+    let (&id_len, rest) = rest.split_first().ok_or(SignError::Malformed)?;
+    if rest.len() < id_len as usize + TAG_SIZE {
+        return Err(SignError::Malformed);
+    }
+    let (key_id, rest) = rest.split_at(id_len as usize);
+    let key_id = std::str::from_utf8(key_id).map_err(|_| SignError::Malformed)?;
+    let (expected, payload) = rest.split_at(TAG_SIZE);
+    tag(keys, key_id, payload)?.verify_slice(expected).map_err(|_| SignError::BadSignature)?;
+    Ok((key_id, payload))
+}
+
+fn tag(keys: &dyn KeyProvider, key_id: &str, payload: &[u8]) -> Result<HmacSha256, SignError> {
+    let key = keys.key(key_id).ok_or_else(|| SignError::UnknownKey(key_id.to_string()))?;
+    let mut mac = HmacSha256::new_from_slice(&key).expect("HMAC accepts keys of any length");
+    mac.update(&[key_id.len() as u8]);
+    mac.update(key_id.as_bytes());
+    mac.update(payload);
+    Ok(mac)
+}
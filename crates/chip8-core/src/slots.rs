@@ -0,0 +1,190 @@
+//Save state slots for one ROM, numbered for quick save and quick load hotkeys or named, kept in memory and
+//optionally in a directory (a file a slot) so they outlive the session
+//Slot file layout: "C8SL", when it was saved (u64 seconds since the Unix epoch, big-endian), the ROM's SHA-1 (a
+//length byte and hex), then the State in its snapshot form
+
+use crate::chip8::Emulator;
+use crate::error::{SlotError, StateError};
+use crate::state::State;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const SLOT_MAGIC: &[u8; 4] = b"C8SL";
+const SLOT_EXTENSION: &str = "c8slot";
+//Numbered slots for the quick save hotkeys, 0 to 9
+pub const QUICK_SLOTS: u8 = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slot {
+    pub state: State,
+    //To the second once it went through a file
+    pub saved_at: SystemTime,
+    //SHA-1 of the ROM it was saved with, see rom_hash
+    pub rom_hash: String,
+}
+
+impl Slot {
+    fn to_bytes(&self) -> Vec<u8> {
+        let seconds = self.saved_at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let mut bytes = SLOT_MAGIC.to_vec();
+        bytes.extend_from_slice(&seconds.to_be_bytes());
+        bytes.push(self.rom_hash.len() as u8);
+        bytes.extend_from_slice(self.rom_hash.as_bytes());
+        bytes.extend(self.state.to_snapshot_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, StateError> {
+        let rest = bytes.strip_prefix(SLOT_MAGIC).ok_or(StateError::NotASnapshot)?;
+        let (seconds, rest) = rest.split_first_chunk::<8>().ok_or(StateError::Truncated)?;
+        let (&hash_len, rest) = rest.split_first().ok_or(StateError::Truncated)?;
+        let (rom_hash, snapshot) = rest.split_at_checked(hash_len as usize).ok_or(StateError::Truncated)?;
+        Ok(Self {
+            state: State::from_snapshot_bytes(snapshot)?,
+            saved_at: UNIX_EPOCH + Duration::from_secs(u64::from_be_bytes(*seconds)),
+            rom_hash: String::from_utf8(rom_hash.to_vec()).map_err(|_| StateError::Corrupt)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StateSlots {
+    rom_hash: String,
+    slots: BTreeMap<String, Slot>,
+    //Where quick_save and quick_load go
+    selected: u8,
+    //Every save is written here too, None keeps them in memory only
+    dir: Option<PathBuf>,
+}
+
+impl StateSlots {
+    //In memory only, for the ROM with this SHA-1 (rom_hash of its bytes)
+    pub fn new(rom_hash: impl Into<String>) -> Self {
+        Self { rom_hash: rom_hash.into(), slots: BTreeMap::new(), selected: 0, dir: None }
+    }
+
+    //Slots kept in dir, with the ones already there loaded. dir is created by the first save
+    //Files that are not slots are left alone, unreadable slot files are an error
+    pub fn open(dir: impl Into<PathBuf>, rom_hash: impl Into<String>) -> Result<Self, SlotError> {
+        let dir = dir.into();
+        let mut slots = BTreeMap::new();
+        let entries = match fs::read_dir(&dir) {
+            Err(error) if error.kind() == ErrorKind::NotFound => None,
+            entries => Some(entries?),
+        };
+        for entry in entries.into_iter().flatten() {
+            let path = entry?.path();
+            if path.extension().is_some_and(|extension| extension == SLOT_EXTENSION) {
+                let Some(name) = path.file_stem().and_then(|name| name.to_str()) else {
+                    continue;
+                };
+                slots.insert(name.to_string(), Slot::from_bytes(&fs::read(&path)?)?);
+            }
+        }
+        Ok(Self { rom_hash: rom_hash.into(), slots, selected: 0, dir: Some(dir) })
+    }
+
+    //The ROM's path with the extension swapped for .slots, like FlagFile::for_rom
+    pub fn dir_for_rom(rom: &Path) -> PathBuf {
+        rom.with_extension("slots")
+    }
+
+    pub fn rom_hash(&self) -> &str {
+        &self.rom_hash
+    }
+
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    //Save the emulator's state in the slot called name, replacing what was there
+    pub fn save(&mut self, name: &str, emulator: &Emulator) -> Result<(), SlotError> {
+        check_name(name)?;
+        let slot = Slot { state: emulator.save_state(), saved_at: SystemTime::now(), rom_hash: self.rom_hash.clone() };
+        if let Some(dir) = &self.dir {
+            fs::create_dir_all(dir)?;
+            fs::write(slot_path(dir, name), slot.to_bytes())?;
+        }
+        self.slots.insert(name.to_string(), slot);
+        Ok(())
+    }
+
+    //Put the emulator back in the state saved in name, refused if it was saved with another ROM
+    pub fn load(&self, name: &str, emulator: &mut Emulator) -> Result<(), SlotError> {
+        let slot = self.slots.get(name).ok_or_else(|| SlotError::Empty { name: name.to_string() })?;
+        if slot.rom_hash != self.rom_hash {
+            return Err(SlotError::RomMismatch { expected: slot.rom_hash.clone() });
+        }
+        emulator.load_state(&slot.state)?;
+        Ok(())
+    }
+
+    //The slot and its file, false if there was nothing in it
+    pub fn remove(&mut self, name: &str) -> Result<bool, SlotError> {
+        check_name(name)?;
+        if let Some(dir) = &self.dir {
+            match fs::remove_file(slot_path(dir, name)) {
+                Err(error) if error.kind() != ErrorKind::NotFound => return Err(error.into()),
+                _ => {},
+            }
+        }
+        Ok(self.slots.remove(name).is_some())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Slot> {
+        self.slots.get(name)
+    }
+
+    //Every saved slot by name, for a slot menu
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Slot)> {
+        self.slots.iter().map(|(name, slot)| (name.as_str(), slot))
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    //The numbered slot quick_save and quick_load use, wrapping round QUICK_SLOTS
+    pub fn select(&mut self, slot: u8) {
+        self.selected = slot % QUICK_SLOTS;
+    }
+
+    pub fn selected(&self) -> u8 {
+        self.selected
+    }
+
+    //For one key cycling through the numbered slots
+    pub fn select_next(&mut self) -> u8 {
+        self.select(self.selected + 1);
+        self.selected
+    }
+
+    pub fn quick_save(&mut self, emulator: &Emulator) -> Result<(), SlotError> {
+        self.save(&self.selected.to_string(), emulator)
+    }
+
+    pub fn quick_load(&self, emulator: &mut Emulator) -> Result<(), SlotError> {
+        self.load(&self.selected.to_string(), emulator)
+    }
+}
+
+fn check_name(name: &str) -> Result<(), SlotError> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(SlotError::BadName { name: name.to_string() });
+    }
+    Ok(())
+}
+
+fn slot_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(name).with_extension(SLOT_EXTENSION)
+}
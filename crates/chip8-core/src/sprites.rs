@@ -0,0 +1,84 @@
+//Sprite data pulled out of a ROM without running it, for previewing graphics in ROM hacking tools
+//Either at a known address, or found through the DXYN instructions in the disassembly
+
+use crate::disasm::disassemble;
+use crate::instruction::Instruction;
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sprite {
+    pub address: u16,
+    //8, or 16 for the 16x16 sprites DXY0 draws
+    pub width: usize,
+    pub height: usize,
+    //Row by row, true is a set pixel
+    pub pixels: Vec<bool>,
+    //DXYN instructions that draw it, empty for sprite_at
+    pub drawn_at: Vec<u16>,
+}
+
+impl Sprite {
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        self.pixels[y * self.width + x]
+    }
+
+    //Bytes of sprite data, 1 a row (2 when 16 wide)
+    pub fn len(&self) -> usize {
+        self.height * self.width / 8
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.height == 0
+    }
+}
+
+//One line a row, '#' for set pixels and '.' for clear ones
+impl fmt::Display for Sprite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.pixels.chunks(self.width) {
+            let row: String = row.iter().map(|&set| if set { '#' } else { '.' }).collect();
+            writeln!(f, "{}", row)?;
+        }
+        Ok(())
+    }
+}
+
+//The sprite DXYN with this N would draw from address: N rows 8 wide, or 16x16 for N = 0
+//rom is loaded at origin, None if the sprite does not lie within it
+pub fn sprite_at(rom: &[u8], origin: u16, address: u16, n: u8) -> Option<Sprite> {
+    let (width, height) = if n == 0 { (16, 16) } else { (8, n as usize) };
+    let start = address.checked_sub(origin)? as usize;
+    let data = rom.get(start..start + height * width / 8)?;
+    let pixels = data.iter().flat_map(|&byte| (0..8).rev().map(move |bit| byte & 1 << bit != 0)).collect();
+    Some(Sprite { address, width, height, pixels, drawn_at: Vec::new() })
+}
+
+//Sprites drawn by the ROM's DXYN instructions, by address, each size drawn from an address once
+//I is taken from the last ANNN before each DXYN in the listing, so sprites found through FX1E, FX29 or
+//I computed at run time are missed, and a branch between the two can point it at the wrong data
+pub fn find_sprites(rom: &[u8], origin: u16) -> Vec<Sprite> {
+    let mut sprites: Vec<Sprite> = Vec::new();
+    let mut i = None;
+    for line in disassemble(rom, origin) {
+        match line.instruction {
+            Some(Instruction::LoadI(address) | Instruction::LoadILong(address)) => i = Some(address),
+            Some(Instruction::Draw { n, .. }) => {
+                let Some(address) = i else {
+                    continue;
+                };
+                let height = if n == 0 { 16 } else { n as usize };
+                match sprites.iter_mut().find(|sprite| sprite.address == address && sprite.height == height) {
+                    Some(sprite) => sprite.drawn_at.push(line.address),
+                    None => sprites.extend(sprite_at(rom, origin, address, n).map(|sprite| Sprite { drawn_at: vec![line.address], ..sprite })),
+                }
+            },
+            _ => {},
+        }
+    }
+    sprites.sort_by_key(|sprite| (sprite.address, sprite.height));
+    sprites
+}
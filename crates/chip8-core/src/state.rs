@@ -0,0 +1,388 @@
+use crate::audio::DEFAULT_PITCH;
+use crate::chip8::{EmulatorState, EndReason};
+use crate::error::{Chip8Error, StateError};
+use crate::framebuffer::ALL_PLANES;
+use crate::megachip::{BlendMode, MegaChip, Sample, MEGA_MEMORY_SIZE, SCREEN_SIZE};
+use crate::quirks::{Quirks, RandomModel};
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+//Binary snapshot layout, all numbers big-endian:
+//"C8ST", version (u16), emulator state, PC, I, V0-VF, delay and sound timers, flags (hires, exited, two page),
+//planes, audio pattern, pitch, keys (u16 bitmask), stack depth and entries, quirks (bitmask in Quirks::flags order),
+//random model (tag, plus the seed for Seeded) and generator state (u64),
+//then RAM and screen, each as a u32 length and run-length encoded bytes (0x00 n is n + 1 zeros)
+//Then MegaChip: the top byte of I, memory past RAM as a chunk like RAM, and a tag, 1 in MegaChip mode, followed by
+//the shown and drawn screens (RGBA) and the palette indices as chunks, the palette (256 RGBA), sprite width and
+//height (u16), alpha, blend mode (080N's N), collision colour and the sample (tag, rate u16, looping, data chunk)
+//Version 1 had no random model or generator state, those load as Entropy
+//Versions before 3 had no pitch, it loads as the default 64
+//Versions before 4 had no MegaChip part, they load outside MegaChip mode
+const SNAPSHOT_MAGIC: &[u8; 4] = b"C8ST";
+pub(crate) const SNAPSHOT_VERSION: u16 = 4;
+
+//Everything needed to resume a program later, from Emulator::save_state
+//RAM and screen are Vecs so serde can handle them (the MemoryMap's size and the hires buffer size long)
+//Debugging aids (effects, explanations, pending events) are not part of it, nor are the RPL flags,
+//which outlive a program the way a battery save does
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct State {
+    pub state: EmulatorState,
+    pub program_counter: u16,
+    pub ram: Vec<u8>,
+    //Plane bitmask per pixel of the full 128x64 buffer
+    pub screen: Vec<u8>,
+    pub hires: bool,
+    //The 64x64 two page hires screen
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub two_page: bool,
+    pub planes: u8,
+    pub audio_pattern: [u8; 16],
+    #[cfg_attr(feature = "serde", serde(default = "default_pitch"))]
+    pub pitch: u8,
+    pub exited: bool,
+    pub v_registers: [u8; 16],
+    pub i_register: u16,
+    //Return addresses, oldest first
+    pub stack: Vec<u16>,
+    pub keys: [bool; 16],
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    pub quirks: Quirks,
+    //Generator state of the Seeded and Vip random models
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub random_state: u64,
+    //MegaChip's top byte of the 24 bit I, 0 otherwise
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub i_high: u8,
+    //MegaChip ROM bytes past the end of RAM
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub extended: Vec<u8>,
+    //Some in MegaChip mode
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub megachip: Option<Box<MegaChip>>,
+}
+
+impl State {
+    //Compact binary form that later crate versions can still read
+    pub fn to_snapshot_bytes(&self) -> Vec<u8> {
+        let mut bytes = SNAPSHOT_MAGIC.to_vec();
+        bytes.extend_from_slice(&SNAPSHOT_VERSION.to_be_bytes());
+        match self.state {
+            //Only a hand made State is Paused, snapshots keep it as running
+            EmulatorState::Running | EmulatorState::Paused => bytes.push(0),
+            EmulatorState::WaitingForKey { dest_register } => bytes.extend_from_slice(&[1, dest_register]),
+            EmulatorState::WaitingForRelease { dest_register, key } => bytes.extend_from_slice(&[2, dest_register, key]),
+            EmulatorState::Halted { error } => {
+                bytes.push(3);
+                match error {
+                    Chip8Error::UnknownOpcode { pc, opcode } => {
+                        bytes.push(0);
+                        bytes.extend_from_slice(&pc.to_be_bytes());
+                        bytes.extend_from_slice(&opcode.to_be_bytes());
+                    },
+                    Chip8Error::StackOverflow { pc } => {
+                        bytes.push(1);
+                        bytes.extend_from_slice(&pc.to_be_bytes());
+                    },
+                    Chip8Error::StackUnderflow { pc } => {
+                        bytes.push(2);
+                        bytes.extend_from_slice(&pc.to_be_bytes());
+                    },
+                    Chip8Error::InvalidKey { pc, key } => {
+                        bytes.push(3);
+                        bytes.extend_from_slice(&pc.to_be_bytes());
+                        bytes.push(key);
+                    },
+                    Chip8Error::ProtectedWrite { pc, address } => {
+                        bytes.push(4);
+                        bytes.extend_from_slice(&pc.to_be_bytes());
+                        bytes.extend_from_slice(&address.to_be_bytes());
+                    },
+                    Chip8Error::Internal { pc } => {
+                        bytes.push(5);
+                        bytes.extend_from_slice(&pc.to_be_bytes());
+                    },
+                }
+            },
+            EmulatorState::Finished { pc, reason } => {
+                bytes.push(4);
+                bytes.extend_from_slice(&pc.to_be_bytes());
+                bytes.push(reason as u8);
+            },
+        }
+        bytes.extend_from_slice(&self.program_counter.to_be_bytes());
+        bytes.extend_from_slice(&self.i_register.to_be_bytes());
+        bytes.extend_from_slice(&self.v_registers);
+        bytes.extend_from_slice(&[self.delay_timer, self.sound_timer]);
+        bytes.push(self.hires as u8 | (self.exited as u8) << 1 | (self.two_page as u8) << 2);
+        bytes.push(self.planes);
+        bytes.extend_from_slice(&self.audio_pattern);
+        bytes.push(self.pitch);
+        let keys = self.keys.iter().enumerate().fold(0u16, |keys, (key, &pressed)| keys | (pressed as u16) << key);
+        bytes.extend_from_slice(&keys.to_be_bytes());
+        bytes.push(self.stack.len() as u8);
+        for address in &self.stack {
+            bytes.extend_from_slice(&address.to_be_bytes());
+        }
+        let quirks = self.quirks.flags().iter().enumerate().fold(0u8, |quirks, (bit, &(_, on))| quirks | (on as u8) << bit);
+        bytes.push(quirks);
+        match self.quirks.random {
+            RandomModel::Entropy => bytes.push(0),
+            RandomModel::Seeded(seed) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&seed.to_be_bytes());
+            },
+            RandomModel::Vip => bytes.push(2),
+        }
+        bytes.extend_from_slice(&self.random_state.to_be_bytes());
+        chunk(&mut bytes, &self.ram);
+        chunk(&mut bytes, &self.screen);
+        bytes.push(self.i_high);
+        chunk(&mut bytes, &self.extended);
+        match &self.megachip {
+            None => bytes.push(0),
+            Some(mega) => {
+                bytes.push(1);
+                chunk(&mut bytes, mega.front.as_flattened());
+                chunk(&mut bytes, mega.back.as_flattened());
+                chunk(&mut bytes, &mega.indices);
+                bytes.extend_from_slice(mega.palette.as_flattened());
+                bytes.extend_from_slice(&(mega.sprite_width as u16).to_be_bytes());
+                bytes.extend_from_slice(&(mega.sprite_height as u16).to_be_bytes());
+                bytes.extend_from_slice(&[mega.alpha, mega.blend.n(), mega.collision_color]);
+                match &mega.sample {
+                    None => bytes.push(0),
+                    Some(sample) => {
+                        bytes.push(1);
+                        bytes.extend_from_slice(&sample.rate.to_be_bytes());
+                        bytes.push(sample.looping as u8);
+                        chunk(&mut bytes, &sample.data);
+                    },
+                }
+            },
+        }
+        bytes
+    }
+
+    //Read a snapshot from to_snapshot_bytes. Sizes are checked by Emulator::load_state
+    pub fn from_snapshot_bytes(bytes: &[u8]) -> Result<Self, StateError> {
+        let rest = bytes.strip_prefix(SNAPSHOT_MAGIC).ok_or(StateError::NotASnapshot)?;
+        let mut reader = Reader(rest);
+        let version = reader.u16()?;
+        if version == 0 || version > SNAPSHOT_VERSION {
+            return Err(StateError::UnsupportedVersion { version });
+        }
+
+        let state = match reader.u8()? {
+            0 => EmulatorState::Running,
+            1 => EmulatorState::WaitingForKey { dest_register: reader.u8()? },
+            2 => EmulatorState::WaitingForRelease { dest_register: reader.u8()?, key: reader.u8()? },
+            3 => {
+                let error = match reader.u8()? {
+                    0 => Chip8Error::UnknownOpcode { pc: reader.u16()?, opcode: reader.u16()? },
+                    1 => Chip8Error::StackOverflow { pc: reader.u16()? },
+                    2 => Chip8Error::StackUnderflow { pc: reader.u16()? },
+                    3 => Chip8Error::InvalidKey { pc: reader.u16()?, key: reader.u8()? },
+                    4 => Chip8Error::ProtectedWrite { pc: reader.u16()?, address: reader.u16()? },
+                    5 => Chip8Error::Internal { pc: reader.u16()? },
+                    _ => return Err(StateError::Corrupt),
+                };
+                EmulatorState::Halted { error }
+            },
+            4 => {
+                let pc = reader.u16()?;
+                let reason = match reader.u8()? {
+                    0 => EndReason::JumpToSelf,
+                    1 => EndReason::EndlessLoop,
+                    _ => return Err(StateError::Corrupt),
+                };
+                EmulatorState::Finished { pc, reason }
+            },
+            _ => return Err(StateError::Corrupt),
+        };
+        let program_counter = reader.u16()?;
+        let i_register = reader.u16()?;
+        let v_registers = reader.array()?;
+        let delay_timer = reader.u8()?;
+        let sound_timer = reader.u8()?;
+        let flags = reader.u8()?;
+        let planes = reader.u8()?;
+        let audio_pattern = reader.array()?;
+        let pitch = if version >= 3 { reader.u8()? } else { DEFAULT_PITCH };
+        let keys = reader.u16()?;
+        let depth = reader.u8()?;
+        let stack = (0..depth).map(|_| reader.u16()).collect::<Result<_, _>>()?;
+        let quirk_bits = reader.u8()?;
+        let mut quirks = Quirks::default();
+        for (bit, (name, _)) in Quirks::default().flags().iter().enumerate() {
+            quirks.set_flag(name, quirk_bits & (1 << bit) != 0);
+        }
+        let mut random_state = 0;
+        if version >= 2 {
+            quirks.random = match reader.u8()? {
+                0 => RandomModel::Entropy,
+                1 => RandomModel::Seeded(u64::from_be_bytes(reader.array()?)),
+                2 => RandomModel::Vip,
+                _ => return Err(StateError::Corrupt),
+            };
+            random_state = u64::from_be_bytes(reader.array()?);
+        }
+        let ram = unpack(reader.chunk()?)?;
+        let screen = unpack(reader.chunk()?)?;
+        let (mut i_high, mut extended, mut megachip) = (0, Vec::new(), None);
+        if version >= 4 {
+            i_high = reader.u8()?;
+            extended = unpack(reader.chunk()?)?;
+            megachip = match reader.u8()? {
+                0 => None,
+                1 => Some(Box::new(reader.megachip()?)),
+                _ => return Err(StateError::Corrupt),
+            };
+        }
+        if !reader.0.is_empty() {
+            return Err(StateError::Corrupt);
+        }
+
+        let state = Self {
+            state,
+            program_counter,
+            ram,
+            screen,
+            hires: flags & 1 != 0,
+            two_page: flags & 4 != 0,
+            planes,
+            audio_pattern,
+            pitch,
+            exited: flags & 2 != 0,
+            v_registers,
+            i_register,
+            stack,
+            keys: core::array::from_fn(|key| keys & (1 << key) != 0),
+            delay_timer,
+            sound_timer,
+            quirks,
+            random_state,
+            i_high,
+            extended,
+            megachip,
+        };
+        state.check()?;
+        Ok(state)
+    }
+
+    //Values no emulator could have saved: a key wait into a register past VF, a key past F, planes past the
+    //two XO-CHIP ones, more memory than a 24 bit I reaches or MegaChip screens and sprites of the wrong size
+    //Emulator::load_state checks it too, for States from serde or by hand
+    pub(crate) fn check(&self) -> Result<(), StateError> {
+        let registers_ok = match self.state {
+            EmulatorState::WaitingForKey { dest_register } => dest_register <= 0xF,
+            EmulatorState::WaitingForRelease { dest_register, key } => dest_register <= 0xF && key <= 0xF,
+            _ => true,
+        };
+        let planes_ok = self.planes & !ALL_PLANES == 0 && self.screen.iter().all(|&pixel| pixel & !ALL_PLANES == 0);
+        let memory_ok = self.ram.len() + self.extended.len() <= MEGA_MEMORY_SIZE;
+        let megachip_ok = self.megachip.as_deref().is_none_or(|mega| {
+            [mega.front.len(), mega.back.len(), mega.indices.len()] == [SCREEN_SIZE; 3]
+                && (1..=256).contains(&mega.sprite_width)
+                && (1..=256).contains(&mega.sprite_height)
+        });
+        if registers_ok && planes_ok && memory_ok && megachip_ok { Ok(()) } else { Err(StateError::Corrupt) }
+    }
+}
+
+#[cfg(feature = "serde")]
+fn default_pitch() -> u8 {
+    DEFAULT_PITCH
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], StateError> {
+        if self.0.len() < len {
+            return Err(StateError::Truncated);
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, StateError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, StateError> {
+        Ok(u16::from_be_bytes(self.array()?))
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], StateError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn chunk(&mut self) -> Result<&'a [u8], StateError> {
+        let len = u32::from_be_bytes(self.array()?);
+        self.take(len as usize)
+    }
+
+    fn colors(&mut self) -> Result<Vec<[u8; 4]>, StateError> {
+        Ok(unpack(self.chunk()?)?.chunks_exact(4).map(|rgba| rgba.try_into().unwrap()).collect())
+    }
+
+    fn megachip(&mut self) -> Result<MegaChip, StateError> {
+        let front = self.colors()?;
+        let back = self.colors()?;
+        let indices = unpack(self.chunk()?)?;
+        let mut palette = [[0; 4]; 256];
+        for color in &mut palette {
+            *color = self.array()?;
+        }
+        let sprite_width = self.u16()? as usize;
+        let sprite_height = self.u16()? as usize;
+        let [alpha, blend, collision_color] = self.array()?;
+        let sample = match self.u8()? {
+            0 => None,
+            1 => Some(Sample { rate: self.u16()?, looping: self.u8()? != 0, data: unpack(self.chunk()?)? }),
+            _ => return Err(StateError::Corrupt),
+        };
+        Ok(MegaChip { front, back, indices, palette, sprite_width, sprite_height, alpha, blend: BlendMode::from_n(blend), collision_color, sample })
+    }
+}
+
+//A u32 length and the run-length encoded bytes
+fn chunk(bytes: &mut Vec<u8>, data: &[u8]) {
+    let packed = pack(data);
+    bytes.extend_from_slice(&(packed.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(&packed);
+}
+
+//Zero runs become 0x00 and the run length minus one, everything else is copied
+fn pack(data: &[u8]) -> Vec<u8> {
+    let mut packed = Vec::new();
+    let mut i = 0;
+    while i < data.len() {
+        if data[i] != 0 {
+            packed.push(data[i]);
+            i += 1;
+            continue;
+        }
+        let run = data[i..].iter().take(256).take_while(|&&byte| byte == 0).count();
+        packed.extend_from_slice(&[0, (run - 1) as u8]);
+        i += run;
+    }
+    packed
+}
+
+fn unpack(packed: &[u8]) -> Result<Vec<u8>, StateError> {
+    let mut data = Vec::new();
+    let mut bytes = packed.iter();
+    while let Some(&byte) = bytes.next() {
+        match byte {
+            0 => data.resize(data.len() + *bytes.next().ok_or(StateError::Corrupt)? as usize + 1, 0),
+            byte => data.push(byte),
+        }
+    }
+    Ok(data)
+}
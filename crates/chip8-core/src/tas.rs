@@ -0,0 +1,219 @@
+use crate::chip8::Emulator;
+use crate::error::ReplayError;
+use crate::input::Key;
+use crate::quirks::{Quirks, RandomModel};
+use crate::romdb::rom_hash;
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+const INPUT_LOG_HEADER: &str = "chip8-inputs 1";
+const SESSION_HEADER: &str = "chip8-session 1";
+
+//A key change and the tick it happened before, counted from when recording started
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputEvent {
+    pub tick: u64,
+    pub key: Key,
+    pub pressed: bool,
+}
+
+//Key changes to the tick, for tool-assisted runs and reproducible bug reports. Unlike a Replay it keeps
+//no random bytes: play it back with the same seed (Emulator::set_seed) for the same CXNN results
+//Text format, one event per line after the header: "1234 3+" presses key 3 before tick 1234
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputLog {
+    //In tick order
+    pub events: Vec<InputEvent>,
+}
+
+impl InputLog {
+    //Ticks until the last event, playback needs at least this many to deliver them all
+    pub fn length(&self) -> u64 {
+        self.events.last().map_or(0, |event| event.tick)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{}\n", INPUT_LOG_HEADER);
+        for event in &self.events {
+            let _ = writeln!(text, "{} {}{}", event.tick, event.key, if event.pressed { '+' } else { '-' });
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ReplayError> {
+        let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line.trim())).filter(|(_, line)| !line.is_empty());
+        match lines.next() {
+            Some((_, INPUT_LOG_HEADER)) => {},
+            Some((line, _)) => return Err(malformed(line, "not an input log (or an unsupported version)".to_string())),
+            None => return Err(malformed(0, "empty input log".to_string())),
+        }
+        let mut events: Vec<InputEvent> = Vec::new();
+        for (line, text) in lines {
+            let invalid = || malformed(line, format!("invalid event '{}'", text));
+            let (tick, input) = text.split_once(' ').ok_or_else(invalid)?;
+            let tick: u64 = tick.parse().map_err(|_| invalid())?;
+            let (key, pressed) = input.split_at(input.len().saturating_sub(1));
+            let key = usize::from_str_radix(key, 16).ok().and_then(Key::from_index).ok_or_else(invalid)?;
+            let pressed = match pressed {
+                "+" => true,
+                "-" => false,
+                _ => return Err(invalid()),
+            };
+            if events.last().is_some_and(|last| last.tick > tick) {
+                return Err(malformed(line, "events out of order".to_string()));
+            }
+            events.push(InputEvent { tick, key, pressed });
+        }
+        Ok(Self { events })
+    }
+}
+
+//Records the key changes sent through it with the tick they happened on. Route the frontend's
+//keypress calls through it and run frames as usual; a reset while recording throws the tick count off
+#[derive(Debug, Clone)]
+pub struct InputRecorder {
+    //Emulator::tick_count when recording started
+    start: u64,
+    log: InputLog,
+}
+
+impl InputRecorder {
+    pub fn new(emulator: &Emulator) -> Self {
+        Self { start: emulator.tick_count(), log: InputLog::default() }
+    }
+
+    pub fn keypress(&mut self, emulator: &mut Emulator, key: Key, pressed: bool) {
+        let tick = emulator.tick_count() - self.start;
+        self.log.events.push(InputEvent { tick, key, pressed });
+        emulator.keypress(key, pressed);
+    }
+
+    //Recorded so far
+    pub fn log(&self) -> &InputLog {
+        &self.log
+    }
+
+    pub fn finish(self) -> InputLog {
+        self.log
+    }
+
+    //The run so far as a Session to verify later, None unless emulator draws its random numbers from a
+    //seed (Emulator::set_seed), the only way CXNN comes out the same again
+    //Only meaningful if recording started straight after loading rom
+    pub fn session(&self, emulator: &Emulator, rom: &[u8]) -> Option<Session> {
+        let RandomModel::Seeded(seed) = emulator.quirks().random else {
+            return None;
+        };
+        Some(Session {
+            rom_sha1: rom_hash(rom),
+            seed,
+            quirks: *emulator.quirks(),
+            ticks_per_frame: emulator.ticks_per_frame(),
+            ticks: emulator.tick_count() - self.start,
+            inputs: self.log.clone(),
+            state_hash: emulator.state_hash(),
+        })
+    }
+}
+
+//A run to check the emulator against: a ROM, a seed and the inputs, and the state they led to
+//Record one with InputRecorder::session, then verify it after a change to catch any difference
+//Text format: a short header, then the input log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    pub rom_sha1: String,
+    pub seed: u64,
+    pub quirks: Quirks,
+    pub ticks_per_frame: usize,
+    //Ticks the run lasted, whole frames when recorded with run_frame
+    pub ticks: u64,
+    pub inputs: InputLog,
+    //Emulator::state_hash at the end
+    pub state_hash: String,
+}
+
+impl Session {
+    //Run the session on a fresh emulator, frame by frame until it has run as many ticks as recorded
+    pub fn play(&self, rom: &[u8]) -> Result<Emulator, ReplayError> {
+        if rom_hash(rom) != self.rom_sha1 {
+            return Err(ReplayError::RomMismatch { expected: self.rom_sha1.clone() });
+        }
+        let mut emulator = Emulator::new();
+        emulator.set_quirks(self.quirks);
+        emulator.set_seed(self.seed);
+        emulator.set_ticks_per_frame(self.ticks_per_frame);
+        emulator.load_rom(rom).map_err(ReplayError::Rom)?;
+        emulator.play_inputs(&self.inputs);
+        while emulator.tick_count() < self.ticks {
+            emulator.run_frame().map_err(ReplayError::Fault)?;
+        }
+        Ok(emulator)
+    }
+
+    //Ok if the run ends in the recorded state, ReplayError::Diverged if not
+    pub fn verify(&self, rom: &[u8]) -> Result<(), ReplayError> {
+        let actual = self.play(rom)?.state_hash();
+        if actual != self.state_hash {
+            return Err(ReplayError::Diverged { expected: self.state_hash.clone(), actual });
+        }
+        Ok(())
+    }
+
+    pub fn to_text(&self) -> String {
+        let quirks: Vec<&str> = self.quirks.flags().iter().filter(|(_, on)| *on).map(|(name, _)| *name).collect();
+        format!(
+            "{}\nrom {}\nseed {}\nquirks {}\nticks-per-frame {}\nticks {}\nstate {}\n{}",
+            SESSION_HEADER,
+            self.rom_sha1,
+            self.seed,
+            quirks.join(","),
+            self.ticks_per_frame,
+            self.ticks,
+            self.state_hash,
+            self.inputs.to_text()
+        )
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ReplayError> {
+        let lines: Vec<&str> = text.lines().collect();
+        if lines.first().map(|line| line.trim()) != Some(SESSION_HEADER) {
+            return Err(malformed(1, "not a session (or an unsupported version)".to_string()));
+        }
+        let header = |line: usize, name: &str| -> Result<String, ReplayError> {
+            let text = lines.get(line - 1).ok_or_else(|| malformed(0, "unexpected end of session".to_string()))?;
+            match text.trim().strip_prefix(name).and_then(|rest| rest.strip_prefix(' ').or(Some(rest).filter(|r| r.is_empty()))) {
+                Some(value) => Ok(value.to_string()),
+                None => Err(malformed(line, format!("expected '{}'", name))),
+            }
+        };
+        let number = |line: usize, name: &str| -> Result<u64, ReplayError> {
+            let value = header(line, name)?;
+            value.parse().map_err(|_| malformed(line, format!("invalid {} '{}'", name, value)))
+        };
+        let rom_sha1 = header(2, "rom")?;
+        let seed = number(3, "seed")?;
+        let mut quirks = Quirks::default();
+        for name in header(4, "quirks")?.split(',').filter(|name| !name.is_empty()) {
+            if !quirks.set_flag(name, true) {
+                return Err(malformed(4, format!("unknown quirk '{}'", name)));
+            }
+        }
+        quirks.random = RandomModel::Seeded(seed);
+        let ticks_per_frame = number(5, "ticks-per-frame")? as usize;
+        let ticks = number(6, "ticks")?;
+        let state_hash = header(7, "state")?;
+        //Line numbers in errors count from the start of the session
+        let inputs = InputLog::parse(&lines[7.min(lines.len())..].join("\n")).map_err(|error| match error {
+            ReplayError::Malformed { line, message } if line > 0 => malformed(line + 7, message),
+            error => error,
+        })?;
+        Ok(Self { rom_sha1, seed, quirks, ticks_per_frame, ticks, inputs, state_hash })
+    }
+}
+
+fn malformed(line: usize, message: String) -> ReplayError {
+    ReplayError::Malformed { line, message }
+}
@@ -0,0 +1,67 @@
+//How run_frame decides how much a frame runs: a fixed number of instructions, or the COSMAC VIP's
+//machine cycles with each instruction costing roughly what it did in the original interpreter
+//The VIP figures are estimates from the interpreter's code paths, close enough for music and demo
+//ROMs that pace themselves by instruction speed, not exact to the cycle
+
+use crate::instruction::Instruction;
+
+//1.76 MHz / 8 clocks a machine cycle / 60 Hz
+pub const VIP_CYCLES_PER_FRAME: u32 = 3668;
+//The CDP1861 takes one cycle a byte for 128 lines of 8 bytes, plus the interrupt routine around it
+pub const VIP_DISPLAY_CYCLES: u32 = 1024 + 96;
+//Left to the interpreter each frame
+pub const VIP_FREE_CYCLES: u32 = VIP_CYCLES_PER_FRAME - VIP_DISPLAY_CYCLES;
+//Fetching and dispatching an instruction, on top of what it costs to execute
+const VIP_FETCH_CYCLES: u32 = 40;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Timing {
+    //ticks_per_frame instructions a frame whatever they are
+    #[default]
+    Instructions,
+    //VIP_FREE_CYCLES machine cycles a frame, an instruction that runs over borrows from the next frame
+    Vip,
+}
+
+//Machine cycles the VIP interpreter takes for instruction, fetch included
+//Skips cost the same taken or not, sprites grow with their rows, FX55/FX65 with their registers
+pub fn vip_cycles(instruction: &Instruction) -> u32 {
+    let execute = match *instruction {
+        Instruction::ClearScreen => 800,
+        Instruction::Return | Instruction::Jump(_) | Instruction::LoadI(_) => 12,
+        Instruction::Call(_) | Instruction::JumpV0(_) => 24,
+        Instruction::SkipEqImm { .. }
+        | Instruction::SkipNeImm { .. }
+        | Instruction::SkipEqReg { .. }
+        | Instruction::SkipNeReg { .. } => 14,
+        Instruction::LoadImm { .. } => 6,
+        Instruction::AddImm { .. } | Instruction::LoadDelay { .. } | Instruction::SetDelay { .. } | Instruction::SetSound { .. } => 10,
+        //The VIP builds and runs a little machine code routine for each ALU operation
+        Instruction::LoadReg { .. }
+        | Instruction::Or { .. }
+        | Instruction::And { .. }
+        | Instruction::Xor { .. }
+        | Instruction::AddReg { .. }
+        | Instruction::SubReg { .. }
+        | Instruction::ShiftRight { .. }
+        | Instruction::SubN { .. }
+        | Instruction::ShiftLeft { .. } => 44,
+        Instruction::Random { .. } => 36,
+        Instruction::Draw { n, .. } => 170 + 120 * n.max(1) as u32,
+        Instruction::SkipKeyPressed { .. } | Instruction::SkipKeyNotPressed { .. } => 18,
+        Instruction::WaitKey { .. } => 10,
+        Instruction::AddI { .. } => 16,
+        Instruction::LoadFont { .. } => 20,
+        Instruction::StoreBcd { .. } => 180,
+        Instruction::StoreRegs { x } | Instruction::LoadRegs { x } => 14 + 14 * (x as u32 + 1),
+        //Not VIP instructions, as cheap as a register load
+        _ => 6,
+    };
+    VIP_FETCH_CYCLES + execute
+}
+
+//One pass of the interpreter's wait loop, what a tick costs while FX0A waits or the emulator is paused
+pub(crate) fn vip_idle_cycles() -> u32 {
+    vip_cycles(&Instruction::WaitKey { x: 0 })
+}
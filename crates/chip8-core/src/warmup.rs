@@ -0,0 +1,66 @@
+use crate::chip8::{Emulator, EmulatorState};
+use crate::error::Chip8Error;
+
+use core::time::Duration;
+
+//Frames the screen has to stay the same (and not blank) to count as settled, half a second
+const STABLE_FRAMES: u32 = 30;
+
+//Why warm_up stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarmUpOutcome {
+    //Something is on screen and it stopped changing
+    Stable,
+    //FX0A is waiting, the screen cannot change until a key is pressed
+    WaitingForKey,
+    //The program ran 00FD
+    Exited,
+    FrameLimit,
+    TimeLimit,
+    Fault(Chip8Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WarmUp {
+    pub outcome: WarmUpOutcome,
+    pub frames: u32,
+}
+
+impl Emulator {
+    //Run headless until the screen settles or a limit is hit, e.g. to grab a title screen
+    //No input is given, frames are run as fast as possible
+    pub fn warm_up(&mut self, max_frames: u32, max_millis: u64) -> WarmUp {
+        let deadline = self.time_source().now() + Duration::from_millis(max_millis);
+        let mut previous = self.get_screen();
+        let mut unchanged = 0;
+
+        for frames in 0..max_frames {
+            let outcome = if let Err(error) = self.run_frame() {
+                Some(WarmUpOutcome::Fault(error))
+            } else if self.has_exited() {
+                Some(WarmUpOutcome::Exited)
+            } else if self.state() != EmulatorState::Running {
+                Some(WarmUpOutcome::WaitingForKey)
+            } else {
+                let screen = self.get_screen();
+                if screen == previous && screen.iter().any(|&pixel| pixel != 0) {
+                    unchanged += 1;
+                } else {
+                    unchanged = 0;
+                    previous = screen;
+                }
+                if unchanged >= STABLE_FRAMES {
+                    Some(WarmUpOutcome::Stable)
+                } else if self.time_source().now() >= deadline {
+                    Some(WarmUpOutcome::TimeLimit)
+                } else {
+                    None
+                }
+            };
+            if let Some(outcome) = outcome {
+                return WarmUp { outcome, frames: frames + 1 };
+            }
+        }
+        WarmUp { outcome: WarmUpOutcome::FrameLimit, frames: max_frames }
+    }
+}
@@ -0,0 +1,126 @@
+use crate::chip8::Emulator;
+use crate::clock::TimeSource;
+use crate::framebuffer::Palette;
+use crate::input::Key;
+use crate::romdb::RomSettings;
+
+use wasm_bindgen::prelude::*;
+
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use std::time::Duration;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = performance, js_name = now)]
+    fn performance_now() -> f64;
+}
+
+//The page's clock for metrics and profiling, SystemClock reads zero here. The page paces frames
+//(requestAnimationFrame), sleeping returns at once
+struct PerformanceClock;
+
+impl TimeSource for PerformanceClock {
+    fn now(&self) -> Duration {
+        Duration::from_secs_f64(performance_now().max(0.0) / 1000.0)
+    }
+
+    fn sleep(&self, _duration: Duration) {}
+}
+
+//The emulator as a JavaScript class, for wasm-pack builds with the wasm feature:
+//const chip8 = new Chip8(); chip8.loadRom(bytes);
+//then on every animation frame chip8.runFrame() and put chip8.rgba() into an ImageData of width() x height()
+#[wasm_bindgen(js_name = Chip8)]
+pub struct WasmEmulator {
+    emulator: Emulator,
+    palette: Palette,
+}
+
+#[wasm_bindgen(js_class = Chip8)]
+impl WasmEmulator {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        let mut emulator = Emulator::new();
+        emulator.set_time_source(PerformanceClock);
+        Self { emulator, palette: Palette::default() }
+    }
+
+    //rom is a Uint8Array. XO-CHIP ROMs get 64 KB of RAM, see RomSettings::detect
+    #[wasm_bindgen(js_name = loadRom)]
+    pub fn load_rom(&mut self, rom: &[u8]) -> Result<(), JsError> {
+        RomSettings::detect(rom).apply(&mut self.emulator);
+        self.emulator.load_rom(rom).map_err(|err| JsError::new(&err.to_string()))
+    }
+
+    //Back to a blank machine, load the ROM again afterwards
+    pub fn reset(&mut self) {
+        self.emulator.reset();
+    }
+
+    //Start rom over from scratch, the settings stay
+    #[wasm_bindgen(js_name = reloadRom)]
+    pub fn reload_rom(&mut self, rom: &[u8]) -> Result<(), JsError> {
+        self.emulator.reload_rom(rom).map_err(|err| JsError::new(&err.to_string()))
+    }
+
+    pub fn tick(&mut self) -> Result<(), JsError> {
+        self.emulator.tick().map_err(|err| JsError::new(&err.to_string()))
+    }
+
+    //One 60 Hz frame, true if the screen changed and is worth redrawing
+    #[wasm_bindgen(js_name = runFrame)]
+    pub fn run_frame(&mut self) -> Result<bool, JsError> {
+        let output = self.emulator.run_frame().map_err(|err| JsError::new(&err.to_string()))?;
+        Ok(output.screen_changed)
+    }
+
+    pub fn width(&self) -> usize {
+        self.emulator.screen_width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.emulator.screen_height()
+    }
+
+    //The screen as RGBA bytes, a new Uint8Array of width() * height() * 4
+    pub fn rgba(&self) -> Vec<u8> {
+        let mut rgba = vec![0; self.width() * self.height() * 4];
+        self.emulator.render_rgba(&mut rgba, &self.palette);
+        rgba
+    }
+
+    //Colors as 0xRRGGBB, plane 2 and overlapping pixels get shades in between
+    #[wasm_bindgen(js_name = setColors)]
+    pub fn set_colors(&mut self, background: u32, foreground: u32) {
+        let rgba = |color: u32| [(color >> 16) as u8, (color >> 8) as u8, color as u8, 255];
+        self.palette = Palette::new(rgba(background), rgba(foreground));
+    }
+
+    //Keys are 0-15, anything else is ignored
+    #[wasm_bindgen(js_name = keyDown)]
+    pub fn key_down(&mut self, key: u8) {
+        if let Some(key) = Key::from_index(key as usize) {
+            self.emulator.keypress(key, true);
+        }
+    }
+
+    #[wasm_bindgen(js_name = keyUp)]
+    pub fn key_up(&mut self, key: u8) {
+        if let Some(key) = Key::from_index(key as usize) {
+            self.emulator.keypress(key, false);
+        }
+    }
+
+    //The sound timer is running, play a tone while this is true
+    pub fn sound(&self) -> bool {
+        self.emulator.sound_timer() > 0
+    }
+}
+
+impl Default for WasmEmulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
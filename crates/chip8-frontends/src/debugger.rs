@@ -0,0 +1,351 @@
+//The debugger in the terminal: panes in columns as the session's Layout has them, starting paused.
+//F5 (or Space) runs and pauses, F11 steps, F10 steps over a call and F12 out of one. F9 sets or clears
+//a breakpoint at the disassembly cursor, F8 a watchpoint at the memory cursor. Tab picks a pane, Alt and
+//the arrows move it, Alt+C closes it and Alt+O opens a closed one in its column. With the memory pane
+//picked and the program paused hex digits edit memory, otherwise the keyboard is the keypad as in the TUI
+
+use crate::finished;
+use crate::tui::{is_quit, screen_lines, Keypad, Terminal};
+
+use chip8_core::{Break, Chip8Error, Clock, DebugSession, Emulator, EmulatorState, Pane, RunError};
+
+use crossterm::cursor;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::queue;
+use crossterm::style::Print;
+use crossterm::terminal;
+
+use std::io::{self, Write};
+use std::time::Duration;
+
+//Bytes on a line of the memory pane
+const MEMORY_ROW: u16 = 8;
+//The most ticks step over and step out run before giving up
+const MAX_STEP_TICKS: usize = 1_000_000;
+//Rows of the sprite pane, as many as DXYN draws
+const SPRITE_ROWS: u16 = 15;
+const HELP: &str = "F5 run  F11 step  F10 over  F12 out  F9 break  F8 watch  Tab pane  Alt+arrows move  Alt+C close  Alt+O open  Esc quit";
+
+struct Debugger {
+    focus: Pane,
+    running: bool,
+    //Why it last stopped, on the status line
+    status: String,
+    //The disassembly pane's cursor, the PC whenever the program stops
+    code_cursor: u16,
+    //The high digit of a byte being typed into memory
+    nibble: Option<u8>,
+}
+
+//Until Esc or Ctrl+C. The session's breakpoints are set first, and what they are at the end is put back
+//in it along with the layout
+pub(crate) fn run_debugger(emulator: &mut Emulator, session: &mut DebugSession) -> Result<(), RunError> {
+    session.apply(emulator);
+    let pc = emulator.program_counter();
+    let focus = session.layout.columns()[0][0];
+    let mut debugger = Debugger { focus, running: false, status: format!("Paused at {:#05X}", pc), code_cursor: pc, nibble: None };
+    let result = debug(emulator, session, &mut debugger);
+    session.record(emulator);
+    result
+}
+
+fn debug(emulator: &mut Emulator, session: &mut DebugSession, debugger: &mut Debugger) -> Result<(), RunError> {
+    let terminal = Terminal::enter()?;
+    let mut keypad = Keypad::new(&terminal);
+    let mut stdout = io::stdout().lock();
+    let mut clock = Clock::default();
+    let mut redraw = true;
+
+    loop {
+        clock.wait(emulator);
+        while event::poll(Duration::ZERO)? {
+            let event = event::read()?;
+            redraw = true;
+            let Event::Key(input) = event else { continue };
+            if is_quit(&input) {
+                return Ok(());
+            }
+            if input.kind == KeyEventKind::Release || !debugger.command(emulator, session, &input) {
+                keypad.input(emulator, &input);
+            }
+        }
+        keypad.frame(emulator);
+        if debugger.running {
+            debugger.run_frame(emulator);
+            redraw = true;
+        }
+        if redraw {
+            draw(emulator, session, debugger, &mut stdout)?;
+            redraw = false;
+        }
+    }
+}
+
+impl Debugger {
+    //Handles input meant for the debugger, false for the keypad's
+    fn command(&mut self, emulator: &mut Emulator, session: &mut DebugSession, input: &KeyEvent) -> bool {
+        let paused = !self.running;
+        if input.modifiers.contains(KeyModifiers::ALT) {
+            let layout = &mut session.layout;
+            match input.code {
+                KeyCode::Left => layout.move_across(self.focus, -1),
+                KeyCode::Right => layout.move_across(self.focus, 1),
+                KeyCode::Up => layout.move_within(self.focus, -1),
+                KeyCode::Down => layout.move_within(self.focus, 1),
+                KeyCode::Char('c') if layout.close(self.focus) => self.focus = layout.columns()[0][0],
+                KeyCode::Char('o') => {
+                    if let (Some(&pane), Some((column, _))) = (layout.hidden().first(), layout.position(self.focus)) {
+                        layout.open(pane, column);
+                        self.focus = pane;
+                    }
+                },
+                _ => return false,
+            }
+            return true;
+        }
+        match input.code {
+            KeyCode::F(5) | KeyCode::Char(' ') => {
+                self.running = !self.running;
+                self.nibble = None;
+                if self.running {
+                    self.status = "Running".to_string();
+                } else {
+                    self.stopped(emulator, Ok(None));
+                }
+            },
+            KeyCode::F(11) if paused => {
+                let result = emulator.step_into();
+                self.stopped(emulator, result);
+            },
+            KeyCode::F(10) if paused => {
+                let result = emulator.step_over(MAX_STEP_TICKS);
+                self.stopped(emulator, result);
+            },
+            KeyCode::F(12) if paused => {
+                let result = emulator.step_out(MAX_STEP_TICKS);
+                self.stopped(emulator, result);
+            },
+            KeyCode::F(9) => {
+                let pc = self.code_cursor;
+                if emulator.breakpoints().pcs().any(|address| address == pc) {
+                    emulator.clear_breakpoint(pc);
+                } else {
+                    emulator.set_breakpoint(pc);
+                }
+            },
+            KeyCode::F(8) => {
+                let address = session.memory_address;
+                if emulator.breakpoints().watchpoints().any(|watched| watched == address) {
+                    emulator.clear_watchpoint(address);
+                } else {
+                    emulator.set_watchpoint(address);
+                }
+            },
+            KeyCode::Tab | KeyCode::BackTab => {
+                let panes: Vec<Pane> = session.layout.columns().iter().flatten().copied().collect();
+                let index = panes.iter().position(|&pane| pane == self.focus).unwrap_or(0);
+                let step = if input.code == KeyCode::Tab { 1 } else { panes.len() - 1 };
+                self.focus = panes[(index + step) % panes.len()];
+                self.nibble = None;
+            },
+            KeyCode::Up | KeyCode::Down | KeyCode::Left | KeyCode::Right | KeyCode::PageUp | KeyCode::PageDown => {
+                self.scroll(session, input.code);
+            },
+            KeyCode::Char(digit) if paused && self.focus == Pane::Memory && digit.is_ascii_hexdigit() => {
+                let digit = digit.to_digit(16).unwrap_or(0) as u8;
+                match self.nibble.take() {
+                    None => self.nibble = Some(digit),
+                    Some(high) => {
+                        emulator.poke(session.memory_address, high << 4 | digit);
+                        session.memory_address = session.memory_address.wrapping_add(1);
+                    },
+                }
+            },
+            _ => return false,
+        }
+        true
+    }
+
+    //The arrows move the cursor of the disassembly or memory pane, pages a screenful of either
+    fn scroll(&mut self, session: &mut DebugSession, key: KeyCode) {
+        let (line, page) = match self.focus {
+            Pane::Disassembly => (2, 32),
+            Pane::Memory => (MEMORY_ROW, MEMORY_ROW * 16),
+            _ => return,
+        };
+        let step = match key {
+            KeyCode::Up => line.wrapping_neg(),
+            KeyCode::Down => line,
+            KeyCode::Left => 1u16.wrapping_neg(),
+            KeyCode::Right => 1,
+            KeyCode::PageUp => page.wrapping_neg(),
+            KeyCode::PageDown => page,
+            _ => return,
+        };
+        self.nibble = None;
+        match self.focus {
+            Pane::Memory => session.memory_address = session.memory_address.wrapping_add(step),
+            _ if matches!(key, KeyCode::Left | KeyCode::Right) => {},
+            _ => self.code_cursor = self.code_cursor.wrapping_add(step),
+        }
+    }
+
+    //A frame's ticks, stopping at a break, a fault or the end of the program
+    fn run_frame(&mut self, emulator: &mut Emulator) {
+        let result = emulator.run_to_frame_end(emulator.ticks_per_frame());
+        self.code_cursor = emulator.program_counter();
+        if !matches!(result, Ok(None)) || finished(emulator) {
+            self.stopped(emulator, result);
+        }
+    }
+
+    fn stopped(&mut self, emulator: &Emulator, result: Result<Option<Break>, Chip8Error>) {
+        self.running = false;
+        self.code_cursor = emulator.program_counter();
+        self.status = match result {
+            Ok(Some(hit)) => hit.to_string(),
+            Ok(None) => format!("Paused at {:#05X}", emulator.program_counter()),
+            Err(error) => format!("Halted: {}", error),
+        };
+    }
+}
+
+fn draw(emulator: &Emulator, session: &DebugSession, debugger: &Debugger, out: &mut impl Write) -> io::Result<()> {
+    let (width, height) = terminal::size()?;
+    let (width, height) = (width as usize, (height as usize).max(2));
+    let columns: Vec<Vec<String>> = session.layout.columns().iter().map(|panes| {
+        let column_width = panes.iter().map(|&pane| natural_width(pane, emulator)).max().unwrap_or(0);
+        let mut lines = Vec::new();
+        for (index, &pane) in panes.iter().enumerate() {
+            //The last pane takes what is left over
+            let rows = if index + 1 == panes.len() { height - 1 - lines.len() } else { (height - 1) / panes.len() };
+            let fill = if pane == debugger.focus { '═' } else { '─' };
+            lines.push(fit(&format!("{} {} {}", fill, title(pane), String::from(fill).repeat(column_width)), column_width));
+            let content = pane_lines(pane, emulator, session, debugger, rows.saturating_sub(1));
+            lines.extend(content.iter().chain(std::iter::repeat(&String::new())).take(rows.saturating_sub(1)).map(|line| fit(line, column_width)));
+        }
+        lines
+    }).collect();
+
+    for row in 0..height - 1 {
+        let line: Vec<&str> = columns.iter().map(|lines| lines.get(row).map_or("", String::as_str)).collect();
+        queue!(out, cursor::MoveTo(0, row as u16), Print(fit(&line.join("│"), width)))?;
+    }
+    let status = format!("{}  |  {}", debugger.status, HELP);
+    queue!(out, cursor::MoveTo(0, height as u16 - 1), Print(fit(&status, width)))?;
+    out.flush()
+}
+
+//line cut or padded with spaces to width characters
+fn fit(line: &str, width: usize) -> String {
+    let line: String = line.chars().take(width).collect();
+    format!("{:<width$}", line)
+}
+
+fn title(pane: Pane) -> &'static str {
+    match pane {
+        Pane::Screen => "Screen",
+        Pane::Registers => "Registers",
+        Pane::Disassembly => "Disassembly",
+        Pane::Memory => "Memory",
+        Pane::Trace => "Trace",
+        Pane::Sprite => "Sprite at I",
+    }
+}
+
+//Columns a pane needs to show a whole line
+fn natural_width(pane: Pane, emulator: &Emulator) -> usize {
+    match pane {
+        Pane::Screen => emulator.frame_buffer().width(),
+        Pane::Registers => 24,
+        Pane::Disassembly => 32,
+        //Address, the bytes and the same as text
+        Pane::Memory => 5 + MEMORY_ROW as usize * 3 + 2 + MEMORY_ROW as usize,
+        Pane::Trace => 28,
+        Pane::Sprite => 16,
+    }
+}
+
+//Up to rows lines of a pane's contents
+fn pane_lines(pane: Pane, emulator: &Emulator, session: &DebugSession, debugger: &Debugger, rows: usize) -> Vec<String> {
+    match pane {
+        Pane::Screen => screen_lines(emulator),
+        Pane::Registers => registers(emulator),
+        Pane::Disassembly => disassembly(emulator, debugger.code_cursor, rows),
+        Pane::Memory => memory(emulator, session.memory_address, debugger.nibble, rows),
+        Pane::Trace => {
+            let pcs = emulator.recent_pcs();
+            pcs[pcs.len().saturating_sub(rows)..].iter().map(|&pc| {
+                emulator.disassemble(pc..pc.saturating_add(2)).first().map_or_else(String::new, |line| line.to_string())
+            }).collect()
+        },
+        Pane::Sprite => {
+            let i = emulator.i_register();
+            (0..SPRITE_ROWS.min(rows as u16)).map(|row| {
+                let address = i.wrapping_add(row);
+                let byte = emulator.peek(address);
+                let pixels: String = (0..8).rev().map(|bit| if byte >> bit & 1 != 0 { '█' } else { '·' }).collect();
+                format!("{:04X} {:02X} {}", address, byte, pixels)
+            }).collect()
+        },
+    }
+}
+
+fn registers(emulator: &Emulator) -> Vec<String> {
+    let mut lines = vec![format!("PC {:#05X}  I {:#05X}", emulator.program_counter(), emulator.i_register())];
+    for row in emulator.v_registers().chunks(4).enumerate().map(|(row, registers)| {
+        registers.iter().enumerate().map(|(n, v)| format!("V{:X} {:02X}", row * 4 + n, v)).collect::<Vec<_>>().join(" ")
+    }) {
+        lines.push(row);
+    }
+    lines.push(format!("DT {:02X}  ST {:02X}", emulator.delay_timer(), emulator.sound_timer()));
+    let stack: Vec<String> = emulator.stack().iter().map(|address| format!("{:03X}", address)).collect();
+    lines.push(format!("Stack {}", if stack.is_empty() { "empty".to_string() } else { stack.join(" ") }));
+    lines.push(match emulator.state() {
+        EmulatorState::Running => "Running".to_string(),
+        EmulatorState::WaitingForKey { dest_register } => format!("Waiting for a key, V{:X}", dest_register),
+        EmulatorState::WaitingForRelease { dest_register, key } => format!("Waiting for {:X} up, V{:X}", key, dest_register),
+        EmulatorState::Paused => "Paused".to_string(),
+        EmulatorState::Halted { error } => format!("Halted: {}", error),
+        EmulatorState::Finished { reason, .. } => format!("Finished: {}", reason),
+    });
+    lines
+}
+
+//A third of the lines before the cursor. * is a breakpoint, > the PC and » the cursor
+fn disassembly(emulator: &Emulator, cursor: u16, rows: usize) -> Vec<String> {
+    let start = cursor.saturating_sub(rows as u16 / 3 * 2);
+    let pc = emulator.program_counter();
+    emulator.disassemble(start..start.saturating_add(rows as u16 * 4)).iter().take(rows).map(|line| {
+        let breakpoint = emulator.breakpoints().pcs().any(|address| address == line.address);
+        format!(
+            "{}{}{} {}",
+            if breakpoint { '*' } else { ' ' },
+            if line.address == pc { '>' } else { ' ' },
+            if line.address == cursor { '»' } else { ' ' },
+            line
+        )
+    }).collect()
+}
+
+//Rows of MEMORY_ROW bytes round the cursor, a third of them before it. > marks the cursor and * a
+//watchpoint, a digit and _ a byte half typed
+fn memory(emulator: &Emulator, cursor: u16, nibble: Option<u8>, rows: usize) -> Vec<String> {
+    let top = (cursor - cursor % MEMORY_ROW).wrapping_sub(rows as u16 / 3 * MEMORY_ROW);
+    (0..rows as u16).map(|row| {
+        let start = top.wrapping_add(row * MEMORY_ROW);
+        let mut line = format!("{:04X}", start);
+        let mut text = String::new();
+        for address in (0..MEMORY_ROW).map(|offset| start.wrapping_add(offset)) {
+            let byte = emulator.peek(address);
+            let watched = emulator.breakpoints().watchpoints().any(|watched| watched == address);
+            line.push(if address == cursor { '>' } else if watched { '*' } else { ' ' });
+            match nibble {
+                Some(high) if address == cursor => line.push_str(&format!("{:X}_", high)),
+                _ => line.push_str(&format!("{:02X}", byte)),
+            }
+            text.push(if byte.is_ascii_graphic() { byte as char } else { '.' });
+        }
+        format!("{}  {}", line, text)
+    }).collect()
+}
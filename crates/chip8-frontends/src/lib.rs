@@ -0,0 +1,120 @@
+use chip8_core::{Clock, Emulator, EmulatorState, RomSettings, RunError};
+#[cfg(feature = "tui")]
+use chip8_core::DebugSession;
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+#[cfg(feature = "tui")]
+mod debugger;
+#[cfg(feature = "sdl")]
+mod speaker;
+#[cfg(feature = "tui")]
+mod tui;
+#[cfg(feature = "sdl")]
+mod window;
+
+#[cfg(feature = "sdl")]
+pub use crate::speaker::Speaker;
+#[cfg(feature = "sdl")]
+pub use crate::window::{draw_frame, draw_phosphor, draw_screen, key_for, WindowDisplay};
+
+//Ready made ways to play a ROM, for getting started. Anything fancier drives an Emulator directly:
+//chip8::Chip8::run_rom_file("game.ch8", FrontendChoice::Window)?;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrontendChoice {
+    //SDL window with sound and the keyboard mapped to the hex keypad (see key_for), until it is closed
+    #[cfg(feature = "sdl")]
+    Window,
+    //Draw to stdout as text at 60 FPS for frames frames, no input
+    Terminal { frames: u32 },
+    //Interactive in the terminal: block characters and the keyboard as the keypad, until Esc
+    #[cfg(feature = "tui")]
+    Tui,
+    //Run frames frames as fast as possible without showing anything
+    Headless { frames: u32 },
+}
+
+//Entry points wiring up an Emulator with the default quirks and a frontend
+pub struct Chip8;
+
+impl Chip8 {
+    //Returns the emulator as it was when the frontend stopped (window closed, frames run, program exited)
+    pub fn run_rom_file(path: impl AsRef<Path>, frontend: FrontendChoice) -> Result<Emulator, RunError> {
+        Self::run_rom(&fs::read(path)?, frontend)
+    }
+
+    pub fn run_rom(rom: &[u8], frontend: FrontendChoice) -> Result<Emulator, RunError> {
+        let mut emulator = Emulator::new();
+        //XO-CHIP ROMs need 64 KB, see RomSettings::detect
+        RomSettings::detect(rom).apply(&mut emulator);
+        emulator.load_rom(rom)?;
+        //Each frontend stops, or shows the program finished, instead of running its last loop forever
+        emulator.set_end_detection(true);
+        match frontend {
+            #[cfg(feature = "sdl")]
+            FrontendChoice::Window => window::run_window(&mut emulator)?,
+            FrontendChoice::Terminal { frames } => run_terminal(&mut emulator, frames)?,
+            #[cfg(feature = "tui")]
+            FrontendChoice::Tui => tui::run_tui(&mut emulator)?,
+            FrontendChoice::Headless { frames } => run_headless(&mut emulator, frames)?,
+        }
+        Ok(emulator)
+    }
+
+    //The debugger in the terminal (see debugger.rs), with its layout and breakpoints read from the ROM's
+    //.c8debug file when there is one and saved back to it on the way out
+    #[cfg(feature = "tui")]
+    pub fn debug_rom_file(path: impl AsRef<Path>) -> Result<Emulator, RunError> {
+        let rom = fs::read(path.as_ref())?;
+        let mut emulator = Emulator::new();
+        RomSettings::detect(&rom).apply(&mut emulator);
+        emulator.load_rom(&rom)?;
+        emulator.set_end_detection(true);
+        let session_path = DebugSession::path_for_rom(path.as_ref());
+        let mut session = match fs::read_to_string(&session_path) {
+            Ok(text) => DebugSession::parse(&text).map_err(|error| RunError::Frontend(error.to_string()))?,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => DebugSession::default(),
+            Err(error) => return Err(error.into()),
+        };
+        debugger::run_debugger(&mut emulator, &mut session)?;
+        fs::write(session_path, session.to_text())?;
+        Ok(emulator)
+    }
+}
+
+//Whether the frontend should stop: the program exited or halted
+fn finished(emulator: &Emulator) -> bool {
+    emulator.has_exited() || matches!(emulator.state(), EmulatorState::Halted { .. } | EmulatorState::Finished { .. })
+}
+
+fn run_headless(emulator: &mut Emulator, frames: u32) -> Result<(), RunError> {
+    for _ in 0..frames {
+        emulator.run_frame()?;
+        if finished(emulator) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn run_terminal(emulator: &mut Emulator, frames: u32) -> Result<(), RunError> {
+    let mut stdout = io::stdout().lock();
+    let mut clock = Clock::default();
+    for _ in 0..frames {
+        clock.wait(emulator);
+        emulator.run_frame()?;
+        if emulator.screen_changed() {
+            emulator.take_dirty_rows();
+            //Cursor home, then the screen over the previous one
+            write!(stdout, "\x1B[H{}", emulator.frame_buffer().to_ascii('#', ' '))?;
+            stdout.flush()?;
+        }
+        if finished(emulator) {
+            break;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use chip8_core::{AudioState, Beeper, PatternPlayer};
+
+use sdl2::audio::{AudioCallback, AudioDevice, AudioSpecDesired};
+use sdl2::AudioSubsystem;
+
+use std::sync::{Arc, Mutex};
+
+const SAMPLE_RATE: i32 = 44_100;
+
+//Plays the sound timer through SDL audio: the XO-CHIP pattern once a program loads one, a beep otherwise
+//SDL pulls samples on its own thread, the frontend hands over the emulator's AudioState once a frame
+pub struct Speaker {
+    //Stops playback when dropped
+    _device: AudioDevice<SpeakerCallback>,
+    audio: Arc<Mutex<AudioState>>,
+}
+
+struct SpeakerCallback {
+    beeper: Beeper,
+    player: PatternPlayer,
+    audio: Arc<Mutex<AudioState>>,
+}
+
+impl AudioCallback for SpeakerCallback {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        let audio = *self.audio.lock().unwrap();
+        if audio.has_pattern() {
+            self.player.fill(&audio, out);
+        } else {
+            self.beeper.fill(audio.sound, out);
+        }
+    }
+}
+
+impl Speaker {
+    pub fn open(subsystem: &AudioSubsystem) -> Result<Self, String> {
+        let audio = Arc::new(Mutex::new(AudioState::default()));
+        let desired = AudioSpecDesired { freq: Some(SAMPLE_RATE), channels: Some(1), samples: None };
+        let shared = audio.clone();
+        let device = subsystem.open_playback(None, &desired, |spec| SpeakerCallback {
+            beeper: Beeper::new(spec.freq as u32),
+            player: PatternPlayer::new(spec.freq as u32),
+            audio: shared,
+        })?;
+        device.resume();
+        Ok(Self { _device: device, audio })
+    }
+
+    //What to play until the next call, from Emulator::audio_state
+    pub fn update(&self, audio: AudioState) {
+        *self.audio.lock().unwrap() = audio;
+    }
+}
@@ -0,0 +1,148 @@
+use crate::finished;
+
+use chip8_core::{Clock, Emulator, Key, KeyMap, RunError};
+
+use crossterm::cursor;
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags, PopKeyboardEnhancementFlags,
+    PushKeyboardEnhancementFlags,
+};
+use crossterm::style::Print;
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+
+use std::io::{self, Write};
+use std::time::Duration;
+
+//Most terminals only report presses (and repeats while held), so a press holds the key this many frames,
+//long enough to bridge the pause before key repeat starts
+const HOLD_FRAMES: u32 = 30;
+//Held until the terminal reports the release
+const UNTIL_RELEASED: u32 = u32::MAX;
+
+//Raw mode and the alternate screen while it lives, put back even when the program faults
+pub(crate) struct Terminal {
+    //The terminal reports key releases (kitty keyboard protocol)
+    releases: bool,
+}
+
+impl Terminal {
+    pub(crate) fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        let releases = terminal::supports_keyboard_enhancement().unwrap_or(false);
+        execute!(io::stdout(), EnterAlternateScreen, cursor::Hide)?;
+        if releases {
+            execute!(io::stdout(), PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES))?;
+        }
+        Ok(Self { releases })
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        if self.releases {
+            let _ = execute!(io::stdout(), PopKeyboardEnhancementFlags);
+        }
+        let _ = execute!(io::stdout(), cursor::Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+//The keyboard as the keypad, through the default KeyMap. Without releases from the terminal a press holds
+//the key for HOLD_FRAMES
+pub(crate) struct Keypad {
+    keymap: KeyMap,
+    //Frames each key stays down for
+    held: [u32; Key::ALL.len()],
+    releases: bool,
+}
+
+impl Keypad {
+    pub(crate) fn new(terminal: &Terminal) -> Self {
+        Self { keymap: KeyMap::default(), held: [0; Key::ALL.len()], releases: terminal.releases }
+    }
+
+    //A key event for the keypad, false if it has no key there
+    pub(crate) fn input(&mut self, emulator: &mut Emulator, input: &KeyEvent) -> bool {
+        let KeyCode::Char(host) = input.code else { return false };
+        let Some(key) = self.keymap.get(host) else { return false };
+        if input.kind == KeyEventKind::Release {
+            self.held[key.index()] = 0;
+            emulator.keypress(key, false);
+        } else {
+            self.held[key.index()] = if self.releases { UNTIL_RELEASED } else { HOLD_FRAMES };
+            emulator.keypress(key, true);
+        }
+        true
+    }
+
+    //Once a frame, lets go of the keys whose time is up
+    pub(crate) fn frame(&mut self, emulator: &mut Emulator) {
+        for key in Key::ALL {
+            let frames = &mut self.held[key.index()];
+            if *frames != 0 && *frames != UNTIL_RELEASED {
+                *frames -= 1;
+                if *frames == 0 {
+                    emulator.keypress(key, false);
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn is_quit(input: &KeyEvent) -> bool {
+    input.code == KeyCode::Esc || input.code == KeyCode::Char('c') && input.modifiers.contains(KeyModifiers::CONTROL)
+}
+
+//Until Esc or Ctrl+C, the keyboard mapped with the default KeyMap
+pub(crate) fn run_tui(emulator: &mut Emulator) -> Result<(), RunError> {
+    let terminal = Terminal::enter()?;
+    let mut keypad = Keypad::new(&terminal);
+    let mut stdout = io::stdout().lock();
+    let mut clock = Clock::default();
+
+    loop {
+        clock.wait(emulator);
+        while event::poll(Duration::ZERO)? {
+            let Event::Key(input) = event::read()? else { continue };
+            if is_quit(&input) {
+                return Ok(());
+            }
+            keypad.input(emulator, &input);
+        }
+        keypad.frame(emulator);
+        if !finished(emulator) {
+            emulator.run_frame()?;
+        }
+        if emulator.screen_changed() {
+            emulator.take_dirty_rows();
+            draw(emulator, &mut stdout)?;
+        }
+    }
+}
+
+fn draw(emulator: &Emulator, out: &mut impl Write) -> io::Result<()> {
+    queue!(out, cursor::MoveTo(0, 0))?;
+    for line in screen_lines(emulator) {
+        queue!(out, Print(line), cursor::MoveToNextLine(1))?;
+    }
+    out.flush()
+}
+
+//Two pixel rows per line with half blocks, so the screen keeps its shape (64x32 takes 64x16 cells)
+pub(crate) fn screen_lines(emulator: &Emulator) -> Vec<String> {
+    let screen = emulator.frame_buffer();
+    (0..screen.height())
+        .step_by(2)
+        .map(|y| {
+            (0..screen.width())
+                .map(|x| match (screen.pixel(x, y) != 0, screen.pixel(x, y + 1) != 0) {
+                    (false, false) => ' ',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (true, true) => '█',
+                })
+                .collect()
+        })
+        .collect()
+}
@@ -0,0 +1,167 @@
+//The SDL window: drawing a screen to a canvas, the keypad on the keyboard, and FrontendChoice::Window
+
+use crate::{finished, Speaker};
+
+use chip8_core::megachip::{MEGA_SCREEN_HEIGHT, MEGA_SCREEN_WIDTH};
+use chip8_core::{AudioState, Clock, Display, Emulator, Frame, Key, KeyMap, Palette, Phosphor, RunError, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+use sdl2::event::Event;
+use sdl2::keyboard::{Keycode, Scancode};
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
+use sdl2::render::Canvas;
+use sdl2::video::Window;
+
+//Window pixels per low resolution CHIP-8 pixel
+const WINDOW_SCALE: u32 = 15;
+
+pub(crate) fn run_window(emulator: &mut Emulator) -> Result<(), RunError> {
+    let sdl = sdl2::init().map_err(RunError::Frontend)?;
+    let window = sdl
+        .video()
+        .map_err(RunError::Frontend)?
+        .window("Chip-8 Emulator", SCREEN_WIDTH as u32 * WINDOW_SCALE, SCREEN_HEIGHT as u32 * WINDOW_SCALE)
+        .position_centered()
+        .build()
+        .map_err(|err| RunError::Frontend(err.to_string()))?;
+    let mut canvas = window.into_canvas().build().map_err(|err| RunError::Frontend(err.to_string()))?;
+    let mut events = sdl.event_pump().map_err(RunError::Frontend)?;
+    //No audio device is no reason not to play
+    let speaker = sdl.audio().and_then(|audio| Speaker::open(&audio)).ok();
+    let palette = Palette::default();
+    let keymap = KeyMap::default();
+    //Paced by the clock alone, vsync on top would pace drawn and undrawn frames differently
+    let mut clock = Clock::default();
+
+    loop {
+        clock.wait(emulator);
+        for event in events.poll_iter() {
+            match event {
+                Event::Quit { .. } => return Ok(()),
+                Event::KeyDown { keycode, scancode, .. } => key_for(&keymap, keycode, scancode).into_iter().for_each(|key| emulator.keypress(key, true)),
+                Event::KeyUp { keycode, scancode, .. } => key_for(&keymap, keycode, scancode).into_iter().for_each(|key| emulator.keypress(key, false)),
+                _ => {},
+            }
+        }
+        if !finished(emulator) {
+            emulator.run_frame()?;
+        }
+        if let Some(speaker) = &speaker {
+            //A halted program's sound timer never runs out
+            speaker.update(if finished(emulator) { AudioState::default() } else { emulator.audio_state() });
+        }
+        if emulator.screen_changed() {
+            emulator.take_dirty_rows();
+            draw_screen(emulator, &mut canvas, &palette).map_err(RunError::Frontend)?;
+        }
+    }
+}
+
+//The keypad key keymap binds to an SDL key, looked up by the character the key types
+//sdl2 has no Keycode for characters like AZERTY's é, those keys are looked up by what they type on QWERTY,
+//a digit for the top row
+pub fn key_for(keymap: &KeyMap, keycode: Option<Keycode>, scancode: Option<Scancode>) -> Option<Key> {
+    let name = match (keycode, scancode) {
+        (Some(keycode), _) => keycode.name(),
+        (None, Some(scancode)) => scancode.name().to_string(),
+        (None, None) => return None,
+    };
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(host), None) => keymap.get(host),
+        _ => None,
+    }
+}
+
+//Fill the canvas with the screen, pixels scaled to the canvas width, and present it
+pub fn draw_screen(emulator: &Emulator, canvas: &mut Canvas<Window>, palette: &Palette) -> Result<(), String> {
+    draw_frame(&emulator.frame(), canvas, palette)
+}
+
+//draw_screen for a frame a Display was handed
+pub fn draw_frame(frame: &Frame, canvas: &mut Canvas<Window>, palette: &Palette) -> Result<(), String> {
+    if let Some(mega) = frame.megachip {
+        let mut rgba = vec![0; MEGA_SCREEN_WIDTH * MEGA_SCREEN_HEIGHT * 4];
+        mega.render_rgba(&mut rgba);
+        return draw_rgba(canvas, &rgba, MEGA_SCREEN_WIDTH as u32, MEGA_SCREEN_HEIGHT as u32);
+    }
+    //CHIP-8X colours come from the screen, not the palette, and a 64x64 screen does not fill the window
+    let screen = frame.screen;
+    let (width, height) = (screen.width(), screen.height());
+    if screen.color_zones().is_some() || width != height * 2 {
+        let mut rgba = vec![0; width * height * 4];
+        screen.render_rgba(&mut rgba, palette);
+        return draw_rgba(canvas, &rgba, width as u32, height as u32);
+    }
+    let [r, g, b, a] = palette.colors[0];
+    canvas.set_draw_color(Color::RGBA(r, g, b, a));
+    canvas.clear();
+
+    //Hires screens use smaller pixels in the same window
+    let scale = canvas.output_size()?.0 / width as u32;
+    for (y, (&plane1, &plane2)) in screen.plane_rows(0).iter().zip(screen.plane_rows(1)).enumerate() {
+        //Column 0 is the top bit of a packed row, reversed it is bit x for column x
+        let (plane1, plane2) = (plane1.reverse_bits(), plane2.reverse_bits());
+        for x in (0..width).filter(|&x| (plane1 | plane2) >> x & 1 != 0) {
+            let index = (plane1 >> x & 1 | (plane2 >> x & 1) << 1) as usize;
+            let [r, g, b, a] = palette.colors[index];
+            canvas.set_draw_color(Color::RGBA(r, g, b, a));
+            canvas.fill_rect(Rect::new(x as i32 * scale as i32, y as i32 * scale as i32, scale, scale))?;
+        }
+    }
+    canvas.present();
+    Ok(())
+}
+
+//draw_screen through phosphor, to be called every frame whether the screen changed or not so pixels fade
+//MegaChip's colour screen is drawn as it is
+pub fn draw_phosphor(emulator: &Emulator, canvas: &mut Canvas<Window>, palette: &Palette, phosphor: &mut Phosphor) -> Result<(), String> {
+    let frame = emulator.frame();
+    if frame.megachip.is_some() {
+        return draw_frame(&frame, canvas, palette);
+    }
+    let (width, height) = (frame.screen.width(), frame.screen.height());
+    let mut rgba = vec![0; width * height * 4];
+    phosphor.render_rgba(frame.screen, palette, &mut rgba);
+    draw_rgba(canvas, &rgba, width as u32, height as u32)
+}
+
+//An SDL window as a Display, for Emulator::run_frame_to (a window cannot go to another thread)
+pub struct WindowDisplay<'a> {
+    pub canvas: &'a mut Canvas<Window>,
+    pub palette: Palette,
+    //The last draw that failed
+    pub error: Option<String>,
+}
+
+impl<'a> WindowDisplay<'a> {
+    pub fn new(canvas: &'a mut Canvas<Window>, palette: Palette) -> Self {
+        Self { canvas, palette, error: None }
+    }
+}
+
+impl Display for WindowDisplay<'_> {
+    fn draw_frame(&mut self, frame: &Frame) {
+        if let Err(err) = draw_frame(frame, self.canvas, &self.palette) {
+            self.error = Some(err);
+        }
+    }
+}
+
+//An RGBA picture as large as fits and centred
+fn draw_rgba(canvas: &mut Canvas<Window>, rgba: &[u8], width: u32, height: u32) -> Result<(), String> {
+    let creator = canvas.texture_creator();
+    let mut texture = creator.create_texture_static(PixelFormatEnum::RGBA32, width, height).map_err(|err| err.to_string())?;
+    texture.update(None, rgba, width as usize * 4).map_err(|err| err.to_string())?;
+
+    let (output_width, output_height) = canvas.output_size()?;
+    let scale = (output_width as f32 / width as f32).min(output_height as f32 / height as f32);
+    let (scaled_width, scaled_height) = ((width as f32 * scale) as u32, (height as f32 * scale) as u32);
+    let x = (output_width - scaled_width) as i32 / 2;
+    let y = (output_height - scaled_height) as i32 / 2;
+    canvas.set_draw_color(Color::RGB(0, 0, 0));
+    canvas.clear();
+    canvas.copy(&texture, None, Rect::new(x, y, scaled_width, scaled_height))?;
+    canvas.present();
+    Ok(())
+}
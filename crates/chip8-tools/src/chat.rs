@@ -0,0 +1,173 @@
+use chip8_core::{Emulator, Key};
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::io::{self, BufRead, BufReader};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+//"Chat plays CHIP-8": key commands from a text stream, one per line, optionally prefixed by the sender
+//"alice: 5" taps key 5, "alice: 5 12" holds it for 12 frames. Keys are hex digits
+//Commands queue up and each one is held for a whole number of frames, so inputs land the same way however fast chat is
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChatConfig {
+    //Frames a key stays down for a plain tap, and the unit longer holds are rounded up to
+    pub hold_frames: u32,
+    //Longest hold anyone can ask for
+    pub max_hold_frames: u32,
+    //Frames a sender has to wait between accepted commands
+    pub cooldown_frames: u64,
+    //Commands waiting their turn, anything past this is dropped
+    pub max_queue: usize,
+}
+
+impl Default for ChatConfig {
+    fn default() -> Self {
+        Self {
+            hold_frames: 6,
+            max_hold_frames: 60,
+            cooldown_frames: 30,
+            max_queue: 32,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejected {
+    Unrecognized,
+    RateLimited,
+    QueueFull,
+}
+
+impl fmt::Display for Rejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejected::Unrecognized => write!(f, "not a key command"),
+            Rejected::RateLimited => write!(f, "too many commands, slow down"),
+            Rejected::QueueFull => write!(f, "too many commands waiting"),
+        }
+    }
+}
+
+pub struct ChatInput {
+    config: ChatConfig,
+    lines: Option<Receiver<String>>,
+    queue: VecDeque<(Key, u32)>,
+    //Key that is down and the frames it has left
+    held: Option<(Key, u32)>,
+    last_accepted: HashMap<String, u64>,
+    frame: u64,
+}
+
+impl ChatInput {
+    //Commands only come from submit()
+    pub fn new(config: ChatConfig) -> Self {
+        Self {
+            config,
+            lines: None,
+            queue: VecDeque::new(),
+            held: None,
+            last_accepted: HashMap::new(),
+            frame: 0,
+        }
+    }
+
+    //Read commands from reader on a background thread
+    pub fn from_reader<R: BufRead + Send + 'static>(reader: R, config: ChatConfig) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in reader.lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Self { lines: Some(receiver), ..Self::new(config) }
+    }
+
+    pub fn from_stdin(config: ChatConfig) -> Self {
+        Self::from_reader(BufReader::new(io::stdin()), config)
+    }
+
+    //Read commands from a TCP relay (a chat bot forwarding messages one per line)
+    pub fn connect(address: impl ToSocketAddrs, config: ChatConfig) -> io::Result<Self> {
+        Ok(Self::from_reader(BufReader::new(TcpStream::connect(address)?), config))
+    }
+
+    //Queue one chat line
+    pub fn submit(&mut self, line: &str) -> Result<(), Rejected> {
+        let (sender, command) = match line.split_once(':') {
+            Some((sender, command)) => (sender.trim(), command),
+            None => ("", line),
+        };
+        let (key, frames) = parse_command(command).ok_or(Rejected::Unrecognized)?;
+        if let Some(&last) = self.last_accepted.get(sender) {
+            if self.frame < last + self.config.cooldown_frames {
+                return Err(Rejected::RateLimited);
+            }
+        }
+        if self.queue.len() >= self.config.max_queue {
+            return Err(Rejected::QueueFull);
+        }
+
+        let unit = self.config.hold_frames.max(1);
+        let frames = frames.unwrap_or(unit).div_ceil(unit) * unit;
+        self.queue.push_back((key, frames.min(self.config.max_hold_frames.max(unit))));
+        self.last_accepted.insert(sender.to_string(), self.frame);
+        Ok(())
+    }
+
+    //Commands waiting behind the one being held
+    pub fn pending(&self) -> usize {
+        self.queue.len()
+    }
+
+    //Call once per frame before running it: picks up new lines and presses or releases keys
+    pub fn frame(&mut self, emulator: &mut Emulator) {
+        while let Some(receiver) = &self.lines {
+            match receiver.try_recv() {
+                Ok(line) => {
+                    let _ = self.submit(&line);
+                },
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => self.lines = None,
+            }
+        }
+
+        if let Some((key, frames)) = self.held {
+            if frames > 0 {
+                self.held = Some((key, frames - 1));
+            } else {
+                emulator.keypress(key, false);
+                self.held = None;
+            }
+        }
+        //A key released this frame leaves one frame up before the next press, so taps of the same key register twice
+        else if let Some((key, frames)) = self.queue.pop_front() {
+            emulator.keypress(key, true);
+            self.held = Some((key, frames - 1));
+        }
+        self.frame += 1;
+    }
+}
+
+//"5" or "5 12"
+fn parse_command(command: &str) -> Option<(Key, Option<u32>)> {
+    let mut words = command.split_whitespace();
+    let mut key = words.next()?.chars();
+    let key = match (key.next(), key.next()) {
+        (Some(digit), None) => Key::from_digit(digit)?,
+        _ => return None,
+    };
+    let frames = match words.next() {
+        Some(frames) => Some(frames.parse().ok().filter(|&frames| frames > 0)?),
+        None => None,
+    };
+    match words.next() {
+        Some(_) => None,
+        None => Some((key, frames)),
+    }
+}
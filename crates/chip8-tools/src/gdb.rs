@@ -0,0 +1,295 @@
+use chip8_core::{Break, Chip8Error, Emulator, EmulatorState, TICKS_PER_FRAME};
+
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+//GDB remote serial protocol server, so gdb, lldb or a custom tool can drive the emulator over TCP
+//Supported: register and memory reads/writes, software breakpoints (Z0/Z1), write watchpoints (Z2),
+//continue, single step and Ctrl-C. Continuing runs in real time, one frame of instructions every 1/60 s
+//Register numbers: 0-15 V0-VF (1 byte), 16 I (2 bytes), 17 PC (2 bytes), 18 stack depth, 19 delay timer,
+//20 sound timer (1 byte each, read only). Multi-byte registers are little-endian like any gdb target
+const REGISTER_COUNT: usize = 21;
+const FRAME: Duration = Duration::from_micros(16_667);
+
+//Signals in stop replies
+const SIGINT: u8 = 2;
+const SIGILL: u8 = 4;
+const SIGTRAP: u8 = 5;
+const SIGSEGV: u8 = 11;
+
+//Wait for one debugger to connect on address and serve it until it detaches or kills the session
+pub fn listen(emulator: &mut Emulator, address: impl ToSocketAddrs) -> io::Result<()> {
+    let (stream, _) = TcpListener::bind(address)?.accept()?;
+    serve(emulator, stream)
+}
+
+//Serve a debugger on an already connected stream
+pub fn serve(emulator: &mut Emulator, stream: TcpStream) -> io::Result<()> {
+    stream.set_nodelay(true)?;
+    let mut session = Session { emulator, stream, ticks: 0 };
+    loop {
+        let Some(packet) = session.read_packet()? else { return Ok(()) };
+        match session.handle(&packet)? {
+            Some(reply) => session.send(&reply)?,
+            None => return Ok(()),
+        }
+    }
+}
+
+struct Session<'a> {
+    emulator: &'a mut Emulator,
+    stream: TcpStream,
+    //Instructions since the last timer update
+    ticks: usize,
+}
+
+impl Session<'_> {
+    //None once the debugger hangs up. A Ctrl-C outside a packet is ignored, nothing is running then
+    fn read_packet(&mut self) -> io::Result<Option<String>> {
+        let mut byte = [0];
+        loop {
+            if self.stream.read(&mut byte)? == 0 {
+                return Ok(None);
+            }
+            if byte[0] == b'$' {
+                break;
+            }
+        }
+        let mut data = Vec::new();
+        loop {
+            if self.stream.read(&mut byte)? == 0 {
+                return Ok(None);
+            }
+            if byte[0] == b'#' {
+                break;
+            }
+            data.push(byte[0]);
+        }
+        let mut checksum = [0; 2];
+        self.stream.read_exact(&mut checksum)?;
+        let valid = std::str::from_utf8(&checksum).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok()) == Some(checksum_of(&data));
+        self.stream.write_all(if valid { b"+" } else { b"-" })?;
+        if !valid {
+            return self.read_packet();
+        }
+        Ok(Some(String::from_utf8_lossy(&data).into_owned()))
+    }
+
+    fn send(&mut self, reply: &str) -> io::Result<()> {
+        write!(self.stream, "${}#{:02x}", reply, checksum_of(reply.as_bytes()))?;
+        self.stream.flush()
+    }
+
+    //The reply, None to end the session
+    fn handle(&mut self, packet: &str) -> io::Result<Option<String>> {
+        let (command, args) = packet.split_at(packet.len().min(1));
+        let reply = match command {
+            "?" => stop_reply(SIGTRAP),
+            "g" => (0..REGISTER_COUNT).map(|register| self.read_register(register).unwrap()).collect(),
+            "G" => self.write_registers(args),
+            "p" => usize::from_str_radix(args, 16).ok().and_then(|register| self.read_register(register)).unwrap_or_else(error),
+            "P" => self.write_register(args).unwrap_or_else(error),
+            "m" => self.read_memory(args).unwrap_or_else(error),
+            "M" => self.write_memory(args).unwrap_or_else(error),
+            "Z" | "z" => self.breakpoint(command == "Z", args).unwrap_or_else(error),
+            "s" => {
+                self.resume_at(args);
+                self.step()
+            },
+            "c" => {
+                self.resume_at(args);
+                self.run()?
+            },
+            "H" => ok(),
+            "q" if args.starts_with("Supported") => "PacketSize=1000;swbreak+".to_string(),
+            "q" if args == "Attached" => "1".to_string(),
+            "q" if args == "C" => String::new(),
+            "D" => {
+                self.send(&ok())?;
+                return Ok(None);
+            },
+            "k" => return Ok(None),
+            _ => String::new(),
+        };
+        Ok(Some(reply))
+    }
+
+    fn read_register(&self, register: usize) -> Option<String> {
+        let emulator = &self.emulator;
+        Some(match register {
+            0..=15 => format!("{:02x}", emulator.v_register(register as u8)),
+            16 => hex(&emulator.i_register().to_le_bytes()),
+            17 => hex(&emulator.program_counter().to_le_bytes()),
+            18 => format!("{:02x}", emulator.stack_depth()),
+            19 => format!("{:02x}", emulator.delay_timer()),
+            20 => format!("{:02x}", emulator.sound_timer()),
+            _ => return None,
+        })
+    }
+
+    //"n=value" with value in target byte order
+    fn write_register(&mut self, args: &str) -> Option<String> {
+        let (register, value) = args.split_once('=')?;
+        let register = usize::from_str_radix(register, 16).ok()?;
+        let bytes = unhex(value)?;
+        match (register, bytes.as_slice()) {
+            (0..=15, &[value]) => self.emulator.set_v_register(register as u8, value),
+            (16, &[low, high]) => self.emulator.set_i_register(u16::from_le_bytes([low, high])),
+            (17, &[low, high]) => self.emulator.set_program_counter(u16::from_le_bytes([low, high])),
+            _ => return None,
+        }
+        Some(ok())
+    }
+
+    //The read only registers at the end are ignored
+    fn write_registers(&mut self, args: &str) -> String {
+        let Some(bytes) = unhex(args).filter(|bytes| bytes.len() >= 20) else { return error() };
+        for (register, &value) in bytes[..16].iter().enumerate() {
+            self.emulator.set_v_register(register as u8, value);
+        }
+        self.emulator.set_i_register(u16::from_le_bytes([bytes[16], bytes[17]]));
+        self.emulator.set_program_counter(u16::from_le_bytes([bytes[18], bytes[19]]));
+        ok()
+    }
+
+    //"address,length"
+    fn read_memory(&self, args: &str) -> Option<String> {
+        let (address, len) = parse_range(args)?;
+        let bytes: Vec<u8> = (0..len).map(|offset| self.emulator.peek(address.wrapping_add(offset))).collect();
+        Some(hex(&bytes))
+    }
+
+    //"address,length:bytes"
+    fn write_memory(&mut self, args: &str) -> Option<String> {
+        let (range, data) = args.split_once(':')?;
+        let (address, len) = parse_range(range)?;
+        let bytes = unhex(data).filter(|bytes| bytes.len() == len as usize)?;
+        for (offset, &byte) in bytes.iter().enumerate() {
+            self.emulator.poke(address.wrapping_add(offset as u16), byte);
+        }
+        Some(ok())
+    }
+
+    //"type,address,kind": 0 and 1 are breakpoints, 2 a write watchpoint over kind bytes
+    fn breakpoint(&mut self, insert: bool, args: &str) -> Option<String> {
+        let mut fields = args.split(',');
+        let kind = fields.next()?;
+        let address = u16::from_str_radix(fields.next()?, 16).ok()?;
+        let len = u16::from_str_radix(fields.next().unwrap_or("1"), 16).ok()?;
+        match (kind, insert) {
+            ("0" | "1", true) => self.emulator.set_breakpoint(address),
+            ("0" | "1", false) => self.emulator.clear_breakpoint(address),
+            ("2", true) => (0..len.max(1)).for_each(|offset| self.emulator.set_watchpoint(address.wrapping_add(offset))),
+            ("2", false) => (0..len.max(1)).for_each(|offset| self.emulator.clear_watchpoint(address.wrapping_add(offset))),
+            //Read and access watchpoints are not supported
+            _ => return Some(String::new()),
+        }
+        Some(ok())
+    }
+
+    //s and c can carry an address to resume from
+    fn resume_at(&mut self, args: &str) {
+        if let Ok(address) = u16::from_str_radix(args, 16) {
+            self.emulator.set_program_counter(address);
+        }
+    }
+
+    //One instruction, the breakpoint under the PC (if any) is stepped over
+    fn step(&mut self) -> String {
+        let result = self.emulator.step_into();
+        self.count_tick();
+        self.stop(result)
+    }
+
+    //Run until a breakpoint, a fault, an exit or a Ctrl-C from the debugger
+    fn run(&mut self) -> io::Result<String> {
+        let mut result = self.emulator.step_into();
+        self.count_tick();
+        loop {
+            match result {
+                Ok(None) if !self.emulator.has_exited() && !matches!(self.emulator.state(), EmulatorState::Halted { .. } | EmulatorState::Finished { .. }) => {},
+                result => return Ok(self.stop(result)),
+            }
+            if self.interrupted()? {
+                return Ok(stop_reply(SIGINT));
+            }
+            let ticks = TICKS_PER_FRAME - self.ticks;
+            result = self.emulator.debug_run(ticks);
+            match result {
+                Ok(None) => {
+                    self.emulator.timers();
+                    self.ticks = 0;
+                    self.emulator.time_source().sleep(FRAME);
+                },
+                //Counting exactly where the frame stopped is not worth it, the next frame starts fresh
+                _ => self.ticks = 0,
+            }
+        }
+    }
+
+    fn count_tick(&mut self) {
+        self.ticks += 1;
+        if self.ticks >= TICKS_PER_FRAME {
+            self.emulator.timers();
+            self.ticks = 0;
+        }
+    }
+
+    //Whether gdb sent Ctrl-C (a bare 0x03 byte) while the program was running
+    fn interrupted(&mut self) -> io::Result<bool> {
+        self.stream.set_nonblocking(true)?;
+        let mut byte = [0];
+        let read = self.stream.read(&mut byte);
+        self.stream.set_nonblocking(false)?;
+        match read {
+            Ok(0) => Err(ErrorKind::ConnectionAborted.into()),
+            Ok(_) => Ok(byte[0] == 0x03),
+            Err(err) if err.kind() == ErrorKind::WouldBlock => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn stop(&self, result: Result<Option<Break>, Chip8Error>) -> String {
+        match result {
+            _ if self.emulator.has_exited() => "W00".to_string(),
+            Ok(Some(Break::Watchpoint { address, .. })) => format!("T{:02x}watch:{:x};", SIGTRAP, address),
+            Ok(Some(Break::Breakpoint { .. })) => format!("T{:02x}swbreak:;", SIGTRAP),
+            Ok(_) => stop_reply(SIGTRAP),
+            Err(Chip8Error::UnknownOpcode { .. }) => stop_reply(SIGILL),
+            Err(_) => stop_reply(SIGSEGV),
+        }
+    }
+}
+
+fn stop_reply(signal: u8) -> String {
+    format!("S{:02x}", signal)
+}
+
+fn ok() -> String {
+    "OK".to_string()
+}
+
+fn error() -> String {
+    "E01".to_string()
+}
+
+fn checksum_of(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len()).step_by(2).map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok()).collect()
+}
+
+fn parse_range(args: &str) -> Option<(u16, u16)> {
+    let (address, len) = args.split_once(',')?;
+    Some((u16::from_str_radix(address, 16).ok()?, u16::from_str_radix(len, 16).ok()?))
+}
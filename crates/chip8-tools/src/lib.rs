@@ -0,0 +1,10 @@
+//Tools built on chip8-core that talk to the outside world: ROM library management, chat input,
+//and behind features the gdb and language servers and netplay
+pub mod chat;
+#[cfg(feature = "gdb")]
+pub mod gdb;
+pub mod library;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+#[cfg(feature = "netplay")]
+pub mod netplay;
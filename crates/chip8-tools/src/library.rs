@@ -0,0 +1,156 @@
+use chip8_core::romdb::{rom_hash, RomDb};
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const ROM_EXTENSIONS: [&str; 4] = ["ch8", "c8", "sc8", "xo8"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RomFile {
+    pub path: PathBuf,
+    pub sha1: String,
+}
+
+//What organize does with one ROM file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    //Already named after its title, or unknown to the database
+    Keep,
+    Rename { to: PathBuf },
+    //Same image as an earlier file, moved into the duplicates folder
+    Duplicate { of: PathBuf, to: PathBuf },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanEntry {
+    pub rom: RomFile,
+    pub title: Option<String>,
+    pub action: Action,
+}
+
+//ROM files directly inside dir (by extension), sorted by path
+pub fn scan(dir: &Path) -> io::Result<Vec<RomFile>> {
+    let mut roms = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && is_rom_path(&path) {
+            let sha1 = rom_hash(&fs::read(&path)?);
+            roms.push(RomFile { path, sha1 });
+        }
+    }
+    roms.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(roms)
+}
+
+pub fn is_rom_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| ROM_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+}
+
+//Decide renames and duplicates without touching the filesystem
+//The first file (by path) with a given hash is the one that is kept
+pub fn plan(roms: &[RomFile], db: &RomDb, dir: &Path) -> Vec<PlanEntry> {
+    //Where the kept copy of each image ends up
+    let mut first_seen: HashMap<&str, PathBuf> = HashMap::new();
+    let mut taken: Vec<PathBuf> = roms.iter().map(|rom| rom.path.clone()).collect();
+
+    roms.iter()
+        .map(|rom| {
+            let title = db.lookup(&rom.sha1).map(|entry| entry.title.clone());
+            let action = if let Some(original) = first_seen.get(rom.sha1.as_str()) {
+                let to = dir.join("duplicates").join(rom.path.file_name().unwrap());
+                Action::Duplicate { of: original.clone(), to }
+            } else {
+                let action = match &title {
+                    Some(title) => {
+                        let extension = rom.path.extension().unwrap().to_string_lossy();
+                        let to = dir.join(format!("{}.{}", sanitize(title), extension));
+                        if to == rom.path || taken.contains(&to) {
+                            Action::Keep
+                        } else {
+                            taken.push(to.clone());
+                            Action::Rename { to }
+                        }
+                    },
+                    None => Action::Keep,
+                };
+                let kept_at = match &action {
+                    Action::Rename { to } => to.clone(),
+                    _ => rom.path.clone(),
+                };
+                first_seen.insert(&rom.sha1, kept_at);
+                action
+            };
+            PlanEntry { rom: rom.clone(), title, action }
+        })
+        .collect()
+}
+
+//Carry out a plan. Sidecar files with the same stem (e.g. the .txt notes) move along with the ROM
+pub fn apply(plan: &[PlanEntry]) -> io::Result<()> {
+    for entry in plan {
+        let to = match &entry.action {
+            Action::Keep => continue,
+            Action::Rename { to } => to,
+            Action::Duplicate { to, .. } => {
+                fs::create_dir_all(to.parent().unwrap())?;
+                to
+            },
+        };
+        fs::rename(&entry.rom.path, to)?;
+        for (from, sidecar_to) in sidecars(&entry.rom.path, to)? {
+            if !sidecar_to.exists() {
+                fs::rename(from, sidecar_to)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn sidecars(rom: &Path, to: &Path) -> io::Result<Vec<(PathBuf, PathBuf)>> {
+    let stem = rom.file_stem().unwrap().to_os_string();
+    let new_stem = to.file_stem().unwrap().to_string_lossy().into_owned();
+    let mut found = Vec::new();
+    for entry in fs::read_dir(rom.parent().unwrap_or(Path::new(".")))? {
+        let path = entry?.path();
+        if path.is_file() && !is_rom_path(&path) && path.file_stem() == Some(stem.as_os_str()) {
+            if let Some(extension) = path.extension() {
+                let sidecar_to = to.with_file_name(format!("{}.{}", new_stem, extension.to_string_lossy()));
+                found.push((path, sidecar_to));
+            }
+        }
+    }
+    Ok(found)
+}
+
+//Tab separated manifest: sha1, file name, title, action
+pub fn manifest(plan: &[PlanEntry]) -> String {
+    let mut text = String::from("# sha1\tfile\ttitle\taction\n");
+    for entry in plan {
+        let file = match &entry.action {
+            Action::Keep => &entry.rom.path,
+            Action::Rename { to } | Action::Duplicate { to, .. } => to,
+        };
+        let action = match &entry.action {
+            Action::Keep => "kept".to_string(),
+            Action::Rename { .. } => format!("renamed from {}", entry.rom.path.file_name().unwrap().to_string_lossy()),
+            Action::Duplicate { of, .. } => format!("duplicate of {}", of.file_name().unwrap().to_string_lossy()),
+        };
+        text.push_str(&format!(
+            "{}\t{}\t{}\t{}\n",
+            entry.rom.sha1,
+            file.file_name().unwrap().to_string_lossy(),
+            entry.title.as_deref().unwrap_or(""),
+            action
+        ));
+    }
+    text
+}
+
+//Titles can contain characters that are not valid in file names on every platform
+fn sanitize(title: &str) -> String {
+    title.chars().map(|c| if "/\\:*?\"<>|".contains(c) { '_' } else { c }).collect()
+}
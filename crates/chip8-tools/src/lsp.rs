@@ -0,0 +1,239 @@
+use chip8_core::asm::{assemble, Assembly};
+use chip8_core::disasm::disassemble;
+use serde_json::{json, Value};
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, ErrorKind, Write};
+
+//Minimal language server for the assembler syntax, speaking LSP over stdio (Content-Length framed JSON-RPC)
+//Supported: diagnostics from the assembler, go to definition of labels, constants and aliases,
+//and hover showing what a line assembles to. Documents are synced whole on every change
+const METHOD_NOT_FOUND: i64 = -32601;
+
+//Serve one client until it sends exit or closes input
+pub fn run(input: impl BufRead, output: impl Write) -> io::Result<()> {
+    let mut server = Server { input, output, documents: HashMap::new() };
+    while let Some(message) = server.read_message()? {
+        if !server.handle(&message)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+struct Server<I, O> {
+    input: I,
+    output: O,
+    //Text of each open document by URI
+    documents: HashMap<String, String>,
+}
+
+impl<I: BufRead, O: Write> Server<I, O> {
+    //None once the input is closed. Messages that are not JSON are skipped
+    fn read_message(&mut self) -> io::Result<Option<Value>> {
+        loop {
+            let mut len = None;
+            loop {
+                let mut header = String::new();
+                if self.input.read_line(&mut header)? == 0 {
+                    return Ok(None);
+                }
+                let header = header.trim_end();
+                if header.is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.eq_ignore_ascii_case("Content-Length") {
+                        len = value.trim().parse().ok();
+                    }
+                }
+            }
+            let Some(len) = len else { continue };
+            let mut body = vec![0; len];
+            self.input.read_exact(&mut body).map_err(|err| match err.kind() {
+                ErrorKind::UnexpectedEof => io::Error::new(ErrorKind::UnexpectedEof, "message cut short"),
+                _ => err,
+            })?;
+            if let Ok(message) = serde_json::from_slice(&body) {
+                return Ok(Some(message));
+            }
+        }
+    }
+
+    fn send(&mut self, message: Value) -> io::Result<()> {
+        let body = message.to_string();
+        write!(self.output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+        self.output.flush()
+    }
+
+    //false on exit
+    fn handle(&mut self, message: &Value) -> io::Result<bool> {
+        let method = message["method"].as_str().unwrap_or("");
+        let params = &message["params"];
+        let id = message.get("id").cloned();
+        let result = match method {
+            "initialize" => json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "definitionProvider": true,
+                    "hoverProvider": true,
+                },
+                "serverInfo": { "name": "chip8-asm" },
+            }),
+            "textDocument/didOpen" => {
+                let document = &params["textDocument"];
+                self.update(document["uri"].as_str().unwrap_or(""), document["text"].as_str().unwrap_or(""))?;
+                return Ok(true);
+            },
+            "textDocument/didChange" => {
+                //Full sync, the last change holds the whole text
+                if let Some(text) = params["contentChanges"].as_array().and_then(|changes| changes.last()).and_then(|change| change["text"].as_str()) {
+                    self.update(params["textDocument"]["uri"].as_str().unwrap_or(""), text)?;
+                }
+                return Ok(true);
+            },
+            "textDocument/didClose" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+                self.documents.remove(uri);
+                self.send(diagnostics(uri, Vec::new()))?;
+                return Ok(true);
+            },
+            "textDocument/definition" => self.definition(params),
+            "textDocument/hover" => self.hover(params),
+            "shutdown" => Value::Null,
+            "exit" => return Ok(false),
+            //Other notifications are ignored
+            _ if id.is_none() => return Ok(true),
+            _ => {
+                let error = json!({ "code": METHOD_NOT_FOUND, "message": format!("Unsupported method '{}'", method) });
+                self.send(json!({ "jsonrpc": "2.0", "id": id, "error": error }))?;
+                return Ok(true);
+            },
+        };
+        if id.is_some() {
+            self.send(json!({ "jsonrpc": "2.0", "id": id, "result": result }))?;
+        }
+        Ok(true)
+    }
+
+    //Store the new text and publish its diagnostics
+    fn update(&mut self, uri: &str, text: &str) -> io::Result<()> {
+        let found = match assemble(text) {
+            Ok(_) => Vec::new(),
+            Err(error) => {
+                let line = error.line.saturating_sub(1);
+                let end = text.lines().nth(line).map_or(0, |line| line.chars().count());
+                vec![json!({
+                    "range": range(line, 0, end),
+                    "severity": 1,
+                    "source": "chip8-asm",
+                    "message": error.message,
+                })]
+            },
+        };
+        self.documents.insert(uri.to_string(), text.to_string());
+        self.send(diagnostics(uri, found))
+    }
+
+    //Where the name under the cursor is defined, null when it is not a label, constant or alias
+    fn definition(&self, params: &Value) -> Value {
+        let Some((uri, text, line, character)) = self.position(params) else { return Value::Null };
+        let Some((name, _, _)) = word_at(text, line, character) else { return Value::Null };
+        text.lines()
+            .enumerate()
+            .find_map(|(i, source)| {
+                let words = words(source);
+                let defined = words.windows(2).find(|pair| matches!(pair[0].0, ":" | ":const" | ":alias") && pair[1].0 == name)?;
+                let (_, start, end) = defined[1];
+                Some(json!({ "uri": uri, "range": range(i, start, end) }))
+            })
+            .unwrap_or(Value::Null)
+    }
+
+    //The label's address and what the line under the cursor assembles to
+    fn hover(&self, params: &Value) -> Value {
+        let Some((_, text, line, character)) = self.position(params) else { return Value::Null };
+        //Hover needs addresses, so it only works while the document assembles
+        let Ok(assembly) = assemble(text) else { return Value::Null };
+        let mut contents = Vec::new();
+        if let Some((name, _, _)) = word_at(text, line, character) {
+            if let Some(address) = assembly.labels.get(name) {
+                contents.push(format!("{}: {:#05X}", name, address));
+            }
+        }
+        contents.extend(encodings(&assembly, line + 1));
+        if contents.is_empty() {
+            return Value::Null;
+        }
+        json!({ "contents": { "kind": "plaintext", "value": contents.join("\n") } })
+    }
+
+    //URI, text, line and character of a textDocument/position request
+    fn position<'a>(&'a self, params: &'a Value) -> Option<(&'a str, &'a str, usize, usize)> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let text = self.documents.get(uri)?;
+        let position = &params["position"];
+        Some((uri, text, position["line"].as_u64()? as usize, position["character"].as_u64()? as usize))
+    }
+}
+
+//One line per instruction the source line (1-based) assembled to, e.g. "0x202  6005  LD V0, 0x05"
+fn encodings(assembly: &Assembly, line: usize) -> Vec<String> {
+    assembly
+        .spans
+        .iter()
+        .filter(|span| span.line == line)
+        .flat_map(|span| {
+            let start = (span.address - assembly.origin) as usize;
+            disassemble(&assembly.bytes[start..start + span.len as usize], span.address)
+        })
+        .map(|line| match line.instruction {
+            Some(instruction) => {
+                let opcode: String = instruction.to_bytes().iter().map(|byte| format!("{:02X}", byte)).collect();
+                format!("{:#05X}  {}  {}", line.address, opcode, instruction)
+            },
+            None => format!("{:#05X}  {:04X}  data", line.address, line.opcode),
+        })
+        .collect()
+}
+
+//Words of a source line outside its comment, with their start and end characters
+fn words(line: &str) -> Vec<(&str, usize, usize)> {
+    let code = line.split('#').next().unwrap_or("");
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, (offset, c)) in code.char_indices().enumerate() {
+        match (c.is_whitespace(), start) {
+            (false, None) => start = Some((i, offset)),
+            (true, Some((first, from))) => {
+                words.push((&code[from..offset], first, i));
+                start = None;
+            },
+            _ => {},
+        }
+    }
+    if let Some((first, from)) = start {
+        words.push((&code[from..], first, code.chars().count()));
+    }
+    words
+}
+
+//Word touching the cursor, the character just after a word still counts as on it
+fn word_at(text: &str, line: usize, character: usize) -> Option<(&str, usize, usize)> {
+    words(text.lines().nth(line)?).into_iter().find(|&(_, start, end)| (start..=end).contains(&character))
+}
+
+fn range(line: usize, start: usize, end: usize) -> Value {
+    json!({
+        "start": { "line": line, "character": start },
+        "end": { "line": line, "character": end },
+    })
+}
+
+fn diagnostics(uri: &str, diagnostics: Vec<Value>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
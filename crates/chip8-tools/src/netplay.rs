@@ -0,0 +1,190 @@
+use chip8_core::{rom_hash, Chip8Error, Emulator, FrameOutput, RandomModel, State, StateError, Timing};
+
+use std::collections::hash_map::RandomState;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+//Lockstep play over TCP for the two player games that share one keypad (Pong 2, Tank): both machines run
+//the same emulator, swap their players' keys every frame and press what either holds, so they stay in step
+//without sending the screen. The host's state (quirks and a fixed random seed included) goes to the guest
+//first, after that only keys
+//Keys are applied delay frames after they are pressed on both sides, so neither waits on the other's
+//round trip every frame. At 60 frames a second 2 or 3 frames hide a LAN, more is needed over the internet
+//Handshake, host to guest: "C8NP", version, delay, ticks per frame (u16), timing (0 instructions, 1 VIP),
+//the ROM's SHA-1 (a length byte and hex), the state snapshot (u32 length and the bytes)
+//Every frame after, both ways: the frame it was sent on (u32), the keys pressed (u16 bitmask)
+//and every SYNC_INTERVAL frames the state hash before that frame, to catch a desync early (u64, else 0)
+const MAGIC: &[u8; 4] = b"C8NP";
+const VERSION: u8 = 1;
+const MESSAGE_SIZE: usize = 14;
+const SYNC_INTERVAL: u64 = 60;
+//A peer silent this long has gone
+const TIMEOUT: Duration = Duration::from_secs(10);
+pub const DEFAULT_DELAY: u8 = 2;
+
+#[derive(Debug)]
+pub enum NetplayError {
+    Io(io::Error),
+    //The other side is not this protocol, or another version of it
+    Handshake,
+    //The host is playing another ROM (SHA-1 of the host's)
+    RomMismatch { expected: String },
+    State(StateError),
+    //The two emulators no longer agree, the frame where it was noticed
+    Desync { frame: u64 },
+    Fault(Chip8Error),
+}
+
+impl fmt::Display for NetplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetplayError::Io(error) => write!(f, "{}", error),
+            NetplayError::Handshake => write!(f, "The other side is not a compatible emulator"),
+            NetplayError::RomMismatch { expected } => write!(f, "The host is playing another ROM (SHA-1 {})", expected),
+            NetplayError::State(error) => write!(f, "{}", error),
+            NetplayError::Desync { frame } => write!(f, "Out of step with the other side at frame {}", frame),
+            NetplayError::Fault(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for NetplayError {}
+
+impl From<io::Error> for NetplayError {
+    fn from(error: io::Error) -> Self {
+        NetplayError::Io(error)
+    }
+}
+
+impl From<StateError> for NetplayError {
+    fn from(error: StateError) -> Self {
+        NetplayError::State(error)
+    }
+}
+
+impl From<Chip8Error> for NetplayError {
+    fn from(error: Chip8Error) -> Self {
+        NetplayError::Fault(error)
+    }
+}
+
+pub struct Netplay {
+    stream: TcpStream,
+    frame: u64,
+    //Keys for the frames from this one on, both filled delay frames ahead with nothing pressed
+    local: VecDeque<u16>,
+    remote: VecDeque<u16>,
+    //Our state hash on the SYNC_INTERVAL frames the other side has not reported on yet
+    hashes: VecDeque<(u64, u64)>,
+}
+
+impl Netplay {
+    //Wait for the other player on address and send them the emulator as it is, with the ROM already loaded
+    //An Entropy random model is swapped for a seeded one, or each side would draw different numbers
+    pub fn host(emulator: &mut Emulator, rom: &[u8], address: impl ToSocketAddrs, delay: u8) -> Result<Self, NetplayError> {
+        let (mut stream, _) = TcpListener::bind(address)?.accept()?;
+        if emulator.quirks().random == RandomModel::Entropy {
+            emulator.set_seed(RandomState::new().build_hasher().finish());
+        }
+        let hash = rom_hash(rom);
+        let snapshot = emulator.save_state().to_snapshot_bytes();
+        let mut handshake = MAGIC.to_vec();
+        handshake.extend([VERSION, delay]);
+        handshake.extend((emulator.ticks_per_frame().min(u16::MAX as usize) as u16).to_be_bytes());
+        handshake.push(matches!(emulator.timing(), Timing::Vip) as u8);
+        handshake.push(hash.len() as u8);
+        handshake.extend(hash.as_bytes());
+        handshake.extend((snapshot.len() as u32).to_be_bytes());
+        handshake.extend(snapshot);
+        stream.write_all(&handshake)?;
+        Self::connected(stream, delay)
+    }
+
+    //Connect to a host playing the same ROM and take on its emulator's state and settings
+    pub fn join(emulator: &mut Emulator, rom: &[u8], address: impl ToSocketAddrs) -> Result<Self, NetplayError> {
+        let mut stream = TcpStream::connect(address)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        let mut header = [0; 10];
+        stream.read_exact(&mut header)?;
+        let [m0, m1, m2, m3, version, delay, t0, t1, timing, hash_len] = header;
+        if &[m0, m1, m2, m3] != MAGIC || version != VERSION {
+            return Err(NetplayError::Handshake);
+        }
+        let mut hash = vec![0; hash_len as usize];
+        stream.read_exact(&mut hash)?;
+        let hash = String::from_utf8(hash).map_err(|_| NetplayError::Handshake)?;
+        if hash != rom_hash(rom) {
+            return Err(NetplayError::RomMismatch { expected: hash });
+        }
+        let mut len = [0; 4];
+        stream.read_exact(&mut len)?;
+        let mut snapshot = vec![0; u32::from_be_bytes(len) as usize];
+        stream.read_exact(&mut snapshot)?;
+        emulator.load_state(&State::from_snapshot_bytes(&snapshot)?)?;
+        emulator.set_ticks_per_frame(u16::from_be_bytes([t0, t1]) as usize);
+        emulator.set_timing(if timing == 1 { Timing::Vip } else { Timing::Instructions });
+        Self::connected(stream, delay)
+    }
+
+    fn connected(stream: TcpStream, delay: u8) -> Result<Self, NetplayError> {
+        stream.set_nodelay(true)?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        let idle = vec![0; delay as usize];
+        Ok(Self { stream, frame: 0, local: idle.clone().into(), remote: idle.into(), hashes: VecDeque::new() })
+    }
+
+    //Frames run together so far
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    //One frame in step: send keys (this player's, Emulator::keys_bitmask bits), wait for the other
+    //player's from delay frames ago if they have not come yet, then run the frame with both pressed
+    //Leave the emulator's keys and input source alone meanwhile, they are overwritten
+    pub fn run_frame(&mut self, emulator: &mut Emulator, keys: u16) -> Result<FrameOutput, NetplayError> {
+        let hash = self.frame.is_multiple_of(SYNC_INTERVAL).then(|| state_hash(emulator));
+        if let Some(hash) = hash {
+            self.hashes.push_back((self.frame, hash));
+        }
+        let mut message = [0; MESSAGE_SIZE];
+        message[..4].copy_from_slice(&(self.frame as u32).to_be_bytes());
+        message[4..6].copy_from_slice(&keys.to_be_bytes());
+        message[6..].copy_from_slice(&hash.unwrap_or(0).to_be_bytes());
+        self.stream.write_all(&message)?;
+        self.local.push_back(keys);
+        while self.remote.is_empty() {
+            self.receive()?;
+        }
+        let (local, remote) = (self.local.pop_front().unwrap_or(0), self.remote.pop_front().unwrap_or(0));
+        emulator.set_keys(local | remote);
+        let output = emulator.run_frame()?;
+        self.frame += 1;
+        Ok(output)
+    }
+
+    fn receive(&mut self) -> Result<(), NetplayError> {
+        let mut message = [0; MESSAGE_SIZE];
+        self.stream.read_exact(&mut message)?;
+        let frame = u32::from_be_bytes([message[0], message[1], message[2], message[3]]) as u64;
+        let keys = u16::from_be_bytes([message[4], message[5]]);
+        if frame.is_multiple_of(SYNC_INTERVAL) {
+            let theirs = u64::from_be_bytes(message[6..].try_into().unwrap_or_default());
+            match self.hashes.pop_front() {
+                Some((ours_at, ours)) if ours_at == frame && ours == theirs => {},
+                _ => return Err(NetplayError::Desync { frame }),
+            }
+        }
+        self.remote.push_back(keys);
+        Ok(())
+    }
+}
+
+//Emulator::state_hash folded to 64 bits to fit a message
+fn state_hash(emulator: &Emulator) -> u64 {
+    u64::from_str_radix(&emulator.state_hash()[..16], 16).unwrap_or_default()
+}
@@ -0,0 +1,13 @@
+//Random ROMs under random settings: cargo +nightly fuzz run rom
+#![no_main]
+
+use chip8_core::fuzz::FuzzInput;
+use chip8_core::Chip8Error;
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: FuzzInput| {
+    if let Err(error @ Chip8Error::Internal { .. }) = input.run() {
+        panic!("{}", error);
+    }
+});
@@ -0,0 +1,23 @@
+//Everything in one crate: the chip8-core emulator, plus chip8-frontends and chip8-tools behind features
+//Embedded and wasm builds turn off default features (or depend on chip8-core directly) to leave out SDL
+pub use chip8_core::*;
+
+#[cfg(feature = "frontends")]
+pub use chip8_frontends as frontend;
+#[cfg(feature = "frontends")]
+pub use chip8_frontends::{Chip8, FrontendChoice};
+#[cfg(feature = "tools")]
+pub use chip8_tools::{chat, library};
+#[cfg(feature = "gdb")]
+pub use chip8_tools::gdb;
+#[cfg(feature = "lsp")]
+pub use chip8_tools::lsp;
+#[cfg(feature = "netplay")]
+pub use chip8_tools::netplay;
+
+//What most programs need in one import: use chip8::prelude::*;
+pub mod prelude {
+    pub use chip8_core::prelude::*;
+    #[cfg(feature = "frontends")]
+    pub use chip8_frontends::{Chip8, FrontendChoice};
+}
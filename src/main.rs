@@ -1,115 +1,461 @@
-mod chip8;
+use chip8::*;
+use chip8::asm::assemble;
+use chip8::disasm::{analyze, disassemble, label_targets, labeled_listing, listing, symbolic_listing, Line};
+use chip8::compat::{check_rom, Limits, Verdict};
+use chip8::graph::graph;
+use chip8::library::{self, Action};
+use chip8::romdb;
 
-use crate::chip8::*;
+use serde_json::{json, Value};
 
 use std::env;
-use std::fs::File;
-use std::io::Read;
-
-use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
-use sdl2::pixels::Color;
-use sdl2::rect::Rect;
-use sdl2::render::Canvas;
-use sdl2::video::Window;
-
-const SCALE: u32 = 15;
-const WINDOW_WIDTH: u32 = (SCREEN_WIDTH as u32) * SCALE;
-const WINDOW_HEIGHT: u32 = (SCREEN_HEIGHT as u32) * SCALE;
-const TICKS_PER_FRAME: usize = 10;
-
-fn draw_screen(emulator: &Emulator, canvas: &mut Canvas<Window>){
-    canvas.set_draw_color(Color::RGB(0,0,0));
-    canvas.clear();
-
-    let screen_buffer = emulator.get_screen();
-    canvas.set_draw_color(Color::RGB(255,255,255));
-    for(i, pixel) in screen_buffer.iter().enumerate(){
-        if *pixel {
-            let x = (i % SCREEN_WIDTH) as u32;
-            let y = (i / SCREEN_WIDTH) as u32;
-
-            let rect = Rect::new((x*SCALE) as i32, (y*SCALE) as i32,SCALE,SCALE);
-            canvas.fill_rect(rect).unwrap();
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+#[cfg(feature = "sdl")]
+mod window;
+
+//run --headless and --terminal without a frame count, 10 seconds
+const DEFAULT_FRAMES: u32 = 600;
+//Anything else first is a ROM to play, these with the wrong arguments get the usage instead
+const SUBCOMMANDS: [&str; 11] = ["asm", "coredump", "dis", "disasm", "graph", "info", "lsp", "netplay", "organize", "script", "validate"];
+//Run every ROM in dir through the compatibility sandbox and print a table of results (or JSON)
+fn validate(dir: &Path, json: bool) {
+    let mut roms: Vec<_> = fs::read_dir(dir)
+        .unwrap_or_else(|err| fail(format_args!("Unable to read {}: {}", dir.display(), err)))
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| library::is_rom_path(path))
+        .collect();
+    roms.sort();
+
+    let names: Vec<String> = roms.iter()
+        .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+        .collect();
+    let width = names.iter().map(|name| name.len()).max().unwrap_or(0).max("ROM".len());
+
+    if !json {
+        println!("{:<width$}  {:>8}  RESULT", "ROM", "CYCLES", width = width);
+    }
+    let mut failures = 0;
+    let mut results = Vec::new();
+    for (path, name) in roms.iter().zip(&names) {
+        let (ok, cycles, result) = match fs::read(path) {
+            Ok(data) => {
+                let report = check_rom(&data, &Limits::default());
+                (report.verdict.is_ok(), report.cycles, report.verdict.to_string())
+            },
+            Err(err) => (false, 0, format!("unreadable: {}", err)),
+        };
+        if !ok {
+            failures += 1;
+        }
+        if json {
+            results.push(json!({"rom": name, "ok": ok, "cycles": cycles, "result": result}));
+        } else {
+            println!("{:<width$}  {:>8}  {}", name, cycles, result, width = width);
+        }
+    }
+    if json {
+        print_json(&json!({"roms": results, "total": roms.len(), "problems": failures}));
+    } else {
+        println!("{} ROMs, {} with problems", roms.len(), failures);
+    }
+}
+
+//Rename ROMs in dir to their database titles, move duplicates aside and write manifest.tsv
+fn organize(dir: &Path, dry_run: bool) {
+    let roms = library::scan(dir).expect("Unable to read directory");
+    let plan = library::plan(&roms, &RomDb::builtin(), dir);
+
+    for entry in &plan {
+        let name = entry.rom.path.file_name().unwrap().to_string_lossy();
+        match &entry.action {
+            Action::Keep if entry.title.is_none() => println!("unknown    {}", name),
+            Action::Keep => {},
+            Action::Rename { to } => println!("rename     {} -> {}", name, to.file_name().unwrap().to_string_lossy()),
+            Action::Duplicate { of, .. } => println!("duplicate  {} (same as {})", name, of.file_name().unwrap().to_string_lossy()),
+        }
+    }
+    let renamed = plan.iter().filter(|entry| matches!(entry.action, Action::Rename { .. })).count();
+    let duplicates = plan.iter().filter(|entry| matches!(entry.action, Action::Duplicate { .. })).count();
+    println!("{} ROMs, {} to rename, {} duplicates", plan.len(), renamed, duplicates);
+
+    if dry_run {
+        println!("Dry run, nothing changed");
+        return
+    }
+    library::apply(&plan).expect("Unable to organize ROMs");
+    fs::write(dir.join("manifest.tsv"), library::manifest(&plan)).expect("Unable to write manifest");
+}
+
+//Assemble Octo style source, the output defaults to the input with a .ch8 extension
+fn asm(input: &Path, output: Option<&Path>) {
+    let source = fs::read_to_string(input).expect("Unable to read source");
+    let output = output.map(Path::to_path_buf).unwrap_or_else(|| input.with_extension("ch8"));
+    match assemble(&source) {
+        Ok(assembly) => {
+            fs::write(&output, &assembly.bytes).expect("Unable to write ROM");
+            println!("Wrote {} bytes to {}", assembly.bytes.len(), output.display());
+        },
+        Err(err) => println!("{}:{}", input.display(), err),
+    }
+}
+
+//Print a listing of a ROM as loaded at 0x200, or a JSON array with one object per line
+//Symbolic follows the code from the entry point and lists the rest as data
+fn dis(rom: &Path, labels: bool, symbolic: bool, json: bool) {
+    let data = read_rom(rom);
+    let analysis = symbolic.then(|| analyze(&data, 0x200));
+    let lines = analysis.as_ref().map_or_else(|| disassemble(&data, 0x200), |analysis| analysis.lines.clone());
+    if !json {
+        match &analysis {
+            Some(analysis) => print!("{}", symbolic_listing(analysis)),
+            None => print!("{}", if labels { labeled_listing(&lines) } else { listing(&lines) }),
         }
+        return
+    }
+    let targets = match &analysis {
+        Some(analysis) => analysis.labels.clone(),
+        None if labels => label_targets(&lines).into_iter().map(|address| (address, format!("L{:03X}", address))).collect(),
+        None => Default::default(),
+    };
+    let objects: Vec<Value> = lines.iter().map(|line| {
+        let mut object = line_json(line);
+        if let Some(label) = targets.get(&line.address) {
+            object["label"] = json!(label);
+        }
+        object
+    }).collect();
+    print_json(&Value::Array(objects));
+}
+
+//The ROM's control flow graph in Graphviz DOT, with the bytes nothing reaches listed as comments.
+//As JSON, the blocks with their lines and edges, and the unreached ranges
+fn cfg(rom: &Path, json: bool) {
+    let data = read_rom(rom);
+    let analysis = analyze(&data, 0x200);
+    let graph = graph(&analysis);
+    if json {
+        let blocks: Vec<Value> = graph.blocks.values().map(|block| json!({
+            "start": block.start,
+            "name": graph.name(block.start),
+            "lines": block.lines.iter().map(line_json).collect::<Vec<_>>(),
+            "edges": block.edges.iter().map(|edge| json!({"target": edge.target, "flow": format!("{:?}", edge.flow).to_lowercase()})).collect::<Vec<_>>(),
+        })).collect();
+        let unreached: Vec<Value> = analysis.unreached().iter().map(|range| json!({"start": range.start, "end": range.end})).collect();
+        return print_json(&json!({"blocks": blocks, "unreached": unreached}))
+    }
+    print!("{}", graph.to_dot());
+    for range in analysis.unreached() {
+        println!("// unreached {:#05X}..{:#05X}", range.start, range.end);
+    }
+}
+
+//Run the ROM headless under a script (see chip8::script) and print what it printed and how it ended
+#[cfg(feature = "scripting")]
+fn script(rom: &Path, script: &Path, frames: u32) {
+    let source = fs::read_to_string(script).expect("Unable to read script");
+    let mut script = match chip8::script::Script::parse(&source) {
+        Ok(script) => script,
+        Err(err) => return println!("{}: {}", script.display(), err),
+    };
+    let data = fs::read(rom).expect("Unable to read ROM");
+    let mut emulator = Emulator::new();
+    RomDb::builtin().configure(&mut emulator, &data);
+    if let Err(err) = emulator.load_rom(&data) {
+        return println!("Unable to load {}: {}", rom.display(), err);
+    }
+    let result = script.run(&mut emulator, frames as u64);
+    for line in script.output() {
+        println!("{}", line);
+    }
+    match result {
+        Ok(frames) => println!("Ran {} frames", frames),
+        Err(err) => println!("{}", err),
+    }
+}
+
+//Print what a core dump holds: the error, registers, stack, recent PCs and the code round the PC
+fn coredump(path: &Path, json: bool) -> CoreDump {
+    let dump = CoreDump::load(path).unwrap_or_else(|err| fail(format_args!("{}: {}", path.display(), err)));
+    if !json {
+        print!("{}", dump);
+        return dump
+    }
+    let state = &dump.state;
+    print_json(&json!({
+        "error": dump.error().map(|error| error.to_string()),
+        "ticks": dump.ticks,
+        "pc": state.program_counter,
+        "i": state.i_register,
+        "dt": state.delay_timer,
+        "st": state.sound_timer,
+        "v": state.v_registers,
+        "stack": state.stack,
+        "recent_pcs": dump.recent_pcs,
+        "code": dump.code().iter().map(line_json).collect::<Vec<_>>(),
+    }));
+    dump
+}
+
+//Serve a core dump to gdb, which can then read its memory and registers (target remote address)
+#[cfg(feature = "gdb")]
+fn debug_core_dump(dump: &CoreDump, address: &str) {
+    let mut emulator = Emulator::new();
+    if let Err(err) = emulator.load_core_dump(dump) {
+        return println!("Unable to open the core dump: {}", err);
+    }
+    println!("Waiting for gdb on {}", address);
+    if let Err(err) = chip8::gdb::listen(&mut emulator, address) {
+        println!("gdb: {}", err);
+    }
+}
+
+//Size, hash, database title, first instruction and the extensions a ROM uses
+fn info(rom: &Path, json: bool) {
+    let data = read_rom(rom);
+    let lines = disassemble(&data, 0x200);
+    let title = RomDb::builtin().lookup_rom(&data).map(|entry| entry.title.clone());
+    let report = check_rom(&data, &Limits::default());
+    //The test run stops at the first extension instruction it runs. The scan also picks up data that
+    //decodes as one, so on its own it only says what the ROM might use
+    let instructions: Vec<_> = lines.iter().filter_map(|line| line.instruction).collect();
+    let mut scanned = Vec::new();
+    if instructions.iter().any(Instruction::is_schip) {
+        scanned.push("SUPER-CHIP");
     }
-    canvas.present();
-}
-
-fn key_btn(key: Keycode) -> Option<usize>{
-    match key {
-        Keycode::Num1 => Some(0x1),
-        Keycode::Num2 => Some(0x2),
-        Keycode::Num3 => Some(0x3),
-        Keycode::Num4 => Some(0xC),
-        Keycode::Q => Some(0x4),
-        Keycode::W => Some(0x5),
-        Keycode::E => Some(0x6),
-        Keycode::R => Some(0xD),
-        Keycode::A => Some(0x7),
-        Keycode::S => Some(0x8),
-        Keycode::D => Some(0x9),
-        Keycode::F => Some(0xE),
-        Keycode::Z => Some(0xA),
-        Keycode::X => Some(0x0),
-        Keycode::C => Some(0xB),
-        Keycode::V => Some(0xF),
+    if instructions.iter().any(Instruction::is_xochip) {
+        scanned.push("XO-CHIP");
+    }
+    if instructions.iter().any(Instruction::is_megachip) {
+        scanned.push("MegaChip");
+    }
+    if instructions.iter().any(Instruction::is_chip8x) {
+        scanned.push("CHIP-8X");
+    }
+    let needs = match report.verdict {
+        Verdict::NeedsSchip { .. } => Some("SUPER-CHIP"),
+        Verdict::NeedsXoChip { .. } => Some("XO-CHIP"),
+        Verdict::NeedsMegaChip { .. } => Some("MegaChip"),
+        Verdict::NeedsChip8x { .. } => Some("CHIP-8X"),
         _ => None,
+    };
+    if json {
+        return print_json(&json!({
+            "size": data.len(),
+            "sha1": romdb::rom_hash(&data),
+            "title": title,
+            "entry_point": lines.first().map(line_json),
+            "extensions": needs.into_iter().collect::<Vec<_>>(),
+            "may_use": scanned,
+            "test_run": report.verdict.to_string(),
+            "cycles": report.cycles,
+        }))
     }
+    let extensions = match needs {
+        Some(extension) => extension.to_string(),
+        None if scanned.is_empty() => "none".to_string(),
+        None => format!("none in the test run, the code may use {}", scanned.join(" or ")),
+    };
+
+    println!("Size:        {} bytes", data.len());
+    println!("SHA-1:       {}", romdb::rom_hash(&data));
+    println!("Title:       {}", title.as_deref().unwrap_or("unknown"));
+    match lines.first() {
+        Some(line) => println!("Entry point: {}", line),
+        None => println!("Entry point: 0x200"),
+    }
+    println!("Extensions:  {}", extensions);
+    println!("Test run:    {} ({} cycles)", report.verdict, report.cycles);
+}
+
+//One line of a listing, the instruction is null for a data word
+fn line_json(line: &Line) -> Value {
+    json!({
+        "address": line.address,
+        "opcode": line.opcode,
+        "instruction": line.instruction.map(|instruction| instruction.to_string()),
+    })
+}
+
+fn print_json(value: &Value) {
+    println!("{}", serde_json::to_string_pretty(value).expect("JSON values always serialize"));
+}
+
+fn read_rom(rom: &Path) -> Vec<u8> {
+    fs::read(rom).unwrap_or_else(|err| fail(format_args!("Unable to read {}: {}", rom.display(), err)))
+}
+
+//Errors go to stderr with a non-zero exit status, so stdout only ever has the report (or its JSON)
+fn fail(message: impl fmt::Display) -> ! {
+    eprintln!("{}", message);
+    process::exit(1)
 }
 
 fn main() {
-    let args: Vec<_> = env::args().collect();
-    if args.len() != 2 {
-        println!("Usage: cargo run path/to/game");
+    let mut args: Vec<_> = env::args().collect();
+    //--json can go anywhere, validate, dis, graph, coredump and info print JSON instead of text
+    let json = args.iter().any(|arg| arg == "--json");
+    args.retain(|arg| arg != "--json");
+    #[cfg(feature = "lsp")]
+    if args.len() == 2 && args[1] == "lsp" {
+        if let Err(err) = chip8::lsp::run(std::io::stdin().lock(), std::io::stdout().lock()) {
+            eprintln!("Language server stopped: {}", err);
+        }
         return
     }
-
-    let mut rom = File::open(&args[1]).expect("Unopen to open file");
-    let mut buffer = Vec::new();
-    let mut chip8 = Emulator::new();
-
-    rom.read_to_end(&mut buffer).unwrap();
-    chip8.load_rom(&buffer);
-
-    let sdl_context = sdl2::init().unwrap();
-    let video = sdl_context.video().unwrap();
-    let window = video
-        .window("Chip-8 Emulator",WINDOW_WIDTH,WINDOW_HEIGHT)
-        .position_centered()
-        .opengl()
-        .build()
-        .unwrap();
-
-    let mut canvas = window.into_canvas().present_vsync().build().unwrap();
-    canvas.clear();
-    canvas.present();
-
-    let mut event_pump = sdl_context.event_pump().unwrap();
-
-    'gameloop: loop {
-        for evt in event_pump.poll_iter() {
-            match evt {
-                Event::Quit {..} => {
-                    break 'gameloop;
-                },
-                Event::KeyDown{keycode: Some(key), ..} => {
-                    if let Some(k) = key_btn(key) {
-                        chip8.keypress(k,true);
-                    }
-                },
-                Event::KeyUp {keycode: Some(key), ..} => {
-                    if let Some(k) = key_btn(key) {
-                        chip8.keypress(k,false);
-                    }
-                },
-                _ => ()
-            }
+    if args.len() >= 3 && args[1] == "asm" {
+        match &args[3..] {
+            [] => return asm(Path::new(&args[2]), None),
+            [flag, output] if flag == "-o" => return asm(Path::new(&args[2]), Some(Path::new(output))),
+            _ => {},
         }
-        for _ in 0..TICKS_PER_FRAME {
-            chip8.tick();
+    }
+    //dis is the short name
+    if (args.len() == 3 || args.len() == 4 && (args[3] == "--labels" || args[3] == "--symbolic")) && (args[1] == "disasm" || args[1] == "dis") {
+        let flag = args.get(3).map(String::as_str);
+        dis(Path::new(&args[2]), flag == Some("--labels"), flag == Some("--symbolic"), json);
+        return
+    }
+    if args.len() == 3 && args[1] == "graph" {
+        cfg(Path::new(&args[2]), json);
+        return
+    }
+    if args.len() == 3 && args[1] == "validate" {
+        validate(Path::new(&args[2]), json);
+        return
+    }
+    if (args.len() == 3 || args.len() == 4 && args[3] == "--dry-run") && args[1] == "organize" {
+        organize(Path::new(&args[2]), args.len() == 4);
+        return
+    }
+    #[cfg(feature = "scripting")]
+    if (args.len() == 4 || args.len() == 5) && args[1] == "script" {
+        let frames = args.get(4).map_or(Some(DEFAULT_FRAMES), |frames| frames.parse().ok());
+        if let Some(frames) = frames {
+            script(Path::new(&args[2]), Path::new(&args[3]), frames);
+            return
         }
-        chip8.timers();
-        draw_screen(&chip8, &mut canvas);
     }
-}
\ No newline at end of file
+    #[cfg(all(feature = "netplay", feature = "sdl"))]
+    if (args.len() == 5 || args.len() == 6 && args[2] == "host") && args[1] == "netplay" && (args[2] == "host" || args[2] == "join") {
+        let delay = args.get(5).map_or(Some(chip8::netplay::DEFAULT_DELAY), |delay| delay.parse().ok());
+        if let Some(delay) = delay {
+            window::netplay(args[2] == "host", &args[3], Path::new(&args[4]), delay);
+            return
+        }
+    }
+    if args.len() == 3 && args[1] == "coredump" {
+        coredump(Path::new(&args[2]), json);
+        return
+    }
+    #[cfg(feature = "gdb")]
+    if args.len() == 5 && args[1] == "coredump" && args[3] == "--gdb" {
+        let dump = coredump(Path::new(&args[2]), json);
+        debug_core_dump(&dump, &args[4]);
+        return
+    }
+    if args.len() == 3 && args[1] == "info" {
+        info(Path::new(&args[2]), json);
+        return
+    }
+    if args.get(1).is_some_and(|command| SUBCOMMANDS.contains(&command.as_str())) {
+        usage()
+    }
+    //run is optional, cargo run path/to/game plays it as well
+    match args.get(1..) {
+        Some([command, rom, flags @ ..]) if command == "run" => run(rom, flags),
+        Some([rom, flags @ ..]) if rom != "run" => run(rom, flags),
+        _ => usage(),
+    }
+}
+
+//On stderr with a non-zero exit status, it is only printed for arguments that make no sense
+fn usage() -> ! {
+    #[cfg(feature = "sdl")]
+    eprintln!("Usage: cargo run [run] path/to/game [--chat] [--low-power] [--azerty] [--eti660] [--vip-timing] [--chip8x]");
+    #[cfg(feature = "sdl")]
+    eprintln!("       cargo run run path/to/game --headless [frames]");
+    #[cfg(not(feature = "sdl"))]
+    eprintln!("Usage: cargo run run path/to/game --headless [frames]");
+    eprintln!("       cargo run run path/to/game --terminal [frames]");
+    #[cfg(feature = "tui")]
+    eprintln!("       cargo run --features tui run path/to/game --tui");
+    #[cfg(feature = "tui")]
+    eprintln!("       cargo run --features tui run path/to/game --debug");
+    eprintln!("       cargo run info path/to/game [--json]");
+    eprintln!("       cargo run coredump path/to/game.c8dump [--json]");
+    #[cfg(feature = "gdb")]
+    eprintln!("       cargo run --features gdb coredump path/to/game.c8dump --gdb address:port");
+    eprintln!("       cargo run disasm path/to/game [--labels | --symbolic] [--json]");
+    eprintln!("       cargo run graph path/to/game [--json] > game.dot");
+    eprintln!("       cargo run asm path/to/source.8o [-o path/to/out.ch8]");
+    eprintln!("       cargo run validate path/to/roms [--json]");
+    eprintln!("       cargo run organize path/to/roms [--dry-run]");
+    #[cfg(feature = "lsp")]
+    eprintln!("       cargo run --features lsp lsp");
+    #[cfg(all(feature = "netplay", feature = "sdl"))]
+    eprintln!("       cargo run --features netplay netplay host|join address:port path/to/game [delay]");
+    #[cfg(feature = "scripting")]
+    eprintln!("       cargo run --features scripting script path/to/game path/to/script [frames]");
+    process::exit(2)
+}
+
+//Play rom in the window, or with --headless / --terminal for a number of frames and print where it stopped
+fn run(rom: &str, flags: &[String]) {
+    let frontend = match flags {
+        [flag, frames @ ..] if flag == "--headless" || flag == "--terminal" => {
+            let frames = match frames {
+                [] => Some(DEFAULT_FRAMES),
+                [frames] => frames.parse().ok(),
+                _ => None,
+            };
+            let Some(frames) = frames else { usage() };
+            if flag == "--headless" { FrontendChoice::Headless { frames } } else { FrontendChoice::Terminal { frames } }
+        },
+        //Plays in the terminal instead of a window, e.g. over SSH
+        #[cfg(feature = "tui")]
+        [flag] if flag == "--tui" => FrontendChoice::Tui,
+        //The debugger, with its panes and breakpoints kept in path/to/game.c8debug
+        #[cfg(feature = "tui")]
+        [flag] if flag == "--debug" => {
+            if let Err(err) = Chip8::debug_rom_file(rom) {
+                println!("Unable to debug {}: {}", rom, err);
+            }
+            return
+        },
+        #[cfg(not(feature = "sdl"))]
+        _ => return println!("Built without the window (the sdl feature), play with --headless, --terminal or --tui"),
+        #[cfg(feature = "sdl")]
+        flags => {
+            let chat = flags.iter().any(|flag| flag == "--chat");
+            let low_power = flags.iter().any(|flag| flag == "--low-power");
+            let azerty = flags.iter().any(|flag| flag == "--azerty");
+            //ETI-660 programs load at 0x600
+            let start_address = if flags.iter().any(|flag| flag == "--eti660") { ETI_660_START_ADDRESS } else { START_ADDRESS };
+            //Paced by the VIP's machine cycles, for music and demo ROMs
+            let timing = if flags.iter().any(|flag| flag == "--vip-timing") { Timing::Vip } else { Timing::Instructions };
+            //For the VP-590 colour board
+            let chip8x = flags.iter().any(|flag| flag == "--chip8x");
+            if flags.iter().any(|flag| !["--chat", "--low-power", "--azerty", "--eti660", "--vip-timing", "--chip8x"].contains(&flag.as_str())) {
+                usage()
+            }
+            return window::play(Path::new(rom), chat, low_power, azerty, start_address, timing, chip8x)
+        },
+    };
+    match Chip8::run_rom_file(rom, frontend) {
+        Ok(emulator) if matches!(frontend, FrontendChoice::Headless { .. }) => {
+            print!("{}", emulator.frame_buffer().to_ascii('#', ' '));
+            let status = match emulator.state() {
+                _ if emulator.has_exited() => "exited".to_string(),
+                EmulatorState::Finished { reason, .. } => format!("finished ({})", reason),
+                _ => "running".to_string(),
+            };
+            println!("Stopped at 0x{:03X}, {}", emulator.program_counter(), status);
+        },
+        Ok(_) => {},
+        Err(err) => println!("Unable to play {}: {}", rom, err),
+    }
+}
@@ -0,0 +1,355 @@
+//The SDL window: play with every hotkey, and netplay, with the sdl feature
+
+use chip8::*;
+use chip8::chat::{ChatConfig, ChatInput};
+use chip8::export::Format;
+use chip8::frontend::{draw_phosphor, draw_screen, key_for, Speaker};
+use chip8::power::{AudioMode, PowerGovernor, PowerProfile};
+use chip8::recorder::{Recorder, RecorderConfig};
+use chip8::screenshot::ImageFormat;
+
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::render::Canvas;
+use sdl2::video::Window;
+
+const SCALE: u32 = 15;
+//Frames kept for rewinding, 10 seconds at 60 FPS
+const REWIND_DEPTH: usize = 600;
+const WINDOW_WIDTH: u32 = (SCREEN_WIDTH as u32) * SCALE;
+const WINDOW_HEIGHT: u32 = (SCREEN_HEIGHT as u32) * SCALE;
+#[cfg(feature = "png")]
+const SCREENSHOT_FORMAT: ImageFormat = ImageFormat::Png;
+#[cfg(not(feature = "png"))]
+const SCREENSHOT_FORMAT: ImageFormat = ImageFormat::Ppm;
+
+//Frames without draw activity keep what is already in the window, unless phosphor is fading them out
+fn draw_if_changed(emulator: &mut Emulator, canvas: &mut Canvas<Window>, phosphor: Option<&mut Phosphor>){
+    if let Some(phosphor) = phosphor {
+        emulator.take_dirty_rows();
+        draw_phosphor(emulator, canvas, &Palette::default(), phosphor).unwrap();
+    } else if emulator.screen_changed() {
+        emulator.take_dirty_rows();
+        draw_screen(emulator, canvas, &Palette::default()).unwrap();
+    }
+}
+
+//Save state slots in a directory next to the ROM, only in memory when it cannot be used
+fn open_slots(rom_path: &Path, rom: &[u8]) -> StateSlots {
+    StateSlots::open(StateSlots::dir_for_rom(rom_path), rom_hash(rom)).unwrap_or_else(|err| {
+        println!("Save slots are not kept: {}", err);
+        StateSlots::new(rom_hash(rom))
+    })
+}
+
+//The cheats in a file next to the ROM, none if there is no file
+fn load_cheats(rom_path: &Path) -> Cheats {
+    let Ok(text) = fs::read_to_string(Cheats::path_for_rom(rom_path)) else { return Cheats::new() };
+    Cheats::parse(&text).unwrap_or_else(|err| {
+        println!("Cheats are off: {}", err);
+        Cheats::new()
+    })
+}
+
+//Left next to the ROM when it halts on an error, for cargo run coredump
+fn write_core_dump(emulator: &Emulator, rom_path: &Path) {
+    let path = CoreDump::path_for_rom(rom_path);
+    match emulator.core_dump().save(&path) {
+        Ok(()) => eprintln!("Core dump written to {}", path.display()),
+        Err(err) => eprintln!("Unable to write {}: {}", path.display(), err),
+    }
+}
+
+//Two players on two machines: the host waits on address for the other to join with the same ROM, then both
+//windows play in step. No hotkeys, pausing or rewinding one side would leave the other behind
+#[cfg(feature = "netplay")]
+pub fn netplay(host: bool, address: &str, rom: &Path, delay: u8) {
+    use chip8::netplay::Netplay;
+
+    let data = fs::read(rom).expect("Unable to read ROM");
+    let mut emulator = Emulator::new();
+    RomDb::builtin().configure(&mut emulator, &data);
+    if let Err(err) = emulator.load_rom(&data) {
+        return println!("Unable to load {}: {}", rom.display(), err);
+    }
+    println!("{} {}", if host { "Waiting for the other player on" } else { "Joining" }, address);
+    let session = if host { Netplay::host(&mut emulator, &data, address, delay) } else { Netplay::join(&mut emulator, &data, address) };
+    let mut session = match session {
+        Ok(session) => session,
+        Err(err) => return println!("Unable to start: {}", err),
+    };
+
+    let sdl_context = sdl2::init().unwrap();
+    let window = sdl_context.video().unwrap()
+        .window("Chip-8 Emulator (netplay)", WINDOW_WIDTH, WINDOW_HEIGHT)
+        .position_centered()
+        .build()
+        .unwrap();
+    let mut canvas = window.into_canvas().build().unwrap();
+    let mut event_pump = sdl_context.event_pump().unwrap();
+    let keymap = KeyMap::qwerty();
+    //This player's keys, the emulator's are both players' together
+    let mut keys = 0u16;
+    let mut clock = Clock::default();
+    loop {
+        clock.wait(&emulator);
+        for event in event_pump.poll_iter() {
+            match event {
+                Event::Quit {..} => return,
+                Event::KeyDown{keycode, scancode, ..} => if let Some(key) = key_for(&keymap, keycode, scancode) {
+                    keys |= 1 << key.index();
+                },
+                Event::KeyUp{keycode, scancode, ..} => if let Some(key) = key_for(&keymap, keycode, scancode) {
+                    keys &= !(1 << key.index());
+                },
+                _ => (),
+            }
+        }
+        if let Err(err) = session.run_frame(&mut emulator, keys) {
+            return println!("Stopped at frame {}: {}", session.frame(), err);
+        }
+        draw_if_changed(&mut emulator, &mut canvas, None);
+    }
+}
+
+//The desktop window with every hotkey, see the match on events below
+pub fn play(rom_path: &Path, chat: bool, low_power: bool, azerty: bool, start_address: u16, timing: Timing, chip8x: bool) {
+    let mut rom_path = rom_path.to_path_buf();
+    let mut rom = File::open(&rom_path).expect("Unopen to open file");
+    let mut buffer = Vec::new();
+    let mut chip8 = Emulator::new();
+
+    rom.read_to_end(&mut buffer).unwrap();
+    //The window draws MegaChip's colour screen, so its ROMs can switch to it
+    chip8.set_megachip(true);
+    chip8.set_chip8x(chip8x);
+    //A finished ROM stays on screen and the reason is printed once
+    chip8.set_end_detection(true);
+    //Known ROMs get the quirks they need
+    RomDb::builtin().configure(&mut chip8, &buffer);
+    //Before loading, so the ROM patches among them apply
+    chip8.set_cheats(load_cheats(&rom_path));
+    if let Err(err) = chip8.load_rom_at(start_address, &buffer) {
+        println!("Unable to load {}: {}", rom_path.display(), err);
+        return
+    }
+    chip8.set_timing(timing);
+    //SCHIP high scores (FX75) are kept next to the ROM
+    chip8.set_flag_store(FlagFile::for_rom(&rom_path));
+
+    let sdl_context = sdl2::init().unwrap();
+    let video = sdl_context.video().unwrap();
+    let window = video
+        .window("Chip-8 Emulator",WINDOW_WIDTH,WINDOW_HEIGHT)
+        .position_centered()
+        .opengl()
+        .build()
+        .unwrap();
+
+    //Paced by the clock alone, vsync on top would pace drawn and undrawn frames differently
+    let mut canvas = window.into_canvas().build().unwrap();
+    canvas.clear();
+    canvas.present();
+
+    let mut event_pump = sdl_context.event_pump().unwrap();
+    //Plays silently without an audio device
+    let speaker = match sdl_context.audio().and_then(|audio| Speaker::open(&audio)) {
+        Ok(speaker) => Some(speaker),
+        Err(err) => {
+            println!("No sound: {}", err);
+            None
+        },
+    };
+    //--chat takes key commands from stdin as well, e.g. piped from a chat bot
+    let mut chat_input = chat.then(|| ChatInput::from_stdin(ChatConfig::default()));
+    let mut rewind = Rewind::new(REWIND_DEPTH);
+    let mut slots = open_slots(&rom_path, &buffer);
+    let mut rewinding = false;
+    let mut recorder: Option<Recorder> = None;
+    //--low-power (or F9 while playing) runs slower and draws less for weak or battery powered hosts
+    let mut power = PowerGovernor::new(if low_power { PowerProfile::low_power() } else { PowerProfile::full() });
+    let keymap = if azerty { KeyMap::azerty() } else { KeyMap::qwerty() };
+    //Blends frames against XOR flicker when on
+    let mut phosphor: Option<Phosphor> = None;
+    //Far behind (e.g. the window was dragged), it skips ahead rather than rushing to catch up
+    let mut clock = Clock::default();
+
+    'gameloop: loop {
+        clock.wait(&chip8);
+        for evt in event_pump.poll_iter() {
+            match evt {
+                Event::Quit {..} => {
+                    break 'gameloop;
+                },
+                //F2 saves to the selected slot, F4 loads it back and F3 selects the next one
+                Event::KeyDown{keycode: Some(Keycode::F2), ..} => match slots.quick_save(&chip8) {
+                    Ok(()) => println!("Saved slot {}", slots.selected()),
+                    Err(err) => println!("Unable to save slot {}: {}", slots.selected(), err),
+                },
+                Event::KeyDown{keycode: Some(Keycode::F3), ..} => println!("Slot {}", slots.select_next()),
+                Event::KeyDown{keycode: Some(Keycode::F4), ..} => match slots.quick_load(&mut chip8) {
+                    Ok(()) => {
+                        rewind.clear();
+                        println!("Loaded slot {}", slots.selected());
+                    },
+                    Err(err) => println!("Unable to load slot {}: {}", slots.selected(), err),
+                },
+                //F12 dumps the state next to the ROM for bug reports
+                Event::KeyDown{keycode: Some(Keycode::F12), ..} => {
+                    let path = rom_path.with_extension("state.toml");
+                    match fs::write(&path, chip8.export_state(Format::Toml)) {
+                        Ok(()) => println!("Wrote state to {}", path.display()),
+                        Err(err) => println!("Unable to write {}: {}", path.display(), err),
+                    }
+                },
+                //F11 saves a screenshot next to the ROM
+                Event::KeyDown{keycode: Some(Keycode::F11), ..} => {
+                    let path = rom_path.with_extension(SCREENSHOT_FORMAT.extension());
+                    let scale = WINDOW_WIDTH as usize / chip8.screen_width();
+                    match fs::write(&path, chip8.screenshot(SCREENSHOT_FORMAT, &Palette::default(), scale)) {
+                        Ok(()) => println!("Wrote screenshot to {}", path.display()),
+                        Err(err) => println!("Unable to write {}: {}", path.display(), err),
+                    }
+                },
+                //F10 starts a GIF recording, pressing it again writes it next to the ROM
+                Event::KeyDown{keycode: Some(Keycode::F10), ..} => match recorder.take() {
+                    Some(recording) => {
+                        let path = rom_path.with_extension("gif");
+                        match recording.to_gif().map(|gif| fs::write(&path, gif)) {
+                            Some(Ok(())) => println!("Wrote recording to {}", path.display()),
+                            Some(Err(err)) => println!("Unable to write {}: {}", path.display(), err),
+                            None => println!("Nothing was recorded"),
+                        }
+                    },
+                    None => {
+                        recorder = Some(Recorder::new(RecorderConfig::default()));
+                        println!("Recording, press F10 again to stop");
+                    },
+                },
+                Event::KeyDown{keycode: Some(Keycode::F9), ..} => {
+                    let profile = if power.selected() == PowerProfile::full() { PowerProfile::low_power() } else { PowerProfile::full() };
+                    power.select(profile);
+                    println!("Power profile: {:?}", profile);
+                },
+                //F7 turns phosphor persistence on and off
+                Event::KeyDown{keycode: Some(Keycode::F7), ..} => {
+                    phosphor = match phosphor {
+                        Some(_) => None,
+                        None => Some(Phosphor::default()),
+                    };
+                    //The window keeps the faded frame otherwise
+                    draw_screen(&chip8, &mut canvas, &Palette::default()).unwrap();
+                    println!("Phosphor: {}", if phosphor.is_some() { "on" } else { "off" });
+                },
+                //F8 prints how fast the emulator is running, to tune the instructions per frame
+                Event::KeyDown{keycode: Some(Keycode::F8), ..} => println!("{}", chip8.metrics()),
+                //P pauses and resumes, the last frame stays on screen
+                Event::KeyDown{keycode: Some(Keycode::P), repeat: false, ..} => {
+                    if chip8.is_paused() {
+                        chip8.resume();
+                        println!("Resumed");
+                    } else {
+                        chip8.pause();
+                        println!("Paused");
+                    }
+                },
+                //F5 starts the ROM over
+                Event::KeyDown{keycode: Some(Keycode::F5), ..} => {
+                    chip8.reload_rom(&buffer).unwrap();
+                    rewind.clear();
+                },
+                //Dropping a ROM on the window plays it instead, with the same quirks unless the database knows better
+                Event::DropFile{filename, ..} => {
+                    let loaded = fs::read(&filename).map_err(|err| err.to_string()).and_then(|data| {
+                        let mut emulator = Emulator::new();
+                        emulator.set_quirks(*chip8.quirks());
+                        emulator.set_timing(chip8.timing());
+                        emulator.set_megachip(chip8.megachip_enabled());
+                        emulator.set_chip8x(chip8.chip8x_enabled());
+                        emulator.set_end_detection(true);
+                        RomDb::builtin().configure(&mut emulator, &data);
+                        emulator.set_cheats(load_cheats(Path::new(&filename)));
+                        emulator.load_rom_at(chip8.start_address(), &data).map_err(|err| err.to_string())?;
+                        emulator.set_flag_store(FlagFile::for_rom(Path::new(&filename)));
+                        Ok((emulator, data))
+                    });
+                    match loaded {
+                        Ok((emulator, data)) => {
+                            chip8 = emulator;
+                            buffer = data;
+                            rom_path = PathBuf::from(filename);
+                            slots = open_slots(&rom_path, &buffer);
+                            rewind.clear();
+                            println!("Playing {}", rom_path.display());
+                        },
+                        Err(err) => println!("Unable to load {}: {}", filename, err),
+                    }
+                },
+                //Holding backspace runs time backwards
+                Event::KeyDown{keycode: Some(Keycode::Backspace), ..} => rewinding = true,
+                Event::KeyUp{keycode: Some(Keycode::Backspace), ..} => rewinding = false,
+                Event::KeyDown{keycode, scancode, ..} => {
+                    if let Some(k) = key_for(&keymap, keycode, scancode) {
+                        chip8.keypress(k,true);
+                    }
+                },
+                Event::KeyUp {keycode, scancode, ..} => {
+                    if let Some(k) = key_for(&keymap, keycode, scancode) {
+                        chip8.keypress(k,false);
+                    }
+                },
+                _ => ()
+            }
+        }
+        if rewinding {
+            if let Err(err) = rewind.rewind(&mut chip8, 1) {
+                println!("Unable to rewind: {}", err);
+                rewinding = false;
+            }
+            draw_if_changed(&mut chip8, &mut canvas, phosphor.as_mut());
+            continue;
+        }
+        if let Some(speaker) = &speaker {
+            let audio = chip8.audio_state();
+            speaker.update(match power.profile().audio {
+                _ if matches!(chip8.state(), EmulatorState::Paused | EmulatorState::Halted { .. } | EmulatorState::Finished { .. }) => AudioState::default(),
+                AudioMode::Full => audio,
+                AudioMode::Beep => AudioState { pattern: AudioState::default().pattern, ..audio },
+                AudioMode::Off => AudioState::default(),
+            });
+        }
+        if chip8.is_paused() {
+            draw_if_changed(&mut chip8, &mut canvas, phosphor.as_mut());
+            continue;
+        }
+        if let Some(chat_input) = &mut chat_input {
+            chat_input.frame(&mut chip8);
+        }
+        let (ticks, render) = power.frame();
+        //A faulted or finished ROM stays on screen as it was, the reason is reported once
+        if !matches!(chip8.state(), EmulatorState::Halted { .. } | EmulatorState::Finished { .. }) {
+            rewind.record(&chip8);
+            if let Err(err) = chip8.run_frame_with(ticks) {
+                eprintln!("{}", err);
+                write_core_dump(&chip8, &rom_path);
+            }
+            if let EmulatorState::Finished { pc, reason } = chip8.state() {
+                eprintln!("Program finished: {} at 0x{:03X}", reason, pc);
+            }
+        }
+        if let Some(recorder) = &mut recorder {
+            recorder.capture(&chip8);
+        }
+        for event in chip8.take_events() {
+            eprintln!("{}", event);
+        }
+        //Changes made on skipped frames are drawn with the next drawn one
+        if render {
+            draw_if_changed(&mut chip8, &mut canvas, phosphor.as_mut());
+        }
+    }
+}
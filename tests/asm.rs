@@ -0,0 +1,94 @@
+//The assembler's output for each construct, as the words it emits, and the errors it reports
+
+use chip8::asm::{assemble, assemble_at};
+
+//Big-endian instruction words of an assembled program
+fn words(source: &str) -> Vec<u16> {
+    let bytes = assemble(source).unwrap().bytes;
+    bytes.chunks(2).map(|word| u16::from_be_bytes([word[0], *word.get(1).unwrap_or(&0)])).collect()
+}
+
+fn error(source: &str) -> String {
+    assemble(source).unwrap_err().to_string()
+}
+
+#[test]
+fn labels_resolve_backward_and_forward() {
+    let assembly = assemble(": start\nv0 := 5\njump start\njump end\n: end\nclear").unwrap();
+    assert_eq!(assembly.labels["start"], 0x200);
+    assert_eq!(assembly.labels["end"], 0x206);
+    assert_eq!(words(": start\nv0 := 5\njump start\njump end\n: end\nclear"), [0x6005, 0x1200, 0x1206, 0x00E0]);
+}
+
+#[test]
+fn forward_calls_and_i_are_fixed_up() {
+    assert_eq!(words(":call sub\ni := data\nsub\n: sub\nreturn\n: data\n0xFF"), [0x2206, 0xA208, 0x2206, 0x00EE, 0xFF00]);
+    //All 16 bits of the operand word
+    assert_eq!(words("i := long data\n:org 0x1234\n: data\n1"), {
+        let mut expected = vec![0xF000, 0x1234];
+        expected.resize((0x1234 - 0x200) / 2, 0);
+        expected.push(0x0100);
+        expected
+    });
+}
+
+#[test]
+fn undefined_and_duplicate_labels_are_errors() {
+    assert_eq!(error("v0 := 1\njump nowhere"), "line 2: undefined label 'nowhere'");
+    assert_eq!(error(": a\n: a"), "line 2: label 'a' defined twice");
+}
+
+#[test]
+fn if_then_skips_one_statement() {
+    assert_eq!(words("if v0 == 1 then v1 := 2"), [0x4001, 0x6102]);
+    assert_eq!(words("if v0 != v1 then clear"), [0x5010, 0x00E0]);
+}
+
+#[test]
+fn if_else_end_jumps_round_each_branch() {
+    //skip, jump to else, then branch, jump past else, else branch
+    assert_eq!(words("if v0 == 1 begin\nv1 := 1\nelse\nv1 := 2\nend"), [0x3001, 0x1208, 0x6101, 0x120A, 0x6102]);
+    assert_eq!(words("if v0 == 1 begin\nv1 := 1\nend"), [0x3001, 0x1206, 0x6101]);
+    assert_eq!(error("else"), "line 1: 'else' without 'if ... begin'");
+    assert_eq!(error("if v0 == 1 begin\nclear"), "line 1: unclosed block");
+}
+
+#[test]
+fn loops_jump_back_and_while_breaks_out() {
+    //The while skips its break while the condition holds
+    assert_eq!(words("loop\nv0 += 1\nwhile v0 != 8\nagain"), [0x7001, 0x4008, 0x1208, 0x1200]);
+    assert_eq!(error("while v0 == 1"), "line 1: 'while' outside of a loop");
+    assert_eq!(error("again"), "line 1: 'again' without 'loop'");
+}
+
+#[test]
+fn org_pads_and_moves_labels() {
+    let assembly = assemble("clear\n:org 0x210\n: there\njump there").unwrap();
+    assert_eq!(assembly.labels["there"], 0x210);
+    assert_eq!(assembly.bytes.len(), 0x12);
+    assert!(assembly.bytes[2..0x10].iter().all(|&byte| byte == 0));
+    //Only the code, not the padding, counts as the :org line's
+    assert_eq!(assembly.spans.iter().map(|span| (span.line, span.address, span.len)).collect::<Vec<_>>(), [(1, 0x200, 2), (4, 0x210, 2)]);
+    assert_eq!(error("clear\nclear\n:org 0x202"), "line 3: :org 0x202 is behind the current address 0x204");
+}
+
+#[test]
+fn programs_may_fill_memory_but_not_run_past_it() {
+    assert_eq!(assemble(":org 0xFFFE\n1 2").unwrap().bytes.len(), 0xFFFE - 0x200 + 2);
+    assert_eq!(error(":org 0xFFFE\n1 2 3 4"), "line 2: program runs past the end of memory (0xFFFF)");
+    assert_eq!(assemble_at("clear", 0xFFFF).unwrap_err().line, 1);
+}
+
+#[test]
+fn targets_past_12_bits_are_out_of_range() {
+    let message = "target out of range: 'far' is 0x1000, past 0xFFF (i := long reaches it)";
+    //Forward, fixed up at the end
+    assert_eq!(error("jump far\n:org 0x1000\n: far\nclear"), format!("line 1: {}", message));
+    //Backward, known at once
+    assert_eq!(error(":org 0x1000\n: far\nclear\n:call far"), format!("line 4: {}", message));
+    assert_eq!(error("i := 0x1234"), "line 1: target out of range: '0x1234' is 0x1234, past 0xFFF (i := long reaches it)");
+    //Blocks jump to where they end
+    assert!(error(":org 0xFFC\nif v0 == 1 begin\nclear\nclear\nend").contains("target out of range"));
+    assert!(error(":org 0x1000\nloop\nagain").contains("target out of range"));
+    assert!(assemble("i := long far\n:org 0x1000\n: far\nclear").is_ok());
+}
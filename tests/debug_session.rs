@@ -0,0 +1,79 @@
+//The .c8debug file the debugger keeps its panes and breakpoints in, and moving panes round the layout
+
+use chip8::{DebugSession, Emulator, Layout, Pane};
+
+#[test]
+fn a_session_reads_back_what_it_wrote() {
+    let text = "# saved by the debugger\nlayout screen,registers | disassembly | memory,sprite\nmemory 0x300\nbreak 0x2A4\nbreak 0x200\nwatch 3F0\n";
+    let session = DebugSession::parse(text).unwrap();
+    let columns = [vec![Pane::Screen, Pane::Registers], vec![Pane::Disassembly], vec![Pane::Memory, Pane::Sprite]];
+    assert_eq!(session.layout.columns(), columns);
+    assert_eq!(session.layout.hidden(), [Pane::Trace]);
+    assert_eq!(session.memory_address, 0x300);
+    assert_eq!(session.breakpoints.iter().copied().collect::<Vec<_>>(), [0x200, 0x2A4]);
+    assert_eq!(session.to_text(), "layout screen,registers | disassembly | memory,sprite\nmemory 0x300\nbreak 0x200\nbreak 0x2A4\nwatch 0x3F0\n");
+    assert_eq!(DebugSession::parse(&session.to_text()).unwrap(), session);
+    //Nothing set is the default layout
+    assert_eq!(DebugSession::parse("").unwrap().layout, Layout::default());
+}
+
+#[test]
+fn malformed_lines_are_reported_with_their_number() {
+    for (text, line) in [
+        ("layout screen | screen", 1),
+        ("memory 0x300\nlayout screen,tape", 2),
+        ("layout |", 1),
+        ("\nbreak 0xG00", 2),
+        ("zoom 2", 1),
+        ("memory", 1),
+    ] {
+        assert_eq!(DebugSession::parse(text).unwrap_err().line, line, "{}", text);
+    }
+}
+
+#[test]
+fn panes_move_between_and_within_columns() {
+    let mut layout = Layout::default();
+    layout.move_across(Pane::Registers, 1);
+    assert_eq!(layout.position(Pane::Registers), Some((1, 1)));
+    assert_eq!(layout.columns()[1], [Pane::Disassembly, Pane::Registers, Pane::Trace]);
+    layout.move_within(Pane::Registers, -5);
+    assert_eq!(layout.position(Pane::Registers), Some((1, 0)));
+    //Past the left edge is a new column, and the one left empty goes
+    layout.move_across(Pane::Screen, -1);
+    assert_eq!(layout.to_string(), "screen | registers,disassembly,trace | memory,sprite");
+    //A pane alone in an end column stays put
+    layout.move_across(Pane::Screen, -1);
+    assert_eq!(layout.to_string(), "screen | registers,disassembly,trace | memory,sprite");
+    layout.move_across(Pane::Screen, 1);
+    //At the same row it had
+    assert_eq!(layout.to_string(), "screen,registers,disassembly,trace | memory,sprite");
+}
+
+#[test]
+fn every_pane_but_the_last_can_be_closed_and_opened_again() {
+    let mut layout = Layout::default();
+    for pane in [Pane::Screen, Pane::Registers, Pane::Disassembly, Pane::Trace, Pane::Memory] {
+        assert!(layout.close(pane));
+    }
+    assert_eq!(layout.columns(), [vec![Pane::Sprite]]);
+    assert!(!layout.close(Pane::Sprite));
+    assert!(!layout.close(Pane::Screen));
+    layout.open(Pane::Screen, 4);
+    layout.open(Pane::Screen, 0);
+    assert_eq!(layout.to_string(), "sprite,screen");
+    assert_eq!(layout.hidden(), [Pane::Registers, Pane::Disassembly, Pane::Memory, Pane::Trace]);
+}
+
+#[test]
+fn breakpoints_go_onto_the_emulator_and_come_back() {
+    let mut session = DebugSession::parse("break 0x204\nwatch 0x300").unwrap();
+    let mut emulator = Emulator::new();
+    session.apply(&mut emulator);
+    assert_eq!(emulator.breakpoints().pcs().collect::<Vec<_>>(), [0x204]);
+    assert_eq!(emulator.breakpoints().watchpoints().collect::<Vec<_>>(), [0x300]);
+    emulator.clear_breakpoint(0x204);
+    emulator.set_breakpoint(0x208);
+    session.record(&emulator);
+    assert_eq!(session.to_text(), "layout screen,registers | disassembly,trace | memory,sprite\nmemory 0x000\nbreak 0x208\nwatch 0x300\n");
+}
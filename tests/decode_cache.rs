@@ -0,0 +1,51 @@
+//Self-modifying code under the decode cache: a write to any byte of a cached instruction, the operand
+//word of the 4 byte ones and wrapped writes included, has it decoded again
+
+use chip8::Emulator;
+
+//Runs rom for ticks ticks (after poking in the bytes past it) with and without the cache, the pair of I
+//registers it ends with
+fn i_after(rom: &[u8], pokes: &[(u16, u8)], megachip: bool, ticks: usize) -> [u16; 2] {
+    [false, true].map(|decode_cache| {
+        let mut emulator = Emulator::new();
+        emulator.set_megachip(megachip);
+        emulator.set_decode_cache(decode_cache);
+        emulator.load_rom(rom).unwrap();
+        for &(address, value) in pokes {
+            emulator.poke(address, value);
+        }
+        for _ in 0..ticks {
+            emulator.tick().unwrap();
+        }
+        emulator.i_register()
+    })
+}
+
+#[test]
+fn writing_the_operand_of_f000_nnnn_decodes_it_again() {
+    //I := 0x0300, then FX55 writes 07 55 over its operand and jumps back
+    let rom = [0xF0, 0x00, 0x03, 0x00, 0xA2, 0x02, 0x60, 0x07, 0x61, 0x55, 0xF1, 0x55, 0x12, 0x00];
+    assert_eq!(i_after(&rom, &[], false, 1), [0x300; 2]);
+    assert_eq!(i_after(&rom, &[], false, 7), [0x755; 2]);
+}
+
+#[test]
+fn writing_the_operand_of_01nn_nnnn_decodes_it_again() {
+    //MegaChip mode on, I := 0x123456, then FX55 writes 07 08 over its low word and jumps back to it
+    let rom = [0x00, 0x11, 0x01, 0x12, 0x34, 0x56, 0xA2, 0x04, 0x60, 0x07, 0x61, 0x08, 0xF1, 0x55, 0x12, 0x02];
+    assert_eq!(i_after(&rom, &[], true, 2), [0x3456; 2]);
+    assert_eq!(i_after(&rom, &[], true, 8), [0x0708; 2]);
+}
+
+#[test]
+fn writes_past_the_end_of_ram_decode_it_again() {
+    //F000 NNNN at the very end of RAM with its operand at 0, and a jump back to 0x202 after it
+    let pokes = [(0xFFE, 0xF0), (0xFFF, 0x00), (0x000, 0x03), (0x001, 0x00), (0x002, 0x12), (0x003, 0x02)];
+    //Run it, then FX55 writes 07 55 over the operand and runs it again: from 0 and from 0xFFF, which
+    //writes 00 at the end of RAM and wraps to 0 for the rest
+    let from_start = [0x1F, 0xFE, 0xA0, 0x00, 0x60, 0x07, 0x61, 0x55, 0xF1, 0x55, 0x1F, 0xFE];
+    let wrapping = [0x1F, 0xFE, 0xAF, 0xFF, 0x60, 0x00, 0x61, 0x07, 0x62, 0x55, 0xF2, 0x55, 0x1F, 0xFE];
+    assert_eq!(i_after(&from_start, &pokes, false, 2), [0x300; 2]);
+    assert_eq!(i_after(&from_start, &pokes, false, 9), [0x755; 2]);
+    assert_eq!(i_after(&wrapping, &pokes, false, 10), [0x755; 2]);
+}
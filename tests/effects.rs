@@ -0,0 +1,49 @@
+//What an instruction reports it wrote, and the watchpoints that go by it
+
+use chip8::{Break, Emulator, MemoryWrite};
+
+//I := 0xFFE, then FX55 of V0-V3, which runs off the end of 4 KB and carries on at 0
+const WRAPPING_STORE: [u8; 4] = [0xAF, 0xFE, 0xF3, 0x55];
+
+fn after_store(watchpoint: Option<u16>) -> (Emulator, Option<Break>) {
+    let mut emulator = Emulator::new();
+    emulator.load_rom(&WRAPPING_STORE).unwrap();
+    if let Some(address) = watchpoint {
+        emulator.set_watchpoint(address);
+    }
+    emulator.tick().unwrap();
+    let stopped = emulator.debug_step().unwrap();
+    (emulator, stopped)
+}
+
+#[test]
+fn a_store_in_one_piece_is_one_range() {
+    let mut emulator = Emulator::new();
+    emulator.load_rom(&[0xA3, 0x00, 0xF3, 0x55]).unwrap();
+    emulator.tick().unwrap();
+    emulator.tick().unwrap();
+    let effects = emulator.last_effects();
+    assert_eq!(effects.memory_written, Some(MemoryWrite { address: 0x300, len: 4 }));
+    assert_eq!(effects.memory_wrapped, None);
+}
+
+#[test]
+fn a_store_that_wraps_is_two_ranges() {
+    let (emulator, _) = after_store(None);
+    let effects = emulator.last_effects();
+    assert_eq!(effects.memory_written, Some(MemoryWrite { address: 0xFFE, len: 2 }));
+    assert_eq!(effects.memory_wrapped, Some(MemoryWrite { address: 0, len: 2 }));
+    assert!(effects.wrote_memory(0xFFF) && effects.wrote_memory(0x001));
+    assert!(!effects.wrote_memory(0x002) && !effects.wrote_memory(0x800) && !effects.wrote_memory(0xFFD));
+}
+
+#[test]
+fn watchpoints_fire_only_on_the_bytes_a_wrapping_store_wrote() {
+    for address in [0xFFE, 0x000, 0x001] {
+        let (_, stopped) = after_store(Some(address));
+        assert!(matches!(stopped, Some(Break::Watchpoint { pc: 0x202, address: watched, .. }) if watched == address), "{:#05X}", address);
+    }
+    for address in [0x002, 0x200, 0x800, 0xFFD] {
+        assert_eq!(after_store(Some(address)).1, None, "{:#05X}", address);
+    }
+}
@@ -0,0 +1,63 @@
+//Programs that end in a loop they cannot leave, seen as EmulatorState::Finished when end detection is on
+
+use chip8::{Emulator, EmulatorState, EndReason};
+
+//Jump to self
+const JUMP_TO_SELF: [u8; 2] = [0x12, 0x00];
+//V0 := 5 and back, round with the same registers every time
+const ENDLESS_LOOP: [u8; 4] = [0x60, 0x05, 0x12, 0x00];
+//V0 += 1 and back, a different V0 every time
+const COUNTER: [u8; 4] = [0x70, 0x01, 0x12, 0x00];
+
+fn run(rom: &[u8], end_detection: bool, ticks: usize) -> Emulator {
+    let mut emulator = Emulator::new();
+    emulator.set_end_detection(end_detection);
+    emulator.load_rom(rom).unwrap();
+    for _ in 0..ticks {
+        emulator.tick().unwrap();
+    }
+    emulator
+}
+
+#[test]
+fn it_is_off_by_default() {
+    for rom in [JUMP_TO_SELF.as_slice(), &ENDLESS_LOOP] {
+        let mut emulator = Emulator::new();
+        emulator.load_rom(rom).unwrap();
+        for _ in 0..100 {
+            emulator.tick().unwrap();
+        }
+        assert_eq!(emulator.state(), EmulatorState::Running);
+        assert_eq!(Emulator::builder().rom(rom).build().unwrap().state(), EmulatorState::Running);
+    }
+}
+
+#[test]
+fn a_jump_to_self_finishes() {
+    let mut emulator = run(&JUMP_TO_SELF, true, 1);
+    assert_eq!(emulator.state(), EmulatorState::Finished { pc: 0x200, reason: EndReason::JumpToSelf });
+    //Until reset, ticks leave it there
+    emulator.tick().unwrap();
+    assert!(matches!(emulator.state(), EmulatorState::Finished { .. }));
+    emulator.reset();
+    assert_eq!(emulator.state(), EmulatorState::Running);
+
+    assert_eq!(run(&JUMP_TO_SELF, false, 100).state(), EmulatorState::Running);
+}
+
+#[test]
+fn a_loop_that_comes_round_unchanged_finishes() {
+    //Once round to see the registers, the second time they are the same
+    assert_eq!(run(&ENDLESS_LOOP, true, 2).state(), EmulatorState::Running);
+    let emulator = run(&ENDLESS_LOOP, true, 4);
+    assert_eq!(emulator.state(), EmulatorState::Finished { pc: 0x200, reason: EndReason::EndlessLoop });
+
+    assert_eq!(run(&ENDLESS_LOOP, false, 100).state(), EmulatorState::Running);
+}
+
+#[test]
+fn a_loop_that_changes_registers_keeps_running() {
+    let emulator = run(&COUNTER, true, 1000);
+    assert_eq!(emulator.state(), EmulatorState::Running);
+    assert_eq!(emulator.v_register(0), (500 % 256) as u8);
+}
@@ -0,0 +1,89 @@
+//The faults that halt a program (Chip8Error), and what I out of range does instead of faulting
+
+use chip8::{Access, Chip8Error, Emulator, EmulatorState, Event, MemoryProtection, Violation, Warning};
+
+//Runs rom until it faults, or for ticks ticks
+fn run(rom: &[u8], ticks: usize) -> (Emulator, Result<(), Chip8Error>) {
+    let mut emulator = Emulator::new();
+    emulator.load_rom(rom).unwrap();
+    let result = (0..ticks).try_for_each(|_| emulator.tick());
+    (emulator, result)
+}
+
+#[test]
+fn a_key_above_f_is_invalid() {
+    //V0 := 0x10, then EX9E and EXA1 of it
+    for skip in [0x9E, 0xA1] {
+        let (mut emulator, result) = run(&[0x60, 0x10, 0xE0, skip], 2);
+        let error = Chip8Error::InvalidKey { pc: 0x202, key: 0x10 };
+        assert_eq!(result, Err(error));
+        assert_eq!(emulator.state(), EmulatorState::Halted { error });
+        //Until reset, every tick reports it again
+        assert_eq!(emulator.tick(), Err(error));
+    }
+}
+
+#[test]
+fn the_seventeenth_call_overflows_the_stack() {
+    //Calls itself
+    let (emulator, result) = run(&[0x22, 0x00], 16);
+    assert_eq!(result, Ok(()));
+    assert_eq!(emulator.stack_depth(), 16);
+    let (_, result) = run(&[0x22, 0x00], 17);
+    assert_eq!(result, Err(Chip8Error::StackOverflow { pc: 0x200 }));
+}
+
+#[test]
+fn a_return_with_nothing_on_the_stack_underflows() {
+    let (_, result) = run(&[0x00, 0xEE], 1);
+    assert_eq!(result, Err(Chip8Error::StackUnderflow { pc: 0x200 }));
+    //Call, return, return again
+    let (_, result) = run(&[0x22, 0x04, 0x00, 0xEE, 0x00, 0xEE], 4);
+    assert_eq!(result, Err(Chip8Error::StackUnderflow { pc: 0x202 }));
+}
+
+#[test]
+fn i_past_the_end_of_ram_wraps_round_to_0() {
+    //I := 0xFFF, I += 3 with V0, then FX65 of V0 from 0x1002, which is 0x002 in 4 KB
+    let (emulator, result) = run(&[0xAF, 0xFF, 0x60, 0x03, 0xF0, 0x1E, 0xF0, 0x65], 4);
+    assert_eq!(result, Ok(()));
+    assert_eq!(emulator.i_register(), 0x1002);
+    assert_eq!(emulator.v_register(0), emulator.memory()[0x002]);
+
+    //FX33 of V0 = 123 from I := 0xFFF writes 1 at the end of RAM and 2, 3 at 0 and 1, and says it wrapped
+    let mut emulator = Emulator::new();
+    emulator.set_invariant_checks(true);
+    emulator.load_rom(&[0x60, 123, 0xAF, 0xFF, 0xF0, 0x33]).unwrap();
+    (0..3).try_for_each(|_| emulator.tick()).unwrap();
+    assert_eq!((emulator.memory()[0xFFF], emulator.memory()[0], emulator.memory()[1]), (1, 2, 3));
+    assert!(emulator.take_events().contains(&Event::Violation(Violation::MemoryWrap { pc: 0x204, address: 0xFFF, len: 3 })));
+}
+
+#[test]
+fn fx33_checks_memory_like_the_other_stores() {
+    //V0 := 123, I := 0x100, FX33
+    let rom = [0x60, 123, 0xA1, 0x00, 0xF0, 0x33];
+    let mut emulator = Emulator::new();
+    emulator.set_strict_mode(true);
+    emulator.load_rom(&rom).unwrap();
+    (0..3).try_for_each(|_| emulator.tick()).unwrap();
+    let warning = Warning::ReservedMemoryAccess { pc: 0x204, address: 0x100, access: Access::Write };
+    assert!(emulator.take_events().contains(&Event::Warning(warning)));
+
+    let mut emulator = Emulator::new();
+    emulator.set_memory_protection(MemoryProtection { interpreter: true, rom: false });
+    emulator.load_rom(&rom).unwrap();
+    let result = (0..3).try_for_each(|_| emulator.tick());
+    assert_eq!(result, Err(Chip8Error::ProtectedWrite { pc: 0x204, address: 0x100 }));
+    assert_eq!(emulator.memory()[0x100], 0);
+}
+
+#[test]
+fn fx29_and_fx30_use_the_low_digit_of_vx() {
+    //V0 := 0x1A, then FX29 and FX30 of it
+    let (emulator, _) = run(&[0x60, 0x1A, 0xF0, 0x29], 2);
+    let map = emulator.memory_map();
+    assert_eq!(emulator.i_register(), map.font_address + 0xA * 5);
+    let (emulator, _) = run(&[0x60, 0x1A, 0xF0, 0x30], 2);
+    assert_eq!(emulator.i_register(), map.big_font_address + 0xA * 10);
+}
@@ -0,0 +1,114 @@
+//The packed display: one 128 bit word per row and plane, read back per pixel and per row, with sprites
+//drawn across the middle and the end of a row's word and into either plane
+
+use chip8::{Emulator, Quirks};
+
+fn screen(hires: bool, clip_sprites: bool) -> Emulator {
+    let mut emulator = Emulator::new();
+    emulator.set_quirks(Quirks { clip_sprites, display_wait: false, ..Quirks::default() });
+    //00FF switches to 128x64, 1200 is only there to load something
+    emulator.load_rom(if hires { &[0x00, 0xFF] } else { &[0x12, 0x00] }).unwrap();
+    if hires {
+        emulator.tick().unwrap();
+    }
+    emulator
+}
+
+//FN01 to select planes, then DXYN of sprite (each selected plane's rows one after the other) at (x, y),
+//returns VF
+fn draw(emulator: &mut Emulator, x: u8, y: u8, sprite: &[u8], planes: u8) -> u8 {
+    let rows = sprite.len() as u8 / planes.count_ones() as u8;
+    emulator.poke_range(0x300, sprite);
+    emulator.poke_range(0x200, &[0xF0 | planes, 0x01, 0xD0, 0x10 | rows]);
+    emulator.set_program_counter(0x200);
+    emulator.set_i_register(0x300);
+    emulator.set_v_register(0, x);
+    emulator.set_v_register(1, y);
+    emulator.tick().unwrap();
+    emulator.tick().unwrap();
+    emulator.v_register(0xF)
+}
+
+fn lit_columns(emulator: &Emulator, y: usize) -> Vec<usize> {
+    let screen = emulator.frame_buffer();
+    (0..screen.width()).filter(|&x| screen.is_lit(x, y)).collect()
+}
+
+#[test]
+fn pixels_read_back_from_the_packed_rows() {
+    let mut emulator = screen(false, true);
+    draw(&mut emulator, 0, 0, &[0b1000_0001], 1);
+    draw(&mut emulator, 63, 31, &[0b1000_0000], 1);
+    let screen = emulator.frame_buffer();
+    assert_eq!((screen.pixel(0, 0), screen.pixel(7, 0), screen.pixel(1, 0)), (1, 1, 0));
+    assert_eq!(screen.pixel(63, 31), 1);
+    //Column 0 is the top bit, low resolution only uses the top 64
+    assert_eq!(screen.plane_rows(0)[0], (1 << 127) | (1 << 120));
+    assert_eq!(screen.plane_rows(0)[31], 1 << 64);
+    assert_eq!(screen.plane_rows(0).len(), 32);
+    //Off the screen is blank
+    assert_eq!((screen.pixel(64, 0), screen.pixel(0, 32), screen.pixel(usize::MAX, 0)), (0, 0, 0));
+}
+
+#[test]
+fn low_resolution_sprites_wrap_or_clip_at_column_64() {
+    let mut wrapping = screen(false, false);
+    draw(&mut wrapping, 60, 0, &[0xFF], 1);
+    assert_eq!(lit_columns(&wrapping, 0), [0, 1, 2, 3, 60, 61, 62, 63]);
+    //Nothing leaks into the unused low half of the word
+    assert_eq!(wrapping.frame_buffer().plane_rows(0)[0] as u64, 0);
+
+    let mut clipped = screen(false, true);
+    draw(&mut clipped, 60, 0, &[0xFF], 1);
+    assert_eq!(lit_columns(&clipped, 0), [60, 61, 62, 63]);
+    //The start wraps either way
+    draw(&mut clipped, 64 + 8, 1, &[0x80], 1);
+    assert_eq!(lit_columns(&clipped, 1), [8]);
+}
+
+#[test]
+fn high_resolution_sprites_cross_the_middle_of_the_word_and_wrap_at_its_end() {
+    let mut emulator = screen(true, false);
+    draw(&mut emulator, 60, 0, &[0xFF], 1);
+    assert_eq!(lit_columns(&emulator, 0), (60..68).collect::<Vec<_>>());
+    draw(&mut emulator, 124, 1, &[0xFF], 1);
+    assert_eq!(lit_columns(&emulator, 1), [0, 1, 2, 3, 124, 125, 126, 127]);
+    assert_eq!(emulator.frame_buffer().plane_rows(0)[1], 0xF << 124 | 0xF);
+
+    let mut clipped = screen(true, true);
+    draw(&mut clipped, 124, 1, &[0xFF], 1);
+    assert_eq!(lit_columns(&clipped, 1), [124, 125, 126, 127]);
+}
+
+#[test]
+fn drawing_xors_and_reports_only_real_overlaps() {
+    let mut emulator = screen(true, false);
+    assert_eq!(draw(&mut emulator, 60, 0, &[0xFF], 1), 0);
+    //Next to it across the middle of the word, no pixel in common
+    assert_eq!(draw(&mut emulator, 52, 0, &[0xFF], 1), 0);
+    assert_eq!(lit_columns(&emulator, 0), (52..68).collect::<Vec<_>>());
+    //One pixel in common on each side of the middle
+    assert_eq!(draw(&mut emulator, 63, 0, &[0xC0], 1), 1);
+    assert_eq!(lit_columns(&emulator, 0), [52, 53, 54, 55, 56, 57, 58, 59, 60, 61, 62, 65, 66, 67]);
+    //The wrapped part collides too
+    draw(&mut emulator, 0, 1, &[0x80], 1);
+    assert_eq!(draw(&mut emulator, 121, 1, &[0xFF], 1), 1);
+    assert_eq!(lit_columns(&emulator, 1), (121..128).collect::<Vec<_>>());
+}
+
+#[test]
+fn the_planes_are_separate_words() {
+    let mut emulator = screen(false, true);
+    assert_eq!(draw(&mut emulator, 0, 0, &[0b1100_0000], 1), 0);
+    //Plane 2 over the same pixels is no collision
+    assert_eq!(draw(&mut emulator, 1, 0, &[0b1100_0000], 2), 0);
+    let screen = emulator.frame_buffer();
+    assert_eq!((screen.pixel(0, 0), screen.pixel(1, 0), screen.pixel(2, 0)), (1, 3, 2));
+    assert_eq!(screen.plane_rows(1)[0], 0b11 << 125);
+
+    //Both planes, a row for each: a collision in either one counts
+    assert_eq!(draw(&mut emulator, 0, 0, &[0b0000_0000, 0b0010_0000], 3), 1);
+    assert_eq!(emulator.frame_buffer().pixel(2, 0), 0);
+    assert_eq!(draw(&mut emulator, 0, 0, &[0b0000_0001, 0b0000_0001], 3), 0);
+    assert_eq!(emulator.frame_buffer().pixel(7, 0), 3);
+}
@@ -0,0 +1,98 @@
+//Golden traces of the in-tree quirk test ROM under each quirk preset
+//A change to a preset (or to how an instruction behaves under it) shows up as a diff of tests/golden/*.trace
+//Run with UPDATE_GOLDEN=1 to rewrite the traces after a deliberate change
+
+use chip8::asm::assemble;
+use chip8::{rom_hash, Emulator, Quirks};
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+const STEPS: usize = 40;
+
+fn presets() -> [(&'static str, Quirks); 4] {
+    [
+        ("default", Quirks::default()),
+        ("cosmac_vip", Quirks::cosmac_vip()),
+        ("schip", Quirks::schip()),
+        ("xochip", Quirks::xochip()),
+    ]
+}
+
+//One line per instruction with the registers after it ran, then a hash of the final registers, RAM and screen
+fn trace(rom: &[u8], quirks: Quirks) -> String {
+    let mut emulator = Emulator::new();
+    emulator.set_quirks(quirks);
+    emulator.load_rom(rom).unwrap();
+
+    let mut trace = String::new();
+    for _ in 0..STEPS {
+        let pc = emulator.program_counter();
+        let line = emulator.disassemble(pc..pc + 4)[0];
+        emulator.tick().unwrap();
+        let registers: Vec<String> = (0..16).map(|x| format!("{:02X}", emulator.v_register(x))).collect();
+        trace.push_str(&format!("{:<24} V={} I={:04X}\n", line.to_string(), registers.join(" "), emulator.i_register()));
+    }
+    trace.push_str(&format!("state {}\n", rom_hash(&machine_bytes(&emulator))));
+    trace
+}
+
+//The machine itself rather than a saved state, so a change to a state format leaves the traces alone
+fn machine_bytes(emulator: &Emulator) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&emulator.program_counter().to_be_bytes());
+    bytes.extend_from_slice(&emulator.i_register().to_be_bytes());
+    bytes.extend((0..16).map(|x| emulator.v_register(x)));
+    bytes.extend_from_slice(&[emulator.delay_timer(), emulator.sound_timer(), emulator.stack().len() as u8]);
+    bytes.extend(emulator.stack().iter().flat_map(|address| address.to_be_bytes()));
+    bytes.extend_from_slice(emulator.memory());
+    bytes.extend(emulator.frame_buffer().pixels());
+    bytes
+}
+
+#[test]
+fn quirk_presets_match_golden_traces() {
+    let source = fs::read_to_string("tests/roms/quirks.8o").unwrap();
+    let rom = assemble(&source).unwrap().bytes;
+    let update = env::var_os("UPDATE_GOLDEN").is_some();
+
+    let mut mismatches = Vec::new();
+    for (name, quirks) in presets() {
+        let actual = trace(&rom, quirks);
+        let path = Path::new("tests/golden").join(format!("{}.trace", name));
+        if update {
+            fs::write(&path, &actual).unwrap();
+            continue;
+        }
+        let expected = fs::read_to_string(&path).unwrap_or_default();
+        if actual != expected {
+            let first_difference = actual
+                .lines()
+                .zip(expected.lines())
+                .position(|(actual, expected)| actual != expected)
+                .unwrap_or(actual.lines().count().min(expected.lines().count()));
+            mismatches.push(format!(
+                "{}: first difference at line {}\n  expected: {}\n  actual:   {}",
+                path.display(),
+                first_difference + 1,
+                expected.lines().nth(first_difference).unwrap_or("<end of trace>"),
+                actual.lines().nth(first_difference).unwrap_or("<end of trace>"),
+            ));
+        }
+    }
+    assert!(mismatches.is_empty(), "golden traces differ (rerun with UPDATE_GOLDEN=1 if intended):\n{}", mismatches.join("\n"));
+}
+
+//The presets must actually disagree on this ROM, otherwise the traces prove nothing
+#[test]
+fn quirk_presets_produce_distinct_traces() {
+    let source = fs::read_to_string("tests/roms/quirks.8o").unwrap();
+    let rom = assemble(&source).unwrap().bytes;
+    let traces: Vec<String> = presets().iter().map(|(_, quirks)| trace(&rom, *quirks)).collect();
+    for (i, a) in traces.iter().enumerate() {
+        for b in &traces[i + 1..] {
+            assert_ne!(a, b);
+        }
+    }
+}
@@ -0,0 +1,38 @@
+//The keyboard presets: which host keys reach which keypad keys, and through SDL key names in the window
+
+use chip8::{Key, KeyMap};
+
+#[test]
+fn qwerty_binds_the_block_under_1234() {
+    let map = KeyMap::qwerty();
+    let keys: Vec<Option<Key>> = "1234qwerasdfzxcv".chars().map(|host| map.get(host)).collect();
+    assert_eq!(keys, "123C456D789EA0BF".chars().map(Key::from_digit).collect::<Vec<_>>());
+    assert_eq!(map.get('Q'), Some(Key::Key4));
+}
+
+#[test]
+fn azerty_binds_the_unshifted_top_row() {
+    let map = KeyMap::azerty();
+    let keys: Vec<Option<Key>> = "&é\"'azerqsdfwxcv".chars().map(|host| map.get(host)).collect();
+    assert_eq!(keys, "123C456D789EA0BF".chars().map(Key::from_digit).collect::<Vec<_>>());
+    //Shifted, the top row types the digits
+    assert_eq!("1234".chars().map(|host| map.get(host)).collect::<Vec<_>>(), "123C".chars().map(Key::from_digit).collect::<Vec<_>>());
+}
+
+#[cfg(feature = "sdl")]
+#[test]
+fn the_window_finds_azerty_keys_by_their_sdl_names() {
+    use chip8::frontend::key_for;
+    use sdl2::keyboard::{Keycode, Scancode};
+
+    let map = KeyMap::azerty();
+    assert_eq!(key_for(&map, Some(Keycode::Ampersand), Some(Scancode::Num1)), Some(Key::Key1));
+    //sdl2 has no Keycode for é, the event only has the scancode
+    assert_eq!(key_for(&map, None, Some(Scancode::Num2)), Some(Key::Key2));
+    assert_eq!(key_for(&map, Some(Keycode::Quotedbl), Some(Scancode::Num3)), Some(Key::Key3));
+    assert_eq!(key_for(&map, Some(Keycode::Quote), Some(Scancode::Num4)), Some(Key::KeyC));
+    //By the character, not the position: A is where QWERTY has Q
+    assert_eq!(key_for(&map, Some(Keycode::A), Some(Scancode::Q)), Some(Key::Key4));
+    assert_eq!(key_for(&map, Some(Keycode::Space), Some(Scancode::Space)), None);
+    assert_eq!(key_for(&map, None, None), None);
+}
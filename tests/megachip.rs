@@ -0,0 +1,53 @@
+//MegaChip mode's 4 byte 01NN NNNN: skipped, decoded and disassembled as one instruction only in the mode
+
+use chip8::Emulator;
+
+//After a first instruction: skip if V0 = 0, then 01NN NNNN in MegaChip mode (I := 0x126101) or an unknown
+//0112 and V1 := 1 outside it, then V2 := 2 and a jump to itself
+const BODY: [u8; 10] = [0x30, 0x00, 0x01, 0x12, 0x61, 0x01, 0x62, 0x02, 0x12, 0x0A];
+
+fn emulator(megachip: bool, decode_cache: bool) -> Emulator {
+    let mut emulator = Emulator::new();
+    emulator.set_megachip(megachip);
+    emulator.set_decode_cache(decode_cache);
+    //0011 turns MegaChip mode on, 0000 does nothing
+    let first = if megachip { [0x00, 0x11] } else { [0x00, 0x00] };
+    emulator.load_rom(&[first.as_slice(), &BODY].concat()).unwrap();
+    emulator.tick().unwrap();
+    emulator
+}
+
+#[test]
+fn skips_take_01nn_as_4_bytes_only_in_megachip_mode() {
+    for decode_cache in [false, true] {
+        let mut mega = emulator(true, decode_cache);
+        mega.tick().unwrap();
+        assert_eq!(mega.program_counter(), 0x208);
+        mega.tick().unwrap();
+        assert_eq!((mega.v_register(1), mega.v_register(2)), (0, 2));
+
+        let mut classic = emulator(false, decode_cache);
+        classic.tick().unwrap();
+        assert_eq!(classic.program_counter(), 0x206);
+        classic.tick().unwrap();
+        assert_eq!(classic.v_register(1), 1);
+    }
+}
+
+#[test]
+fn decoding_agrees_with_skips() {
+    let mut mega = emulator(true, true);
+    let lines = mega.disassemble(0x204..0x208);
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0].to_string(), "0x204: LDHI I, 0x126101");
+    mega.set_program_counter(0x204);
+    mega.tick().unwrap();
+    assert_eq!((mega.i_register(), mega.program_counter()), (0x6101, 0x208));
+
+    let mut classic = emulator(false, true);
+    let lines = classic.disassemble(0x204..0x208);
+    assert_eq!(lines.iter().map(|line| line.to_string()).collect::<Vec<_>>(), ["0x204: DW 0x0112", "0x206: LD V1, 0x01"]);
+    classic.set_program_counter(0x204);
+    assert!(classic.tick().is_err());
+    assert_eq!(classic.program_counter(), 0x204);
+}
@@ -0,0 +1,42 @@
+//How much RAM an emulator gets: 4 KB unless the ROM or the settings call for XO-CHIP's 64 KB
+
+use chip8::{Emulator, MemoryMap, Quirks, RandomModel, RomSettings};
+
+#[test]
+fn the_default_is_the_vips_4_kb() {
+    assert_eq!(Emulator::new().memory_map(), MemoryMap::chip8());
+    assert_eq!(Emulator::new().memory().len(), 4096);
+    assert_eq!(Emulator::builder().build().unwrap().memory_map().size, 4096);
+}
+
+#[test]
+fn xochip_quirks_and_settings_get_64_kb() {
+    let quirks = Quirks { random: RandomModel::Seeded(1), ..Quirks::xochip() };
+    assert_eq!(Emulator::builder().quirks(quirks).build().unwrap().memory_map().size, 0x10000);
+    //An explicit map wins
+    assert_eq!(Emulator::builder().quirks(quirks).memory_map(MemoryMap::chip8()).build().unwrap().memory_map().size, 4096);
+
+    let settings: RomSettings = "xochip,ticks=100".parse().unwrap();
+    let mut emulator = Emulator::new();
+    settings.apply(&mut emulator);
+    assert_eq!(emulator.memory_map().size, 0x10000);
+    assert_eq!("schip".parse::<RomSettings>().unwrap().memory_size, None);
+}
+
+#[test]
+fn detect_finds_roms_that_need_64_kb() {
+    //Jump to self
+    assert_eq!(RomSettings::detect(&[0x12, 0x00]).memory_size, None);
+    //F000 NNNN reached from the start, and one only in data past a jump to self
+    assert_eq!(RomSettings::detect(&[0xF0, 0x00, 0x12, 0x34, 0x12, 0x04]).memory_size, Some(0x10000));
+    assert_eq!(RomSettings::detect(&[0x12, 0x00, 0xF0, 0x00, 0x12, 0x34]).memory_size, None);
+    //Too big for 4 KB
+    let mut big = vec![0x12, 0x00];
+    big.resize(4096 - 0x200 + 1, 0);
+    assert_eq!(RomSettings::detect(&big).memory_size, Some(0x10000));
+
+    let mut emulator = Emulator::new();
+    assert!(emulator.load_rom(&big).is_err());
+    RomSettings::detect(&big).apply(&mut emulator);
+    assert!(emulator.load_rom(&big).is_ok());
+}
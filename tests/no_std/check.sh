@@ -0,0 +1,7 @@
+#!/bin/sh
+#Build chip8-core without std for a microcontroller, to keep it no_std. Needs the thumbv7em-none-eabihf target
+#(rustup target add thumbv7em-none-eabihf)
+set -e
+cd "$(dirname "$0")/../.."
+cargo clippy -p chip8-core --target thumbv7em-none-eabihf --no-default-features --features heatmap,serde -- -D warnings
+cargo build -p chip8-core --target thumbv7em-none-eabihf --no-default-features --release
@@ -0,0 +1,49 @@
+//The rewind buffer: how far back it goes, and snapshots from before the memory map changed
+
+use chip8::{Emulator, MemoryMap, Rewind, StateError};
+
+//V0 += 1, then a jump back to it
+const COUNTER: [u8; 4] = [0x70, 0x01, 0x12, 0x00];
+
+fn counter() -> Emulator {
+    let mut emulator = Emulator::new();
+    emulator.load_rom(&COUNTER).unwrap();
+    emulator
+}
+
+#[test]
+fn rewinding_goes_back_to_the_start_of_a_recorded_frame() {
+    let mut emulator = counter();
+    let mut rewind = Rewind::new(3);
+    let mut starts = Vec::new();
+    for _ in 0..5 {
+        rewind.record(&emulator);
+        starts.push(emulator.v_register(0));
+        emulator.run_frame().unwrap();
+    }
+    assert_eq!(rewind.len(), 3);
+    assert_eq!(rewind.rewind(&mut emulator, 2), Ok(2));
+    assert_eq!(emulator.v_register(0), starts[3]);
+    //Only as far as the buffer reaches
+    assert_eq!(rewind.rewind(&mut emulator, 10), Ok(1));
+    assert_eq!(emulator.v_register(0), starts[2]);
+    assert_eq!(rewind.rewind(&mut emulator, 1), Ok(0));
+}
+
+#[test]
+fn snapshots_from_another_memory_map_are_dropped() {
+    let mut emulator = counter();
+    let mut rewind = Rewind::new(10);
+    rewind.record(&emulator);
+    emulator.run_frame().unwrap();
+    rewind.record(&emulator);
+
+    //An XO-CHIP ROM reloaded with 64 KB
+    emulator.set_memory_map(MemoryMap::xochip()).unwrap();
+    emulator.load_rom(&COUNTER).unwrap();
+    emulator.run_frame().unwrap();
+    let before = emulator.save_state();
+    assert_eq!(rewind.rewind(&mut emulator, 1), Err(StateError::RamSize { len: 4096, expected: 0x10000 }));
+    assert!(rewind.is_empty());
+    assert_eq!(emulator.save_state(), before);
+}
@@ -0,0 +1,39 @@
+//Screens of in-tree ROMs after a fixed number of instructions, compared by hash
+//A change to how drawing (or anything before it) behaves shows up as a different hash
+
+use chip8::Emulator;
+
+use std::fs;
+
+//Fresh emulator with rom loaded and a fixed seed, so CXNN gives the same screens every run
+fn load(rom: &str) -> Emulator {
+    let mut emulator = Emulator::new();
+    emulator.set_seed(1);
+    emulator.load_rom(&fs::read(format!("ROMS/{}.ch8", rom)).unwrap()).unwrap();
+    emulator
+}
+
+#[test]
+fn screens_match_known_hashes() {
+    let expected = [
+        ("Brix [Andreas Gustafsson, 1990]", 20_000, 0xF2FB_EBDD_7452_F795),
+        ("Connect 4 [David Winter]", 5_000, 0xF22C_2E21_E9BC_10D3),
+        ("Missile [David Winter]", 20_000, 0x2E2C_AD4F_4B77_D59D),
+    ];
+    for (rom, cycles, hash) in expected {
+        let mut emulator = load(rom);
+        assert_eq!(emulator.run_until(cycles, |_| false).unwrap(), None);
+        assert_eq!(emulator.screen_hash(), hash, "{} after {} cycles: {:#018X}", rom, cycles, emulator.screen_hash());
+    }
+}
+
+#[test]
+fn run_until_stops_after_the_first_draw() {
+    let mut emulator = load("Brix [Andreas Gustafsson, 1990]");
+    let blank = emulator.screen_hash();
+    //A new screen counts as changed until taken
+    emulator.take_dirty_rows();
+    let cycles = emulator.run_until(1_000, |emulator| emulator.screen_changed()).unwrap();
+    assert!(cycles.is_some());
+    assert_ne!(emulator.screen_hash(), blank);
+}
@@ -0,0 +1,88 @@
+//Rhai automation scripts: what they see and change of the machine, what they keep between frames and
+//how their errors are reported
+#![cfg(feature = "scripting")]
+
+use chip8::script::{Flow, Script, ScriptError};
+use chip8::Emulator;
+
+fn emulator(rom: &[u8]) -> Emulator {
+    let mut emulator = Emulator::new();
+    emulator.load_rom(rom).unwrap();
+    emulator
+}
+
+fn failure(source: &str, frames: u64) -> ScriptError {
+    //V0 := 0x2A, then a jump to itself
+    Script::parse(source).unwrap().run(&mut emulator(&[0x60, 0x2A, 0x12, 0x02]), frames).unwrap_err()
+}
+
+#[test]
+fn a_bot_presses_keys_and_stops() {
+    //V0 := 5, wait for key 5 with EX9E, then V1 := 1 and a jump to itself
+    let mut emulator = emulator(&[0x60, 0x05, 0xE0, 0x9E, 0x12, 0x02, 0x61, 0x01, 0x12, 0x08]);
+    let mut script = Script::parse("if frame == 2 { press(5) }\nif v(1) == 1 { stop() }").unwrap();
+    assert_eq!(script.run(&mut emulator, 100), Ok(3));
+    assert_eq!(script.frame(), 4);
+    assert_eq!(emulator.v_register(1), 1);
+}
+
+#[test]
+fn scripts_write_registers_memory_and_tap_keys() {
+    let mut emulator = emulator(&[0x12, 0x00]);
+    let mut script = Script::parse("if frame == 0 { set_v(3, 0x1FF); set_i(0x345); poke(0x300, 7); tap(0xA) } else { assert(!key(0xA)) }").unwrap();
+    assert_eq!(script.run_frame(&mut emulator), Ok(Flow::Continue));
+    assert_eq!((emulator.v_register(3), emulator.i_register(), emulator.peek(0x300)), (0xFF, 0x345, 7));
+    assert_eq!(emulator.keys_bitmask(), 1 << 0xA);
+    //Released before the next frame's script
+    assert_eq!(script.run_frame(&mut emulator), Ok(Flow::Continue));
+    assert_eq!(emulator.keys_bitmask(), 0);
+}
+
+#[test]
+fn vars_last_from_frame_to_frame_and_lets_do_not() {
+    let mut emulator = emulator(&[0x12, 0x00]);
+    let mut script = Script::parse("let once = 1;\nonce += 1;\nassert(once == 2);\nvars.count += 1;\n\nfn init() { #{ count: 10 } }").unwrap();
+    assert_eq!(script.run(&mut emulator, 5), Ok(5));
+    assert_eq!(script.variable("count"), 15);
+    assert_eq!(script.variable("missing"), 0);
+
+    let mut script = Script::parse("vars.seen = true;").unwrap();
+    assert_eq!(script.run(&mut emulator, 2), Ok(2));
+}
+
+#[test]
+fn print_output_is_tagged_with_the_frame() {
+    let mut script = Script::parse("if frame < 2 { print(v(0)) }").unwrap();
+    //The script runs before the frame, so V0 is only set by the second
+    assert_eq!(script.run(&mut emulator(&[0x60, 0x2A, 0x12, 0x02]), 3), Ok(3));
+    assert_eq!(script.output(), ["frame 0: 0", "frame 1: 42"]);
+    assert_eq!(script.take_output().len(), 2);
+    assert!(script.output().is_empty());
+}
+
+#[test]
+fn failures_report_the_line_and_frame() {
+    match failure("let a = 1;\nassert(frame < 2, \"too late\");", 5) {
+        ScriptError::Failed { line, frame, message } => {
+            assert_eq!((line, frame), (2, 2));
+            assert!(message.contains("assertion failed: too late"), "{}", message);
+        },
+        error => panic!("{:?}", error),
+    }
+    match failure("\n\nv(16)", 1) {
+        ScriptError::Failed { line, frame, message } => {
+            assert_eq!((line, frame), (3, 0));
+            assert!(message.contains("no register v16"), "{}", message);
+        },
+        error => panic!("{:?}", error),
+    }
+    assert!(matches!(failure("press(16)", 1), ScriptError::Failed { .. }));
+    //Stuck in a loop
+    assert!(matches!(failure("loop {}", 1), ScriptError::Failed { line: 1, frame: 0, .. }));
+}
+
+#[test]
+fn parse_errors_report_the_line() {
+    assert!(matches!(Script::parse("let a = 1;\nlet b = ;"), Err(ScriptError::Parse { line: 2, .. })));
+    assert!(Script::parse("if v(0) == 1 { press(1) ").is_err());
+}
@@ -0,0 +1,151 @@
+//The binary snapshot format: what round-trips, what older versions load as and what is rejected
+
+use chip8::{Emulator, EmulatorState, MemoryMap, RandomModel, State, StateError};
+
+//Offsets into a snapshot of a running program with the Entropy model and an empty stack
+const STATE_TAG: usize = 6;
+const PLANES: usize = 30;
+const PITCH: usize = 47;
+const RANDOM: usize = 52;
+const RAM: usize = 61;
+//I high, an empty chunk of memory past RAM and the MegaChip tag, at the end since version 4
+const MEGACHIP_PART: usize = 6;
+
+fn running() -> Emulator {
+    let mut emulator = Emulator::new();
+    //V0 := 0x2A, I := 0x300, then a jump to itself
+    emulator.load_rom(&[0x60, 0x2A, 0xA3, 0x00, 0x12, 0x04]).unwrap();
+    emulator.tick().unwrap();
+    emulator.tick().unwrap();
+    emulator
+}
+
+fn waiting_for_key() -> Emulator {
+    let mut emulator = Emulator::new();
+    //V5 := key
+    emulator.load_rom(&[0xF5, 0x0A]).unwrap();
+    emulator.tick().unwrap();
+    assert_eq!(emulator.save_state().state, EmulatorState::WaitingForKey { dest_register: 5 });
+    emulator
+}
+
+fn megachip() -> Emulator {
+    let mut emulator = Emulator::new();
+    emulator.set_memory_map(MemoryMap::xochip()).unwrap();
+    emulator.set_megachip(true);
+    //MegaChip on, I := 0x123456, then a jump to itself, with the ROM running 4 bytes past RAM
+    let mut rom = vec![0x00, 0x11, 0x01, 0x12, 0x34, 0x56, 0x12, 0x06];
+    rom.resize(0x10000 - 0x200 + 4, 0xEE);
+    emulator.load_rom(&rom).unwrap();
+    emulator.tick().unwrap();
+    emulator.tick().unwrap();
+    emulator
+}
+
+fn with_version(bytes: &[u8], version: u16) -> Vec<u8> {
+    let mut bytes = bytes.to_vec();
+    bytes[4..6].copy_from_slice(&version.to_be_bytes());
+    bytes
+}
+
+#[test]
+fn snapshots_round_trip() {
+    for emulator in [running(), waiting_for_key()] {
+        let state = emulator.save_state();
+        assert_eq!(State::from_snapshot_bytes(&state.to_snapshot_bytes()), Ok(state.clone()));
+        let mut loaded = Emulator::new();
+        loaded.load_state(&state).unwrap();
+        assert_eq!(loaded.save_state(), state);
+    }
+}
+
+#[test]
+fn megachip_mode_is_saved_and_needs_megachip_to_load() {
+    let state = megachip().save_state();
+    assert!(state.megachip.is_some());
+    assert_eq!((state.i_high, state.extended.as_slice()), (0x12, [0xEE; 4].as_slice()));
+    assert_eq!(State::from_snapshot_bytes(&state.to_snapshot_bytes()), Ok(state.clone()));
+
+    let mut loaded = Emulator::new();
+    loaded.set_memory_map(MemoryMap::xochip()).unwrap();
+    assert_eq!(loaded.load_state(&state), Err(StateError::MegaChipOff));
+    loaded.set_megachip(true);
+    loaded.load_state(&state).unwrap();
+    assert!(loaded.megachip().is_some());
+    assert_eq!(loaded.save_state(), state);
+
+    //Leaving MegaChip mode by loading a state from outside it
+    let mut outside = Emulator::new();
+    outside.set_memory_map(MemoryMap::xochip()).unwrap();
+    outside.load_rom(&[0x12, 0x00]).unwrap();
+    loaded.load_state(&outside.save_state()).unwrap();
+    assert!(loaded.megachip().is_none());
+}
+
+#[test]
+fn truncated_snapshots_are_rejected() {
+    let bytes = running().save_state().to_snapshot_bytes();
+    assert_eq!(State::from_snapshot_bytes(&bytes[..3]), Err(StateError::NotASnapshot));
+    for len in [4, 6, PITCH, RAM, bytes.len() - 1] {
+        assert_eq!(State::from_snapshot_bytes(&bytes[..len]), Err(StateError::Truncated), "cut at {}", len);
+    }
+    let mut longer = bytes.clone();
+    longer.push(0);
+    assert_eq!(State::from_snapshot_bytes(&longer), Err(StateError::Corrupt));
+}
+
+#[test]
+fn newer_versions_are_unsupported() {
+    let bytes = running().save_state().to_snapshot_bytes();
+    assert_eq!(State::from_snapshot_bytes(&with_version(&bytes, 0)), Err(StateError::UnsupportedVersion { version: 0 }));
+    assert_eq!(State::from_snapshot_bytes(&with_version(&bytes, 999)), Err(StateError::UnsupportedVersion { version: 999 }));
+}
+
+#[test]
+fn version_2_loads_with_the_default_pitch() {
+    let state = running().save_state();
+    let mut bytes = with_version(&state.to_snapshot_bytes(), 2);
+    bytes.truncate(bytes.len() - MEGACHIP_PART);
+    bytes.remove(PITCH);
+    let loaded = State::from_snapshot_bytes(&bytes).unwrap();
+    assert_eq!(loaded.pitch, 64);
+    assert_eq!(State { pitch: state.pitch, ..loaded }, state);
+}
+
+#[test]
+fn version_1_loads_with_entropy_and_the_default_pitch() {
+    let state = State { random_state: 0, ..running().save_state() };
+    let mut bytes = with_version(&state.to_snapshot_bytes(), 1);
+    bytes.truncate(bytes.len() - MEGACHIP_PART);
+    bytes.drain(RANDOM..RAM);
+    bytes.remove(PITCH);
+    let loaded = State::from_snapshot_bytes(&bytes).unwrap();
+    assert_eq!(loaded.quirks.random, RandomModel::Entropy);
+    assert_eq!(loaded.random_state, 0);
+    assert_eq!(loaded.v_registers[0], 0x2A);
+    assert_eq!(loaded.i_register, 0x300);
+}
+
+#[test]
+fn out_of_range_registers_and_planes_are_corrupt() {
+    let bytes = waiting_for_key().save_state().to_snapshot_bytes();
+    let mut bad_register = bytes.clone();
+    bad_register[STATE_TAG + 1] = 0x10;
+    assert_eq!(State::from_snapshot_bytes(&bad_register), Err(StateError::Corrupt));
+
+    let state = running().save_state();
+    let mut bytes = state.to_snapshot_bytes();
+    bytes[PLANES] = 0b100;
+    assert_eq!(State::from_snapshot_bytes(&bytes), Err(StateError::Corrupt));
+
+    //Also when the State did not come from bytes
+    let mut emulator = running();
+    for bad in [
+        State { state: EmulatorState::WaitingForKey { dest_register: 0x10 }, ..state.clone() },
+        State { state: EmulatorState::WaitingForRelease { dest_register: 0, key: 0x10 }, ..state.clone() },
+        State { planes: 0xFF, ..state.clone() },
+    ] {
+        assert_eq!(emulator.load_state(&bad), Err(StateError::Corrupt));
+    }
+    assert_eq!(emulator.save_state(), state);
+}
@@ -0,0 +1,92 @@
+//FX0A under both settings of Quirks::wait_key_on_release: which press (or release) completes it
+
+use chip8::{Emulator, EmulatorState, Key, Quirks};
+
+//V3 := the key, then a jump to self
+const WAIT_KEY: [u8; 4] = [0xF3, 0x0A, 0x12, 0x02];
+
+fn waiting(wait_key_on_release: bool) -> Emulator {
+    let mut emulator = Emulator::new();
+    emulator.set_quirks(Quirks { wait_key_on_release, ..Quirks::default() });
+    emulator.load_rom(&WAIT_KEY).unwrap();
+    emulator.tick().unwrap();
+    emulator
+}
+
+#[test]
+fn without_the_quirk_a_press_completes_it() {
+    let mut emulator = waiting(false);
+    assert_eq!(emulator.state(), EmulatorState::WaitingForKey { dest_register: 3 });
+    //Waiting, ticks run nothing
+    emulator.tick().unwrap();
+    assert_eq!(emulator.program_counter(), 0x202);
+    assert_eq!(emulator.v_register(3), 0);
+
+    emulator.keypress(Key::Key5, true);
+    assert_eq!(emulator.state(), EmulatorState::Running);
+    assert_eq!(emulator.v_register(3), 5);
+    //The release comes after and changes nothing
+    emulator.keypress(Key::Key5, false);
+    assert_eq!(emulator.v_register(3), 5);
+}
+
+#[test]
+fn with_the_quirk_the_release_of_the_pressed_key_completes_it() {
+    let mut emulator = waiting(true);
+    assert_eq!(emulator.state(), EmulatorState::WaitingForKey { dest_register: 3 });
+    emulator.keypress(Key::Key5, true);
+    assert_eq!(emulator.state(), EmulatorState::WaitingForRelease { dest_register: 3, key: 5 });
+    assert_eq!(emulator.v_register(3), 0);
+
+    //Another key going down and up meanwhile is not the one it waits for
+    emulator.keypress(Key::Key6, true);
+    emulator.keypress(Key::Key6, false);
+    assert_eq!(emulator.state(), EmulatorState::WaitingForRelease { dest_register: 3, key: 5 });
+
+    emulator.keypress(Key::Key5, false);
+    assert_eq!(emulator.state(), EmulatorState::Running);
+    assert_eq!(emulator.v_register(3), 5);
+}
+
+#[test]
+fn a_key_held_from_an_earlier_frame_is_ignored() {
+    for wait_key_on_release in [false, true] {
+        let mut emulator = Emulator::new();
+        emulator.set_quirks(Quirks { wait_key_on_release, ..Quirks::default() });
+        emulator.load_rom(&WAIT_KEY).unwrap();
+        emulator.keypress(Key::Key7, true);
+        emulator.timers();
+        emulator.tick().unwrap();
+        assert_eq!(emulator.state(), EmulatorState::WaitingForKey { dest_register: 3 });
+        //A repeated press event is not a new press
+        emulator.keypress(Key::Key7, true);
+        assert_eq!(emulator.state(), EmulatorState::WaitingForKey { dest_register: 3 });
+        //Letting go is not a press either, the next one is
+        emulator.keypress(Key::Key7, false);
+        assert_eq!(emulator.state(), EmulatorState::WaitingForKey { dest_register: 3 });
+        emulator.keypress(Key::Key8, true);
+        emulator.keypress(Key::Key8, false);
+        assert_eq!(emulator.state(), EmulatorState::Running);
+        assert_eq!(emulator.v_register(3), 8);
+    }
+}
+
+#[test]
+fn with_the_quirk_a_key_pressed_earlier_in_the_frame_has_done_the_press() {
+    for wait_key_on_release in [false, true] {
+        let mut emulator = Emulator::new();
+        emulator.set_quirks(Quirks { wait_key_on_release, ..Quirks::default() });
+        emulator.load_rom(&WAIT_KEY).unwrap();
+        //Delivered between frames, before FX0A ran
+        emulator.keypress(Key::Key9, true);
+        emulator.tick().unwrap();
+        if wait_key_on_release {
+            assert_eq!(emulator.state(), EmulatorState::WaitingForRelease { dest_register: 3, key: 9 });
+            emulator.keypress(Key::Key9, false);
+            assert_eq!(emulator.v_register(3), 9);
+        } else {
+            //Without the quirk it is a key held from before
+            assert_eq!(emulator.state(), EmulatorState::WaitingForKey { dest_register: 3 });
+        }
+    }
+}
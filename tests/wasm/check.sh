@@ -0,0 +1,8 @@
+#!/bin/sh
+#Build chip8-core for the browser and run frames of it under node. Needs the wasm32-unknown-unknown target,
+#wasm-bindgen-cli at the wasm-bindgen version in Cargo.lock, and node
+set -e
+cd "$(dirname "$0")/../.."
+cargo build -p chip8-core --target wasm32-unknown-unknown --features wasm --release
+wasm-bindgen --target nodejs --out-dir target/wasm-check target/wasm32-unknown-unknown/release/chip8_core.wasm
+node tests/wasm/run_frame.js "$PWD/target/wasm-check/chip8_core.js"
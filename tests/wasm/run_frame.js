@@ -0,0 +1,18 @@
+//The wasm feature's Chip8 class under node, for check.sh. wasm32-unknown-unknown has no clock or threads,
+//anything the emulator reads of them traps there, so this runs frames that draw as a page would
+const { Chip8 } = require(process.argv[2]);
+
+//CLS, I := the 0 glyph, V0 := 0, V1 := 0, draw it, jump back to the draw
+const rom = new Uint8Array([0x00, 0xE0, 0xA0, 0x50, 0x60, 0x00, 0x61, 0x00, 0xD0, 0x15, 0x12, 0x08]);
+
+const chip8 = new Chip8();
+chip8.loadRom(rom);
+chip8.tick();
+let changed = 0;
+for (let frame = 0; frame < 60; frame++) {
+    changed += chip8.runFrame() ? 1 : 0;
+}
+if (changed === 0 || chip8.rgba().length !== chip8.width() * chip8.height() * 4) {
+    throw new Error("the frames drew nothing");
+}
+console.log("wasm32 ok");